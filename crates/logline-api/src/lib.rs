@@ -13,6 +13,8 @@ pub enum AuthMode {
     ApiKey,
     Bearer,
     Mtls,
+    /// No credentials, e.g. a `process` backend on the local machine.
+    None,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,21 +23,130 @@ pub struct BackendAuth {
     pub secret_ref: String,
 }
 
+/// Which connector `DefaultConnectorFactory` builds for a backend.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackendKind {
+    #[default]
+    Http,
+    /// Intents run as local subprocesses, mapped through `BackendConfig::commands`.
+    Process,
+    /// An external executable speaking JSON-RPC over stdio, named by `BackendConfig::plugin`.
+    Plugin,
+    /// A Supabase project driven through `PostgREST`, mapped by `BackendConfig::supabase`.
+    Supabase,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SupabaseOp {
+    Select,
+    Insert,
+    Upsert,
+    Update,
+    Delete,
+    Rpc,
+}
+
+/// The `PostgREST` call a `supabase` backend makes for one intent type.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SupabaseOperation {
+    pub op: SupabaseOp,
+    /// Table name, or function name for `rpc`.
+    pub target: String,
+    /// `PostgREST` filter such as `app_id=eq.{{app_id}}`; required for `update` and `delete`.
+    #[serde(default)]
+    pub filter: Option<String>,
+    /// Conflict columns for `upsert`.
+    #[serde(default)]
+    pub on_conflict: Option<String>,
+    /// An `rpc` without side effects, so readonly profiles may call it.
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+/// Intent mapping and event source of a `supabase` backend.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SupabaseSpec {
+    #[serde(default)]
+    pub intents: BTreeMap<String, SupabaseOperation>,
+    /// Table read by `events_since`, e.g. `service_status_log`.
+    #[serde(default)]
+    pub events_table: Option<String>,
+    /// Increasing column used as the event cursor; defaults to `id`.
+    #[serde(default)]
+    pub events_cursor: Option<String>,
+    /// Column whose value becomes the event kind.
+    #[serde(default)]
+    pub events_kind: Option<String>,
+    /// Timestamp column for `ts_unix_ms`.
+    #[serde(default)]
+    pub events_time: Option<String>,
+}
+
+/// The executable behind a `plugin` backend; see `docs/logline-cli/PLUGIN_PROTOCOL.md`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PluginSpec {
+    /// Program name looked up on `PATH`, e.g. `logline-connector-foo`, or a path.
+    pub executable: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Free-form settings handed to the plugin during the handshake.
+    #[serde(default)]
+    pub settings: BTreeMap<String, String>,
+}
+
+/// The executable a `process` backend runs for one intent type.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProcessCommand {
+    pub program: String,
+    /// Arguments; `{{key}}` is replaced with the payload value for `key`.
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Variables set on top of an otherwise cleared environment.
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// Defaults to a scratch directory created for each run.
+    #[serde(default)]
+    pub working_dir: Option<String>,
+    /// Kill the process after this long; unset means no limit.
+    #[serde(default)]
+    pub timeout_seconds: Option<u64>,
+    /// The command has no side effects, so readonly profiles may run it.
+    #[serde(default)]
+    pub read_only: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BackendConfig {
     pub backend_id: BackendId,
+    #[serde(default)]
+    pub kind: BackendKind,
     pub base_url: String,
     pub auth: BackendAuth,
     pub connect_timeout_ms: u64,
     pub request_timeout_ms: u64,
     pub extra_headers: BTreeMap<String, String>,
+    /// Intent type to command; only used by `process` backends.
+    #[serde(default)]
+    pub commands: BTreeMap<String, ProcessCommand>,
+    /// Only used by `plugin` backends.
+    #[serde(default)]
+    pub plugin: Option<PluginSpec>,
+    /// Only used by `supabase` backends.
+    #[serde(default)]
+    pub supabase: Option<SupabaseSpec>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct BackendCapabilities {
     pub supports_streaming: bool,
     pub supports_write: bool,
     pub supports_history: bool,
+    /// Whether `BackendConnector::plan` can predict effects without executing.
+    #[serde(default)]
+    pub supports_plan: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -49,6 +160,44 @@ pub struct ExecutionResult {
     pub run_id: RunId,
     pub status: String,
     pub output: BTreeMap<String, String>,
+    /// Set when the run succeeded but its health, registry or audit record
+    /// could not be written; the run itself still counts as done.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recording_error: Option<String>,
+}
+
+/// One change an intent is expected to make if executed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedEffect {
+    pub action: String,
+    pub resource: String,
+    pub description: String,
+}
+
+/// What a successful health probe confirmed about a backend.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HealthReport {
+    pub latency_ms: u64,
+    /// Version the server reported, if it reports one.
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Capabilities the server itself confirmed, as opposed to configured ones.
+    #[serde(default)]
+    pub capabilities: Option<BackendCapabilities>,
+    /// `notAfter` of the server's TLS certificate, in unix seconds.
+    #[serde(default)]
+    pub tls_expires_unix: Option<i64>,
+}
+
+/// Predicted effects of an intent, produced without executing it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionPlan {
+    pub backend_id: BackendId,
+    pub intent_type: String,
+    /// False only when the backend is sure the intent changes nothing.
+    pub mutating: bool,
+    pub effects: Vec<PlannedEffect>,
+    pub warnings: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -91,8 +240,17 @@ pub trait SecretStore: Send + Sync {
 pub trait BackendConnector: Send + Sync {
     fn id(&self) -> &str;
     fn capabilities(&self) -> BackendCapabilities;
-    fn health(&self) -> Result<(), LoglineError>;
+    fn health(&self) -> Result<HealthReport, LoglineError>;
     fn execute(&self, intent: &Intent) -> Result<ExecutionResult, LoglineError>;
+    /// Predict what `execute` would do. Connectors that cannot plan keep
+    /// `supports_plan = false` and inherit this refusal.
+    fn plan(&self, intent: &Intent) -> Result<ExecutionPlan, LoglineError> {
+        Err(LoglineError::Validation(format!(
+            "backend {} cannot plan intent {}",
+            self.id(),
+            intent.intent_type
+        )))
+    }
     fn stop(&self, run_id: &RunId) -> Result<(), LoglineError>;
     fn events_since(&self, cursor: Option<&EventCursor>) -> Result<Vec<DomainEvent>, LoglineError>;
 }
@@ -108,8 +266,9 @@ pub trait ConnectorFactory: Send + Sync {
 pub trait RuntimeEngine: Send + Sync {
     fn status(&self) -> Result<RuntimeStatus, LoglineError>;
     fn run_intent(&self, intent: Intent) -> Result<ExecutionResult, LoglineError>;
+    fn plan_intent(&self, intent: Intent) -> Result<ExecutionPlan, LoglineError>;
     fn stop_run(&self, run_id: RunId) -> Result<(), LoglineError>;
     fn events_since(&self, cursor: Option<EventCursor>) -> Result<Vec<DomainEvent>, LoglineError>;
-    fn test_backend(&self, backend_id: BackendId) -> Result<(), LoglineError>;
+    fn test_backend(&self, backend_id: BackendId) -> Result<HealthReport, LoglineError>;
     fn select_profile(&self, profile_id: ProfileId) -> Result<(), LoglineError>;
 }
@@ -0,0 +1,449 @@
+//! In-memory JWKS and OIDC discovery cache owned by a [`JwtVerifier`](crate::JwtVerifier).
+//!
+//! The cache only decides; callers do the fetching. [`JwksCache::lookup`] either
+//! answers from memory or asks for a fetch (handing back stale keys to fall back
+//! on), and the caller reports the result with [`JwksCache::store`] or
+//! [`JwksCache::fetch_failed`]. That keeps it usable from async and blocking code
+//! alike, and lets tests drive it with a fake [`Clock`] and canned key sets.
+
+use crate::jwt::JwksSet;
+
+use dashmap::DashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Time source for cache expiry.
+pub trait Clock: Send + Sync {
+    /// Milliseconds since the Unix epoch.
+    fn now_ms(&self) -> u128;
+}
+
+/// The system clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_ms(&self) -> u128 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or(Duration::from_secs(0))
+            .as_millis()
+    }
+}
+
+/// Limits for a [`JwksCache`].
+#[derive(Debug, Clone)]
+pub struct JwksCacheConfig {
+    /// Most JWKS URLs (and, separately, discovery documents) kept; the least
+    /// recently used entry is evicted beyond this.
+    pub max_entries: usize,
+
+    /// Least time between two fetches of the same URL, whether triggered by an
+    /// unknown `kid` or by retrying an issuer that is down.
+    pub min_refresh_interval_seconds: u64,
+
+    /// How long past expiry keys may still be served when refreshing fails.
+    pub max_stale_seconds: u64,
+}
+
+impl Default for JwksCacheConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: 64,
+            min_refresh_interval_seconds: 30,
+            max_stale_seconds: 3600,
+        }
+    }
+}
+
+/// Counters since the cache was created.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Lookups answered from memory with fresh keys.
+    pub hits: u64,
+    /// Lookups that asked for a fetch.
+    pub misses: u64,
+    /// Misses caused by a `kid` the cached set does not contain.
+    pub kid_refreshes: u64,
+    /// Times expired keys were served because the issuer could not be reached.
+    pub stale_served: u64,
+    /// Entries dropped to stay within `max_entries`.
+    pub evictions: u64,
+    /// JWKS entries currently held.
+    pub entries: usize,
+}
+
+/// Outcome of [`JwksCache::lookup`].
+#[derive(Debug, Clone)]
+pub enum Lookup {
+    /// Serve these keys.
+    Hit(JwksSet),
+    /// Fetch the URL; `stale` is what to fall back on if that fails.
+    Fetch {
+        /// Expired (or kid-less) keys still within the stale window.
+        stale: Option<JwksSet>,
+        /// `ETag` of `stale`, to revalidate it with `If-None-Match`.
+        etag: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Entry<T> {
+    value: T,
+    etag: Option<String>,
+    fetched_at_ms: u128,
+    expires_at_ms: u128,
+    /// Last fetch attempt, successful or not; rate-limits refreshes.
+    attempted_at_ms: u128,
+    used_at_ms: u128,
+    /// Whether the last fetch attempt failed.
+    failed: bool,
+}
+
+impl<T> Entry<T> {
+    fn new(value: T, etag: Option<String>, now: u128, ttl_seconds: u64) -> Self {
+        Self {
+            value,
+            etag,
+            fetched_at_ms: now,
+            expires_at_ms: now + u128::from(ttl_seconds) * 1000,
+            attempted_at_ms: now,
+            used_at_ms: now,
+            failed: false,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Counters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    kid_refreshes: AtomicU64,
+    stale_served: AtomicU64,
+    evictions: AtomicU64,
+}
+
+struct Inner {
+    config: JwksCacheConfig,
+    clock: Arc<dyn Clock>,
+    jwks: DashMap<String, Entry<JwksSet>>,
+    discovery: DashMap<String, Entry<String>>,
+    counters: Counters,
+}
+
+/// JWKS and discovery cache. Clones share the same entries and counters.
+#[derive(Clone)]
+pub struct JwksCache {
+    inner: Arc<Inner>,
+}
+
+impl Default for JwksCache {
+    fn default() -> Self {
+        Self::new(JwksCacheConfig::default())
+    }
+}
+
+impl fmt::Debug for JwksCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JwksCache")
+            .field("config", &self.inner.config)
+            .field("stats", &self.stats())
+            .finish_non_exhaustive()
+    }
+}
+
+impl JwksCache {
+    /// A cache on the system clock.
+    pub fn new(config: JwksCacheConfig) -> Self {
+        Self::with_clock(config, Arc::new(SystemClock))
+    }
+
+    /// A cache on `clock`, e.g. a fake one in tests.
+    pub fn with_clock(config: JwksCacheConfig, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                config,
+                clock,
+                jwks: DashMap::new(),
+                discovery: DashMap::new(),
+                counters: Counters::default(),
+            }),
+        }
+    }
+
+    /// Decide whether `url` can be answered from memory for a token with `kid`.
+    pub fn lookup(&self, url: &str, kid: Option<&str>) -> Lookup {
+        let now = self.now_ms();
+        let min_interval = u128::from(self.inner.config.min_refresh_interval_seconds) * 1000;
+        let max_stale = u128::from(self.inner.config.max_stale_seconds) * 1000;
+        let counters = &self.inner.counters;
+
+        let Some(mut entry) = self.inner.jwks.get_mut(url) else {
+            counters.misses.fetch_add(1, Ordering::Relaxed);
+            return Lookup::Fetch {
+                stale: None,
+                etag: None,
+            };
+        };
+        entry.used_at_ms = now;
+        let may_retry = now.saturating_sub(entry.attempted_at_ms) >= min_interval;
+
+        if entry.expires_at_ms > now {
+            let knows_kid = kid.is_none_or(|kid| {
+                entry
+                    .value
+                    .keys
+                    .iter()
+                    .any(|k| k.kid.as_deref() == Some(kid))
+            });
+            if knows_kid || !may_retry {
+                counters.hits.fetch_add(1, Ordering::Relaxed);
+                return Lookup::Hit(entry.value.clone());
+            }
+            counters.misses.fetch_add(1, Ordering::Relaxed);
+            counters.kid_refreshes.fetch_add(1, Ordering::Relaxed);
+            entry.attempted_at_ms = now;
+            return Lookup::Fetch {
+                stale: Some(entry.value.clone()),
+                etag: entry.etag.clone(),
+            };
+        }
+
+        let within_stale = now < entry.expires_at_ms + max_stale;
+        if within_stale && entry.failed && !may_retry {
+            // The last refresh just failed; don't hammer the issuer.
+            counters.stale_served.fetch_add(1, Ordering::Relaxed);
+            return Lookup::Hit(entry.value.clone());
+        }
+        counters.misses.fetch_add(1, Ordering::Relaxed);
+        entry.attempted_at_ms = now;
+        Lookup::Fetch {
+            stale: within_stale.then(|| entry.value.clone()),
+            etag: within_stale.then(|| entry.etag.clone()).flatten(),
+        }
+    }
+
+    /// Record freshly fetched (or revalidated) keys for `url`, valid for `ttl_seconds`.
+    pub fn store(&self, url: &str, jwks: &JwksSet, etag: Option<&str>, ttl_seconds: u64) {
+        let now = self.now_ms();
+        self.make_room(&self.inner.jwks, url);
+        self.inner.jwks.insert(
+            url.to_string(),
+            Entry::new(jwks.clone(), etag.map(str::to_string), now, ttl_seconds),
+        );
+    }
+
+    /// Forget `url`, e.g. because the issuer sent `Cache-Control: no-store`.
+    pub fn remove(&self, url: &str) {
+        self.inner.jwks.remove(url);
+    }
+
+    /// The fetch for `url` failed: hand back the stale keys from
+    /// [`Lookup::Fetch`] if there were any.
+    pub fn fetch_failed(&self, url: &str, stale: Option<JwksSet>) -> Option<JwksSet> {
+        if let Some(mut entry) = self.inner.jwks.get_mut(url) {
+            entry.failed = true;
+        }
+        if stale.is_some() {
+            self.inner
+                .counters
+                .stale_served
+                .fetch_add(1, Ordering::Relaxed);
+        }
+        stale
+    }
+
+    /// Cached `jwks_uri` for an OIDC discovery URL, if still fresh.
+    pub fn discovery(&self, discovery_url: &str) -> Option<String> {
+        let now = self.now_ms();
+        let mut entry = self.inner.discovery.get_mut(discovery_url)?;
+        if entry.expires_at_ms <= now {
+            return None;
+        }
+        entry.used_at_ms = now;
+        Some(entry.value.clone())
+    }
+
+    /// Record the `jwks_uri` resolved from `discovery_url`.
+    pub fn store_discovery(&self, discovery_url: &str, jwks_uri: &str, ttl_seconds: u64) {
+        let now = self.now_ms();
+        self.make_room(&self.inner.discovery, discovery_url);
+        self.inner.discovery.insert(
+            discovery_url.to_string(),
+            Entry::new(jwks_uri.to_string(), None, now, ttl_seconds),
+        );
+    }
+
+    /// Drop every cached entry (counters are kept).
+    pub fn clear(&self) {
+        self.inner.jwks.clear();
+        self.inner.discovery.clear();
+    }
+
+    /// Counters and current size.
+    pub fn stats(&self) -> CacheStats {
+        let c = &self.inner.counters;
+        CacheStats {
+            hits: c.hits.load(Ordering::Relaxed),
+            misses: c.misses.load(Ordering::Relaxed),
+            kid_refreshes: c.kid_refreshes.load(Ordering::Relaxed),
+            stale_served: c.stale_served.load(Ordering::Relaxed),
+            evictions: c.evictions.load(Ordering::Relaxed),
+            entries: self.inner.jwks.len(),
+        }
+    }
+
+    fn now_ms(&self) -> u128 {
+        self.inner.clock.now_ms()
+    }
+
+    /// Evict least recently used entries so one more `key` fits.
+    fn make_room<T>(&self, map: &DashMap<String, Entry<T>>, key: &str) {
+        let max = self.inner.config.max_entries.max(1);
+        while !map.contains_key(key) && map.len() >= max {
+            let oldest = map
+                .iter()
+                .min_by_key(|e| (e.used_at_ms, e.fetched_at_ms))
+                .map(|e| e.key().clone());
+            let Some(oldest) = oldest else { break };
+            map.remove(&oldest);
+            self.inner
+                .counters
+                .evictions
+                .fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jwt::Jwk;
+
+    #[derive(Default)]
+    struct FakeClock(AtomicU64);
+
+    impl FakeClock {
+        fn advance(&self, seconds: u64) {
+            self.0.fetch_add(seconds * 1000, Ordering::Relaxed);
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now_ms(&self) -> u128 {
+            u128::from(self.0.load(Ordering::Relaxed))
+        }
+    }
+
+    fn set(kid: &str) -> JwksSet {
+        JwksSet {
+            keys: vec![Jwk {
+                kty: "OKP".to_string(),
+                kid: Some(kid.to_string()),
+                use_: None,
+                alg: None,
+                n: None,
+                e: None,
+                crv: Some("Ed25519".to_string()),
+                x: Some(String::new()),
+                y: None,
+                k: None,
+            }],
+        }
+    }
+
+    fn cache(clock: &Arc<FakeClock>, max_entries: usize) -> JwksCache {
+        JwksCache::with_clock(
+            JwksCacheConfig {
+                max_entries,
+                min_refresh_interval_seconds: 30,
+                max_stale_seconds: 600,
+            },
+            clock.clone(),
+        )
+    }
+
+    fn kid(lookup: &Lookup) -> Option<&str> {
+        match lookup {
+            Lookup::Hit(set) => set.keys[0].kid.as_deref(),
+            Lookup::Fetch { .. } => None,
+        }
+    }
+
+    #[test]
+    fn unknown_kid_refreshes_at_most_once_per_interval() {
+        let clock = Arc::new(FakeClock::default());
+        let cache = cache(&clock, 8);
+        assert!(matches!(
+            cache.lookup("u", None),
+            Lookup::Fetch { stale: None, .. }
+        ));
+        cache.store("u", &set("old"), None, 300);
+        assert_eq!(kid(&cache.lookup("u", Some("old"))), Some("old"));
+
+        clock.advance(31);
+        assert!(matches!(
+            cache.lookup("u", Some("new")),
+            Lookup::Fetch { stale: Some(_), .. }
+        ));
+        // Rate-limited: a second unknown kid right away is served the cached keys.
+        assert_eq!(kid(&cache.lookup("u", Some("newer"))), Some("old"));
+
+        cache.store("u", &set("new"), None, 300);
+        assert_eq!(kid(&cache.lookup("u", Some("new"))), Some("new"));
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.kid_refreshes), (3, 2, 1));
+    }
+
+    #[test]
+    fn expired_keys_are_served_stale_while_the_issuer_is_down() {
+        let clock = Arc::new(FakeClock::default());
+        let cache = cache(&clock, 8);
+        cache.store("u", &set("k1"), Some("\"v1\""), 60);
+        // An entry that merely expired (e.g. `no-cache`) is refetched right away.
+        cache.store("n", &set("n"), None, 0);
+        assert!(matches!(
+            cache.lookup("n", None),
+            Lookup::Fetch { stale: Some(_), .. }
+        ));
+
+        clock.advance(61);
+        let Lookup::Fetch { stale, etag } = cache.lookup("u", Some("k1")) else {
+            panic!("expired entry must be refetched");
+        };
+        assert_eq!(etag.as_deref(), Some("\"v1\""));
+        assert!(cache.fetch_failed("u", stale).is_some());
+        // Within the retry interval the stale keys are served without a fetch.
+        assert_eq!(kid(&cache.lookup("u", Some("k1"))), Some("k1"));
+
+        clock.advance(600);
+        assert!(matches!(
+            cache.lookup("u", Some("k1")),
+            Lookup::Fetch {
+                stale: None,
+                etag: None
+            }
+        ));
+        assert_eq!(cache.stats().stale_served, 2);
+    }
+
+    #[test]
+    fn least_recently_used_entry_is_evicted() {
+        let clock = Arc::new(FakeClock::default());
+        let cache = cache(&clock, 2);
+        cache.store("a", &set("a"), None, 300);
+        clock.advance(1);
+        cache.store("b", &set("b"), None, 300);
+        clock.advance(1);
+        cache.lookup("a", None);
+        cache.store("c", &set("c"), None, 300);
+
+        assert!(matches!(cache.lookup("a", None), Lookup::Hit(_)));
+        assert!(matches!(cache.lookup("b", None), Lookup::Fetch { .. }));
+        let stats = cache.stats();
+        assert_eq!((stats.entries, stats.evictions), (2, 1));
+    }
+}
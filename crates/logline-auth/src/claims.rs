@@ -0,0 +1,254 @@
+//! Claim and header validators run after a token's signature checks out.
+
+use crate::jwt::VerifiedJwt;
+use crate::{Error, Result};
+
+use serde_json::Value;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Check run by a custom [`ClaimValidator`]; the `Err` string becomes the reason.
+pub type ClaimCheck = Arc<dyn Fn(&VerifiedJwt) -> std::result::Result<(), String> + Send + Sync>;
+
+/// A check listed in [`VerifyOptions::validators`](crate::VerifyOptions::validators).
+///
+/// Each failure is reported as [`Error::Validation`] naming the claim (or header
+/// field) that failed.
+#[derive(Clone)]
+pub enum ClaimValidator {
+    /// The claim must be present (and not `null`).
+    Required(String),
+    /// The claim must equal this value.
+    Equals(String, Value),
+    /// The claim must contain this value: an array holding it, or a
+    /// space-separated string (such as `scope`) holding it as a word.
+    Contains(String, Value),
+    /// `iat` must be present and at most this many seconds old (plus leeway).
+    MaxAge(u64),
+    /// `azp` must be present and one of these client ids.
+    AuthorizedParty(Vec<String>),
+    /// The header `typ` must be one of these, compared case-insensitively with
+    /// any `application/` prefix ignored (e.g. `at+jwt`).
+    Typ(Vec<String>),
+    /// A custom check, reported against `claim`.
+    Custom {
+        /// Claim named in the error when the check fails.
+        claim: String,
+        /// The check.
+        check: ClaimCheck,
+    },
+}
+
+impl ClaimValidator {
+    /// The claim must be present.
+    pub fn required(claim: impl Into<String>) -> Self {
+        Self::Required(claim.into())
+    }
+
+    /// The claim must equal `value`.
+    pub fn equals(claim: impl Into<String>, value: impl Into<Value>) -> Self {
+        Self::Equals(claim.into(), value.into())
+    }
+
+    /// The claim must contain `value`.
+    pub fn contains(claim: impl Into<String>, value: impl Into<Value>) -> Self {
+        Self::Contains(claim.into(), value.into())
+    }
+
+    /// A custom check reported against `claim`.
+    pub fn custom(
+        claim: impl Into<String>,
+        check: impl Fn(&VerifiedJwt) -> std::result::Result<(), String> + Send + Sync + 'static,
+    ) -> Self {
+        Self::Custom {
+            claim: claim.into(),
+            check: Arc::new(check),
+        }
+    }
+
+    /// Run the check against a token whose signature and `exp`/`nbf` are already verified.
+    pub fn validate(&self, jwt: &VerifiedJwt, leeway_seconds: u64) -> Result<()> {
+        match self {
+            Self::Required(claim) => {
+                present(jwt, claim)?;
+            }
+            Self::Equals(claim, expected) => {
+                if present(jwt, claim)? != expected {
+                    return Err(Error::validation(claim, format!("expected {expected}")));
+                }
+            }
+            Self::Contains(claim, expected) => {
+                let found = match present(jwt, claim)? {
+                    Value::Array(items) => items.contains(expected),
+                    Value::String(s) => expected
+                        .as_str()
+                        .is_some_and(|word| s.split_whitespace().any(|w| w == word)),
+                    _ => false,
+                };
+                if !found {
+                    return Err(Error::validation(
+                        claim,
+                        format!("does not contain {expected}"),
+                    ));
+                }
+            }
+            Self::MaxAge(max_age) => {
+                let iat = present(jwt, "iat")?
+                    .as_u64()
+                    .ok_or_else(|| Error::validation("iat", "not a timestamp"))?;
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |d| d.as_secs());
+                let age = now.saturating_sub(iat);
+                if age > max_age.saturating_add(leeway_seconds) {
+                    return Err(Error::validation(
+                        "iat",
+                        format!("issued {age}s ago, more than {max_age}s"),
+                    ));
+                }
+            }
+            Self::AuthorizedParty(allowed) => {
+                let azp = present(jwt, "azp")?.as_str().unwrap_or_default();
+                if !allowed.iter().any(|a| a == azp) {
+                    return Err(Error::validation("azp", format!("{azp} is not allowed")));
+                }
+            }
+            Self::Typ(allowed) => {
+                let typ = jwt
+                    .header
+                    .typ
+                    .as_deref()
+                    .ok_or_else(|| Error::validation("typ", "missing header"))?;
+                if !allowed.iter().any(|a| media_type(a) == media_type(typ)) {
+                    return Err(Error::validation("typ", format!("{typ} is not allowed")));
+                }
+            }
+            Self::Custom { claim, check } => {
+                check(jwt).map_err(|reason| Error::validation(claim, reason))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for ClaimValidator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Required(claim) => f.debug_tuple("Required").field(claim).finish(),
+            Self::Equals(claim, value) => {
+                f.debug_tuple("Equals").field(claim).field(value).finish()
+            }
+            Self::Contains(claim, value) => {
+                f.debug_tuple("Contains").field(claim).field(value).finish()
+            }
+            Self::MaxAge(max_age) => f.debug_tuple("MaxAge").field(max_age).finish(),
+            Self::AuthorizedParty(allowed) => {
+                f.debug_tuple("AuthorizedParty").field(allowed).finish()
+            }
+            Self::Typ(allowed) => f.debug_tuple("Typ").field(allowed).finish(),
+            Self::Custom { claim, .. } => f
+                .debug_struct("Custom")
+                .field("claim", claim)
+                .finish_non_exhaustive(),
+        }
+    }
+}
+
+fn present<'a>(jwt: &'a VerifiedJwt, claim: &str) -> Result<&'a Value> {
+    jwt.claim(claim)
+        .filter(|v| !v.is_null())
+        .ok_or_else(|| Error::validation(claim, "missing"))
+}
+
+/// `typ` compared as RFC 8725 suggests: case-insensitive, `application/` optional.
+fn media_type(typ: &str) -> String {
+    let lower = typ.to_ascii_lowercase();
+    match lower.strip_prefix("application/") {
+        Some(rest) => rest.to_string(),
+        None => lower,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonwebtoken::{Algorithm, Header};
+
+    fn jwt(claims: Value, typ: Option<&str>) -> VerifiedJwt {
+        let mut header = Header::new(Algorithm::EdDSA);
+        header.typ = typ.map(str::to_string);
+        VerifiedJwt { header, claims }
+    }
+
+    fn failing_claim(validator: &ClaimValidator, jwt: &VerifiedJwt) -> Option<String> {
+        match validator.validate(jwt, 60) {
+            Ok(()) => None,
+            Err(Error::Validation { claim, .. }) => Some(claim),
+            Err(e) => panic!("unexpected error: {e}"),
+        }
+    }
+
+    #[test]
+    fn validators_name_the_failing_claim() {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let token = jwt(
+            serde_json::json!({
+                "sub": "user-1",
+                "role": "authenticated",
+                "scope": "read write",
+                "amr": ["pwd", "otp"],
+                "azp": "cli",
+                "iat": now - 600,
+                "email": null,
+            }),
+            Some("at+JWT"),
+        );
+
+        let passing = [
+            ClaimValidator::required("sub"),
+            ClaimValidator::equals("role", "authenticated"),
+            ClaimValidator::contains("scope", "write"),
+            ClaimValidator::contains("amr", "otp"),
+            ClaimValidator::MaxAge(3600),
+            ClaimValidator::AuthorizedParty(vec!["web".into(), "cli".into()]),
+            ClaimValidator::Typ(vec!["application/at+jwt".into()]),
+            ClaimValidator::custom("sub", |jwt| {
+                jwt.sub()
+                    .filter(|s| s.starts_with("user-"))
+                    .map(|_| ())
+                    .ok_or_else(|| "not a user".to_string())
+            }),
+        ];
+        for validator in &passing {
+            assert_eq!(failing_claim(validator, &token), None, "{validator:?}");
+        }
+
+        let failing = [
+            (ClaimValidator::required("email"), "email"),
+            (ClaimValidator::equals("role", "service_role"), "role"),
+            (ClaimValidator::contains("scope", "admin"), "scope"),
+            (ClaimValidator::MaxAge(300), "iat"),
+            (ClaimValidator::AuthorizedParty(vec!["web".into()]), "azp"),
+            (ClaimValidator::Typ(vec!["JWT".into()]), "typ"),
+            (
+                ClaimValidator::custom("aal", |_| Err("aal2 required".into())),
+                "aal",
+            ),
+        ];
+        for (validator, claim) in &failing {
+            assert_eq!(failing_claim(validator, &token).as_deref(), Some(*claim));
+        }
+        assert_eq!(
+            failing_claim(
+                &ClaimValidator::Typ(vec!["JWT".into()]),
+                &jwt(Value::Null, None)
+            )
+            .as_deref(),
+            Some("typ")
+        );
+    }
+}
@@ -73,12 +73,12 @@ fn cookie_name(opts: &CookieOptions) -> Result<String> {
     if opts.use_host_prefix && opts.domain.is_none() {
         // Enforce __Host- cookie requirements.
         if opts.path != "/" {
-            return Err(Error::Validation(
+            return Err(Error::InvalidCookie(
                 "__Host- cookies must have Path=/".to_string(),
             ));
         }
         if !opts.secure {
-            return Err(Error::Validation(
+            return Err(Error::InvalidCookie(
                 "__Host- cookies must be Secure".to_string(),
             ));
         }
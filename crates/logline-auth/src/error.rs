@@ -24,9 +24,22 @@ pub enum Error {
     #[error("no suitable key found in JWKS")]
     NoMatchingKey,
 
-    /// Token claims failed validation.
-    #[error("token validation failed: {0}")]
-    Validation(String),
+    /// A token claim (or header field) failed validation.
+    #[error("token validation failed: {claim}: {reason}")]
+    Validation {
+        /// The claim that failed, e.g. `exp`, `aud` or `typ`.
+        claim: String,
+        /// Why it failed.
+        reason: String,
+    },
+
+    /// Cookie options violate a cookie prefix's requirements.
+    #[error("invalid cookie options: {0}")]
+    InvalidCookie(String),
+
+    /// A passkey signature or key could not be verified.
+    #[error("passkey verification failed: {0}")]
+    Passkey(String),
 
     /// An error occurred while performing HTTP requests.
     #[cfg(feature = "fetch-reqwest")]
@@ -41,3 +54,12 @@ pub enum Error {
     #[error(transparent)]
     Jwt(#[from] jsonwebtoken::errors::Error),
 }
+
+impl Error {
+    pub(crate) fn validation(claim: &str, reason: impl Into<String>) -> Self {
+        Self::Validation {
+            claim: claim.to_string(),
+            reason: reason.into(),
+        }
+    }
+}
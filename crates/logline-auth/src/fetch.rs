@@ -0,0 +1,405 @@
+//! Fetching JWKS and OIDC discovery documents.
+//!
+//! [`JwtVerifier`](crate::JwtVerifier) never talks to the network itself; it asks
+//! a [`JwksFetcher`]. The default is [`ReqwestFetcher`] (with the `fetch-reqwest`
+//! feature), which callers can build around their own `reqwest` clients for
+//! pooling, proxies or custom CAs. [`StaticFetcher`], [`FileFetcher`] and
+//! [`MockFetcher`] cover pinned keys, keys on disk and tests.
+
+use crate::{Error, Result};
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+/// Freshness used when a response has no `Cache-Control` lifetime.
+pub const DEFAULT_TTL_SECONDS: u64 = 300;
+
+/// One fetch: the URL and, when revalidating, the cached `ETag`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchRequest {
+    /// Document URL.
+    pub url: String,
+    /// Sent as `If-None-Match`.
+    pub if_none_match: Option<String>,
+}
+
+/// The `Cache-Control` directives the verifier acts on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheControl {
+    /// `max-age`.
+    pub max_age: Option<u64>,
+    /// `s-maxage`; wins over `max-age`, since a verifier's cache serves many requests.
+    pub s_maxage: Option<u64>,
+    /// `no-store`: never cache the response.
+    pub no_store: bool,
+    /// `no-cache`: cache it, but revalidate before every use.
+    pub no_cache: bool,
+}
+
+impl CacheControl {
+    /// Parse a `Cache-Control` header value; unknown directives are ignored.
+    pub fn parse(header: &str) -> Self {
+        let mut cc = Self::default();
+        for directive in header.split(',') {
+            let (name, value) = match directive.split_once('=') {
+                Some((name, value)) => (name, Some(value.trim().trim_matches('"'))),
+                None => (directive, None),
+            };
+            match name.trim().to_ascii_lowercase().as_str() {
+                "max-age" => cc.max_age = value.and_then(|v| v.parse().ok()),
+                "s-maxage" => cc.s_maxage = value.and_then(|v| v.parse().ok()),
+                "no-store" => cc.no_store = true,
+                "no-cache" => cc.no_cache = true,
+                _ => {}
+            }
+        }
+        cc
+    }
+
+    /// Seconds the response may be reused, or `None` when it must not be stored.
+    pub fn ttl_seconds(&self, default: u64) -> Option<u64> {
+        if self.no_store {
+            None
+        } else if self.no_cache {
+            Some(0)
+        } else {
+            Some(self.s_maxage.or(self.max_age).unwrap_or(default))
+        }
+    }
+}
+
+/// What a fetch returned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FetchResponse {
+    /// A (new) document.
+    Body {
+        /// Response body (JSON).
+        body: String,
+        /// `Cache-Control` of the response.
+        cache_control: CacheControl,
+        /// `ETag` of the response, for later revalidation.
+        etag: Option<String>,
+    },
+    /// `304 Not Modified`: the cached document is still current.
+    NotModified {
+        /// `Cache-Control` of the response.
+        cache_control: CacheControl,
+    },
+}
+
+impl FetchResponse {
+    /// A body with no caching headers.
+    pub fn body(body: impl Into<String>) -> Self {
+        Self::Body {
+            body: body.into(),
+            cache_control: CacheControl::default(),
+            etag: None,
+        }
+    }
+}
+
+/// Future returned by [`JwksFetcher::fetch`].
+pub type FetchFuture<'a> = Pin<Box<dyn Future<Output = Result<FetchResponse>> + Send + 'a>>;
+
+/// Source of JWKS and discovery documents for a [`JwtVerifier`](crate::JwtVerifier).
+pub trait JwksFetcher: Send + Sync + fmt::Debug {
+    /// Fetch without an async runtime (used by the `*_blocking` verifier methods).
+    fn fetch_blocking(&self, request: &FetchRequest) -> Result<FetchResponse>;
+
+    /// Fetch from async code. Defaults to [`JwksFetcher::fetch_blocking`], which
+    /// suits fetchers that never wait on the network.
+    fn fetch<'a>(&'a self, request: &'a FetchRequest) -> FetchFuture<'a> {
+        Box::pin(async move { self.fetch_blocking(request) })
+    }
+}
+
+/// A shared fetcher, e.g. a [`MockFetcher`] the test still holds.
+impl<T: JwksFetcher + ?Sized> JwksFetcher for Arc<T> {
+    fn fetch_blocking(&self, request: &FetchRequest) -> Result<FetchResponse> {
+        (**self).fetch_blocking(request)
+    }
+
+    fn fetch<'a>(&'a self, request: &'a FetchRequest) -> FetchFuture<'a> {
+        (**self).fetch(request)
+    }
+}
+
+/// The same document for every URL, e.g. pinned keys.
+#[derive(Debug, Clone)]
+pub struct StaticFetcher {
+    body: String,
+    cache_control: CacheControl,
+}
+
+impl StaticFetcher {
+    /// Serve `body` (JWKS JSON) for every request.
+    pub fn new(body: impl Into<String>) -> Self {
+        Self {
+            body: body.into(),
+            cache_control: CacheControl::default(),
+        }
+    }
+
+    /// Report `cache_control` with every response.
+    #[must_use]
+    pub fn with_cache_control(mut self, cache_control: CacheControl) -> Self {
+        self.cache_control = cache_control;
+        self
+    }
+}
+
+impl JwksFetcher for StaticFetcher {
+    fn fetch_blocking(&self, _request: &FetchRequest) -> Result<FetchResponse> {
+        Ok(FetchResponse::Body {
+            body: self.body.clone(),
+            cache_control: self.cache_control,
+            etag: None,
+        })
+    }
+}
+
+/// Reads a JWKS file on every fetch, so replacing the file rotates the keys.
+#[derive(Debug, Clone)]
+pub struct FileFetcher {
+    path: PathBuf,
+}
+
+impl FileFetcher {
+    /// Serve the contents of `path` for every request.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl JwksFetcher for FileFetcher {
+    fn fetch_blocking(&self, _request: &FetchRequest) -> Result<FetchResponse> {
+        std::fs::read_to_string(&self.path)
+            .map(FetchResponse::body)
+            .map_err(|e| Error::Jwks(format!("failed to read {}: {e}", self.path.display())))
+    }
+}
+
+/// Scripted responses for tests; records every request it receives.
+#[derive(Debug, Default)]
+pub struct MockFetcher {
+    responses: Mutex<VecDeque<Result<FetchResponse>>>,
+    requests: Mutex<Vec<FetchRequest>>,
+}
+
+impl MockFetcher {
+    /// A fetcher with no responses queued (fetching fails until some are).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue the response for the next fetch.
+    pub fn push(&self, response: FetchResponse) {
+        lock(&self.responses).push_back(Ok(response));
+    }
+
+    /// Queue a failure (e.g. the issuer being down) for the next fetch.
+    pub fn push_error(&self, message: impl Into<String>) {
+        lock(&self.responses).push_back(Err(Error::Jwks(message.into())));
+    }
+
+    /// Requests received so far.
+    pub fn requests(&self) -> Vec<FetchRequest> {
+        lock(&self.requests).clone()
+    }
+}
+
+impl JwksFetcher for MockFetcher {
+    fn fetch_blocking(&self, request: &FetchRequest) -> Result<FetchResponse> {
+        lock(&self.requests).push(request.clone());
+        lock(&self.responses)
+            .pop_front()
+            .unwrap_or_else(|| Err(Error::Jwks(format!("no mock response for {}", request.url))))
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// Placeholder when no HTTP client is compiled in.
+#[cfg(not(feature = "fetch-reqwest"))]
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct NoFetcher;
+
+#[cfg(not(feature = "fetch-reqwest"))]
+impl JwksFetcher for NoFetcher {
+    fn fetch_blocking(&self, _request: &FetchRequest) -> Result<FetchResponse> {
+        Err(Error::Jwks(
+            "JwksSource::Url requires the fetch-reqwest feature (or a JwksFetcher, or JwksSource::Json/Set)"
+                .to_string(),
+        ))
+    }
+}
+
+#[cfg(feature = "fetch-reqwest")]
+pub use self::http::ReqwestFetcher;
+
+#[cfg(feature = "fetch-reqwest")]
+mod http {
+    use super::{CacheControl, FetchFuture, FetchRequest, FetchResponse, JwksFetcher};
+    use crate::{Error, Result};
+    use reqwest::StatusCode;
+    use reqwest::header::{CACHE_CONTROL, ETAG, HeaderMap, IF_NONE_MATCH};
+    use std::time::Duration;
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// HTTP fetcher on caller-supplied (or default, 10 s timeout) `reqwest` clients.
+    #[derive(Debug, Clone)]
+    pub struct ReqwestFetcher {
+        client: reqwest::Client,
+        #[cfg(feature = "blocking")]
+        blocking: std::sync::Arc<std::sync::OnceLock<reqwest::blocking::Client>>,
+    }
+
+    impl Default for ReqwestFetcher {
+        fn default() -> Self {
+            let client = reqwest::Client::builder()
+                .timeout(TIMEOUT)
+                .build()
+                .unwrap_or_default();
+            Self::new(client)
+        }
+    }
+
+    impl ReqwestFetcher {
+        /// Fetch with `client` (its pool, proxy, TLS roots and timeouts).
+        pub fn new(client: reqwest::Client) -> Self {
+            Self {
+                client,
+                #[cfg(feature = "blocking")]
+                blocking: std::sync::Arc::default(),
+            }
+        }
+
+        /// Use `client` for blocking fetches instead of a default one built on first use.
+        #[cfg(feature = "blocking")]
+        #[must_use]
+        pub fn with_blocking_client(self, client: reqwest::blocking::Client) -> Self {
+            let blocking = std::sync::OnceLock::new();
+            let _ = blocking.set(client);
+            Self {
+                blocking: std::sync::Arc::new(blocking),
+                ..self
+            }
+        }
+    }
+
+    fn response(
+        status: StatusCode,
+        headers: &HeaderMap,
+        url: &str,
+    ) -> Result<Option<(CacheControl, Option<String>)>> {
+        let cache_control = headers
+            .get(CACHE_CONTROL)
+            .and_then(|h| h.to_str().ok())
+            .map(CacheControl::parse)
+            .unwrap_or_default();
+        if status == StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            return Err(Error::Jwks(format!("fetch {url} failed: {status}")));
+        }
+        let etag = headers
+            .get(ETAG)
+            .and_then(|h| h.to_str().ok())
+            .map(str::to_string);
+        Ok(Some((cache_control, etag)))
+    }
+
+    fn not_modified(headers: &HeaderMap) -> FetchResponse {
+        FetchResponse::NotModified {
+            cache_control: headers
+                .get(CACHE_CONTROL)
+                .and_then(|h| h.to_str().ok())
+                .map(CacheControl::parse)
+                .unwrap_or_default(),
+        }
+    }
+
+    impl JwksFetcher for ReqwestFetcher {
+        #[cfg(feature = "blocking")]
+        fn fetch_blocking(&self, request: &FetchRequest) -> Result<FetchResponse> {
+            let client = self.blocking.get_or_init(|| {
+                reqwest::blocking::Client::builder()
+                    .timeout(TIMEOUT)
+                    .build()
+                    .unwrap_or_default()
+            });
+            let mut req = client.get(&request.url);
+            if let Some(etag) = &request.if_none_match {
+                req = req.header(IF_NONE_MATCH, etag);
+            }
+            let resp = req.send()?;
+            let headers = resp.headers().clone();
+            match response(resp.status(), &headers, &request.url)? {
+                None => Ok(not_modified(&headers)),
+                Some((cache_control, etag)) => Ok(FetchResponse::Body {
+                    body: resp.text()?,
+                    cache_control,
+                    etag,
+                }),
+            }
+        }
+
+        #[cfg(not(feature = "blocking"))]
+        fn fetch_blocking(&self, _request: &FetchRequest) -> Result<FetchResponse> {
+            Err(Error::Jwks(
+                "blocking fetches require the blocking feature".to_string(),
+            ))
+        }
+
+        fn fetch<'a>(&'a self, request: &'a FetchRequest) -> FetchFuture<'a> {
+            Box::pin(async move {
+                let mut req = self.client.get(&request.url);
+                if let Some(etag) = &request.if_none_match {
+                    req = req.header(IF_NONE_MATCH, etag);
+                }
+                let resp = req.send().await?;
+                let headers = resp.headers().clone();
+                match response(resp.status(), &headers, &request.url)? {
+                    None => Ok(not_modified(&headers)),
+                    Some((cache_control, etag)) => Ok(FetchResponse::Body {
+                        body: resp.text().await?,
+                        cache_control,
+                        etag,
+                    }),
+                }
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_control_parser() {
+        let cc = CacheControl::parse("public, max-age=60");
+        assert_eq!(cc.max_age, Some(60));
+        assert_eq!(cc.ttl_seconds(DEFAULT_TTL_SECONDS), Some(60));
+        assert_eq!(CacheControl::parse("max-age=0").ttl_seconds(300), Some(0));
+        assert_eq!(CacheControl::parse("").ttl_seconds(300), Some(300));
+
+        let shared = CacheControl::parse("max-age=60, S-MAXAGE=\"600\"");
+        assert_eq!(shared.ttl_seconds(300), Some(600));
+        assert_eq!(
+            CacheControl::parse("no-store, max-age=60").ttl_seconds(300),
+            None
+        );
+        assert_eq!(CacheControl::parse("no-cache").ttl_seconds(300), Some(0));
+    }
+}
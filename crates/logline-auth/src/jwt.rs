@@ -1,17 +1,18 @@
 //! JWT verification using JWKS.
 
+use crate::claims::ClaimValidator;
+use crate::fetch::{CacheControl, DEFAULT_TTL_SECONDS, FetchRequest, FetchResponse, JwksFetcher};
 use crate::{Error, Result};
 
 use base64::Engine;
 use jsonwebtoken::{Algorithm, DecodingKey, Header, Validation};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::sync::Arc;
 
 #[cfg(feature = "cache")]
-use dashmap::DashMap;
-#[cfg(feature = "cache")]
-use once_cell::sync::Lazy;
+use crate::cache::{JwksCache, Lookup};
 
 /// A JWKS (JSON Web Key Set).
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -89,6 +90,9 @@ pub struct VerifyOptions {
 
     /// If true, reject tokens without a `kid` header.
     pub require_kid: bool,
+
+    /// Further claim and header checks, run in order after `iss`/`aud`.
+    pub validators: Vec<ClaimValidator>,
 }
 
 impl Default for VerifyOptions {
@@ -101,6 +105,7 @@ impl Default for VerifyOptions {
             leeway_seconds: 60,
             max_jwks_age_seconds: 300,
             require_kid: false,
+            validators: Vec::new(),
         }
     }
 }
@@ -139,28 +144,61 @@ impl VerifiedJwt {
     pub fn exp(&self) -> Option<i64> {
         self.claim("exp").and_then(|v| v.as_i64())
     }
-}
 
-#[cfg(feature = "cache")]
-#[derive(Debug, Clone)]
-struct CachedJwks {
-    exp_at_ms: u128,
-    jwks: JwksSet,
+    /// Deserialize the claims into `T`; a mismatch is reported as [`Error::Validation`].
+    pub fn claims_as<T: DeserializeOwned>(&self) -> Result<T> {
+        T::deserialize(&self.claims).map_err(|e| Error::validation("claims", e.to_string()))
+    }
 }
 
-#[cfg(feature = "cache")]
-static JWKS_CACHE: Lazy<DashMap<String, CachedJwks>> = Lazy::new(DashMap::new);
-
 /// Verifies JWTs against a JWKS.
-#[derive(Debug, Clone, Default)]
+///
+/// Keys for [`JwksSource::Url`] come from the verifier's [`JwksFetcher`]
+/// ([`ReqwestFetcher`](crate::ReqwestFetcher) by default). With the `cache`
+/// feature each verifier also owns a [`JwksCache`]; clones share both.
+#[derive(Debug, Clone)]
 pub struct JwtVerifier {
-    _priv: (),
+    #[cfg(feature = "cache")]
+    cache: JwksCache,
+    fetcher: Arc<dyn JwksFetcher>,
+}
+
+impl Default for JwtVerifier {
+    fn default() -> Self {
+        #[cfg(feature = "fetch-reqwest")]
+        let fetcher = crate::fetch::ReqwestFetcher::default();
+        #[cfg(not(feature = "fetch-reqwest"))]
+        let fetcher = crate::fetch::NoFetcher;
+        Self {
+            #[cfg(feature = "cache")]
+            cache: JwksCache::default(),
+            fetcher: Arc::new(fetcher),
+        }
+    }
 }
 
 impl JwtVerifier {
+    /// Use `cache`, e.g. one shared with other verifiers or built on a fake clock.
+    #[cfg(feature = "cache")]
+    #[must_use]
+    pub fn with_cache(self, cache: JwksCache) -> Self {
+        Self { cache, ..self }
+    }
+
+    /// Fetch JWKS and discovery documents with `fetcher`.
+    #[must_use]
+    pub fn with_fetcher(mut self, fetcher: impl JwksFetcher + 'static) -> Self {
+        self.fetcher = Arc::new(fetcher);
+        self
+    }
+
+    /// This verifier's JWKS cache, e.g. for [`JwksCache::stats`].
+    #[cfg(feature = "cache")]
+    pub fn cache(&self) -> &JwksCache {
+        &self.cache
+    }
+
     /// Verify a token using the configured JWKS URL.
-    ///
-    /// Requires the `fetch-reqwest` feature.
     pub async fn verify_with_jwks_url(
         &self,
         token: &str,
@@ -170,6 +208,15 @@ impl JwtVerifier {
             .await
     }
 
+    /// [`JwtVerifier::verify_with_jwks_url`], then deserialize the claims into `T`.
+    pub async fn verify_as<T: DeserializeOwned>(
+        &self,
+        token: &str,
+        opts: VerifyOptions,
+    ) -> Result<T> {
+        self.verify_with_jwks_url(token, opts).await?.claims_as()
+    }
+
     /// Verify a token using a JWKS source (URL, JSON, or parsed set).
     pub async fn verify_with_source(
         &self,
@@ -177,55 +224,27 @@ impl JwtVerifier {
         source: JwksSource,
         opts: VerifyOptions,
     ) -> Result<VerifiedJwt> {
-        let header = jsonwebtoken::decode_header(token)
-            .map_err(|e| Error::InvalidJwt(format!("failed to decode header: {e}")))?;
-
-        if !opts.allowed_algs.contains(&header.alg) {
-            return Err(Error::UnsupportedAlg(header.alg));
-        }
-
-        if opts.require_kid && header.kid.as_deref().unwrap_or("").is_empty() {
-            return Err(Error::InvalidJwt("missing kid".to_string()));
-        }
-
-        let jwks = self.load_jwks(&source, &opts).await?;
+        let header = check_header(token, &opts)?;
+        let jwks = match source {
+            JwksSource::Url(url) => self.load_jwks(&url, header.kid.as_deref(), &opts).await?,
+            JwksSource::Json(json) => serde_json::from_str(&json)?,
+            JwksSource::Set(set) => set,
+        };
         verify_against_jwks(token, &header, &jwks, &opts)
     }
 
-    async fn load_jwks(&self, source: &JwksSource, opts: &VerifyOptions) -> Result<JwksSet> {
-        match source {
-            JwksSource::Set(set) => Ok(set.clone()),
-            JwksSource::Json(json) => Ok(serde_json::from_str(json)?),
-            JwksSource::Url(url) => {
-                #[cfg(feature = "cache")]
-                {
-                    let now_ms = now_epoch_ms();
-                    if let Some(cached) = JWKS_CACHE.get(url) {
-                        if cached.exp_at_ms > now_ms {
-                            return Ok(cached.jwks.clone());
-                        }
-                    }
-
-                    let (set, max_age_seconds) = fetch_jwks_url(url).await?;
-                    let ttl = std::cmp::min(max_age_seconds, opts.max_jwks_age_seconds);
-                    let exp_at_ms = now_ms + (ttl as u128 * 1000);
-                    JWKS_CACHE.insert(
-                        url.clone(),
-                        CachedJwks {
-                            exp_at_ms,
-                            jwks: set.clone(),
-                        },
-                    );
-                    return Ok(set);
-                }
-
-                #[cfg(not(feature = "cache"))]
-                {
-                    let (set, _max_age_seconds) = fetch_jwks_url(url).await?;
-                    Ok(set)
-                }
-            }
-        }
+    async fn load_jwks(
+        &self,
+        url: &str,
+        kid: Option<&str>,
+        opts: &VerifyOptions,
+    ) -> Result<JwksSet> {
+        let (stale, request) = match self.cached_jwks(url, kid) {
+            Ok(set) => return Ok(set),
+            Err(miss) => miss,
+        };
+        let response = self.fetcher.fetch(&request).await;
+        self.fetched_jwks(&request, response, stale, opts)
     }
 
     /// Resolve an OIDC issuer to its `jwks_uri` via discovery.
@@ -233,48 +252,271 @@ impl JwtVerifier {
     /// This is a helper so callers can do:
     /// 1) `jwks_url = resolve_oidc_jwks_url(issuer)`
     /// 2) `verify_with_jwks_url(token, VerifyOptions { jwks_url, issuer: Some(issuer), .. })`
-    ///
-    /// Requires the `fetch-reqwest` feature.
     pub async fn resolve_oidc_jwks_url(
         &self,
         issuer: &str,
         max_age_seconds: u64,
     ) -> Result<String> {
-        let issuer = issuer.trim_end_matches('/');
-        let discovery = format!("{issuer}/.well-known/openid-configuration");
+        let discovery = discovery_url(issuer);
+        if let Some(jwks_uri) = self.cached_discovery(&discovery) {
+            return Ok(jwks_uri);
+        }
+        let request = FetchRequest {
+            url: discovery,
+            if_none_match: None,
+        };
+        let response = self.fetcher.fetch(&request).await?;
+        self.fetched_discovery(&request.url, response, max_age_seconds)
+    }
+}
+
+/// Blocking counterparts for callers without an async runtime (e.g. a CLI built
+/// on `reqwest::blocking`). They share the header checks, key selection, fetcher
+/// and cache with the async methods.
+#[cfg(feature = "blocking")]
+impl JwtVerifier {
+    /// Blocking [`JwtVerifier::verify_with_jwks_url`].
+    pub fn verify_with_jwks_url_blocking(
+        &self,
+        token: &str,
+        opts: VerifyOptions,
+    ) -> Result<VerifiedJwt> {
+        self.verify_with_source_blocking(token, JwksSource::Url(opts.jwks_url.clone()), opts)
+    }
+
+    /// Blocking [`JwtVerifier::verify_as`].
+    pub fn verify_as_blocking<T: DeserializeOwned>(
+        &self,
+        token: &str,
+        opts: VerifyOptions,
+    ) -> Result<T> {
+        self.verify_with_jwks_url_blocking(token, opts)?.claims_as()
+    }
+
+    /// Blocking [`JwtVerifier::verify_with_source`].
+    #[allow(clippy::needless_pass_by_value)] // same signature as the async method
+    pub fn verify_with_source_blocking(
+        &self,
+        token: &str,
+        source: JwksSource,
+        opts: VerifyOptions,
+    ) -> Result<VerifiedJwt> {
+        let header = check_header(token, &opts)?;
+        let jwks = match source {
+            JwksSource::Url(url) => self.jwks_blocking(&url, header.kid.as_deref(), &opts)?,
+            JwksSource::Json(json) => serde_json::from_str(&json)?,
+            JwksSource::Set(set) => set,
+        };
+        verify_against_jwks(token, &header, &jwks, &opts)
+    }
+
+    /// Fetch (or take from the cache) the JWKS at `url`, e.g. to keep a copy
+    /// for verifying offline later with [`JwksSource::Set`].
+    pub fn load_jwks_blocking(&self, url: &str, opts: &VerifyOptions) -> Result<JwksSet> {
+        self.jwks_blocking(url, None, opts)
+    }
+
+    fn jwks_blocking(&self, url: &str, kid: Option<&str>, opts: &VerifyOptions) -> Result<JwksSet> {
+        let (stale, request) = match self.cached_jwks(url, kid) {
+            Ok(set) => return Ok(set),
+            Err(miss) => miss,
+        };
+        let response = self.fetcher.fetch_blocking(&request);
+        self.fetched_jwks(&request, response, stale, opts)
+    }
+
+    /// Blocking [`JwtVerifier::resolve_oidc_jwks_url`].
+    pub fn resolve_oidc_jwks_url_blocking(
+        &self,
+        issuer: &str,
+        max_age_seconds: u64,
+    ) -> Result<String> {
+        let discovery = discovery_url(issuer);
+        if let Some(jwks_uri) = self.cached_discovery(&discovery) {
+            return Ok(jwks_uri);
+        }
+        let request = FetchRequest {
+            url: discovery,
+            if_none_match: None,
+        };
+        let response = self.fetcher.fetch_blocking(&request)?;
+        self.fetched_discovery(&request.url, response, max_age_seconds)
+    }
+}
 
-        #[cfg(feature = "cache")]
-        {
-            static DISCOVERY_CACHE: Lazy<DashMap<String, (u128, String)>> = Lazy::new(DashMap::new);
-            let now_ms = now_epoch_ms();
-            if let Some(cached) = DISCOVERY_CACHE.get(&discovery) {
-                if cached.value().0 > now_ms {
-                    return Ok(cached.value().1.clone());
-                }
+/// Turning fetch responses into cache updates, shared by the async and blocking paths.
+impl JwtVerifier {
+    fn fetched_jwks(
+        &self,
+        request: &FetchRequest,
+        response: Result<FetchResponse>,
+        stale: Option<JwksSet>,
+        opts: &VerifyOptions,
+    ) -> Result<JwksSet> {
+        let fetched = response.and_then(|response| match response {
+            FetchResponse::Body {
+                body,
+                cache_control,
+                etag,
+            } => Ok((serde_json::from_str(&body)?, cache_control, etag)),
+            FetchResponse::NotModified { cache_control } => stale
+                .clone()
+                .map(|set| (set, cache_control, request.if_none_match.clone()))
+                .ok_or_else(|| {
+                    Error::Jwks(format!("{} answered 304 with nothing cached", request.url))
+                }),
+        });
+        match fetched {
+            Ok((set, cache_control, etag)) => {
+                self.store_jwks(&request.url, &set, cache_control, etag.as_deref(), opts);
+                Ok(set)
             }
-            let (doc, max_age) = fetch_json_with_cache_control(&discovery).await?;
-            let jwks_uri = doc
-                .get("jwks_uri")
-                .and_then(|v| v.as_str())
-                .ok_or_else(|| Error::Jwks("OIDC discovery missing jwks_uri".to_string()))?;
-            let ttl = std::cmp::min(max_age, max_age_seconds);
-            DISCOVERY_CACHE.insert(
-                discovery,
-                (now_ms + (ttl as u128 * 1000), jwks_uri.to_string()),
-            );
-            return Ok(jwks_uri.to_string());
+            Err(e) => self.stale_or(&request.url, stale, e),
         }
+    }
 
-        #[cfg(not(feature = "cache"))]
-        {
-            let (doc, _max_age) = fetch_json_with_cache_control(&discovery).await?;
-            let jwks_uri = doc
-                .get("jwks_uri")
-                .and_then(|v| v.as_str())
-                .ok_or_else(|| Error::Jwks("OIDC discovery missing jwks_uri".to_string()))?;
-            Ok(jwks_uri.to_string())
+    fn fetched_discovery(
+        &self,
+        discovery: &str,
+        response: FetchResponse,
+        max_age_seconds: u64,
+    ) -> Result<String> {
+        let FetchResponse::Body {
+            body,
+            cache_control,
+            ..
+        } = response
+        else {
+            return Err(Error::Jwks(format!(
+                "{discovery} answered 304 to an unconditional request"
+            )));
+        };
+        let jwks_uri = jwks_uri_from_discovery(&serde_json::from_str(&body)?)?;
+        if let Some(ttl) = cache_control.ttl_seconds(DEFAULT_TTL_SECONDS) {
+            self.store_discovery(discovery, &jwks_uri, ttl.min(max_age_seconds));
         }
+        Ok(jwks_uri)
+    }
+}
+
+/// Decode the header and apply the algorithm allow-list and `require_kid`.
+fn check_header(token: &str, opts: &VerifyOptions) -> Result<Header> {
+    let header = jsonwebtoken::decode_header(token)
+        .map_err(|e| Error::InvalidJwt(format!("failed to decode header: {e}")))?;
+
+    if !opts.allowed_algs.contains(&header.alg) {
+        return Err(Error::UnsupportedAlg(header.alg));
+    }
+
+    if opts.require_kid && header.kid.as_deref().unwrap_or("").is_empty() {
+        return Err(Error::InvalidJwt("missing kid".to_string()));
     }
+
+    Ok(header)
+}
+
+/// Cache plumbing shared by the async and blocking paths; without the `cache`
+/// feature every lookup misses and nothing is stored.
+#[cfg(feature = "cache")]
+impl JwtVerifier {
+    /// Cached keys for `url`, or the stale keys to fall back on plus the request to send.
+    fn cached_jwks(
+        &self,
+        url: &str,
+        kid: Option<&str>,
+    ) -> std::result::Result<JwksSet, (Option<JwksSet>, FetchRequest)> {
+        match self.cache.lookup(url, kid) {
+            Lookup::Hit(set) => Ok(set),
+            Lookup::Fetch { stale, etag } => Err((
+                stale,
+                FetchRequest {
+                    url: url.to_string(),
+                    if_none_match: etag,
+                },
+            )),
+        }
+    }
+
+    fn store_jwks(
+        &self,
+        url: &str,
+        set: &JwksSet,
+        cache_control: CacheControl,
+        etag: Option<&str>,
+        opts: &VerifyOptions,
+    ) {
+        match cache_control.ttl_seconds(DEFAULT_TTL_SECONDS) {
+            Some(ttl) => {
+                let ttl = std::cmp::min(ttl, opts.max_jwks_age_seconds);
+                self.cache.store(url, set, etag, ttl);
+            }
+            None => self.cache.remove(url),
+        }
+    }
+
+    fn stale_or(&self, url: &str, stale: Option<JwksSet>, err: Error) -> Result<JwksSet> {
+        self.cache.fetch_failed(url, stale).ok_or(err)
+    }
+
+    fn cached_discovery(&self, discovery: &str) -> Option<String> {
+        self.cache.discovery(discovery)
+    }
+
+    fn store_discovery(&self, discovery: &str, jwks_uri: &str, ttl: u64) {
+        self.cache.store_discovery(discovery, jwks_uri, ttl);
+    }
+}
+
+#[cfg(not(feature = "cache"))]
+#[allow(clippy::unused_self)] // same signatures as the cached implementation
+impl JwtVerifier {
+    fn cached_jwks(
+        &self,
+        url: &str,
+        _kid: Option<&str>,
+    ) -> std::result::Result<JwksSet, (Option<JwksSet>, FetchRequest)> {
+        Err((
+            None,
+            FetchRequest {
+                url: url.to_string(),
+                if_none_match: None,
+            },
+        ))
+    }
+
+    fn store_jwks(
+        &self,
+        _url: &str,
+        _set: &JwksSet,
+        _cache_control: CacheControl,
+        _etag: Option<&str>,
+        _opts: &VerifyOptions,
+    ) {
+    }
+
+    fn stale_or(&self, _url: &str, _stale: Option<JwksSet>, err: Error) -> Result<JwksSet> {
+        Err(err)
+    }
+
+    fn cached_discovery(&self, _discovery: &str) -> Option<String> {
+        None
+    }
+
+    fn store_discovery(&self, _discovery: &str, _jwks_uri: &str, _ttl: u64) {}
+}
+
+fn discovery_url(issuer: &str) -> String {
+    format!(
+        "{}/.well-known/openid-configuration",
+        issuer.trim_end_matches('/')
+    )
+}
+
+fn jwks_uri_from_discovery(doc: &Value) -> Result<String> {
+    doc.get("jwks_uri")
+        .and_then(|v| v.as_str())
+        .map(str::to_string)
+        .ok_or_else(|| Error::Jwks("OIDC discovery missing jwks_uri".to_string()))
 }
 
 fn verify_against_jwks(
@@ -314,6 +556,9 @@ fn verify_against_jwks(
                         claims: data.claims,
                     };
                     validate_issuer_audience(&verified, opts)?;
+                    for validator in &opts.validators {
+                        validator.validate(&verified, opts.leeway_seconds)?;
+                    }
                     return Ok(verified);
                 }
                 Err(e) => {
@@ -324,9 +569,14 @@ fn verify_against_jwks(
     }
 
     if let Some(e) = last_err {
-        // If we got here, we at least tried keys.
-        // Map signature/claim errors to a clearer message.
-        return Err(Error::Validation(format!("{e}")));
+        // If we got here, we at least tried keys. Time-based claim failures are
+        // reported against their claim; bad signatures and the like stay as-is.
+        use jsonwebtoken::errors::ErrorKind;
+        return Err(match e.kind() {
+            ErrorKind::ExpiredSignature => Error::validation("exp", "token has expired"),
+            ErrorKind::ImmatureSignature => Error::validation("nbf", "token is not valid yet"),
+            _ => Error::Jwt(e),
+        });
     }
 
     Err(Error::NoMatchingKey)
@@ -357,8 +607,8 @@ fn decoding_key_from_jwk(jwk: &Jwk) -> Result<DecodingKey> {
             Ok(DecodingKey::from_ec_components(x, y)?)
         }
         "OKP" => {
-            // jsonwebtoken supports EdDSA in recent versions. It expects a DER-encoded key.
-            // Many providers publish Ed25519 as OKP + x (public key bytes base64url).
+            // Providers publish Ed25519 as OKP + x (public key bytes base64url); the
+            // rust_crypto backend verifies against those raw bytes.
             let crv = jwk.crv.as_deref().unwrap_or("");
             if crv != "Ed25519" {
                 return Err(Error::Jwks(format!("unsupported OKP curve: {crv}")));
@@ -370,7 +620,10 @@ fn decoding_key_from_jwk(jwk: &Jwk) -> Result<DecodingKey> {
             let pubkey = base64::engine::general_purpose::URL_SAFE_NO_PAD
                 .decode(x)
                 .map_err(|e| Error::Jwks(format!("invalid okp x: {e}")))?;
-            Ok(DecodingKey::from_ed_der(&ed25519_spki_der(&pubkey)))
+            if pubkey.len() != 32 {
+                return Err(Error::Jwks("OKP JWK x must be 32 bytes".to_string()));
+            }
+            Ok(DecodingKey::from_ed_components(x)?)
         }
         other => Err(Error::Jwks(format!("unsupported kty: {other}"))),
     }
@@ -380,18 +633,19 @@ fn validate_issuer_audience(verified: &VerifiedJwt, opts: &VerifyOptions) -> Res
     if let Some(expected_iss) = &opts.issuer {
         let iss = verified
             .iss()
-            .ok_or_else(|| Error::Validation("missing iss".to_string()))?;
+            .ok_or_else(|| Error::validation("iss", "missing"))?;
         if iss != expected_iss {
-            return Err(Error::Validation(format!(
-                "issuer mismatch: expected {expected_iss}, got {iss}"
-            )));
+            return Err(Error::validation(
+                "iss",
+                format!("expected {expected_iss}, got {iss}"),
+            ));
         }
     }
 
     if let Some(expected_aud) = &opts.audience {
         let aud = verified
             .aud()
-            .ok_or_else(|| Error::Validation("missing aud".to_string()))?;
+            .ok_or_else(|| Error::validation("aud", "missing"))?;
         let ok = match aud {
             Value::String(s) => s == expected_aud,
             Value::Array(arr) => arr
@@ -400,118 +654,286 @@ fn validate_issuer_audience(verified: &VerifiedJwt, opts: &VerifyOptions) -> Res
             _ => false,
         };
         if !ok {
-            return Err(Error::Validation(format!(
-                "audience mismatch: expected {expected_aud}"
-            )));
+            return Err(Error::validation("aud", format!("expected {expected_aud}")));
         }
     }
 
     Ok(())
 }
 
-fn ed25519_spki_der(pubkey32: &[u8]) -> Vec<u8> {
-    // SubjectPublicKeyInfo for Ed25519:
-    // SEQUENCE {
-    //   SEQUENCE { OID 1.3.101.112 }
-    //   BIT STRING (pubkey)
-    // }
-    // This is a tiny DER builder just for this structure.
-
-    // OID 1.3.101.112 DER: 06 03 2B 65 70
-    let mut alg_id: Vec<u8> = vec![0x30, 0x05, 0x06, 0x03, 0x2B, 0x65, 0x70];
-
-    // BIT STRING: 03 21 00 <32 bytes>
-    let mut bit_string: Vec<u8> = vec![0x03, 0x21, 0x00];
-    bit_string.extend_from_slice(&pubkey32[..32]);
-
-    // Outer SEQUENCE length = alg_id + bit_string
-    let len = alg_id.len() + bit_string.len();
-    let mut out: Vec<u8> = vec![0x30, len as u8];
-    out.append(&mut alg_id);
-    out.append(&mut bit_string);
-    out
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "cache")]
+    use crate::fetch::MockFetcher;
+    use crate::fetch::StaticFetcher;
+
+    /// Drive `future` to completion. The test fetchers answer without waiting,
+    /// so no executor is needed.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        use std::task::{Context, Poll, Waker};
+
+        let mut future = std::pin::pin!(future);
+        match future
+            .as_mut()
+            .poll(&mut Context::from_waker(Waker::noop()))
+        {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("test fetchers answer immediately"),
+        }
+    }
 
-fn now_epoch_ms() -> u128 {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or(Duration::from_secs(0))
-        .as_millis()
-}
+    /// An `EdDSA` token with `kid` `k1` over `claims` (plus `exp`), and the JWKS to verify it.
+    fn signed(claims: Value) -> (String, JwksSet) {
+        use ed25519_dalek::SigningKey;
+        use jsonwebtoken::{EncodingKey, encode};
+
+        let seed = [3u8; 32];
+        // PKCS#8 v1 wrapper for an Ed25519 seed.
+        let mut pkcs8 = vec![
+            0x30, 0x2e, 0x02, 0x01, 0x00, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x04, 0x22,
+            0x04, 0x20,
+        ];
+        pkcs8.extend_from_slice(&seed);
+        let public = SigningKey::from_bytes(&seed).verifying_key();
+
+        let mut header = Header::new(Algorithm::EdDSA);
+        header.kid = Some("k1".to_string());
+        let exp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            + 600;
+        let mut claims = claims;
+        claims["exp"] = exp.into();
+        let token = encode(&header, &claims, &EncodingKey::from_ed_der(&pkcs8)).unwrap();
+
+        let jwks: JwksSet = serde_json::from_value(serde_json::json!({
+            "keys": [{
+                "kty": "OKP",
+                "crv": "Ed25519",
+                "kid": "k1",
+                "x": base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(public.as_bytes()),
+            }]
+        }))
+        .unwrap();
+        (token, jwks)
+    }
 
-#[cfg(feature = "fetch-reqwest")]
-async fn fetch_jwks_url(url: &str) -> Result<(JwksSet, u64)> {
-    let (json, max_age) = fetch_json_string_with_cache_control(url).await?;
-    let set: JwksSet = serde_json::from_str(&json)?;
-    Ok((set, max_age))
-}
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn blocking_verifier_checks_signature_and_issuer() {
+        let (token, jwks) =
+            signed(serde_json::json!({ "sub": "user-1", "iss": "https://issuer.example" }));
+        let opts = VerifyOptions {
+            issuer: Some("https://issuer.example".to_string()),
+            ..Default::default()
+        };
 
-#[cfg(not(feature = "fetch-reqwest"))]
-async fn fetch_jwks_url(_url: &str) -> Result<(JwksSet, u64)> {
-    Err(Error::Jwks(
-        "JwksSource::Url requires the fetch-reqwest feature (or provide JwksSource::Json/Set)"
-            .to_string(),
-    ))
-}
+        let verifier = JwtVerifier::default();
+        let jwt = verifier
+            .verify_with_source_blocking(&token, JwksSource::Set(jwks.clone()), opts.clone())
+            .unwrap();
+        assert_eq!(jwt.sub(), Some("user-1"));
 
-#[cfg(feature = "fetch-reqwest")]
-async fn fetch_json_with_cache_control(url: &str) -> Result<(Value, u64)> {
-    let (json, max_age) = fetch_json_string_with_cache_control(url).await?;
-    Ok((serde_json::from_str(&json)?, max_age))
-}
+        let wrong_issuer = VerifyOptions {
+            issuer: Some("https://other.example".to_string()),
+            ..opts
+        };
+        assert!(
+            verifier
+                .verify_with_source_blocking(&token, JwksSource::Set(jwks), wrong_issuer)
+                .is_err()
+        );
+    }
 
-#[cfg(not(feature = "fetch-reqwest"))]
-async fn fetch_json_with_cache_control(_url: &str) -> Result<(Value, u64)> {
-    Err(Error::Jwks(
-        "fetching discovery requires the fetch-reqwest feature".to_string(),
-    ))
-}
+    #[cfg(all(feature = "cache", feature = "blocking"))]
+    #[test]
+    fn fetcher_revalidates_with_etag_and_honours_no_store() {
+        use crate::cache::{JwksCache, JwksCacheConfig};
+
+        let (token, jwks) = signed(serde_json::json!({ "sub": "user-1" }));
+        let body = serde_json::to_string(&jwks).unwrap();
+        let mock = Arc::new(MockFetcher::new());
+        let verifier = JwtVerifier::default()
+            .with_cache(JwksCache::new(JwksCacheConfig {
+                min_refresh_interval_seconds: 0,
+                ..Default::default()
+            }))
+            .with_fetcher(Arc::clone(&mock));
+        let opts = VerifyOptions {
+            jwks_url: "https://issuer.example/jwks".to_string(),
+            ..Default::default()
+        };
+        let verify = || verifier.verify_with_jwks_url_blocking(&token, opts.clone());
+
+        // no-cache: stored with its ETag, but revalidated on every use.
+        mock.push(FetchResponse::Body {
+            body: body.clone(),
+            cache_control: CacheControl::parse("no-cache"),
+            etag: Some("\"v1\"".to_string()),
+        });
+        verify().unwrap();
+        mock.push(FetchResponse::NotModified {
+            cache_control: CacheControl::parse("no-cache"),
+        });
+        verify().unwrap();
+        // Issuer down: the revalidated keys are served stale.
+        mock.push_error("connection refused");
+        verify().unwrap();
+
+        let requests = mock.requests();
+        assert_eq!(requests[0].if_none_match, None);
+        assert_eq!(requests[1].if_none_match.as_deref(), Some("\"v1\""));
+        assert_eq!(requests[2].if_none_match.as_deref(), Some("\"v1\""));
+
+        // no-store: nothing is kept, so the next failure has nothing to fall back on.
+        verifier.cache().clear();
+        mock.push(FetchResponse::Body {
+            body,
+            cache_control: CacheControl::parse("no-store"),
+            etag: Some("\"v2\"".to_string()),
+        });
+        verify().unwrap();
+        mock.push_error("connection refused");
+        assert!(verify().is_err());
+        assert_eq!(mock.requests()[4].if_none_match, None);
+    }
 
-#[cfg(feature = "fetch-reqwest")]
-async fn fetch_json_string_with_cache_control(url: &str) -> Result<(String, u64)> {
-    use reqwest::header;
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn typed_claims_and_validators() {
+        #[derive(Deserialize)]
+        struct Claims {
+            sub: String,
+            role: String,
+        }
 
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(10))
-        .build()?;
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)] // only deserialized
+        struct WithEmail {
+            email: String,
+        }
 
-    let resp = client.get(url).send().await?;
-    if !resp.status().is_success() {
-        return Err(Error::Jwks(format!("fetch failed: {}", resp.status())));
+        let (token, jwks) = signed(serde_json::json!({
+            "sub": "user-1",
+            "role": "authenticated",
+            "aud": "authenticated",
+        }));
+        let verifier = JwtVerifier::default()
+            .with_fetcher(StaticFetcher::new(serde_json::to_string(&jwks).unwrap()));
+        let opts = VerifyOptions {
+            jwks_url: "https://issuer.example/jwks".to_string(),
+            audience: Some("authenticated".to_string()),
+            validators: vec![
+                ClaimValidator::required("sub"),
+                ClaimValidator::equals("role", "authenticated"),
+            ],
+            ..Default::default()
+        };
+
+        let claims: Claims = verifier.verify_as_blocking(&token, opts.clone()).unwrap();
+        assert_eq!(
+            (claims.sub.as_str(), claims.role.as_str()),
+            ("user-1", "authenticated")
+        );
+
+        let failing_claim =
+            |opts: VerifyOptions| match verifier.verify_with_jwks_url_blocking(&token, opts) {
+                Err(Error::Validation { claim, .. }) => claim,
+                other => panic!("expected a validation error, got {other:?}"),
+            };
+        let wrong_aud = VerifyOptions {
+            audience: Some("service".to_string()),
+            ..opts.clone()
+        };
+        assert_eq!(failing_claim(wrong_aud), "aud");
+        let mut needs_aal2 = opts.clone();
+        needs_aal2
+            .validators
+            .push(ClaimValidator::equals("aal", "aal2"));
+        assert_eq!(failing_claim(needs_aal2), "aal");
+
+        assert!(matches!(
+            verifier.verify_as_blocking::<WithEmail>(&token, opts),
+            Err(Error::Validation { claim, .. }) if claim == "claims"
+        ));
     }
 
-    let max_age = resp
-        .headers()
-        .get(header::CACHE_CONTROL)
-        .and_then(|h| h.to_str().ok())
-        .and_then(parse_cache_control_max_age)
-        .unwrap_or(300);
+    #[test]
+    fn async_verifier_fetches_keys_and_checks_claims() {
+        #[derive(Deserialize)]
+        struct Claims {
+            sub: String,
+        }
 
-    let text = resp.text().await?;
-    Ok((text, max_age))
-}
+        let (token, jwks) = signed(serde_json::json!({
+            "sub": "user-1",
+            "aud": "authenticated",
+        }));
+        let verifier = JwtVerifier::default()
+            .with_fetcher(StaticFetcher::new(serde_json::to_string(&jwks).unwrap()));
+        let opts = VerifyOptions {
+            jwks_url: "https://issuer.example/jwks".to_string(),
+            audience: Some("authenticated".to_string()),
+            ..Default::default()
+        };
 
-fn parse_cache_control_max_age(cc: &str) -> Option<u64> {
-    // Very small parser: look for max-age=NNN
-    for part in cc.split(',') {
-        let p = part.trim();
-        if let Some(rest) = p.strip_prefix("max-age=") {
-            if let Ok(n) = rest.trim().parse::<u64>() {
-                return Some(n);
-            }
-        }
-    }
-    None
-}
+        let jwt = block_on(verifier.verify_with_jwks_url(&token, opts.clone())).unwrap();
+        assert_eq!(jwt.sub(), Some("user-1"));
+        let claims: Claims = block_on(verifier.verify_as(&token, opts.clone())).unwrap();
+        assert_eq!(claims.sub, "user-1");
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        let wrong_aud = VerifyOptions {
+            audience: Some("service".to_string()),
+            ..opts
+        };
+        assert!(matches!(
+            block_on(verifier.verify_with_jwks_url(&token, wrong_aud)),
+            Err(Error::Validation { claim, .. }) if claim == "aud"
+        ));
+    }
 
+    #[cfg(feature = "cache")]
     #[test]
-    fn cache_control_parser() {
-        assert_eq!(parse_cache_control_max_age("public, max-age=60"), Some(60));
-        assert_eq!(parse_cache_control_max_age("max-age=0"), Some(0));
-        assert_eq!(parse_cache_control_max_age("no-store"), None);
+    fn async_verifier_caches_keys_and_serves_them_stale() {
+        use crate::cache::{JwksCache, JwksCacheConfig};
+
+        let (token, jwks) = signed(serde_json::json!({ "sub": "user-1" }));
+        let mock = Arc::new(MockFetcher::new());
+        let verifier = JwtVerifier::default()
+            .with_cache(JwksCache::new(JwksCacheConfig {
+                min_refresh_interval_seconds: 0,
+                ..Default::default()
+            }))
+            .with_fetcher(Arc::clone(&mock));
+        let opts = VerifyOptions {
+            jwks_url: "https://issuer.example/jwks".to_string(),
+            ..Default::default()
+        };
+        let verify = || block_on(verifier.verify_with_jwks_url(&token, opts.clone()));
+
+        let body = serde_json::to_string(&jwks).unwrap();
+        mock.push(FetchResponse::Body {
+            body: body.clone(),
+            cache_control: CacheControl::parse("max-age=60"),
+            etag: None,
+        });
+        verify().unwrap();
+        // Fresh keys are served from the cache without another fetch.
+        verify().unwrap();
+        assert_eq!(mock.requests().len(), 1);
+
+        // no-cache keys are refetched on every use; an issuer outage falls back to them.
+        verifier.cache().clear();
+        mock.push(FetchResponse::Body {
+            body,
+            cache_control: CacheControl::parse("no-cache"),
+            etag: None,
+        });
+        verify().unwrap();
+        mock.push_error("connection refused");
+        verify().unwrap();
+        assert_eq!(mock.requests().len(), 3);
     }
 }
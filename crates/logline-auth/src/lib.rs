@@ -1,14 +1,18 @@
 //! logline-auth
 //!
 //! Authentication helpers for Logline runtime services and clients.
-//! It focuses on three recurring problems:
+//! It focuses on four recurring problems:
 //!
-//! - **Verifying JWTs using a JWKS** (kid selection, algorithm allow-list, iss/aud/leeway checks)
+//! - **Verifying JWTs using a JWKS** (kid selection, algorithm allow-list, iss/aud/leeway checks,
+//!   pluggable claim validators, typed claims)
 //! - **Deriving a tenant** from request host or token claims
 //! - **Building secure cookies** (`__Host-` semantics, SameSite, etc.)
+//! - **Verifying CLI passkey logins** (Ed25519 signatures over server-issued nonces)
 //!
-//! The core API is `JwtVerifier`, which can verify a token against a JWKS URL (with optional
-//! in-memory caching) or against a JWKS you provide directly.
+//! The core API is `JwtVerifier`, which can verify a token against a JWKS URL (fetched
+//! through a pluggable `JwksFetcher`, with an optional per-verifier `JwksCache`) or
+//! against a JWKS you provide directly. With the `blocking` feature it
+//! also offers `*_blocking` methods for callers without an async runtime.
 //!
 //! ## Quick start
 //! ```no_run
@@ -33,12 +37,29 @@
 
 #![forbid(unsafe_code)]
 
+#[cfg(feature = "cache")]
+mod cache;
+mod claims;
 mod cookie;
 mod error;
+mod fetch;
 mod jwt;
+mod passkey;
 mod tenant;
 
+#[cfg(feature = "cache")]
+pub use cache::{CacheStats, Clock, JwksCache, JwksCacheConfig, Lookup, SystemClock};
+pub use claims::{ClaimCheck, ClaimValidator};
 pub use cookie::{CookieOptions, SameSite, build_clear_cookie, build_set_cookie};
 pub use error::{Error, Result};
-pub use jwt::{JwksSource, JwtVerifier, VerifiedJwt, VerifyOptions};
+#[cfg(feature = "fetch-reqwest")]
+pub use fetch::ReqwestFetcher;
+pub use fetch::{
+    CacheControl, FetchFuture, FetchRequest, FetchResponse, FileFetcher, JwksFetcher, MockFetcher,
+    StaticFetcher,
+};
+pub use jwt::{Jwk, JwksSet, JwksSource, JwtVerifier, VerifiedJwt, VerifyOptions};
+pub use passkey::{
+    PASSKEY_ALGORITHM, PasskeyChallenge, verify_passkey_login, verify_passkey_signature,
+};
 pub use tenant::{TenantConfig, TenantDecision, TenantSource, derive_tenant};
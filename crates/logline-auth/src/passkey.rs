@@ -0,0 +1,114 @@
+//! CLI passkey login helpers.
+//!
+//! `logline auth login --passkey` asks the server for a single-use nonce
+//! (`cli_passkey_challenge`), signs [`PasskeyChallenge::signing_message`] with
+//! the device's Ed25519 key, and sends the signature back. The verifier (the
+//! `cli-passkey-verify` edge function) redeems the nonce
+//! (`redeem_cli_passkey_challenge`) and checks the signature against the
+//! registered public key, as [`verify_passkey_login`] does, before it issues
+//! a session.
+
+use crate::{Error, Result};
+use ed25519_dalek::{Signature, VerifyingKey};
+use serde::{Deserialize, Serialize};
+
+/// Only algorithm `cli_passkey_credentials` accepts today.
+pub const PASSKEY_ALGORITHM: &str = "ed25519";
+
+/// Domain separator so a login signature is never valid for anything else.
+const SIGNING_CONTEXT: &str = "logline-cli-passkey-login/v2";
+
+/// A challenge as issued by `cli_passkey_challenge`. The nonce alone identifies it,
+/// so the anonymous caller learns nothing about the credential or its owner.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PasskeyChallenge {
+    /// Server-generated random nonce, redeemed exactly once.
+    pub nonce: String,
+    /// Expiry as reported by the server, if any.
+    #[serde(default)]
+    pub expires_at: Option<String>,
+}
+
+impl PasskeyChallenge {
+    /// Bytes the client signs: context and nonce, one per line.
+    pub fn signing_message(&self) -> Vec<u8> {
+        format!("{SIGNING_CONTEXT}\n{}", self.nonce).into_bytes()
+    }
+}
+
+/// Verify a hex Ed25519 `signature` over `message` with a hex `public_key`.
+///
+/// Uses strict verification, so malleable or small-order signatures are rejected.
+pub fn verify_passkey_signature(public_key: &str, message: &[u8], signature: &str) -> Result<()> {
+    let key: [u8; 32] = decode_hex(public_key, "public key")?;
+    let key = VerifyingKey::from_bytes(&key)
+        .map_err(|e| Error::Passkey(format!("invalid public key: {e}")))?;
+    let signature = Signature::from_bytes(&decode_hex(signature, "signature")?);
+    key.verify_strict(message, &signature)
+        .map_err(|_| Error::Passkey("signature does not match the registered key".to_string()))
+}
+
+/// Verify a login `signature` for `challenge` against the registered `public_key`.
+///
+/// Expiry and single use are enforced by the server when it redeems the challenge.
+pub fn verify_passkey_login(
+    challenge: &PasskeyChallenge,
+    public_key: &str,
+    signature: &str,
+) -> Result<()> {
+    verify_passkey_signature(public_key, &challenge.signing_message(), signature)
+}
+
+fn decode_hex<const N: usize>(value: &str, what: &str) -> Result<[u8; N]> {
+    hex::decode(value.trim())
+        .map_err(|e| Error::Passkey(format!("{what} is not hex: {e}")))?
+        .try_into()
+        .map_err(|_| Error::Passkey(format!("{what} must be {N} bytes")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    fn challenge() -> PasskeyChallenge {
+        PasskeyChallenge {
+            nonce: "0f".repeat(32),
+            expires_at: None,
+        }
+    }
+
+    #[test]
+    fn signed_challenge_verifies() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let public_key = hex::encode(key.verifying_key().as_bytes());
+        let ch = challenge();
+        // The edge function rebuilds this message byte for byte.
+        assert_eq!(
+            ch.signing_message(),
+            format!("logline-cli-passkey-login/v2\n{}", "0f".repeat(32)).into_bytes()
+        );
+        let signature = hex::encode(key.sign(&ch.signing_message()).to_bytes());
+
+        verify_passkey_login(&ch, &public_key, &signature).unwrap();
+    }
+
+    #[test]
+    fn signature_is_bound_to_the_nonce_and_key() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let ch = challenge();
+        let signature = hex::encode(key.sign(&ch.signing_message()).to_bytes());
+
+        let other = PasskeyChallenge {
+            nonce: "1e".repeat(32),
+            ..challenge()
+        };
+        let public_key = hex::encode(key.verifying_key().as_bytes());
+        assert!(verify_passkey_login(&other, &public_key, &signature).is_err());
+
+        let stranger = SigningKey::from_bytes(&[9; 32]);
+        let stranger_key = hex::encode(stranger.verifying_key().as_bytes());
+        assert!(verify_passkey_login(&ch, &stranger_key, &signature).is_err());
+        assert!(verify_passkey_login(&ch, "zz", &signature).is_err());
+    }
+}
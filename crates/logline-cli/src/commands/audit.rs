@@ -0,0 +1,182 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::bail;
+use clap::Subcommand;
+use logline_api::LoglineError;
+use logline_core::{AuditConfig, AuditSinkKind};
+use logline_runtime::{
+    AuditEntry, AuditRecord, AuditSink, LocalAuditLog, LoglineRuntime, prune_audit_log,
+    verify_audit_log,
+};
+
+use crate::supabase::{SupabaseClient, get_valid_token, load_auth};
+
+#[derive(Debug, Subcommand)]
+pub enum AuditCommands {
+    /// Verify the hash chain of the local audit log
+    Verify,
+    /// Drop records older than `runtime.audit.retention_days`
+    Prune {
+        /// Override the configured retention window
+        #[arg(long)]
+        retention_days: Option<u32>,
+    },
+}
+
+pub fn audit_log_path(cfg_dir: &Path) -> PathBuf {
+    cfg_dir.join("audit.log")
+}
+
+/// Attach the configured audit sink to the runtime.
+pub fn attach(
+    runtime: LoglineRuntime,
+    cfg_dir: &Path,
+    cfg: &AuditConfig,
+) -> anyhow::Result<LoglineRuntime> {
+    if !cfg.enabled {
+        return Ok(runtime);
+    }
+
+    let local = LocalAuditLog::new(audit_log_path(cfg_dir));
+    let auth = load_auth();
+    let principal = auth
+        .as_ref()
+        .and_then(|a| a.email.clone().or_else(|| a.user_id.clone()))
+        .or_else(|| std::env::var("USER").ok().map(|u| format!("local:{u}")))
+        .unwrap_or_else(|| "anonymous".to_string());
+
+    let sink: Box<dyn AuditSink> = match cfg.sink {
+        AuditSinkKind::Local => Box::new(local),
+        AuditSinkKind::Remote => {
+            let user_id = auth.and_then(|a| a.user_id).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "runtime.audit.sink = \"remote\" requires a logged-in user.\n\
+                     Run: logline auth login --passkey"
+                )
+            })?;
+            let config = crate::context::supabase_config()?;
+            let client = SupabaseClient::new(config)?;
+            // Fail at startup rather than on the first audited action.
+            get_valid_token(&client)?;
+            Box::new(RemoteAuditSink {
+                local,
+                client,
+                user_id,
+            })
+        }
+    };
+
+    Ok(runtime.with_audit(sink, principal))
+}
+
+/// Chains locally, then mirrors each record into `protected_action_audit`.
+///
+/// The token is resolved per record so long-lived processes (`schedule run`,
+/// `ui`) keep auditing after the session they started with expires.
+struct RemoteAuditSink {
+    local: LocalAuditLog,
+    client: SupabaseClient,
+    user_id: String,
+}
+
+impl AuditSink for RemoteAuditSink {
+    fn append(&self, entry: AuditEntry) -> Result<AuditRecord, LoglineError> {
+        let record = self.local.append(entry)?;
+        let e = &record.entry;
+
+        let summary = match &e.intent_type {
+            Some(intent) => format!("{intent} on {}/{}", e.profile, e.backend),
+            None => format!(
+                "{} {} on {}/{}",
+                e.action,
+                e.run_id.as_deref().unwrap_or("?"),
+                e.profile,
+                e.backend
+            ),
+        };
+        let execution_result = match &e.error {
+            Some(err) => format!("{}: {err}", e.result),
+            None => e.result.clone(),
+        };
+
+        let row = serde_json::json!({
+            "actor_user_id": self.user_id,
+            "action_type": format!("runtime.{}", e.action),
+            "payload_summary": summary,
+            "decision": "allowed",
+            "execution_result": execution_result,
+            "device_info": {
+                "principal": e.principal,
+                "profile": e.profile,
+                "backend": e.backend,
+                "run_id": e.run_id,
+                "duration_ms": e.duration_ms,
+                "seq": record.seq,
+                "prev_hash": record.prev_hash,
+                "hash": record.hash,
+            },
+        });
+
+        // Callers may insert their own rows but only founders may select
+        // them, so the insert must not ask for the row back.
+        get_valid_token(&self.client)
+            .map_err(|err| LoglineError::Auth(err.to_string()))
+            .and_then(|token| {
+                self.client
+                    .postgrest_insert_minimal("protected_action_audit", &row, &token)
+            })
+            .map_err(|err| {
+                LoglineError::Connection(format!(
+                    "audit record {} stored locally but remote sink failed: {err}",
+                    record.seq
+                ))
+            })?;
+        Ok(record)
+    }
+}
+
+pub fn cmd_audit(command: &AuditCommands, cfg_dir: &Path, cfg: &AuditConfig) -> anyhow::Result<()> {
+    let path = audit_log_path(cfg_dir);
+
+    match command {
+        AuditCommands::Verify => {
+            let report = verify_audit_log(&path)?;
+            let text = if report.ok {
+                format!(
+                    "Audit chain intact: {} record(s) in {}",
+                    report.records,
+                    path.display()
+                )
+            } else {
+                format!(
+                    "Audit chain BROKEN at seq {}: {}",
+                    report.broken_at_seq.unwrap_or_default(),
+                    report.reason.as_deref().unwrap_or("?")
+                )
+            };
+            crate::pout(serde_json::to_value(&report)?, &text)?;
+            if !report.ok {
+                bail!("audit log verification failed");
+            }
+            Ok(())
+        }
+        AuditCommands::Prune { retention_days } => {
+            let days = retention_days.unwrap_or(cfg.retention_days);
+            let now_ms = i64::try_from(
+                std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis(),
+            )
+            .unwrap_or(i64::MAX);
+            let report = prune_audit_log(&path, days, now_ms)?;
+            crate::pout(
+                serde_json::to_value(&report)?,
+                &format!(
+                    "Pruned {} record(s) older than {days} day(s); {} kept",
+                    report.removed, report.kept
+                ),
+            )
+        }
+    }
+}
@@ -1,6 +1,8 @@
 use anyhow::{bail, ensure};
 use clap::Subcommand;
 
+use logline_core::{CommandClass, UnlockMethod};
+
 use crate::commands::secrets;
 
 const SESSION_KEY: &str = "logline_session";
@@ -10,15 +12,26 @@ pub struct SessionToken {
     pub session_id: String,
     pub expires_at: u64,
     pub opened_by: String,
+    /// When `auth unlock` opened the session (0 for sessions from older releases).
+    #[serde(default)]
+    pub opened_at: u64,
+    /// Last presence check: the unlock itself or a later step-up.
+    #[serde(default)]
+    pub verified_at: u64,
+    #[serde(default)]
+    pub verified_by: String,
 }
 
 #[derive(Debug, Subcommand)]
 pub enum SessionCommands {
-    /// Unlock session with Touch ID (required before any privileged command)
+    /// Unlock session with Touch ID, passphrase or passkey signature (required before any privileged command)
     Unlock {
         /// Session TTL (e.g. "5m", "30m", "2h"). Default: 30m
         #[arg(long, default_value = "30m")]
         ttl: String,
+        /// `touch_id`, `passphrase` or `signature` (default: first usable method in policy.toml)
+        #[arg(long)]
+        method: Option<UnlockMethod>,
     },
     /// Lock session immediately (revoke access)
     Lock,
@@ -26,6 +39,18 @@ pub enum SessionCommands {
     Status,
 }
 
+#[derive(Debug, Subcommand)]
+pub enum PassphraseCommands {
+    /// Set or replace the unlock passphrase (kept as an Argon2id hash in the vault)
+    Set {
+        /// Read the new passphrase from stdin instead of prompting
+        #[arg(long)]
+        from_stdin: bool,
+    },
+    /// Remove the unlock passphrase
+    Clear,
+}
+
 fn parse_ttl(ttl: &str) -> anyhow::Result<u64> {
     let s = ttl.trim().to_lowercase();
     if let Some(mins) = s.strip_suffix('m') {
@@ -52,52 +77,6 @@ fn generate_session_id() -> String {
     format!("s_{:x}", ts & 0xFFFF_FFFF)
 }
 
-fn touch_id_prompt() -> anyhow::Result<()> {
-    if !cfg!(target_os = "macos") {
-        eprint!("Press Enter to confirm identity: ");
-        let mut buf = String::new();
-        std::io::stdin().read_line(&mut buf)?;
-        return Ok(());
-    }
-
-    eprintln!("Touch ID required...");
-    let result = std::process::Command::new("swift")
-        .arg("-e")
-        .arg(
-            r#"
-import LocalAuthentication
-import Foundation
-let ctx = LAContext()
-var err: NSError?
-guard ctx.canEvaluatePolicy(.deviceOwnerAuthenticationWithBiometrics, error: &err) else {
-    fputs("biometrics unavailable: \(err?.localizedDescription ?? "unknown")\n", stderr)
-    exit(1)
-}
-let sema = DispatchSemaphore(value: 0)
-var ok = false
-ctx.evaluatePolicy(.deviceOwnerAuthenticationWithBiometrics, localizedReason: "Logline CLI — unlock session") { success, _ in
-    ok = success
-    sema.signal()
-}
-sema.wait()
-exit(ok ? 0 : 1)
-"#,
-        )
-        .output();
-
-    match result {
-        Ok(out) if out.status.success() => Ok(()),
-        Ok(_) => bail!("Touch ID authentication failed or was cancelled."),
-        Err(e) => {
-            eprintln!("Touch ID unavailable ({e}), falling back to Enter confirmation.");
-            eprint!("Press Enter to confirm identity: ");
-            let mut buf = String::new();
-            std::io::stdin().read_line(&mut buf)?;
-            Ok(())
-        }
-    }
-}
-
 fn now_secs() -> u64 {
     std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
@@ -115,60 +94,152 @@ fn save_session(token: &SessionToken) -> anyhow::Result<()> {
     secrets::store_credential(SESSION_KEY, &json)
 }
 
-fn delete_session() -> anyhow::Result<()> {
-    let entry = keyring::Entry::new("logline-cli", SESSION_KEY)
-        .map_err(|e| anyhow::anyhow!("Keychain error: {e}"))?;
-    match entry.delete_credential() {
-        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
-        Err(e) => bail!("Failed to clear session: {e}"),
-    }
+pub fn delete_session() -> anyhow::Result<()> {
+    secrets::delete_credential(SESSION_KEY)
+        .map(|_| ())
+        .map_err(|e| anyhow::anyhow!("Failed to clear session: {e}"))
 }
 
 /// Gate: call at the top of every privileged command.
-/// Returns the active session or a clear error.
+/// Returns the active session or a clear error, after applying the `policy.toml`
+/// session class of the running command (stepping up when it asks for a fresh unlock).
 pub fn require_unlocked() -> anyhow::Result<SessionToken> {
-    let session = load_session().ok_or_else(|| {
+    let mut session = load_session().ok_or_else(|| {
         anyhow::anyhow!("Session locked. Run `logline auth unlock` first.")
     })?;
     ensure!(
         session.expires_at > now_secs(),
         "Session expired. Run `logline auth unlock` to re-authenticate."
     );
+    if let Some((command, class)) = crate::unlock::command_class() {
+        let stepped_up = enforce_class(
+            &mut session,
+            command,
+            class,
+            now_secs(),
+            || load_identity().map(|i| i.auth_method),
+            crate::unlock::step_up,
+        )?;
+        if stepped_up {
+            save_session(&session)?;
+        }
+    }
     Ok(session)
 }
 
-pub fn cmd_auth_session(command: SessionCommands, json: bool) -> anyhow::Result<()> {
+/// Apply a session class to `session`; returns whether a step-up refreshed it
+/// (the caller then saves it). Login method and step-up are passed in for tests.
+fn enforce_class(
+    session: &mut SessionToken,
+    command: &str,
+    class: CommandClass<'_>,
+    now: u64,
+    login_method: impl FnOnce() -> Option<String>,
+    step_up: impl FnOnce(&[UnlockMethod]) -> anyhow::Result<UnlockMethod>,
+) -> anyhow::Result<bool> {
+    let CommandClass { rule, name, class } = class;
+    let blocked = format!("`{command}` is blocked by policy class '{name}' (policy.toml session.commands.\"{rule}\")");
+
+    if let Some(max) = class.max_session_age_minutes {
+        let age = now.saturating_sub(session.opened_at);
+        ensure!(
+            age <= max * 60,
+            "{blocked}: sessions may be at most {max}m old, this one was opened {}m ago.\n\
+             Fix: logline auth lock && logline auth unlock",
+            age / 60
+        );
+    }
+
+    if !class.auth_method.is_empty() {
+        let method = login_method().unwrap_or_else(|| "none".to_string());
+        ensure!(
+            class.auth_method.contains(&method),
+            "{blocked}: requires login via {}, current login is {method}.\n\
+             Fix: logline auth login{}",
+            class.auth_method.join(", "),
+            match class.auth_method[0].as_str() {
+                "passkey" => " --passkey",
+                "qr" => " --qr",
+                _ => "",
+            }
+        );
+    }
+
+    let verified_by = session.verified_by.parse::<UnlockMethod>().ok();
+    let method_ok = class.unlock_method.is_empty()
+        || verified_by.is_some_and(|m| class.unlock_method.contains(&m));
+    let stale = class
+        .fresh_unlock_minutes
+        .is_some_and(|fresh| now.saturating_sub(session.verified_at) > fresh * 60);
+    if method_ok && !stale {
+        return Ok(false);
+    }
+
+    let why = if method_ok {
+        format!(
+            "requires an unlock within the last {}m",
+            class.fresh_unlock_minutes.unwrap_or_default()
+        )
+    } else {
+        format!(
+            "requires an unlock with {}, last one was {}",
+            crate::unlock::join(&class.unlock_method),
+            session.verified_by
+        )
+    };
+    if crate::prompt::non_interactive_reason().is_none() {
+        eprintln!("Step-up: {blocked}: {why}.");
+    }
+    let method = step_up(&class.unlock_method)
+        .map_err(|e| anyhow::anyhow!("{blocked}: {why}.\n{e}"))?;
+    session.verified_at = now;
+    session.verified_by = method.as_str().into();
+    Ok(true)
+}
+
+pub fn cmd_auth_session(command: SessionCommands) -> anyhow::Result<()> {
     match command {
-        SessionCommands::Unlock { ttl } => {
+        SessionCommands::Unlock { ttl, method } => {
             let ttl_secs = parse_ttl(&ttl)?;
-            touch_id_prompt()?;
+            let method = crate::unlock::unlock(method)?;
 
+            let now = now_secs();
             let session = SessionToken {
                 session_id: generate_session_id(),
-                expires_at: now_secs() + ttl_secs,
-                opened_by: "touch_id".into(),
+                expires_at: now + ttl_secs,
+                opened_by: method.as_str().into(),
+                opened_at: now,
+                verified_at: now,
+                verified_by: method.as_str().into(),
             };
             save_session(&session)?;
 
             let expires_str = format_expires(session.expires_at);
-            crate::pout(
-                json,
-                serde_json::json!({
+            let presence_checked = crate::unlock::provider(method).checks_presence();
+            let mut text = format!(
+                "Session active until {expires_str} (unlocked by {method}). ID: {}",
+                session.session_id
+            );
+            if !presence_checked {
+                text.push_str(
+                    "\nNote: a passkey signature only proves the local key is readable, not that \
+                     you are present.",
+                );
+            }
+            crate::pout(serde_json::json!({
                     "ok": true,
                     "session_id": session.session_id,
                     "expires_at": session.expires_at,
                     "ttl_seconds": ttl_secs,
+                    "opened_by": session.opened_by,
+                    "presence_checked": presence_checked,
                 }),
-                &format!(
-                    "Session active until {expires_str}. ID: {}",
-                    session.session_id
-                ),
+                &text,
             )
         }
         SessionCommands::Lock => {
             delete_session()?;
             crate::pout(
-                json,
                 serde_json::json!({"ok": true}),
                 "Session locked. All privileged commands now require `logline auth unlock`.",
             )
@@ -179,30 +250,30 @@ pub fn cmd_auth_session(command: SessionCommands, json: bool) -> anyhow::Result<
                     let remaining = s.expires_at - now_secs();
                     let mins = remaining / 60;
                     let secs = remaining % 60;
-                    crate::pout(
-                        json,
-                        serde_json::json!({
+                    crate::pout(serde_json::json!({
                             "unlocked": true,
                             "session_id": s.session_id,
                             "expires_at": s.expires_at,
                             "remaining_seconds": remaining,
+                            "opened_by": s.opened_by,
+                            "opened_at": s.opened_at,
+                            "verified_at": s.verified_at,
+                            "verified_by": s.verified_by,
                         }),
                         &format!(
-                            "Unlocked — {mins}m {secs}s remaining. ID: {}",
-                            s.session_id
+                            "Unlocked by {} — {mins}m {secs}s remaining. ID: {}",
+                            s.opened_by, s.session_id
                         ),
                     )
                 }
                 Some(_) => {
                     delete_session()?;
                     crate::pout(
-                        json,
                         serde_json::json!({"unlocked": false, "reason": "expired"}),
                         "Session expired. Run `logline auth unlock` to re-authenticate.",
                     )
                 }
                 None => crate::pout(
-                    json,
                     serde_json::json!({"unlocked": false, "reason": "no_session"}),
                     "No active session. Run `logline auth unlock` first.",
                 ),
@@ -211,6 +282,56 @@ pub fn cmd_auth_session(command: SessionCommands, json: bool) -> anyhow::Result<
     }
 }
 
+pub fn cmd_passphrase(command: &PassphraseCommands) -> anyhow::Result<()> {
+    // Replacing or removing an existing passphrase is itself privileged, and so is
+    // setting the first one: it unlocks infra commands under the default policy.
+    // Setting one also needs a presence check, which a `signature` session lacks.
+    let unlocked = load_session().is_some_and(|s| s.expires_at > now_secs());
+    let session = if unlocked || crate::unlock::has_passphrase() {
+        Some(require_unlocked()?)
+    } else {
+        None
+    };
+    if matches!(command, PassphraseCommands::Set { .. }) {
+        let present = session.as_ref().is_some_and(|s| {
+            let last = if s.verified_by.is_empty() { &s.opened_by } else { &s.verified_by };
+            crate::unlock::checked_presence(last)
+        });
+        if !present {
+            crate::unlock::require_presence_for_new_passphrase()?;
+        }
+    }
+    match command {
+        PassphraseCommands::Set { from_stdin } => {
+            let passphrase = if *from_stdin {
+                crate::prompt::read_stdin("passphrase")?
+            } else {
+                let instead = "Pipe it in instead: logline auth passphrase set --from-stdin";
+                let first = crate::prompt::secret("New unlock passphrase (hidden)", instead)?;
+                let again = crate::prompt::secret("Repeat passphrase (hidden)", instead)?;
+                ensure!(first == again, "Passphrases do not match.");
+                first
+            };
+            crate::unlock::set_passphrase(&passphrase)?;
+            crate::pout(
+                serde_json::json!({"ok": true}),
+                "Unlock passphrase set. Use: logline auth unlock --method passphrase",
+            )
+        }
+        PassphraseCommands::Clear => {
+            let removed = crate::unlock::clear_passphrase()?;
+            crate::pout(
+                serde_json::json!({"ok": true, "removed": removed}),
+                if removed {
+                    "Unlock passphrase removed."
+                } else {
+                    "No unlock passphrase was set."
+                },
+            )
+        }
+    }
+}
+
 fn format_expires(epoch: u64) -> String {
     let now = now_secs();
     if epoch <= now {
@@ -235,6 +356,14 @@ pub struct AuthIdentity {
     pub auth_method: String,
     pub is_founder: bool,
     pub profile: String,
+    /// Why the access token could not be verified against the project's signing
+    /// keys; the identity then comes from `auth.json` as stored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unverified: Option<String>,
+    /// Why the token could not be checked at all: the project publishes no
+    /// signing keys. Unlike `unverified` this only warns.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unverifiable: Option<String>,
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -261,34 +390,14 @@ fn save_identity_cache(cache: &IdentityCache) -> anyhow::Result<()> {
 }
 
 fn check_founder_remote(user_id: &str) -> anyhow::Result<bool> {
-    let supabase_url = secrets::load_credential_or_env("supabase_url", "NEXT_PUBLIC_SUPABASE_URL")
-        .ok_or_else(|| anyhow::anyhow!("supabase_url not configured"))?;
-    let anon_key = secrets::load_credential_or_env("supabase_anon_key", "NEXT_PUBLIC_SUPABASE_ANON_KEY")
-        .ok_or_else(|| anyhow::anyhow!("supabase_anon_key not configured"))?;
-
-    let auth = crate::supabase::load_auth()
-        .ok_or_else(|| anyhow::anyhow!("Not logged in"))?;
-
-    let url = format!(
-        "{}/rest/v1/user_capabilities?select=capability&user_id=eq.{}&capability=eq.founder",
-        supabase_url, user_id
-    );
-
-    let client = reqwest::blocking::Client::builder()
-        .timeout(std::time::Duration::from_secs(10))
-        .build()?;
-
-    let resp = client
-        .get(&url)
-        .header("apikey", &anon_key)
-        .header("Authorization", format!("Bearer {}", auth.access_token))
-        .send()?;
-
-    if !resp.status().is_success() {
-        bail!("PostgREST query failed: {}", resp.status());
-    }
-
-    let body: serde_json::Value = resp.json()?;
+    let client = crate::supabase::SupabaseClient::new(crate::context::supabase_config()?)?;
+    let token = crate::supabase::get_valid_token(&client)?;
+
+    let body = client.postgrest_get(
+        "user_capabilities",
+        &format!("select=capability&user_id=eq.{user_id}&capability=eq.founder"),
+        &token,
+    )?;
     let is_founder = body
         .as_array()
         .is_some_and(|arr| !arr.is_empty());
@@ -325,20 +434,66 @@ fn resolve_founder_status(user_id: &str) -> bool {
 
 pub fn load_identity() -> Option<AuthIdentity> {
     let auth = crate::supabase::load_auth()?;
-    let user_id = auth.user_id.clone()?;
-    let method = auth.auth_method.unwrap_or_else(|| "unknown".into());
+    let method = auth.auth_method.clone().unwrap_or_else(|| "unknown".into());
+    let (user_id, email, unverified, unverifiable) = match verify_stored_token(&auth) {
+        Ok(Verified::Locally(jwt)) => (
+            jwt.sub()?.to_string(),
+            jwt.claim("email").and_then(|v| v.as_str()).map(str::to_string),
+            None,
+            None,
+        ),
+        Ok(Verified::ByServer { user_id, email, reason }) => (user_id, email, None, Some(reason)),
+        Err(e) => (auth.user_id.clone()?, auth.email.clone(), Some(e.to_string()), None),
+    };
     let is_founder = resolve_founder_status(&user_id);
     let profile = if is_founder { "founder" } else { "operator" };
 
     Some(AuthIdentity {
         user_id,
-        email: auth.email,
+        email,
         auth_method: method,
         is_founder,
         profile: profile.into(),
+        unverified,
+        unverifiable,
     })
 }
 
+/// Who the stored access token belongs to, and how that was established.
+enum Verified {
+    /// Checked offline against the project's signing keys.
+    Locally(Box<logline_auth::VerifiedJwt>),
+    /// The project has no signing keys, so `/auth/v1/user` vouched for the token;
+    /// the `auth.json` copy of the user id is never trusted on its own.
+    ByServer { user_id: String, email: Option<String>, reason: String },
+}
+
+/// Verify the stored access token, refreshing it first if it has expired.
+fn verify_stored_token(auth: &crate::supabase::StoredAuth) -> anyhow::Result<Verified> {
+    let client = crate::supabase::SupabaseClient::new(crate::context::supabase_config()?)?;
+    let expired = auth.expires_at.is_some_and(|exp| now_secs() >= exp.saturating_sub(30));
+    let token = if expired {
+        crate::supabase::get_valid_token(&client)?
+    } else {
+        auth.access_token.clone()
+    };
+    match crate::supabase::verify_access_token(&token) {
+        Ok(jwt) => Ok(Verified::Locally(Box::new(jwt))),
+        Err(e) if e.is::<crate::supabase::NoSigningKeys>() => {
+            let user = client.get_user(&token)?;
+            let user_id = user["id"]
+                .as_str()
+                .ok_or_else(|| anyhow::anyhow!("/auth/v1/user returned no user id"))?;
+            Ok(Verified::ByServer {
+                user_id: user_id.to_string(),
+                email: user["email"].as_str().map(str::to_string),
+                reason: e.to_string(),
+            })
+        }
+        Err(e) => Err(e),
+    }
+}
+
 pub fn require_logged_in() -> anyhow::Result<AuthIdentity> {
     load_identity().ok_or_else(|| {
         anyhow::anyhow!(
@@ -374,10 +529,104 @@ pub fn require_non_founder(identity: &AuthIdentity) -> anyhow::Result<()> {
 }
 
 /// Single uber-gate for all infra commands (deploy, cicd, db migrate).
-/// Chains: require_unlocked + require_passkey_identity + require_non_founder.
+/// Chains: `require_unlocked` + unlock method allowed by policy + `require_passkey_identity`
+/// + verified access token (or the `[auth] allow_unverified_tokens` opt-in) + `require_non_founder`.
 pub fn require_infra_identity() -> anyhow::Result<(SessionToken, AuthIdentity)> {
     let session = require_unlocked()?;
+    crate::unlock::require_infra_method(&session.opened_by)?;
     let identity = require_passkey_identity()?;
+    if let Some(reason) = &identity.unverified {
+        bail!(
+            "Infra commands need an access token verified against the project's signing keys.\n\
+             {reason}\n\
+             Fix: logline auth login --passkey"
+        );
+    }
+    if let Some(reason) = &identity.unverifiable {
+        ensure!(
+            crate::unlock::auth_policy().allow_unverified_tokens,
+            "Infra commands need an access token verified against the project's signing keys.\n\
+             {reason}\n\
+             To accept tokens the server vouches for instead, set `allow_unverified_tokens = true` \
+             under [auth] in policy.toml."
+        );
+        eprintln!("Warning: access token not verified locally (allowed by policy.toml). {reason}");
+    }
     require_non_founder(&identity)?;
     Ok((session, identity))
 }
+
+#[cfg(test)]
+mod tests {
+    use logline_core::SessionClass;
+
+    use super::*;
+
+    const NOW: u64 = 1_800_000_000;
+
+    fn session(opened_mins_ago: u64, verified_mins_ago: u64, verified_by: &str) -> SessionToken {
+        SessionToken {
+            session_id: "s_test".into(),
+            expires_at: NOW + 3600,
+            opened_by: "passphrase".into(),
+            opened_at: NOW - opened_mins_ago * 60,
+            verified_at: NOW - verified_mins_ago * 60,
+            verified_by: verified_by.into(),
+        }
+    }
+
+    fn check(
+        session: &mut SessionToken,
+        class: &SessionClass,
+        login: &str,
+        step_up: anyhow::Result<UnlockMethod>,
+    ) -> anyhow::Result<bool> {
+        let class = CommandClass { rule: "deploy", name: "critical", class };
+        let login = login.to_string();
+        enforce_class(session, "deploy", class, NOW, || Some(login), |_| step_up)
+    }
+
+    #[test]
+    fn max_age_and_login_method_are_refused_outright() {
+        let class = SessionClass {
+            max_session_age_minutes: Some(60),
+            auth_method: vec!["passkey".into()],
+            ..SessionClass::default()
+        };
+        let unused = || Err(anyhow::anyhow!("step-up must not run"));
+
+        assert!(!check(&mut session(30, 30, "passphrase"), &class, "passkey", unused()).unwrap());
+
+        let err = check(&mut session(90, 1, "passphrase"), &class, "passkey", unused()).unwrap_err();
+        assert!(err.to_string().contains("at most 60m old"), "{err}");
+
+        let err = check(&mut session(5, 5, "passphrase"), &class, "qr", unused()).unwrap_err();
+        assert!(err.to_string().contains("requires login via passkey"), "{err}");
+        assert!(err.to_string().contains("--passkey"), "{err}");
+    }
+
+    #[test]
+    fn wrong_unlock_method_or_stale_unlock_steps_up() {
+        let class = SessionClass {
+            unlock_method: vec![UnlockMethod::TouchId],
+            fresh_unlock_minutes: Some(5),
+            ..SessionClass::default()
+        };
+
+        let mut fresh = session(10, 1, "touch_id");
+        assert!(!check(&mut fresh, &class, "passkey", Err(anyhow::anyhow!("unused"))).unwrap());
+
+        let mut wrong_method = session(10, 1, "passphrase");
+        assert!(check(&mut wrong_method, &class, "passkey", Ok(UnlockMethod::TouchId)).unwrap());
+        assert_eq!((wrong_method.verified_by.as_str(), wrong_method.verified_at), ("touch_id", NOW));
+
+        let mut stale = session(30, 20, "touch_id");
+        assert!(check(&mut stale, &class, "passkey", Ok(UnlockMethod::TouchId)).unwrap());
+        assert_eq!(stale.verified_at, NOW);
+
+        let mut refused = session(30, 20, "touch_id");
+        let err = check(&mut refused, &class, "passkey", Err(anyhow::anyhow!("cancelled"))).unwrap_err();
+        assert!(err.to_string().contains("within the last 5m"), "{err}");
+        assert_eq!(refused.verified_at, NOW - 20 * 60);
+    }
+}
@@ -0,0 +1,167 @@
+use std::fmt::Write as _;
+use std::path::Path;
+
+use anyhow::bail;
+use clap::Subcommand;
+use logline_api::HealthReport;
+use logline_core::ConnectionCatalog;
+use logline_runtime::{BackendHealth, CircuitState, HealthLog, HealthSource, LoglineRuntime};
+
+use crate::commands::schedule::fmt_utc;
+
+#[derive(Debug, Subcommand)]
+pub enum BackendCommands {
+    List,
+    /// Probe a backend now and record the result in its health history
+    Test {
+        #[arg(required_unless_present = "all", conflicts_with = "all")]
+        backend_id: Option<String>,
+        /// Probe every configured backend in parallel
+        #[arg(long)]
+        all: bool,
+    },
+    /// Health history and circuit breaker state, for one backend or all of them
+    Status {
+        backend_id: Option<String>,
+    },
+}
+
+/// Health samples behind `logline backend status`; in-memory when `runtime.persist_events = false`.
+pub fn open_health_log(cfg_dir: &Path, persist: bool) -> HealthLog {
+    if persist {
+        HealthLog::persistent(cfg_dir.join("health.jsonl"))
+    } else {
+        HealthLog::in_memory()
+    }
+}
+
+pub fn cmd_backend(
+    command: &BackendCommands,
+    catalog: &ConnectionCatalog,
+    runtime: &LoglineRuntime,
+) -> anyhow::Result<()> {
+    match command {
+        BackendCommands::List => {
+            let backends: Vec<_> = catalog.backends.keys().cloned().collect();
+            crate::pout(serde_json::to_value(backends)?, "Backends listed")
+        }
+        BackendCommands::Test {
+            backend_id: Some(backend_id),
+            ..
+        } => {
+            let report = runtime.probe_backend(backend_id)?;
+            let text = format!(
+                "Backend {backend_id} is healthy ({})",
+                report_summary(&report)
+            );
+            crate::pout(serde_json::to_value(&report)?, &text)
+        }
+        BackendCommands::Test { .. } => {
+            let probes = runtime.probe_all();
+            let mut text = String::new();
+            for probe in &probes {
+                let detail = match (&probe.report, &probe.error) {
+                    (Some(report), _) => format!("ok    {}", report_summary(report)),
+                    (None, Some(e)) => format!("FAIL  {e}"),
+                    (None, None) => "FAIL".to_string(),
+                };
+                let _ = writeln!(text, "{:<16} {detail}", probe.backend);
+            }
+            crate::pout(serde_json::to_value(&probes)?, text.trim_end())?;
+
+            let failed = probes.iter().filter(|p| p.error.is_some()).count();
+            if failed > 0 {
+                bail!(
+                    "{failed} of {} backend(s) failed the health check",
+                    probes.len()
+                );
+            }
+            Ok(())
+        }
+        BackendCommands::Status {
+            backend_id: Some(backend_id),
+        } => {
+            let health = runtime.backend_health(backend_id)?;
+            crate::pout(serde_json::to_value(&health)?, &render_detail(&health))
+        }
+        BackendCommands::Status { backend_id: None } => {
+            let all = catalog
+                .backends
+                .keys()
+                .map(|id| runtime.backend_health(id))
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut text = String::new();
+            for health in &all {
+                let _ = writeln!(text, "{}", summary_line(health));
+            }
+            if all.is_empty() {
+                text.push_str("No backends configured.");
+            }
+            crate::pout(serde_json::to_value(&all)?, text.trim_end())
+        }
+    }
+}
+
+fn report_summary(report: &HealthReport) -> String {
+    let mut text = format!("{}ms", report.latency_ms);
+    if let Some(version) = &report.version {
+        let _ = write!(text, ", version {version}");
+    }
+    if let Some(expires) = report.tls_expires_unix {
+        let _ = write!(text, ", TLS certificate expires {}", fmt_utc(expires));
+    }
+    text
+}
+
+fn circuit_label(circuit: CircuitState) -> String {
+    match circuit {
+        CircuitState::Closed => "closed".to_string(),
+        CircuitState::Open { until_unix_ms } => {
+            format!("OPEN until {}", fmt_utc(until_unix_ms / 1_000))
+        }
+        CircuitState::HalfOpen => "half-open".to_string(),
+    }
+}
+
+fn summary_line(health: &BackendHealth) -> String {
+    let mut rate = health.success_percent.map_or_else(
+        || "no samples".to_string(),
+        |p| format!("{p}% ok of {}", health.samples.len()),
+    );
+    if let Some(ms) = health.avg_latency_ms {
+        let _ = write!(rate, ", avg {ms}ms");
+    }
+    format!(
+        "{:<16} circuit {:<28} {rate}",
+        health.backend,
+        circuit_label(health.circuit)
+    )
+}
+
+fn render_detail(health: &BackendHealth) -> String {
+    let mut text = summary_line(health);
+    if health.consecutive_failures > 0 {
+        let _ = write!(
+            text,
+            "\n  {} consecutive failure(s)",
+            health.consecutive_failures
+        );
+    }
+    for sample in health.samples.iter().rev() {
+        let source = match sample.source {
+            HealthSource::Probe => "probe",
+            HealthSource::Run => "run",
+        };
+        let outcome = match (&sample.report, &sample.error) {
+            (_, Some(e)) => format!("FAIL  {e}"),
+            (Some(report), None) => format!("ok    {}", report_summary(report)),
+            (None, None) => format!("ok    {}ms", sample.latency_ms),
+        };
+        let _ = write!(
+            text,
+            "\n  {}  {source:<5} {outcome}",
+            fmt_utc(sample.ts_unix_ms / 1_000)
+        );
+    }
+    text
+}
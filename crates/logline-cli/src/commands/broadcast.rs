@@ -0,0 +1,74 @@
+use std::fmt::Write as _;
+
+use anyhow::bail;
+use logline_api::{Intent, ProfileId};
+use logline_runtime::{BroadcastRun, IntentOutcome, LoglineRuntime};
+
+/// `logline run --profiles a,b` / `--all-profiles`.
+pub fn cmd_broadcast(
+    runtime: &LoglineRuntime,
+    profiles: &[ProfileId],
+    intent: &Intent,
+    plan_on_readonly: bool,
+) -> anyhow::Result<()> {
+    let run = runtime.broadcast(profiles, intent, plan_on_readonly)?;
+    crate::pout(serde_json::to_value(&run)?, &render(&run))?;
+
+    let failed = run.failed();
+    if failed > 0 {
+        bail!(
+            "{failed} of {} target(s) failed for broadcast {}",
+            run.targets.len(),
+            run.broadcast_id
+        );
+    }
+    Ok(())
+}
+
+fn render(run: &BroadcastRun) -> String {
+    let mut text = format!("Broadcast {} ({})", run.intent_type, run.broadcast_id);
+    for target in &run.targets {
+        let detail = match (&target.outcome, &target.error) {
+            (Some(IntentOutcome::Executed(r)), _) => format!("ok    {} {}", r.status, r.run_id),
+            (Some(IntentOutcome::Planned { plan, .. }), _) => format!(
+                "plan  readonly; {} effect(s) not executed",
+                plan.effects.len()
+            ),
+            (None, Some(e)) => format!("FAIL  {e}"),
+            (None, None) => "FAIL".to_string(),
+        };
+        let _ = write!(text, "\n  {:<12} {detail}", target.profile);
+    }
+
+    let executed = run
+        .targets
+        .iter()
+        .filter(|t| matches!(t.outcome, Some(IntentOutcome::Executed(_))))
+        .count();
+    if executed < 2 {
+        text.push_str("\nNothing to compare: fewer than two targets executed");
+    } else if run.diff.differing.is_empty() {
+        let _ = write!(
+            text,
+            "\nOutputs match across {executed} targets ({} key(s))",
+            run.diff.identical.len()
+        );
+    } else {
+        let _ = write!(
+            text,
+            "\nOutputs differ on {} key(s):",
+            run.diff.differing.len()
+        );
+        for (key, values) in &run.diff.differing {
+            let _ = write!(text, "\n  {key}");
+            for (profile, value) in values {
+                let _ = write!(
+                    text,
+                    "\n    {profile:<12} {}",
+                    value.as_deref().unwrap_or("(absent)")
+                );
+            }
+        }
+    }
+    text
+}
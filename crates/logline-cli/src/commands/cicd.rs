@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::fmt::Write as _;
 use std::process::{Command, Stdio};
 use std::time::Instant;
 
@@ -100,7 +101,7 @@ pub fn cmd_cicd(command: CicdCommands, json: bool) -> anyhow::Result<()> {
             dry_run,
             non_interactive,
         } => cmd_cicd_run(pipeline.as_deref(), step.as_deref(), dry_run, non_interactive, json),
-        CicdCommands::Status => cmd_cicd_status(json),
+        CicdCommands::Status => cmd_cicd_status(),
     }
 }
 
@@ -189,7 +190,6 @@ fn cmd_cicd_run(
             eprintln!("  [{}/{}] {} — {cmd}", i + 1, total, step.step);
         }
         return crate::pout(
-            json,
             serde_json::json!({"dry_run": true, "pipeline": name, "steps": total}),
             "Dry run complete. No changes made.",
         );
@@ -261,7 +261,7 @@ fn cmd_cicd_run(
 
                     write_receipt(&receipt);
                     if json {
-                        println!("{}", serde_json::to_string_pretty(&receipt)?);
+                        crate::pout(receipt, "")?;
                     }
                     bail!("Pipeline '{name}' failed at step '{}'", step.step);
                 }
@@ -293,7 +293,6 @@ fn cmd_cicd_run(
     write_receipt(&receipt);
 
     crate::pout(
-        json,
         receipt,
         &format!(
             "\nPipeline: {name} — {} step(s) passed in {total_ms}ms.\nReceipt: receipt.json",
@@ -302,7 +301,7 @@ fn cmd_cicd_run(
     )
 }
 
-fn cmd_cicd_status(json: bool) -> anyhow::Result<()> {
+fn cmd_cicd_status() -> anyhow::Result<()> {
     let mut missing_secrets: Vec<&str> = Vec::new();
     let mut present_secrets: Vec<&str> = Vec::new();
 
@@ -342,26 +341,22 @@ fn cmd_cicd_status(json: bool) -> anyhow::Result<()> {
         "ready": missing_secrets.is_empty() && !pipelines_available.is_empty(),
     });
 
-    if json {
-        println!("{}", serde_json::to_string_pretty(&report)?);
-        return Ok(());
-    }
-
-    println!("Pipelines:");
+    let mut text = String::new();
+    let _ = writeln!(text, "Pipelines:");
     if pipelines_available.is_empty() {
-        println!("  (none — create logline.cicd.json)");
+        let _ = writeln!(text, "  (none — create logline.cicd.json)");
     } else {
         for p in &pipelines_available {
-            println!("  - {p}");
+            let _ = writeln!(text, "  - {p}");
         }
     }
 
-    println!("\nSecrets vault:");
+    let _ = writeln!(text, "\nSecrets vault:");
     for &key in &present_secrets {
-        println!("  ✓ {key}");
+        let _ = writeln!(text, "  ✓ {key}");
     }
     for &key in &missing_secrets {
-        println!("  ✗ {key}  <-- run: logline secrets set {key}");
+        let _ = writeln!(text, "  ✗ {key}  <-- run: logline secrets set {key}");
     }
 
     if let Some(receipt) = &last_receipt {
@@ -370,7 +365,7 @@ fn cmd_cicd_status(json: bool) -> anyhow::Result<()> {
         let total_ms = receipt["total_ms"].as_u64().unwrap_or(0);
         let rid = receipt["receipt_id"].as_str().unwrap_or("?");
         let status = if ok { "PASSED" } else { "FAILED" };
-        println!("\nLast run: {pipeline} — {status} ({total_ms}ms) [{rid}]");
+        let _ = writeln!(text, "\nLast run: {pipeline} — {status} ({total_ms}ms) [{rid}]");
 
         if let Some(steps) = receipt["steps"].as_array() {
             for s in steps {
@@ -378,18 +373,18 @@ fn cmd_cicd_status(json: bool) -> anyhow::Result<()> {
                 let st = s["status"].as_str().unwrap_or("?");
                 let ms = s["elapsed_ms"].as_u64().unwrap_or(0);
                 let mark = if st == "ok" { "✓" } else { "✗" };
-                println!("  {mark} {name} ({ms}ms)");
+                let _ = writeln!(text, "  {mark} {name} ({ms}ms)");
             }
         }
     } else {
-        println!("\nNo pipeline runs yet. Run: logline cicd run");
+        let _ = writeln!(text, "\nNo pipeline runs yet. Run: logline cicd run");
     }
 
     if !missing_secrets.is_empty() {
-        println!("\n⚠ {} secret(s) missing — pipeline will fail.", missing_secrets.len());
+        let _ = writeln!(text, "\n⚠ {} secret(s) missing — pipeline will fail.", missing_secrets.len());
     }
 
-    Ok(())
+    crate::pout(report, text.trim_end())
 }
 
 fn run_shell_command(cmd: &str) -> anyhow::Result<()> {
@@ -1,3 +1,4 @@
+use std::fmt::Write as _;
 use std::path::PathBuf;
 
 use anyhow::bail;
@@ -71,27 +72,27 @@ pub fn cmd_db(command: DbCommands, json: bool) -> anyhow::Result<()> {
     crate::require_unlocked()?;
 
     match command {
-        DbCommands::Query { sql } => cmd_db_query(&sql, json),
-        DbCommands::Tables => cmd_db_tables(json),
-        DbCommands::Describe { table } => cmd_db_describe(&table, json),
+        DbCommands::Query { sql } => cmd_db_query(&sql),
+        DbCommands::Tables => cmd_db_tables(),
+        DbCommands::Describe { table } => cmd_db_describe(&table),
         DbCommands::Migrate { command: sub } => match sub {
-            MigrateCommands::Status => cmd_migrate_status(json),
-            MigrateCommands::Review => cmd_migrate_review(json),
+            MigrateCommands::Status => cmd_migrate_status(),
+            MigrateCommands::Review => cmd_migrate_review(),
             MigrateCommands::Apply { env } => cmd_migrate_apply(&env, json),
-            MigrateCommands::Up { env } => cmd_migrate_up(&env, json),
+            MigrateCommands::Up { env } => cmd_migrate_up(&env),
         },
         DbCommands::VerifyRls { env } => cmd_verify_rls(&env, json),
     }
 }
 
-fn cmd_db_query(sql: &str, json: bool) -> anyhow::Result<()> {
+fn cmd_db_query(sql: &str) -> anyhow::Result<()> {
     let url = get_db_url()?;
     let output = std::process::Command::new("psql")
         .arg(&url)
         .arg("-c")
         .arg(sql)
         .arg("--no-psqlrc")
-        .args(if json { vec!["--tuples-only", "--csv"] } else { vec![] })
+        .arg("--csv")
         .env("PGCONNECT_TIMEOUT", "10")
         .output()
         .map_err(|e| anyhow::anyhow!("Failed to run psql: {e}"))?;
@@ -101,17 +102,22 @@ fn cmd_db_query(sql: &str, json: bool) -> anyhow::Result<()> {
         bail!("Query failed: {stderr}");
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    if json {
-        let rows: Vec<&str> = stdout.lines().filter(|l| !l.is_empty()).collect();
-        println!("{}", serde_json::to_string_pretty(&rows)?);
-    } else {
-        print!("{stdout}");
+    let mut reader = csv::Reader::from_reader(output.stdout.as_slice());
+    let headers = reader.headers()?.clone();
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let row: serde_json::Map<String, serde_json::Value> = headers
+            .iter()
+            .zip(record.iter())
+            .map(|(k, v)| (k.to_string(), serde_json::Value::String(v.to_string())))
+            .collect();
+        rows.push(serde_json::Value::Object(row));
     }
-    Ok(())
+    crate::pout(serde_json::Value::Array(rows), "")
 }
 
-fn cmd_db_tables(json: bool) -> anyhow::Result<()> {
+fn cmd_db_tables() -> anyhow::Result<()> {
     let sql = r"
         SELECT schemaname, tablename,
                pg_stat_get_live_tuples(c.oid) AS row_count
@@ -121,10 +127,10 @@ fn cmd_db_tables(json: bool) -> anyhow::Result<()> {
         WHERE schemaname IN ('public', 'app')
         ORDER BY schemaname, tablename;
     ";
-    cmd_db_query(sql.trim(), json)
+    cmd_db_query(sql.trim())
 }
 
-fn cmd_db_describe(table: &str, json: bool) -> anyhow::Result<()> {
+fn cmd_db_describe(table: &str) -> anyhow::Result<()> {
     let sql = format!(
         r"
         SELECT column_name, data_type, is_nullable, column_default
@@ -133,10 +139,10 @@ fn cmd_db_describe(table: &str, json: bool) -> anyhow::Result<()> {
         ORDER BY ordinal_position;
         "
     );
-    cmd_db_query(sql.trim(), json)
+    cmd_db_query(sql.trim())
 }
 
-fn cmd_migrate_status(json: bool) -> anyhow::Result<()> {
+fn cmd_migrate_status() -> anyhow::Result<()> {
     let migrations_dir = find_migrations_dir()?;
     let files = list_migration_files(&migrations_dir)?;
 
@@ -157,20 +163,17 @@ fn cmd_migrate_status(json: bool) -> anyhow::Result<()> {
         }));
     }
 
-    if json {
-        println!("{}", serde_json::to_string_pretty(&statuses)?);
-    } else {
-        for s in &statuses {
-            let name = s["migration"].as_str().unwrap_or("?");
-            let applied = s["applied"].as_bool().unwrap_or(false);
-            let mark = if applied { "✓" } else { "PENDING" };
-            println!("  {mark:<8} {name}");
-        }
+    let mut text = String::new();
+    for s in &statuses {
+        let name = s["migration"].as_str().unwrap_or("?");
+        let applied = s["applied"].as_bool().unwrap_or(false);
+        let mark = if applied { "✓" } else { "PENDING" };
+        let _ = writeln!(text, "  {mark:<8} {name}");
     }
-    Ok(())
+    crate::pout(serde_json::Value::Array(statuses), text.trim_end())
 }
 
-fn cmd_migrate_up(env: &str, json: bool) -> anyhow::Result<()> {
+fn cmd_migrate_up(env: &str) -> anyhow::Result<()> {
     let migrations_dir = find_migrations_dir()?;
     let files = list_migration_files(&migrations_dir)?;
     let url = get_db_url()?;
@@ -188,7 +191,6 @@ fn cmd_migrate_up(env: &str, json: bool) -> anyhow::Result<()> {
 
     if pending.is_empty() {
         return crate::pout(
-            json,
             serde_json::json!({"ok": true, "applied": 0, "env": env}),
             "All migrations already applied.",
         );
@@ -225,7 +227,6 @@ fn cmd_migrate_up(env: &str, json: bool) -> anyhow::Result<()> {
     }
 
     crate::pout(
-        json,
         serde_json::json!({"ok": true, "applied": applied_count, "env": env}),
         &format!("{applied_count} migration(s) applied to {env}."),
     )
@@ -234,7 +235,7 @@ fn cmd_migrate_up(env: &str, json: bool) -> anyhow::Result<()> {
 const REVIEW_RECEIPT_KEY: &str = "logline_migrate_review_receipt";
 const REVIEW_RECEIPT_TTL_SECS: u64 = 3600; // 1 hour
 
-fn cmd_migrate_review(json: bool) -> anyhow::Result<()> {
+fn cmd_migrate_review() -> anyhow::Result<()> {
     let migrations_dir = find_migrations_dir()?;
     let files = list_migration_files(&migrations_dir)?;
     let url = get_db_url()?;
@@ -252,7 +253,6 @@ fn cmd_migrate_review(json: bool) -> anyhow::Result<()> {
 
     if pending.is_empty() {
         return crate::pout(
-            json,
             serde_json::json!({"ok": true, "pending": 0, "review": "nothing_to_review"}),
             "No pending migrations to review.",
         );
@@ -300,9 +300,7 @@ fn cmd_migrate_review(json: bool) -> anyhow::Result<()> {
     eprintln!("Review receipt stored (valid for 1 hour).");
     eprintln!("To apply: logline db migrate apply --env prod");
 
-    crate::pout(
-        json,
-        serde_json::json!({
+    crate::pout(serde_json::json!({
             "ok": true,
             "pending": pending_names.len(),
             "migrations": pending_names,
@@ -348,7 +346,7 @@ fn cmd_migrate_apply(env: &str, json: bool) -> anyhow::Result<()> {
     eprintln!("Review receipt valid. Reviewed: {}", reviewed_migrations.join(", "));
 
     // Apply migrations (reuses existing logic)
-    cmd_migrate_up(env, json)?;
+    cmd_migrate_up(env)?;
 
     // Invalidate the review receipt after successful apply
     let _ = secrets::store_credential(REVIEW_RECEIPT_KEY,
@@ -557,7 +555,7 @@ fn cmd_verify_rls(env: &str, json: bool) -> anyhow::Result<()> {
     if issues.is_empty() {
         let warn_count = warnings.len();
         if json {
-            println!("{}", serde_json::to_string_pretty(&report)?);
+            crate::pout(report, "")?;
         } else {
             eprintln!("RLS verification PASSED ({env}): {tables_checked} tables secured.");
             if warn_count > 0 {
@@ -570,7 +568,7 @@ fn cmd_verify_rls(env: &str, json: bool) -> anyhow::Result<()> {
         Ok(())
     } else {
         if json {
-            println!("{}", serde_json::to_string_pretty(&report)?);
+            crate::pout(report, "")?;
         } else {
             eprintln!("RLS verification FAILED ({env}):");
             for issue in &issues {
@@ -60,24 +60,24 @@ pub enum DeployCommands {
     },
 }
 
-pub fn cmd_deploy(command: DeployCommands, json: bool) -> anyhow::Result<()> {
+pub fn cmd_deploy(command: DeployCommands) -> anyhow::Result<()> {
     crate::require_infra_identity()?;
 
     match command {
-        DeployCommands::All { env } => cmd_deploy_all(&env, json),
-        DeployCommands::Supabase { env } => cmd_deploy_supabase(&env, json),
+        DeployCommands::All { env } => cmd_deploy_all(&env),
+        DeployCommands::Supabase { env } => cmd_deploy_supabase(&env),
         DeployCommands::Github {
             pr,
             title,
             base,
             tag,
             notes,
-        } => cmd_deploy_github(pr, title.as_deref(), &base, tag.as_deref(), notes.as_deref(), json),
-        DeployCommands::Vercel { env } => cmd_deploy_vercel(&env, json),
+        } => cmd_deploy_github(pr, title.as_deref(), &base, tag.as_deref(), notes.as_deref()),
+        DeployCommands::Vercel { env } => cmd_deploy_vercel(&env),
     }
 }
 
-fn cmd_deploy_all(env: &str, json: bool) -> anyhow::Result<()> {
+fn cmd_deploy_all(env: &str) -> anyhow::Result<()> {
     let started_at = now_iso();
     let rid = receipt_id();
     let mut gates: Vec<serde_json::Value> = Vec::new();
@@ -152,18 +152,16 @@ fn cmd_deploy_all(env: &str, json: bool) -> anyhow::Result<()> {
 
     eprintln!();
     crate::pout(
-        json,
         receipt,
         &format!("Deployed to {env}. Production live at {deploy_url}"),
     )
 }
 
-fn cmd_deploy_supabase(env: &str, json: bool) -> anyhow::Result<()> {
+fn cmd_deploy_supabase(env: &str) -> anyhow::Result<()> {
     eprintln!("Deploying Supabase ({env})...");
     supabase_migrate::migrate_up(env, false)?;
     supabase_migrate::verify_rls(env, false)?;
     crate::pout(
-        json,
         serde_json::json!({"ok": true, "target": "supabase", "env": env}),
         &format!("Supabase deploy complete ({env}): migrations applied, RLS verified."),
     )
@@ -175,7 +173,6 @@ fn cmd_deploy_github(
     base: &str,
     tag: Option<&str>,
     notes: Option<&str>,
-    json: bool,
 ) -> anyhow::Result<()> {
     if pr {
         let t = title.unwrap_or("PR from logline deploy");
@@ -183,7 +180,6 @@ fn cmd_deploy_github(
         let result = github::create_pr(t, "", base)?;
         let pr_url = result["pr_url"].as_str().unwrap_or("?").to_string();
         return crate::pout(
-            json,
             result,
             &format!("PR created: {pr_url}"),
         );
@@ -201,10 +197,10 @@ fn cmd_deploy_github(
         eprintln!("  ✓ Release: {url}");
     }
 
-    crate::pout(json, result, "GitHub deploy complete.")
+    crate::pout(result, "GitHub deploy complete.")
 }
 
-fn cmd_deploy_vercel(env: &str, json: bool) -> anyhow::Result<()> {
+fn cmd_deploy_vercel(env: &str) -> anyhow::Result<()> {
     eprintln!("Deploying Vercel ({env})...");
 
     eprintln!("  Syncing env vars...");
@@ -216,9 +212,7 @@ fn cmd_deploy_vercel(env: &str, json: bool) -> anyhow::Result<()> {
     let deploy = vercel::poll_deployment()?;
     let url = deploy["url"].as_str().unwrap_or("?");
 
-    crate::pout(
-        json,
-        serde_json::json!({
+    crate::pout(serde_json::json!({
             "ok": true,
             "target": "vercel",
             "env": env,
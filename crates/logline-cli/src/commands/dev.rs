@@ -31,11 +31,9 @@ fn run_with_db_env(program: &str, args: &[&str], use_unpooled: bool) -> anyhow::
         .stderr(Stdio::inherit())
         .stdin(Stdio::inherit());
 
-    if let Some(url) = secrets::load_credential("supabase_url") {
-        cmd.env("NEXT_PUBLIC_SUPABASE_URL", url);
-    }
-    if let Some(key) = secrets::load_credential("supabase_anon_key") {
-        cmd.env("NEXT_PUBLIC_SUPABASE_ANON_KEY", key);
+    if let Ok(config) = crate::context::supabase_config() {
+        cmd.env("NEXT_PUBLIC_SUPABASE_URL", config.url)
+            .env("NEXT_PUBLIC_SUPABASE_ANON_KEY", config.anon_key);
     }
 
     let status = cmd.status().map_err(|e| {
@@ -1,6 +1,16 @@
+pub mod audit;
 pub mod auth_session;
+pub mod backend;
+pub mod broadcast;
 pub mod db;
 pub mod deploy;
 pub mod dev;
 pub mod cicd;
+pub mod passkey;
+pub mod qr_login;
 pub mod secrets;
+pub mod runs;
+pub mod schedule;
+pub mod telemetry;
+pub mod ui;
+pub mod workflow;
@@ -0,0 +1,422 @@
+use std::fmt::Write as _;
+
+use anyhow::{Context as _, anyhow, bail};
+use clap::Subcommand;
+use ed25519_dalek::{Signer as _, SigningKey};
+use logline_api::LoglineError;
+use logline_auth::{PASSKEY_ALGORITHM, PasskeyChallenge};
+use logline_core::UnlockMethod;
+use logline_supabase::SupabaseClient;
+use rand::rngs::OsRng;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::supabase::{
+    StoredAuth, delete_auth, delete_passkey, get_valid_token, load_passkey, save_auth, save_passkey,
+};
+
+const PASSKEY_LOGIN_INSTEAD: &str = "Log in from a terminal before the pipeline starts; \
+     stored tokens refresh on their own. Scripts can use `logline auth login --email <addr> --password-stdin`.";
+
+#[derive(Debug, Subcommand)]
+pub enum PasskeysCommands {
+    /// List this account's passkeys; `*` marks the one stored on this device
+    List {
+        /// Include revoked credentials
+        #[arg(long)]
+        all: bool,
+    },
+    /// Revoke a passkey by device name or credential id (e.g. a lost laptop)
+    Revoke {
+        device: String,
+        /// Skip the confirmation prompt
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Replace this device's key: register a new one, revoke the old one
+    Rotate,
+    /// Change the device name a passkey is registered under
+    Rename { device: String, new_name: String },
+}
+
+#[derive(Debug, Deserialize)]
+struct PasskeyRow {
+    credential_id: String,
+    device_name: String,
+    public_key: String,
+    status: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct IssuedSession {
+    session_token: String,
+    #[serde(default)]
+    refresh_token: Option<String>,
+    #[serde(default)]
+    expires_in: Option<u64>,
+    #[serde(default)]
+    credential_id: Option<String>,
+    #[serde(default)]
+    device_name: Option<String>,
+}
+
+/// Edge function that redeems a signed nonce for a session.
+const VERIFY_FUNCTION: &str = "cli-passkey-verify";
+
+/// `logline auth login --passkey`: sign a server nonce with the registered key and
+/// trade the signature for a new session. No stored session is needed.
+pub fn cmd_login_passkey(client: &SupabaseClient) -> anyhow::Result<()> {
+    let passkey = load_passkey().ok_or_else(|| {
+        anyhow!("No passkey registered. Run `logline auth passkey-register` first.")
+    })?;
+    let signing_key = SigningKey::from_bytes(&crate::unlock::key_bytes(&passkey, "private_key")?);
+    let public_key = hex::encode(signing_key.verifying_key().as_bytes());
+
+    // Touch ID still guards use of the key where the hardware exists.
+    let touch_id = crate::unlock::provider(UnlockMethod::TouchId);
+    if touch_id.unavailable().is_none() {
+        crate::prompt::ensure_interactive("Touch ID", PASSKEY_LOGIN_INSTEAD)?;
+        touch_id.unlock()?;
+    }
+
+    // The nonce RPC is open to the anon key: we may have no valid session yet.
+    let issued = client
+        .rpc(
+            "cli_passkey_challenge",
+            &serde_json::json!({ "p_public_key": public_key }),
+            &client.config.anon_key,
+        )
+        .map_err(|e| {
+            anyhow!(
+                "Could not get a passkey challenge ({e}).\n\
+                 Check it is still active: logline auth passkeys list"
+            )
+        })?;
+    let challenge: PasskeyChallenge =
+        serde_json::from_value(issued).context("Unexpected passkey challenge response")?;
+    let signature = hex::encode(signing_key.sign(&challenge.signing_message()).to_bytes());
+
+    let verified = client.invoke_function(
+        VERIFY_FUNCTION,
+        &json!({ "nonce": challenge.nonce, "signature": signature }),
+    );
+    let session: IssuedSession = match verified {
+        Ok(value) => serde_json::from_value(value).context("Unexpected passkey login response")?,
+        Err(LoglineError::NotFound(_)) => bail!(
+            "This project has not deployed the {VERIFY_FUNCTION} edge function, so passkey \
+             login is unavailable.\nDeploy it: supabase functions deploy {VERIFY_FUNCTION}"
+        ),
+        Err(LoglineError::Auth(_)) => bail!(
+            "Passkey signature was rejected. Re-register with `logline auth passkey-register`."
+        ),
+        Err(LoglineError::Validation(_)) => {
+            bail!("Passkey challenge expired or was already used; try again.")
+        }
+        Err(e) => bail!("Passkey login failed: {e}"),
+    };
+
+    let user = client.get_user(&session.session_token)?;
+    let user_id = user["id"]
+        .as_str()
+        .ok_or_else(|| anyhow!("Passkey login returned a session without a user."))?
+        .to_string();
+    let email = user["email"].as_str().map(str::to_string);
+
+    save_auth(&StoredAuth {
+        access_token: session.session_token,
+        refresh_token: session.refresh_token.unwrap_or_default(),
+        user_id: Some(user_id.clone()),
+        email: email.clone(),
+        expires_at: Some(crate::now_secs() + session.expires_in.unwrap_or(3_600)),
+        auth_method: Some("passkey".into()),
+    })?;
+
+    let device_name = session.device_name.unwrap_or_else(crate::get_hostname);
+    crate::pout(
+        serde_json::json!({
+            "ok": true,
+            "user_id": user_id,
+            "email": email,
+            "auth_method": "passkey",
+            "device_name": device_name,
+            "credential_id": session.credential_id,
+        }),
+        &format!(
+            "Authenticated via passkey '{device_name}' as {}",
+            email.as_deref().unwrap_or(&user_id)
+        ),
+    )
+}
+
+/// `logline auth passkey-register`: create a key for this device and register it.
+/// A credential already registered under the same device name is revoked.
+pub fn cmd_passkey_register(
+    client: &SupabaseClient,
+    device_name: Option<String>,
+) -> anyhow::Result<()> {
+    let token = get_valid_token(client)?;
+    let device = device_name.unwrap_or_else(crate::get_hostname);
+    let registered = enroll(client, &token, &device)?;
+
+    crate::pout(
+        json!({
+            "ok": true,
+            "device_name": device,
+            "credential_id": registered["credential_id"],
+            "public_key": registered["public_key"],
+            "replaced": registered["replaced"],
+        }),
+        &format!(
+            "Passkey registered for device '{device}'\nPublic key: {}",
+            registered["public_key"].as_str().unwrap_or("?")
+        ),
+    )
+}
+
+pub fn cmd_passkeys(client: &SupabaseClient, command: &PasskeysCommands) -> anyhow::Result<()> {
+    if !matches!(command, PasskeysCommands::List { .. }) {
+        crate::require_unlocked()?;
+    }
+    let token = get_valid_token(client)?;
+    let local_key = load_passkey().and_then(|p| p["public_key"].as_str().map(str::to_lowercase));
+    let is_local = |c: &PasskeyRow| local_key.as_deref() == Some(c.public_key.as_str());
+
+    match command {
+        PasskeysCommands::List { all } => list(client, &token, local_key.as_deref(), *all),
+        PasskeysCommands::Revoke { device, yes } => {
+            revoke(client, &token, local_key.as_deref(), device, *yes)
+        }
+        PasskeysCommands::Rotate => {
+            let local_key = local_key.ok_or_else(|| {
+                anyhow!("No passkey on this device. Run `logline auth passkey-register`.")
+            })?;
+            let rows = client.postgrest_get(
+                "cli_passkey_credentials",
+                &format!(
+                    "select=credential_id,device_name,public_key,status&public_key=eq.{local_key}"
+                ),
+                &token,
+            )?;
+            let cred = serde_json::from_value::<Vec<PasskeyRow>>(rows)?
+                .into_iter()
+                .find(|c| c.status == "active")
+                .ok_or_else(|| {
+                    anyhow!(
+                        "This device's passkey is not active on the server.\n\
+                         Register a new one: logline auth passkey-register"
+                    )
+                })?;
+
+            let registered = enroll(client, &token, &cred.device_name)?;
+            crate::pout(
+                json!({
+                    "ok": true,
+                    "device_name": cred.device_name,
+                    "credential_id": registered["credential_id"],
+                    "public_key": registered["public_key"],
+                    "revoked": cred.credential_id,
+                }),
+                &format!(
+                    "Rotated passkey '{}': new credential {}, old {} revoked.",
+                    cred.device_name,
+                    registered["credential_id"].as_str().unwrap_or("?"),
+                    cred.credential_id
+                ),
+            )
+        }
+        PasskeysCommands::Rename { device, new_name } => {
+            let cred = find_active(client, &token, device)?;
+            update(client, &token, &cred, &json!({ "device_name": new_name })).map_err(|e| {
+                match e.downcast_ref::<LoglineError>() {
+                    Some(LoglineError::Conflict(_)) => {
+                        anyhow!("Another active passkey is already named '{new_name}'.")
+                    }
+                    _ => e,
+                }
+            })?;
+            if is_local(&cred) {
+                if let Some(mut local) = load_passkey() {
+                    local["device_name"] = json!(new_name);
+                    save_passkey(&local)?;
+                }
+            }
+
+            crate::pout(
+                json!({
+                    "ok": true,
+                    "credential_id": cred.credential_id,
+                    "device_name": new_name,
+                    "previous_name": cred.device_name,
+                }),
+                &format!("Renamed passkey '{}' to '{new_name}'.", cred.device_name),
+            )
+        }
+    }
+}
+
+fn list(
+    client: &SupabaseClient,
+    token: &str,
+    local_key: Option<&str>,
+    all: bool,
+) -> anyhow::Result<()> {
+    let mut query =
+        "select=credential_id,device_name,public_key,algorithm,status,created_at,revoked_at\
+         &order=created_at.asc"
+            .to_string();
+    if !all {
+        query.push_str("&status=eq.active");
+    }
+    let rows = client.postgrest_get("cli_passkey_credentials", &query, token)?;
+    let mut rows = match rows {
+        Value::Array(rows) => rows,
+        _ => Vec::new(),
+    };
+    let mut text = String::new();
+    for row in &mut rows {
+        let current = local_key.is_some() && row["public_key"].as_str() == local_key;
+        row["current"] = json!(current);
+        let _ = writeln!(
+            text,
+            "{} {:<24} {:<8} {:<38} {}",
+            if current { "*" } else { " " },
+            row["device_name"].as_str().unwrap_or("?"),
+            row["status"].as_str().unwrap_or("?"),
+            row["credential_id"].as_str().unwrap_or("?"),
+            row["created_at"].as_str().unwrap_or("")
+        );
+    }
+    if rows.is_empty() {
+        text.push_str("No passkeys registered. Run `logline auth passkey-register`.");
+    }
+    crate::pout(Value::Array(rows), text.trim_end())
+}
+
+fn revoke(
+    client: &SupabaseClient,
+    token: &str,
+    local_key: Option<&str>,
+    device: &str,
+    yes: bool,
+) -> anyhow::Result<()> {
+    let cred = find_active(client, token, device)?;
+    let current = local_key == Some(cred.public_key.as_str());
+    if !yes {
+        let question = if current {
+            format!(
+                "Revoke passkey '{}'? It is this device's; local tokens will be wiped.",
+                cred.device_name
+            )
+        } else {
+            format!("Revoke passkey '{}'?", cred.device_name)
+        };
+        let confirmed = crate::prompt::confirm(
+            &question,
+            &format!(
+                "Pass --yes to revoke '{}' without asking.",
+                cred.device_name
+            ),
+        )?;
+        if !confirmed {
+            bail!("Not confirmed; passkey '{}' left active.", cred.device_name);
+        }
+    }
+
+    update(client, token, &cred, &json!({ "status": "revoked" }))?;
+    if current {
+        // The revoked private key must not stay on disk or in the keyring.
+        delete_passkey()?;
+        if load_passkey().is_some() {
+            bail!(
+                "Revoked passkey '{}', but its private key could not be removed from this device.\n\
+                 Delete the 'passkey_ed25519' vault entry and {} by hand.",
+                cred.device_name,
+                crate::supabase::passkey_path().display()
+            );
+        }
+        delete_auth()?;
+        crate::commands::auth_session::delete_session()?;
+    }
+
+    crate::pout(
+        json!({
+            "ok": true,
+            "credential_id": cred.credential_id,
+            "device_name": cred.device_name,
+            "local_wiped": current,
+        }),
+        &if current {
+            format!(
+                "Revoked passkey '{}'. This device's tokens, passkey and session were removed.",
+                cred.device_name
+            )
+        } else {
+            format!("Revoked passkey '{}'.", cred.device_name)
+        },
+    )
+}
+
+/// The active credential registered under `selector` (a device name or credential id).
+fn find_active(client: &SupabaseClient, token: &str, selector: &str) -> anyhow::Result<PasskeyRow> {
+    let rows = client.postgrest_get(
+        "cli_passkey_credentials",
+        "select=credential_id,device_name,public_key,status&status=eq.active",
+        token,
+    )?;
+    serde_json::from_value::<Vec<PasskeyRow>>(rows)?
+        .into_iter()
+        .find(|c| c.credential_id == selector || c.device_name == selector)
+        .ok_or_else(|| {
+            anyhow!("No active passkey named '{selector}'. See: logline auth passkeys list")
+        })
+}
+
+fn update(
+    client: &SupabaseClient,
+    token: &str,
+    cred: &PasskeyRow,
+    body: &Value,
+) -> anyhow::Result<()> {
+    let updated = client.postgrest_update(
+        "cli_passkey_credentials",
+        &format!("credential_id=eq.{}", cred.credential_id),
+        body,
+        token,
+    )?;
+    if updated.as_array().is_some_and(Vec::is_empty) {
+        bail!("Passkey '{}' was not updated.", cred.device_name);
+    }
+    Ok(())
+}
+
+/// Generate a key for `device`, make it the local passkey and register it. The
+/// server revokes the device's previous credential in the same transaction; if
+/// registration fails the previous local entry is put back.
+fn enroll(client: &SupabaseClient, token: &str, device: &str) -> anyhow::Result<Value> {
+    let previous = load_passkey();
+    let signing_key = SigningKey::generate(&mut OsRng);
+    let public_key = hex::encode(signing_key.verifying_key().as_bytes());
+
+    save_passkey(&json!({
+        "device_name": device,
+        "private_key": hex::encode(signing_key.to_bytes()),
+        "public_key": public_key,
+        "algorithm": PASSKEY_ALGORITHM,
+    }))?;
+
+    let registered = client.rpc(
+        "register_cli_passkey",
+        &json!({ "p_device_name": device, "p_public_key": public_key }),
+        token,
+    );
+    match registered {
+        Ok(registered) => Ok(registered),
+        Err(e) => {
+            match previous {
+                Some(previous) => save_passkey(&previous)?,
+                None => delete_passkey()?,
+            }
+            bail!("Registering the passkey failed; the local passkey is unchanged.\n{e}")
+        }
+    }
+}
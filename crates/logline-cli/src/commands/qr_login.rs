@@ -0,0 +1,356 @@
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context as _, anyhow, bail, ensure};
+use logline_supabase::{SupabaseClient, parse_timestamp_ms};
+use qrcode::QrCode;
+use qrcode::render::unicode::Dense1x2;
+use serde::Deserialize;
+
+use crate::supabase::{StoredAuth, get_valid_token, save_auth};
+
+/// Challenges live five minutes (`cli_auth_challenges.expires_at` default); used when
+/// the server omits or garbles `expires_at`.
+const DEFAULT_CHALLENGE_TTL: Duration = Duration::from_secs(300);
+const POLL_START: Duration = Duration::from_secs(1);
+const POLL_MAX: Duration = Duration::from_secs(8);
+/// Carries the challenge nonce on every status poll.
+const NONCE_HEADER: &str = "X-Challenge-Nonce";
+
+#[derive(Debug, Deserialize)]
+struct CreatedChallenge {
+    challenge_id: String,
+    /// Proof that a poller created the challenge; the id alone is in the QR code.
+    nonce: String,
+    challenge_url: String,
+    #[serde(default)]
+    expires_at: Option<String>,
+}
+
+/// The pending challenge as the approving device sees it.
+#[derive(Debug, Deserialize)]
+struct PendingChallenge {
+    status: String,
+    #[serde(default)]
+    device_name: Option<String>,
+    created_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChallengeStatus {
+    status: String,
+    #[serde(default)]
+    session_token: Option<String>,
+    #[serde(default)]
+    refresh_token: Option<String>,
+    #[serde(default)]
+    expires_in: Option<u64>,
+}
+
+/// Base URL of the app serving `/api/v1/cli/auth/*`: `LOGLINE_API_URL`, else the
+/// active context's `api_url`, else `api_url` in the CLI's `config.json`.
+pub fn api_base_url() -> anyhow::Result<String> {
+    if let Ok(url) = std::env::var("LOGLINE_API_URL") {
+        if !url.trim().is_empty() {
+            return Ok(url.trim().trim_end_matches('/').to_string());
+        }
+    }
+    if let Some(url) = crate::context::api_url() {
+        return Ok(url.to_string());
+    }
+    let path = crate::supabase::config_dir().join("config.json");
+    std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+        .and_then(|v| {
+            v["api_url"]
+                .as_str()
+                .map(|u| u.trim_end_matches('/').to_string())
+        })
+        .filter(|u| !u.is_empty())
+        .ok_or_else(|| {
+            anyhow!(
+                "App API URL not configured.\n\
+                 Set LOGLINE_API_URL or add \"api_url\" to {}",
+                path.display()
+            )
+        })
+}
+
+fn http() -> anyhow::Result<reqwest::blocking::Client> {
+    Ok(reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(15))
+        .build()?)
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// `logline auth login --qr`: create a challenge, show it as a QR code and wait for
+/// another device to approve it with `logline auth approve` (or the web app).
+pub fn cmd_login_qr(client: &SupabaseClient, device_name: &str) -> anyhow::Result<()> {
+    let base = api_base_url()?;
+    let http = http()?;
+
+    let resp = http
+        .post(format!("{base}/api/v1/cli/auth/challenge"))
+        .json(&serde_json::json!({ "device_name": device_name }))
+        .send()
+        .context("Failed to create login challenge")?;
+    if !resp.status().is_success() {
+        bail!("Creating login challenge failed: HTTP {}", resp.status());
+    }
+    let challenge: CreatedChallenge = resp.json().context("Unexpected challenge response")?;
+
+    let qr = QrCode::new(challenge.challenge_url.as_bytes())
+        .map_err(|e| anyhow!("Cannot render challenge URL as a QR code: {e}"))?;
+    let image = qr
+        .render::<Dense1x2>()
+        .dark_color(Dense1x2::Light)
+        .light_color(Dense1x2::Dark)
+        .build();
+    eprintln!("{image}\n");
+    eprintln!("Scan the code, or open: {}", challenge.challenge_url);
+    eprintln!(
+        "Or approve from a logged-in device: logline auth approve {}",
+        challenge.challenge_id
+    );
+
+    let ttl = challenge
+        .expires_at
+        .as_deref()
+        .and_then(parse_timestamp_ms)
+        .and_then(|ms| u64::try_from(ms / 1_000).ok())
+        .map_or(DEFAULT_CHALLENGE_TTL, |at| {
+            Duration::from_secs(at.saturating_sub(now_secs()))
+        });
+    let deadline = Instant::now() + ttl;
+    let status_url = format!(
+        "{base}/api/v1/cli/auth/challenge/{}/status",
+        challenge.challenge_id
+    );
+
+    eprintln!("Waiting for approval...");
+    let approved = wait_for_approval(&http, &status_url, &challenge.nonce, deadline)?;
+
+    let access_token = approved
+        .session_token
+        .ok_or_else(|| anyhow!("Challenge approved but no session token was returned."))?;
+    // Without it the session dies with the access token and the next
+    // refresh fails with a confusing error.
+    let refresh_token = approved
+        .refresh_token
+        .filter(|t| !t.is_empty())
+        .ok_or_else(|| anyhow!("Challenge approved but no refresh token was returned."))?;
+    let user = client.get_user(&access_token)?;
+    let user_id = user["id"].as_str().map(str::to_string);
+    let email = user["email"].as_str().map(str::to_string);
+
+    save_auth(&StoredAuth {
+        access_token,
+        refresh_token,
+        user_id: user_id.clone(),
+        email: email.clone(),
+        expires_at: Some(now_secs() + approved.expires_in.unwrap_or(3_600)),
+        auth_method: Some("qr".into()),
+    })?;
+
+    crate::pout(
+        serde_json::json!({
+            "ok": true,
+            "user_id": user_id,
+            "email": email,
+            "auth_method": "qr",
+            "device_name": device_name,
+        }),
+        &format!(
+            "Logged in as {} ({}) via QR approval",
+            email.as_deref().unwrap_or("?"),
+            user_id.as_deref().unwrap_or("?")
+        ),
+    )
+}
+
+/// Poll the challenge status with backoff until it is approved, refused or expires.
+fn wait_for_approval(
+    http: &reqwest::blocking::Client,
+    status_url: &str,
+    nonce: &str,
+    deadline: Instant,
+) -> anyhow::Result<ChallengeStatus> {
+    let mut delay = POLL_START;
+    loop {
+        if Instant::now() >= deadline {
+            bail!("Login challenge expired before it was approved.");
+        }
+        std::thread::sleep(delay.min(deadline.saturating_duration_since(Instant::now())));
+        delay = (delay * 3 / 2).min(POLL_MAX);
+
+        // Transient network errors just cost a poll; the deadline still applies.
+        let Ok(resp) = http
+            .get(status_url)
+            .header(NONCE_HEADER, nonce)
+            .send()
+        else {
+            continue;
+        };
+        if resp.status() == reqwest::StatusCode::GONE {
+            bail!("Login challenge expired before it was approved.");
+        }
+        if resp.status().is_server_error() {
+            continue;
+        }
+        if !resp.status().is_success() {
+            bail!("Challenge status check failed: HTTP {}", resp.status());
+        }
+        let status: ChallengeStatus = resp.json().context("Unexpected challenge status")?;
+        match status.status.as_str() {
+            "pending" => {}
+            "approved" => return Ok(status),
+            "denied" => bail!("Login was denied on the approving device."),
+            "expired" => bail!("Login challenge expired before it was approved."),
+            other => bail!("Unexpected challenge status '{other}'"),
+        }
+    }
+}
+
+/// Challenge id from a bare id or a scanned `challenge_url`
+/// (`?challenge_id=`/`?challenge=` query, else the last path segment).
+/// The id goes into an authenticated request path, so anything outside
+/// `[A-Za-z0-9_-]{1,128}` is refused rather than passed on.
+fn challenge_id(input: &str) -> anyhow::Result<String> {
+    let input = input.trim();
+    let (path, query) = input.split_once('?').unwrap_or((input, ""));
+    let id = query
+        .split('&')
+        .find_map(|pair| match pair.split_once('=') {
+            Some(("challenge_id" | "challenge", value)) => Some(value),
+            _ => None,
+        })
+        .unwrap_or_else(|| path.trim_end_matches('/').rsplit('/').next().unwrap_or(path));
+    let valid = (1..=128).contains(&id.len())
+        && id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-');
+    ensure!(valid, "'{input}' does not contain a valid login challenge id.");
+    Ok(id.to_string())
+}
+
+/// `logline auth approve <challenge>`: approve (or deny) another device's login
+/// with this device's session.
+pub fn cmd_approve(
+    client: &SupabaseClient,
+    challenge: &str,
+    deny: bool,
+    yes: bool,
+) -> anyhow::Result<()> {
+    crate::require_unlocked()?;
+    let token = get_valid_token(client)?;
+    let id = challenge_id(challenge)?;
+    let action = if deny { "deny" } else { "approve" };
+
+    let pending = load_pending(client, &id, &token)?;
+    let device = pending.device_name.as_deref().unwrap_or("unnamed device");
+    let age = parse_timestamp_ms(&pending.created_at)
+        .and_then(|ms| u64::try_from(ms / 1_000).ok())
+        .map(|at| format!(" ({}s ago)", now_secs().saturating_sub(at)))
+        .unwrap_or_default();
+    eprintln!(
+        "Challenge {id}: requested by '{device}' at {}{age}",
+        pending.created_at
+    );
+
+    if !yes {
+        let confirmed = crate::prompt::confirm(
+            &format!(
+                "{} login for '{device}'? Only do this for a device you control.",
+                if deny { "Deny" } else { "Approve" }
+            ),
+            &format!("Pass --yes to {action} without asking."),
+        )?;
+        if !confirmed {
+            bail!("Not confirmed; challenge {id} left pending.");
+        }
+    }
+
+    let base = api_base_url()?;
+    let resp = http()?
+        .post(format!("{base}/api/v1/cli/auth/challenge/{id}/approve"))
+        .bearer_auth(&token)
+        .json(&serde_json::json!({ "action": action }))
+        .send()
+        .context("Failed to reach the approval endpoint")?;
+    match resp.status().as_u16() {
+        200..=299 => {}
+        404 => bail!("Challenge {id} not found."),
+        409 => bail!("Challenge {id} is no longer pending."),
+        410 => bail!("Challenge {id} has expired."),
+        code => bail!("Approval failed: HTTP {code}"),
+    }
+
+    crate::pout(
+        serde_json::json!({ "ok": true, "challenge_id": id, "action": action }),
+        &if deny {
+            format!("Denied login challenge {id}.")
+        } else {
+            format!("Approved login challenge {id}. The other device is now logged in.")
+        },
+    )
+}
+
+/// Read the challenge through `PostgREST` so the approver sees which device asked
+/// before deciding; pending challenges are readable until someone claims them.
+fn load_pending(
+    client: &SupabaseClient,
+    id: &str,
+    token: &str,
+) -> anyhow::Result<PendingChallenge> {
+    let rows = client.postgrest_get(
+        "cli_auth_challenges",
+        &format!("select=status,device_name,created_at&challenge_id=eq.{id}"),
+        token,
+    )?;
+    let pending: PendingChallenge = rows
+        .as_array()
+        .and_then(|rows| rows.first())
+        .cloned()
+        .map(serde_json::from_value)
+        .transpose()
+        .context("Unexpected challenge row")?
+        .ok_or_else(|| anyhow!("Challenge {id} not found."))?;
+    ensure!(
+        pending.status == "pending",
+        "Challenge {id} is no longer pending ({}).",
+        pending.status
+    );
+    Ok(pending)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn challenge_ids_are_restricted_to_a_safe_charset() {
+        assert_eq!(challenge_id(" abc_DEF-123 ").unwrap(), "abc_DEF-123");
+        assert_eq!(
+            challenge_id("https://logline.world/cli/approve/ch_42/").unwrap(),
+            "ch_42"
+        );
+        assert_eq!(
+            challenge_id("https://logline.world/cli/approve?x=1&challenge_id=ch_7").unwrap(),
+            "ch_7"
+        );
+
+        for crafted in [
+            "https://logline.world/cli/approve?challenge_id=../../admin",
+            "https://logline.world/cli/approve?challenge=x%2F..",
+            "ch_1#frag",
+            "",
+        ] {
+            assert!(challenge_id(crafted).is_err(), "{crafted}");
+        }
+        assert!(challenge_id(&"a".repeat(129)).is_err());
+    }
+}
@@ -0,0 +1,133 @@
+use std::fmt::Write as _;
+use std::path::Path;
+
+use anyhow::bail;
+use clap::Subcommand;
+use logline_runtime::{RunRecord, RunRegistry, RunSource};
+
+use crate::commands::schedule::fmt_utc;
+
+#[derive(Debug, Subcommand)]
+pub enum RunsCommands {
+    /// List recent runs, newest first
+    List {
+        /// Maximum number of runs to show
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+        /// Only runs fired by this schedule
+        #[arg(long)]
+        schedule: Option<String>,
+        /// Only steps of this workflow run
+        #[arg(long, conflicts_with = "schedule")]
+        workflow: Option<String>,
+    },
+    /// Show the current state of one run
+    Show { run_id: String },
+}
+
+/// The registry backing `logline runs`; in-memory when `runtime.persist_events = false`.
+pub fn open_registry(cfg_dir: &Path, persist: bool) -> RunRegistry {
+    if persist {
+        RunRegistry::persistent(cfg_dir.join("runs.jsonl"))
+    } else {
+        RunRegistry::in_memory()
+    }
+}
+
+pub fn cmd_runs(command: &RunsCommands, registry: &RunRegistry) -> anyhow::Result<()> {
+    match command {
+        RunsCommands::List {
+            limit,
+            schedule,
+            workflow,
+        } => {
+            let runs: Vec<RunRecord> = registry
+                .list()?
+                .into_iter()
+                .filter(|r| match (schedule, workflow, &r.source) {
+                    (None, None, _) => true,
+                    (Some(want), _, RunSource::Schedule { schedule_id, .. }) => want == schedule_id,
+                    (
+                        _,
+                        Some(want),
+                        RunSource::Workflow {
+                            workflow_run_id,
+                            step_id: Some(_),
+                            ..
+                        },
+                    ) => want == workflow_run_id,
+                    _ => false,
+                })
+                .take(*limit)
+                .collect();
+            let mut text = String::new();
+            for r in &runs {
+                let _ = writeln!(
+                    text,
+                    "{:<24} {:<10} {:<14} {:<12} {:<18} {}",
+                    r.run_id,
+                    r.status,
+                    r.intent_type,
+                    r.profile,
+                    source_label(&r.source),
+                    fmt_utc(r.started_at_ms / 1_000)
+                );
+            }
+            if runs.is_empty() {
+                text.push_str("No runs recorded.");
+            }
+            crate::pout(serde_json::to_value(&runs)?, text.trim_end())
+        }
+        RunsCommands::Show { run_id } => {
+            let Some(run) = registry.get(run_id)? else {
+                bail!("run {run_id} not found in the run registry");
+            };
+            let mut text = format!(
+                "Run {}\n  status:  {}\n  intent:  {}\n  target:  {}/{}\n  source:  {}\n  started: {}",
+                run.run_id,
+                run.status,
+                run.intent_type,
+                run.profile,
+                run.backend,
+                source_label(&run.source),
+                fmt_utc(run.started_at_ms / 1_000)
+            );
+            if let Some(err) = &run.error {
+                let _ = write!(text, "\n  error:   {err}");
+            }
+            if let RunSource::Workflow { step_id: None, .. } = &run.source {
+                let steps: Vec<RunRecord> = registry
+                    .list()?
+                    .into_iter()
+                    .filter(|r| {
+                        matches!(&r.source, RunSource::Workflow { workflow_run_id, step_id: Some(_), .. }
+                            if workflow_run_id == &run.run_id)
+                    })
+                    .collect();
+                let _ = write!(
+                    text,
+                    "\n  steps:   {} (logline runs list --workflow {})",
+                    steps.len(),
+                    run.run_id
+                );
+            }
+            crate::pout(serde_json::to_value(&run)?, &text)
+        }
+    }
+}
+
+fn source_label(source: &RunSource) -> String {
+    match source {
+        RunSource::Manual => "manual".to_string(),
+        RunSource::Schedule { schedule_id, .. } => format!("schedule:{schedule_id}"),
+        RunSource::Workflow {
+            workflow_id,
+            step_id,
+            ..
+        } => match step_id {
+            Some(step) => format!("workflow:{workflow_id}/{step}"),
+            None => format!("workflow:{workflow_id}"),
+        },
+        RunSource::Broadcast { broadcast_id } => format!("broadcast:{broadcast_id}"),
+    }
+}
@@ -0,0 +1,294 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::bail;
+use clap::{Subcommand, ValueEnum};
+use logline_api::Intent;
+use logline_core::{
+    ConnectionCatalog, MissedRunPolicy, ScheduleSpec, ScheduleTrigger, civil_from_days,
+    parse_interval,
+};
+use logline_runtime::{LoglineRuntime, ScheduleEntry, ScheduleStore, Scheduler, TickReport};
+
+use crate::commands::telemetry;
+
+#[derive(Debug, Subcommand)]
+pub enum ScheduleCommands {
+    /// Add a recurring intent
+    Add {
+        /// Schedule id (letters, digits, '-' or '_')
+        id: String,
+        #[arg(long)]
+        intent: String,
+        /// Payload entry; values may use `{{schedule_id}}` and `{{scheduled_for}}`
+        #[arg(long = "arg", value_parser = crate::parse_key_val)]
+        args: Vec<(String, String)>,
+        /// Five-field cron expression, evaluated in UTC (e.g. "*/15 * * * *")
+        #[arg(long, conflicts_with = "every", required_unless_present = "every")]
+        cron: Option<String>,
+        /// Fixed interval (e.g. 15m, 2h, 1d)
+        #[arg(long)]
+        every: Option<String>,
+        /// Target profile (defaults to the first configured profile)
+        #[arg(long)]
+        profile: Option<String>,
+        /// What to do with slots missed while no scheduler was running
+        #[arg(long, value_enum, default_value = "skip")]
+        missed: MissedArg,
+        /// Upper bound on missed slots replayed with --missed catch-up
+        #[arg(long, default_value_t = 10)]
+        max_catch_up: usize,
+        /// Allow a new run to start while the previous one is still in flight
+        #[arg(long)]
+        allow_overlap: bool,
+    },
+    /// List schedules and their next due time
+    List,
+    /// Delete a schedule
+    Remove { id: String },
+    /// Stop firing a schedule until it is resumed
+    Pause { id: String },
+    /// Resume a paused schedule from its next slot
+    Resume { id: String },
+    /// Fire due schedules in the foreground (use a service manager to keep it up)
+    Run {
+        /// Fire whatever is due, wait for it to finish, and exit
+        #[arg(long)]
+        once: bool,
+        /// Seconds between checks for due schedules
+        #[arg(long, default_value_t = 15)]
+        poll_seconds: u64,
+        /// Serve Prometheus metrics on this address while running (e.g. 127.0.0.1:9464)
+        #[arg(long)]
+        metrics_listen: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum MissedArg {
+    Skip,
+    CatchUp,
+}
+
+pub fn schedules_path(cfg_dir: &Path) -> PathBuf {
+    cfg_dir.join("schedules.json")
+}
+
+pub fn cmd_schedule(
+    command: ScheduleCommands,
+    cfg_dir: &Path,
+    catalog: &ConnectionCatalog,
+    runtime: LoglineRuntime,
+    json: bool,
+) -> anyhow::Result<()> {
+    let store = ScheduleStore::new(schedules_path(cfg_dir));
+
+    match command {
+        ScheduleCommands::Add {
+            id,
+            intent,
+            args,
+            cron,
+            every,
+            profile,
+            missed,
+            max_catch_up,
+            allow_overlap,
+        } => {
+            let trigger = build_trigger(cron, every)?;
+            let profile = resolve_profile(catalog, profile)?;
+
+            let spec = ScheduleSpec {
+                id,
+                trigger,
+                intent: Intent {
+                    intent_type: intent,
+                    payload: BTreeMap::from_iter(args),
+                },
+                profile,
+                missed_runs: match missed {
+                    MissedArg::Skip => MissedRunPolicy::Skip,
+                    MissedArg::CatchUp => MissedRunPolicy::CatchUp,
+                },
+                allow_overlap,
+                paused: false,
+                misfire_grace_seconds: 60,
+                max_catch_up,
+            };
+            let entry = store.add(spec, now_unix())?;
+            crate::pout(
+                serde_json::to_value(&entry)?,
+                &format!(
+                    "Schedule {} added; next run {}",
+                    entry.spec.id,
+                    fmt_utc(entry.next_due_unix)
+                ),
+            )
+        }
+        ScheduleCommands::List => {
+            let entries = store.list()?;
+            crate::pout(serde_json::to_value(&entries)?, &render_list(&entries))
+        }
+        ScheduleCommands::Remove { id } => {
+            store.remove(&id)?;
+            crate::pout(
+                serde_json::json!({"ok": true, "removed": id}),
+                &format!("Schedule {id} removed"),
+            )
+        }
+        ScheduleCommands::Pause { id } => {
+            let entry = store.set_paused(&id, true, now_unix())?;
+            crate::pout(
+                serde_json::to_value(&entry)?,
+                &format!("Schedule {id} paused"),
+            )
+        }
+        ScheduleCommands::Resume { id } => {
+            let entry = store.set_paused(&id, false, now_unix())?;
+            crate::pout(
+                serde_json::to_value(&entry)?,
+                &format!(
+                    "Schedule {id} resumed; next run {}",
+                    fmt_utc(entry.next_due_unix)
+                ),
+            )
+        }
+        ScheduleCommands::Run {
+            once,
+            poll_seconds,
+            metrics_listen,
+        } => run_scheduler(
+            store,
+            cfg_dir,
+            runtime,
+            once,
+            poll_seconds,
+            metrics_listen.as_deref(),
+            json,
+        ),
+    }
+}
+
+fn run_scheduler(
+    store: ScheduleStore,
+    cfg_dir: &Path,
+    runtime: LoglineRuntime,
+    once: bool,
+    poll_seconds: u64,
+    metrics_listen: Option<&str>,
+    json: bool,
+) -> anyhow::Result<()> {
+    let runtime = Arc::new(runtime);
+    if let Some(addr) = metrics_listen {
+        let listener = TcpListener::bind(addr)?;
+        let runtime = Arc::clone(&runtime);
+        std::thread::spawn(move || telemetry::serve_metrics(&runtime, &listener));
+    }
+    let scheduler = Scheduler::new(store, cfg_dir.join("schedule-locks"));
+    if !once && !json {
+        eprintln!("Scheduler running (poll every {poll_seconds}s). Ctrl-C to stop.");
+    }
+    loop {
+        let report = scheduler.tick(&runtime, now_unix())?;
+        print_tick(&report, json)?;
+        if once {
+            scheduler.wait();
+            return Ok(());
+        }
+        std::thread::sleep(Duration::from_secs(poll_seconds.max(1)));
+    }
+}
+
+fn build_trigger(cron: Option<String>, every: Option<String>) -> anyhow::Result<ScheduleTrigger> {
+    match (cron, every) {
+        (Some(expr), _) => Ok(ScheduleTrigger::Cron { expr }),
+        (None, Some(every)) => Ok(ScheduleTrigger::Interval {
+            every_seconds: parse_interval(&every)?,
+        }),
+        (None, None) => bail!("one of --cron or --every is required"),
+    }
+}
+
+fn resolve_profile(catalog: &ConnectionCatalog, profile: Option<String>) -> anyhow::Result<String> {
+    let profile = match profile {
+        Some(p) => p,
+        None => catalog
+            .profiles
+            .keys()
+            .next()
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("no profiles configured"))?,
+    };
+    if !catalog.profiles.contains_key(&profile) {
+        bail!("profile {profile} not found");
+    }
+    Ok(profile)
+}
+
+fn render_list(entries: &[ScheduleEntry]) -> String {
+    if entries.is_empty() {
+        return "No schedules. Add one with: logline schedule add <id> --intent <type> --every 1h"
+            .to_string();
+    }
+    let mut text = String::new();
+    for e in entries {
+        let trigger = match &e.spec.trigger {
+            ScheduleTrigger::Cron { expr } => format!("cron \"{expr}\""),
+            ScheduleTrigger::Interval { every_seconds } => format!("every {every_seconds}s"),
+        };
+        let next = if e.spec.paused {
+            "paused".to_string()
+        } else {
+            format!("next {}", fmt_utc(e.next_due_unix))
+        };
+        let last = e.last_status.as_deref().unwrap_or("never run");
+        let _ = writeln!(
+            text,
+            "{:<20} {:<24} {:<14} {:<12} {:<24} last: {last}",
+            e.spec.id, trigger, e.spec.intent.intent_type, e.spec.profile, next
+        );
+    }
+    text.trim_end().to_string()
+}
+
+fn print_tick(report: &TickReport, json: bool) -> anyhow::Result<()> {
+    if json {
+        return crate::pout(serde_json::to_value(report)?, "");
+    }
+    for d in &report.dispatched {
+        let slots: Vec<String> = d.scheduled_for_unix.iter().map(|s| fmt_utc(*s)).collect();
+        println!("fired    {:<20} {}", d.schedule_id, slots.join(", "));
+    }
+    for s in &report.skipped {
+        println!(
+            "skipped  {:<20} {} slot(s) {}..{}: {}",
+            s.schedule_id,
+            s.count,
+            fmt_utc(s.first_unix),
+            fmt_utc(s.last_unix),
+            s.reason
+        );
+    }
+    Ok(())
+}
+
+fn now_unix() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| i64::try_from(d.as_secs()).unwrap_or(i64::MAX))
+}
+
+/// `YYYY-MM-DD HH:MMZ` for a unix timestamp.
+pub fn fmt_utc(unix_secs: i64) -> String {
+    let (y, m, d) = civil_from_days(unix_secs.div_euclid(86_400));
+    let secs = unix_secs.rem_euclid(86_400);
+    format!(
+        "{y:04}-{m:02}-{d:02} {:02}:{:02}Z",
+        secs / 3_600,
+        secs % 3_600 / 60
+    )
+}
@@ -1,9 +1,10 @@
-use anyhow::{bail, ensure};
-use clap::Subcommand;
+use std::fmt::Write as _;
 
-const KEYRING_SERVICE: &str = "logline-cli";
+use anyhow::ensure;
+use clap::Subcommand;
+use logline_core::VaultBackendKind;
 
-const ALL_KEYS: &[&str] = &[
+pub const ALL_KEYS: &[&str] = &[
     "supabase_url",
     "supabase_anon_key",
     "supabase_service_role_key",
@@ -18,19 +19,22 @@ const ALL_KEYS: &[&str] = &[
 
 #[derive(Debug, Subcommand)]
 pub enum SecretsCommands {
-    /// Store a credential in macOS Keychain (prompted, never echoed)
+    /// Store a credential in the vault (prompted, never echoed)
     Set {
         /// Credential key (e.g. github_token, database_url)
         key: String,
+        /// Read the value from stdin instead of prompting (for CI and scripts)
+        #[arg(long)]
+        from_stdin: bool,
     },
-    /// Retrieve a credential from Keychain (requires unlocked session)
+    /// Retrieve a credential from the vault (requires unlocked session)
     Get {
         /// Credential key
         key: String,
     },
     /// List all stored credential keys (names only, never values)
     Ls,
-    /// Remove a credential from Keychain
+    /// Remove a credential from the vault
     Rm {
         /// Credential key to remove
         key: String,
@@ -39,20 +43,30 @@ pub enum SecretsCommands {
     Clear,
     /// Check vault completeness against pipeline requirements
     Doctor,
+    /// Move every stored credential (including login tokens) of every context to another backend
+    MigrateBackend {
+        /// Destination backend: keyring or file
+        #[arg(long)]
+        to: VaultBackendKind,
+        /// Source backend (default: the configured one)
+        #[arg(long)]
+        from: Option<VaultBackendKind>,
+        /// Leave the entries in the source backend too
+        #[arg(long)]
+        keep: bool,
+    },
 }
 
 pub fn store_credential(key: &str, value: &str) -> anyhow::Result<()> {
-    let entry = keyring::Entry::new(KEYRING_SERVICE, key)
-        .map_err(|e| anyhow::anyhow!("Keychain error: {e}"))?;
-    entry
-        .set_password(value)
-        .map_err(|e| anyhow::anyhow!("Failed to store '{key}' in keychain: {e}"))?;
-    Ok(())
+    crate::vault::store().set(key, value)
 }
 
 pub fn load_credential(key: &str) -> Option<String> {
-    let entry = keyring::Entry::new(KEYRING_SERVICE, key).ok()?;
-    entry.get_password().ok()
+    crate::vault::store()
+        .get(key)
+        .map_err(|e| eprintln!("Warning: {e}"))
+        .ok()
+        .flatten()
 }
 
 pub fn load_credential_or_env(keychain_key: &str, env_var: &str) -> Option<String> {
@@ -65,7 +79,7 @@ pub fn load_credential_or_env(keychain_key: &str, env_var: &str) -> Option<Strin
 pub fn require_credential(key: &str) -> anyhow::Result<String> {
     load_credential(key).ok_or_else(|| {
         anyhow::anyhow!(
-            "Credential '{key}' not found in keychain.\n\
+            "Credential '{key}' not found in the vault.\n\
              Store it with: logline secrets set {key}"
         )
     })
@@ -81,14 +95,8 @@ pub fn require_credential_or_env(keychain_key: &str, env_var: &str) -> anyhow::R
     })
 }
 
-fn delete_credential(key: &str) -> anyhow::Result<bool> {
-    let entry = keyring::Entry::new(KEYRING_SERVICE, key)
-        .map_err(|e| anyhow::anyhow!("Keychain error: {e}"))?;
-    match entry.delete_credential() {
-        Ok(()) => Ok(true),
-        Err(keyring::Error::NoEntry) => Ok(false),
-        Err(e) => bail!("Failed to delete '{key}': {e}"),
-    }
+pub fn delete_credential(key: &str) -> anyhow::Result<bool> {
+    crate::vault::store().delete(key)
 }
 
 fn validate_key(key: &str) -> anyhow::Result<()> {
@@ -96,65 +104,70 @@ fn validate_key(key: &str) -> anyhow::Result<()> {
         !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'),
         "Invalid key '{key}'. Use lowercase alphanumeric + underscores (e.g. github_token)"
     );
+    // Session, passphrase hash and tokens would bypass their own commands' checks.
+    ensure!(
+        !crate::vault::INTERNAL_KEYS.contains(&key),
+        "'{key}' is managed by the CLI itself and cannot be set, read or deleted with `secrets`."
+    );
     Ok(())
 }
 
 pub fn cmd_secrets(command: SecretsCommands, json: bool) -> anyhow::Result<()> {
     match command {
-        SecretsCommands::Set { key } => {
+        SecretsCommands::Set { key, from_stdin } => {
             validate_key(&key)?;
-            let value = rpassword::prompt_password(format!("Enter value for '{key}' (hidden): "))?;
+            let value = if from_stdin {
+                crate::prompt::read_stdin("value")?
+            } else {
+                crate::prompt::secret(
+                    &format!("Enter value for '{key}' (hidden)"),
+                    &format!("Pipe it in instead: printf %s \"$VALUE\" | logline secrets set {key} --from-stdin"),
+                )?
+            };
             ensure!(!value.trim().is_empty(), "Value cannot be empty");
             store_credential(&key, value.trim())?;
             crate::pout(
-                json,
                 serde_json::json!({"ok": true, "key": key}),
-                &format!("Stored: {key} -> Keychain"),
+                &format!("Stored: {key} -> {}", crate::vault::store().kind()),
             )
         }
         SecretsCommands::Get { key } => {
+            validate_key(&key)?;
             crate::require_unlocked()?;
             let value = require_credential(&key)?;
             if json {
-                println!(
-                    "{}",
-                    serde_json::to_string_pretty(&serde_json::json!({"key": key, "value": value}))?
-                );
+                crate::pout(serde_json::json!({"key": key, "value": value}), "")?;
             } else {
                 print!("{value}");
             }
             Ok(())
         }
         SecretsCommands::Ls => {
+            let stored = crate::vault::store().keys()?;
             let mut entries = Vec::new();
             for &key in ALL_KEYS {
-                let present = load_credential(key).is_some();
+                let present = stored.iter().any(|k| k == key);
                 entries.push(serde_json::json!({"key": key, "stored": present}));
             }
-            if json {
-                println!("{}", serde_json::to_string_pretty(&entries)?);
-            } else {
-                for entry in &entries {
-                    let k = entry["key"].as_str().unwrap_or("?");
-                    let stored = entry["stored"].as_bool().unwrap_or(false);
-                    let mark = if stored { "✓" } else { "✗" };
-                    println!("  {k:<30} {mark}");
-                }
+            let mut text = String::new();
+            for entry in &entries {
+                let k = entry["key"].as_str().unwrap_or("?");
+                let stored = entry["stored"].as_bool().unwrap_or(false);
+                let mark = if stored { "✓" } else { "✗" };
+                let _ = writeln!(text, "  {k:<30} {mark}");
             }
-            Ok(())
+            crate::pout(serde_json::Value::Array(entries), text.trim_end())
         }
         SecretsCommands::Rm { key } => {
             validate_key(&key)?;
             let deleted = delete_credential(&key)?;
             if deleted {
                 crate::pout(
-                    json,
                     serde_json::json!({"ok": true, "key": key}),
                     &format!("Removed: {key}"),
                 )
             } else {
                 crate::pout(
-                    json,
                     serde_json::json!({"ok": false, "key": key, "reason": "not_found"}),
                     &format!("Key '{key}' was not stored"),
                 )
@@ -168,15 +181,116 @@ pub fn cmd_secrets(command: SecretsCommands, json: bool) -> anyhow::Result<()> {
                 }
             }
             crate::pout(
-                json,
                 serde_json::json!({"ok": true, "removed": removed}),
-                &format!("Cleared {removed} credentials from keychain"),
+                &format!("Cleared {removed} credentials from {}", crate::vault::store().kind()),
             )
         }
         SecretsCommands::Doctor => {
-            cmd_secrets_doctor(json)
+            cmd_secrets_doctor()
+        }
+        SecretsCommands::MigrateBackend { to, from, keep } => {
+            cmd_migrate_backend(from.unwrap_or(crate::vault::store().kind()), to, keep)
+        }
+    }
+}
+
+fn cmd_migrate_backend(from: VaultBackendKind, to: VaultBackendKind, keep: bool) -> anyhow::Result<()> {
+    ensure!(from != to, "Source and destination are both {from}.");
+    crate::require_unlocked()?;
+
+    // runtime.toml picks one backend for every context and the file vault holds
+    // them all, so moving only the active context would strand the others.
+    // Everything is copied and read back before anything is removed.
+    let source = crate::vault::open(from);
+    let dest = crate::vault::open(to);
+    let mut copied = Vec::new();
+    for (namespace, state_dir) in crate::context::scopes()? {
+        let source = source.scoped(&namespace);
+        let dest = dest.scoped(&namespace);
+        let (keys, files) = copy_namespace(source.as_ref(), dest.as_ref(), &state_dir, to)?;
+        if !keys.is_empty() {
+            copied.push((namespace, source, keys, files));
+        }
+    }
+    if !keep {
+        for (_, source, keys, files) in &copied {
+            for key in keys {
+                source.delete(key)?;
+            }
+            for path in files {
+                std::fs::remove_file(path)?;
+            }
+        }
+    }
+
+    let total: usize = copied.iter().map(|(_, _, keys, _)| keys.len()).sum();
+    let moved: serde_json::Map<_, _> = copied
+        .iter()
+        .map(|(namespace, _, keys, _)| (namespace.clone(), serde_json::json!(keys)))
+        .collect();
+    crate::pout(
+        serde_json::json!({
+            "ok": true,
+            "from": from.as_str(),
+            "to": to.as_str(),
+            "moved": moved,
+            "kept_source": keep,
+        }),
+        &format!(
+            "{} {total} entries in {} context(s) from {from} to {to}.\n\
+             Set `backend = \"{to}\"` under [runtime.vault] in runtime.toml to use it.",
+            if keep { "Copied" } else { "Moved" },
+            copied.len()
+        ),
+    )
+}
+
+/// Copy one namespace's entries into `dest` and verify them; returns the keys
+/// copied and the plaintext fallback files they replace.
+fn copy_namespace(
+    source: &dyn crate::vault::CredentialStore,
+    dest: &dyn crate::vault::CredentialStore,
+    state_dir: &std::path::Path,
+    to: VaultBackendKind,
+) -> anyhow::Result<(Vec<String>, Vec<std::path::PathBuf>)> {
+    let mut keys = source.keys()?;
+    for key in &keys {
+        let Some(value) = source.get(key)? else { continue };
+        dest.set(key, &value)?;
+        ensure!(
+            dest.get(key)?.as_deref() == Some(value.as_str()),
+            "'{key}' did not read back from {to}; source left untouched"
+        );
+    }
+    // The encrypted vault replaces the plaintext fallback copies. Those may be the
+    // only copy (the keyring write can fail silently), so they are carried over and
+    // read back before anything is removed.
+    let mut fallback_files = Vec::new();
+    if to == VaultBackendKind::File {
+        let fallbacks = [
+            ("auth_tokens", state_dir.join(crate::supabase::AUTH_FILE)),
+            ("passkey_ed25519", state_dir.join(crate::supabase::PASSKEY_FILE)),
+        ];
+        for (key, path) in fallbacks {
+            let Ok(content) = std::fs::read_to_string(&path) else { continue };
+            ensure!(
+                serde_json::from_str::<serde_json::Value>(&content).is_ok(),
+                "{} is not valid JSON; fix or remove it, then migrate again",
+                path.display()
+            );
+            if !keys.iter().any(|k| k == key) {
+                dest.set(key, content.trim())?;
+                ensure!(
+                    dest.get(key)?.as_deref() == Some(content.trim()),
+                    "'{key}' from {} did not read back from {to}; file left in place",
+                    path.display()
+                );
+                keys.push(key.to_string());
+            }
+            fallback_files.push(path);
         }
     }
+    Ok((keys, fallback_files))
 }
 
 const REQUIRED_FOR_DEPLOY: &[(&str, &str)] = &[
@@ -192,11 +306,6 @@ const REQUIRED_FOR_DEV: &[(&str, &str)] = &[
     ("database_url_unpooled", "drizzle-kit migrate/generate"),
 ];
 
-const REQUIRED_FOR_AUTH: &[(&str, &str)] = &[
-    ("supabase_url", "Supabase Auth login"),
-    ("supabase_anon_key", "Supabase Auth login"),
-];
-
 const DANGEROUS_ENV_VARS: &[(&str, &str)] = &[
     ("DATABASE_URL", "database connection string"),
     ("DATABASE_URL_UNPOOLED", "database connection string"),
@@ -206,13 +315,12 @@ const DANGEROUS_ENV_VARS: &[(&str, &str)] = &[
     ("VERCEL_TOKEN", "Vercel API token"),
 ];
 
-fn cmd_secrets_doctor(json: bool) -> anyhow::Result<()> {
+fn cmd_secrets_doctor() -> anyhow::Result<()> {
     use crate::commands::auth_session;
 
     let groups: &[(&str, &[(&str, &str)])] = &[
         ("deploy", REQUIRED_FOR_DEPLOY),
         ("dev", REQUIRED_FOR_DEV),
-        ("auth", REQUIRED_FOR_AUTH),
     ];
 
     let mut vault_ok = true;
@@ -249,11 +357,22 @@ fn cmd_secrets_doctor(json: bool) -> anyhow::Result<()> {
         .filter(|s| s.expires_at > now)
         .map(|s| s.expires_at - now);
 
+    // Auth talks to the active context's project, not to vault entries.
+    let supabase_url = crate::context::supabase_config().ok().map(|c| c.url);
+    let supabase_ok = supabase_url.is_some();
+
     let identity = auth_session::load_identity();
 
     let logged_in = identity.is_some();
     let auth_method = identity.as_ref().map(|i| i.auth_method.as_str()).unwrap_or("none");
     let passkey_ok = auth_method == "passkey";
+    let unverified = identity.as_ref().and_then(|i| i.unverified.as_deref());
+    let unverifiable = identity.as_ref().and_then(|i| i.unverifiable.as_deref());
+    let token_verified = logged_in && unverified.is_none() && unverifiable.is_none();
+    // Projects without signing keys can't be checked locally; infra accepts that
+    // only with the policy.toml opt-in.
+    let allow_unverified = crate::unlock::auth_policy().allow_unverified_tokens;
+    let token_ok = logged_in && unverified.is_none() && (unverifiable.is_none() || allow_unverified);
     let is_founder = identity.as_ref().is_some_and(|i| i.is_founder);
     let profile = identity.as_ref().map(|i| i.profile.as_str()).unwrap_or("none");
     let subject_email = identity.as_ref().and_then(|i| i.email.as_deref()).unwrap_or("?");
@@ -268,7 +387,7 @@ fn cmd_secrets_doctor(json: bool) -> anyhow::Result<()> {
     }
     let no_leaks = env_leaks.is_empty();
 
-    let ready_for_infra = vault_ok && session_ok && logged_in && passkey_ok && !is_founder && no_leaks;
+    let ready_for_infra = vault_ok && supabase_ok && session_ok && logged_in && passkey_ok && token_ok && !is_founder && no_leaks;
 
     let auth_report = serde_json::json!({
         "logged_in": logged_in,
@@ -278,12 +397,16 @@ fn cmd_secrets_doctor(json: bool) -> anyhow::Result<()> {
         "profile": profile,
         "is_founder": is_founder,
         "passkey_ok": passkey_ok,
+        "token_verified": token_verified,
+        "token_unverifiable": unverifiable,
         "founder_blocked": is_founder,
     });
 
     let report = serde_json::json!({
         "ready_for_infra": ready_for_infra,
         "vault_ok": vault_ok,
+        "vault_backend": crate::vault::store().kind().as_str(),
+        "supabase_url": supabase_url,
         "session_active": session_ok,
         "auth": auth_report,
         "groups": report_groups,
@@ -291,12 +414,10 @@ fn cmd_secrets_doctor(json: bool) -> anyhow::Result<()> {
         "no_leaks": no_leaks,
     });
 
-    if json {
-        println!("{}", serde_json::to_string_pretty(&report)?);
-        return Ok(());
-    }
 
-    println!("Secrets Doctor\n");
+    let mut text = String::new();
+    let _ = writeln!(text, "Secrets Doctor\n");
+    let _ = writeln!(text, "  vault backend: {}\n", crate::vault::store().kind());
 
     // Vault section
     for group in &report_groups {
@@ -305,82 +426,99 @@ fn cmd_secrets_doctor(json: bool) -> anyhow::Result<()> {
         let present = group["present"].as_array();
         let ok = missing.is_some_and(|m| m.is_empty());
         let mark = if ok { "✓" } else { "✗" };
-        println!("{mark} vault/{name}:");
+        let _ = writeln!(text, "{mark} vault/{name}:");
 
         if let Some(arr) = present {
             for k in arr {
-                println!("    ✓ {}", k.as_str().unwrap_or("?"));
+                let _ = writeln!(text, "    ✓ {}", k.as_str().unwrap_or("?"));
             }
         }
         if let Some(arr) = missing {
             for k in arr {
                 let key = k.as_str().unwrap_or("?");
-                println!("    ✗ {key}  <-- logline secrets set {key}");
+                let _ = writeln!(text, "    ✗ {key}  <-- logline secrets set {key}");
             }
         }
     }
 
+    // Supabase project section
+    text.push('\n');
+    match &supabase_url {
+        Some(url) => { let _ = writeln!(text, "✓ supabase: {url}"); }
+        None => { let _ = writeln!(text, "✗ supabase: no project configured  <-- logline context add <name> --url <url> --anon-key <key> --use"); }
+    }
+
     // Session section
-    println!();
+    text.push('\n');
     if session_ok {
         let rem = session_remaining.unwrap_or(0);
         let mins = rem / 60;
         let secs = rem % 60;
-        println!("✓ session: active ({mins}m {secs}s remaining)");
+        let _ = writeln!(text, "✓ session: active ({mins}m {secs}s remaining)");
     } else {
-        println!("✗ session: locked  <-- logline auth unlock");
+        let _ = writeln!(text, "✗ session: locked  <-- logline auth unlock");
     }
 
     // Auth identity section
-    println!();
+    text.push('\n');
     if !logged_in {
-        println!("✗ auth:");
-        println!("    logged_in: false");
-        println!("    Fix: logline auth login --passkey");
+        let _ = writeln!(text, "✗ auth:");
+        let _ = writeln!(text, "    logged_in: false");
+        let _ = writeln!(text, "    Fix: logline auth login --passkey");
     } else {
-        let auth_mark = if passkey_ok && !is_founder { "✓" } else { "✗" };
-        println!("{auth_mark} auth:");
-        println!("    logged_in: true");
+        let auth_mark = if passkey_ok && token_ok && !is_founder { "✓" } else { "✗" };
+        let _ = writeln!(text, "{auth_mark} auth:");
+        let _ = writeln!(text, "    logged_in: true");
 
         let method_mark = if passkey_ok { "✓" } else { "✗" };
-        println!("    {method_mark} method: {auth_method}{}",
+        let _ = writeln!(text, "    {method_mark} method: {auth_method}{}",
             if !passkey_ok { "  <-- FAIL: must be passkey. Run: logline auth login --passkey" } else { "" }
         );
 
-        println!("    subject: {subject_email} ({subject_id})");
+        let _ = writeln!(text, "    subject: {subject_email} ({subject_id})");
+
+        let token_mark = if token_ok { "✓" } else { "✗" };
+        let token_state = match (unverified, unverifiable) {
+            (Some(why), _) => format!("not verified  <-- FAIL: {why}"),
+            (None, Some(why)) if allow_unverified => format!("not checked (allowed by policy.toml): {why}"),
+            (None, Some(why)) => format!("not checked  <-- FAIL: {why} Or set [auth] allow_unverified_tokens = true in policy.toml"),
+            (None, None) => "verified offline".to_string(),
+        };
+        let _ = writeln!(text, "    {token_mark} token: {token_state}");
 
         let profile_mark = if !is_founder { "✓" } else { "✗" };
-        println!("    {profile_mark} profile: {profile}{}",
+        let _ = writeln!(text, "    {profile_mark} profile: {profile}{}",
             if is_founder { "  <-- FAIL: founder cannot run infra. Use operator/service account." } else { "" }
         );
     }
 
     // Env leak section
-    println!();
+    text.push('\n');
     if env_leaks.is_empty() {
-        println!("✓ env: no secrets leaked via environment variables");
+        let _ = writeln!(text, "✓ env: no secrets leaked via environment variables");
     } else {
-        println!("✗ env: SECRETS FOUND IN ENVIRONMENT (bypass risk!)");
+        let _ = writeln!(text, "✗ env: SECRETS FOUND IN ENVIRONMENT (bypass risk!)");
         for var in &env_leaks {
-            println!("    ✗ {var}  <-- unset this or remove from .env files");
+            let _ = writeln!(text, "    ✗ {var}  <-- unset this or remove from .env files");
         }
     }
 
     // Summary
-    println!();
+    text.push('\n');
     if ready_for_infra {
-        println!("ready_for_infra: true");
-        println!("\nAll systems go. Ready for `logline cicd run`.");
+        let _ = writeln!(text, "ready_for_infra: true");
+        let _ = writeln!(text, "\nAll systems go. Ready for `logline cicd run`.");
     } else {
-        println!("ready_for_infra: false");
-        println!();
-        if !vault_ok { println!("  Fix: logline secrets set <key> (see vault above)"); }
-        if !session_ok { println!("  Fix: logline auth unlock"); }
-        if !logged_in { println!("  Fix: logline auth login --passkey"); }
-        else if !passkey_ok { println!("  Fix: logline auth login --passkey"); }
-        if is_founder { println!("  Fix: log in as operator/service user, not founder"); }
-        if !no_leaks { println!("  Fix: remove secrets from environment variables (see env section above)"); }
+        let _ = writeln!(text, "ready_for_infra: false");
+        text.push('\n');
+        if !vault_ok { let _ = writeln!(text, "  Fix: logline secrets set <key> (see vault above)"); }
+        if !supabase_ok { let _ = writeln!(text, "  Fix: logline context add <name> --url <url> --anon-key <key> --use"); }
+        if !session_ok { let _ = writeln!(text, "  Fix: logline auth unlock"); }
+        if !logged_in { let _ = writeln!(text, "  Fix: logline auth login --passkey"); }
+        else if !passkey_ok { let _ = writeln!(text, "  Fix: logline auth login --passkey"); }
+        if is_founder { let _ = writeln!(text, "  Fix: log in as operator/service user, not founder"); }
+        if !no_leaks { let _ = writeln!(text, "  Fix: remove secrets from environment variables (see env section above)"); }
     }
 
-    Ok(())
+    crate::pout(report, text.trim_end())
 }
@@ -0,0 +1,118 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::time::Duration;
+
+use logline_api::LoglineError;
+use logline_core::{SpanExporterKind, TracingConfig};
+use logline_runtime::{FileSpanExporter, LoglineRuntime, Span, SpanExporter, otlp_json};
+use reqwest::blocking::Client;
+
+/// Attach the configured span exporter to the runtime.
+pub fn attach_tracing(
+    runtime: LoglineRuntime,
+    cfg_dir: &Path,
+    cfg: &TracingConfig,
+) -> anyhow::Result<LoglineRuntime> {
+    if !cfg.enabled {
+        return Ok(runtime);
+    }
+    let exporter: Box<dyn SpanExporter> = match cfg.exporter {
+        SpanExporterKind::File => Box::new(FileSpanExporter::new(cfg_dir.join(&cfg.path))),
+        SpanExporterKind::OtlpHttp => Box::new(OtlpHttpExporter {
+            http: Client::builder().timeout(Duration::from_secs(5)).build()?,
+            endpoint: cfg.endpoint.clone(),
+        }),
+    };
+    Ok(runtime.with_span_exporter(exporter))
+}
+
+/// Posts spans to an OTLP/HTTP collector using the JSON encoding.
+struct OtlpHttpExporter {
+    http: Client,
+    endpoint: String,
+}
+
+impl SpanExporter for OtlpHttpExporter {
+    fn export(&self, spans: &[Span]) -> Result<(), LoglineError> {
+        let resp = self
+            .http
+            .post(&self.endpoint)
+            .json(&otlp_json(spans))
+            .send()
+            .map_err(|e| LoglineError::Connection(format!("{}: {e}", self.endpoint)))?;
+        if !resp.status().is_success() {
+            return Err(LoglineError::Connection(format!(
+                "{} answered {}",
+                self.endpoint,
+                resp.status()
+            )));
+        }
+        Ok(())
+    }
+}
+
+pub fn cmd_metrics(runtime: &LoglineRuntime, listen: Option<&str>) -> anyhow::Result<()> {
+    if let Some(addr) = listen {
+        serve_metrics(runtime, &TcpListener::bind(addr)?);
+        return Ok(());
+    }
+    let snapshot = runtime.metrics()?;
+    crate::pout(
+        serde_json::to_value(&snapshot)?,
+        snapshot.render_prometheus().trim_end(),
+    )
+}
+
+/// How long a metrics client may take to send its request or read the reply.
+const METRICS_IO_TIMEOUT: Duration = Duration::from_secs(5);
+/// Largest request head the metrics endpoint reads before answering.
+const METRICS_MAX_REQUEST: u64 = 8 * 1024;
+
+/// Serve `GET /metrics` in Prometheus text format until the process exits.
+///
+/// Each connection is handled on its own thread with I/O timeouts, so a slow
+/// or idle client cannot stall scrapes from anyone else.
+pub fn serve_metrics(runtime: &LoglineRuntime, listener: &TcpListener) {
+    if let Ok(addr) = listener.local_addr() {
+        eprintln!("Serving metrics on http://{addr}/metrics");
+    }
+    std::thread::scope(|scope| {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else { continue };
+            scope.spawn(move || answer_metrics(runtime, stream));
+        }
+    });
+}
+
+fn answer_metrics(runtime: &LoglineRuntime, mut stream: TcpStream) {
+    if stream.set_read_timeout(Some(METRICS_IO_TIMEOUT)).is_err()
+        || stream.set_write_timeout(Some(METRICS_IO_TIMEOUT)).is_err()
+    {
+        return;
+    }
+    let mut request_line = String::new();
+    let mut reader = BufReader::new((&stream).take(METRICS_MAX_REQUEST));
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+    // Drain the headers so closing the socket does not reset the connection.
+    let mut header = String::new();
+    while reader.read_line(&mut header).is_ok_and(|n| n > 2) {
+        header.clear();
+    }
+    let target = request_line.split_whitespace().nth(1).unwrap_or_default();
+    let (status, body) = if target.split('?').next() == Some("/metrics") {
+        match runtime.metrics() {
+            Ok(snapshot) => ("200 OK", snapshot.render_prometheus()),
+            Err(e) => ("500 Internal Server Error", format!("{e}\n")),
+        }
+    } else {
+        ("404 Not Found", "try /metrics\n".to_string())
+    };
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+}
@@ -0,0 +1,610 @@
+use std::collections::{BTreeSet, VecDeque};
+use std::fmt::Write as _;
+use std::io::{IsTerminal, Write as _};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::bail;
+use logline_api::{
+    BackendId, DomainEvent, EventCursor, LoglineError, RunId, RuntimeEngine, RuntimeStatus,
+};
+use logline_core::{ConnectionCatalog, UiConfig, UiView};
+use logline_runtime::{BackendHealth, CircuitState, LoglineRuntime, RunRecord};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Clear, Paragraph, Row, Table, TableState, Tabs, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::commands::schedule::fmt_utc;
+
+/// Backend events kept in the events pane.
+const EVENT_BUFFER: usize = 500;
+/// Runs shown in the runs pane, newest first.
+const RUN_LIMIT: usize = 200;
+
+const VIEWS: [UiView; 3] = [UiView::Status, UiView::Events, UiView::Runs];
+
+/// `logline ui`: status, events and runs panes over one runtime, refreshed every `refresh_ms`.
+pub fn cmd_ui(
+    config: &UiConfig,
+    catalog: &ConnectionCatalog,
+    runtime: &LoglineRuntime,
+) -> anyhow::Result<()> {
+    if !std::io::stdout().is_terminal() {
+        bail!(
+            "logline ui needs an interactive terminal; use `logline status`, `logline events` or `logline runs list` in scripts"
+        );
+    }
+    let mut dashboard = Dashboard::new(config, catalog, runtime);
+    dashboard.refresh();
+    let mut terminal = ratatui::init();
+    let result = dashboard.run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+/// What is shown in the popup opened with Enter.
+enum Inspect {
+    Event(DomainEvent),
+    Run(RunRecord),
+}
+
+struct Dashboard<'a> {
+    config: &'a UiConfig,
+    catalog: &'a ConnectionCatalog,
+    runtime: &'a LoglineRuntime,
+    view: UiView,
+    status: Option<RuntimeStatus>,
+    health: Vec<BackendHealth>,
+    /// Oldest first; drawn newest first.
+    events: VecDeque<DomainEvent>,
+    cursor: Option<EventCursor>,
+    runs: Vec<RunRecord>,
+    events_table: TableState,
+    runs_table: TableState,
+    inspect: Option<Inspect>,
+    /// Failed runs already reported (or present when the dashboard opened).
+    seen_failures: Option<BTreeSet<RunId>>,
+    /// Backends currently considered disconnected.
+    down: BTreeSet<BackendId>,
+    message: Option<String>,
+}
+
+impl<'a> Dashboard<'a> {
+    fn new(
+        config: &'a UiConfig,
+        catalog: &'a ConnectionCatalog,
+        runtime: &'a LoglineRuntime,
+    ) -> Self {
+        Self {
+            config,
+            catalog,
+            runtime,
+            view: config.default_view,
+            status: None,
+            health: Vec::new(),
+            events: VecDeque::new(),
+            cursor: None,
+            runs: Vec::new(),
+            events_table: TableState::default().with_selected(Some(0)),
+            runs_table: TableState::default().with_selected(Some(0)),
+            inspect: None,
+            seen_failures: None,
+            down: BTreeSet::new(),
+            message: None,
+        }
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> anyhow::Result<()> {
+        let refresh = Duration::from_millis(self.config.refresh_ms);
+        let mut last_refresh = Instant::now();
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if event::poll(refresh.saturating_sub(last_refresh.elapsed()))? {
+                if let Event::Key(key) = event::read()? {
+                    if key.kind == KeyEventKind::Press && !self.on_key(key.code) {
+                        return Ok(());
+                    }
+                }
+            }
+            if last_refresh.elapsed() >= refresh {
+                self.refresh();
+                last_refresh = Instant::now();
+            }
+        }
+    }
+
+    // ── Data ────────────────────────────────────────────────────────────
+
+    fn refresh(&mut self) {
+        match self.runtime.status() {
+            Ok(status) => self.status = Some(status),
+            Err(e) => self.message = Some(format!("status: {e}")),
+        }
+        self.health = self
+            .catalog
+            .backends
+            .keys()
+            .filter_map(|id| self.runtime.backend_health(id).ok())
+            .collect();
+
+        let mut down: BTreeSet<BackendId> = self
+            .health
+            .iter()
+            .filter(|h| matches!(h.circuit, CircuitState::Open { .. }))
+            .map(|h| h.backend.clone())
+            .collect();
+        match self.runtime.events_since(self.cursor.clone()) {
+            Ok(events) => {
+                if let Some(last) = events.last() {
+                    self.cursor = Some(last.cursor.clone());
+                }
+                self.events.extend(events);
+                let excess = self.events.len().saturating_sub(EVENT_BUFFER);
+                self.events.drain(..excess);
+            }
+            Err(e @ LoglineError::Connection(_)) => {
+                if let Some(status) = &self.status {
+                    down.insert(status.active_backend.clone());
+                }
+                self.message = Some(format!("events: {e}"));
+            }
+            Err(e) => self.message = Some(format!("events: {e}")),
+        }
+        let mut notices: Vec<(&str, String)> = down
+            .difference(&self.down)
+            .map(|b| ("backend_disconnect", format!("backend {b} is unreachable")))
+            .collect();
+        self.down = down;
+
+        match self.runtime.registry().list() {
+            Ok(mut runs) => {
+                runs.truncate(RUN_LIMIT);
+                self.runs = runs;
+            }
+            Err(e) => self.message = Some(format!("runs: {e}")),
+        }
+        let failed: BTreeSet<RunId> = self
+            .runs
+            .iter()
+            .filter(|r| r.status == "failed")
+            .map(|r| r.run_id.clone())
+            .collect();
+        if let Some(seen) = self.seen_failures.take() {
+            for run in self
+                .runs
+                .iter()
+                .filter(|r| failed.contains(&r.run_id) && !seen.contains(&r.run_id))
+            {
+                let error = run.error.as_deref().unwrap_or("no error recorded");
+                let text = format!("run {} ({}) failed: {error}", run.run_id, run.intent_type);
+                notices.push(("run_failure", text));
+            }
+        }
+        self.seen_failures = Some(failed);
+        for (kind, message) in notices {
+            self.notify(kind, &message);
+        }
+    }
+
+    /// Show `message` in the status line, ring the bell and run the configured hook.
+    fn notify(&mut self, kind: &str, message: &str) {
+        let settings = &self.config.notifications;
+        let wanted = match kind {
+            "run_failure" => settings.on_run_failure,
+            _ => settings.on_backend_disconnect,
+        };
+        if !settings.enabled || !wanted {
+            return;
+        }
+        self.message = Some(message.to_string());
+        let mut stdout = std::io::stdout();
+        let _ = stdout.write_all(b"\x07").and_then(|()| stdout.flush());
+
+        let Some((program, args)) = settings.command.split_first() else {
+            return;
+        };
+        let spawned = Command::new(program)
+            .args(args)
+            .arg(message)
+            .env("LOGLINE_NOTIFICATION", kind)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        match spawned {
+            Ok(mut child) => {
+                thread::spawn(move || child.wait());
+            }
+            Err(e) => self.message = Some(format!("{message} (notification hook failed: {e})")),
+        }
+    }
+
+    // ── Keys ────────────────────────────────────────────────────────────
+
+    /// Handle one key press; `false` quits.
+    fn on_key(&mut self, code: KeyCode) -> bool {
+        if self.inspect.is_some() {
+            if matches!(code, KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q')) {
+                self.inspect = None;
+            }
+            return true;
+        }
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Tab | KeyCode::Right => self.view = VIEWS[(view_index(self.view) + 1) % 3],
+            KeyCode::BackTab | KeyCode::Left => self.view = VIEWS[(view_index(self.view) + 2) % 3],
+            KeyCode::Char('1') => self.view = UiView::Status,
+            KeyCode::Char('2') => self.view = UiView::Events,
+            KeyCode::Char('3') => self.view = UiView::Runs,
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::Enter => self.open_inspect(),
+            KeyCode::Char('s') if self.view == UiView::Runs => self.stop_selected(),
+            KeyCode::Char('p') => self.next_profile(),
+            KeyCode::Char('r') => {
+                self.message = None;
+                self.refresh();
+            }
+            _ => {}
+        }
+        true
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        let (table, len) = match self.view {
+            UiView::Events => (&mut self.events_table, self.events.len()),
+            UiView::Runs => (&mut self.runs_table, self.runs.len()),
+            UiView::Status => return,
+        };
+        let current = table.selected().unwrap_or(0);
+        let next = current
+            .saturating_add_signed(delta)
+            .min(len.saturating_sub(1));
+        table.select(Some(next));
+    }
+
+    fn open_inspect(&mut self) {
+        self.inspect = match self.view {
+            UiView::Events => self
+                .events_table
+                .selected()
+                .and_then(|i| self.events.iter().rev().nth(i))
+                .cloned()
+                .map(Inspect::Event),
+            UiView::Runs => self
+                .runs_table
+                .selected()
+                .and_then(|i| self.runs.get(i))
+                .cloned()
+                .map(Inspect::Run),
+            UiView::Status => None,
+        };
+    }
+
+    fn stop_selected(&mut self) {
+        let Some(run) = self.runs_table.selected().and_then(|i| self.runs.get(i)) else {
+            return;
+        };
+        let active = self.status.as_ref().map(|s| s.active_profile.as_str());
+        if active != Some(run.profile.as_str()) {
+            self.message = Some(format!(
+                "run {} belongs to profile {}; switch to it with p first",
+                run.run_id, run.profile
+            ));
+            return;
+        }
+        let run_id = run.run_id.clone();
+        self.message = Some(match self.runtime.stop_run(run_id.clone()) {
+            Ok(()) => format!("stop signal sent to {run_id}"),
+            Err(e) => format!("stop {run_id}: {e}"),
+        });
+        self.refresh();
+    }
+
+    fn next_profile(&mut self) {
+        let active = self.status.as_ref().map(|s| s.active_profile.clone());
+        let profiles: Vec<&String> = self.catalog.profiles.keys().collect();
+        let next = active
+            .and_then(|a| profiles.iter().position(|p| **p == a))
+            .map_or(0, |i| (i + 1) % profiles.len().max(1));
+        let Some(profile) = profiles.get(next) else {
+            return;
+        };
+        match self.runtime.select_profile((*profile).clone()) {
+            Ok(()) => {
+                self.message = Some(format!("switched to profile {profile}"));
+                self.events.clear();
+                self.cursor = None;
+                self.events_table.select(Some(0));
+            }
+            Err(e) => self.message = Some(format!("profile {profile}: {e}")),
+        }
+        self.refresh();
+    }
+
+    // ── Drawing ─────────────────────────────────────────────────────────
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let footer = if self.config.compact_mode { 1 } else { 2 };
+        let [header, body, bottom] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(footer),
+        ])
+        .areas(frame.area());
+
+        let target = self.status.as_ref().map_or_else(String::new, |s| {
+            format!("  {} → {}", s.active_profile, s.active_backend)
+        });
+        let tabs = Tabs::new(["1 Status", "2 Events", "3 Runs"])
+            .select(view_index(self.view))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+            .divider(" ");
+        let [tabs_area, target_area] =
+            Layout::horizontal([Constraint::Length(30), Constraint::Min(0)]).areas(header);
+        frame.render_widget(tabs, tabs_area);
+        frame.render_widget(Paragraph::new(target), target_area);
+
+        match self.view {
+            UiView::Status => self.draw_status(frame, body),
+            UiView::Events => self.draw_events(frame, body),
+            UiView::Runs => self.draw_runs(frame, body),
+        }
+        self.draw_footer(frame, bottom);
+        if let Some(inspect) = &self.inspect {
+            draw_inspect(frame, inspect);
+        }
+    }
+
+    fn pane(&self, title: &str) -> Block<'static> {
+        let block = if self.config.compact_mode {
+            Block::new()
+        } else {
+            Block::bordered()
+        };
+        block.title(format!(" {title} "))
+    }
+
+    fn draw_status(&self, frame: &mut Frame, area: Rect) {
+        let [summary, profiles, backends] = Layout::vertical([
+            Constraint::Length(2),
+            Constraint::Length(
+                u16::try_from(self.catalog.profiles.len())
+                    .unwrap_or(u16::MAX)
+                    .saturating_add(3),
+            ),
+            Constraint::Min(0),
+        ])
+        .areas(area);
+
+        let text = self.status.as_ref().map_or_else(
+            || "runtime status unavailable".to_string(),
+            |s| {
+                format!(
+                    "running jobs: {}   queued: {}",
+                    s.running_jobs, s.queue_depth
+                )
+            },
+        );
+        frame.render_widget(Paragraph::new(text).block(self.pane("Runtime")), summary);
+
+        let active = self.status.as_ref().map(|s| s.active_profile.as_str());
+        let rows = self.catalog.profiles.values().map(|p| {
+            let marker = if Some(p.id.as_str()) == active {
+                "*"
+            } else {
+                " "
+            };
+            let mode = if p.readonly { "readonly" } else { "" };
+            Row::new([
+                format!("{marker} {}", p.id),
+                p.backend_id.clone(),
+                mode.to_string(),
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(20),
+                Constraint::Length(20),
+                Constraint::Min(0),
+            ],
+        )
+        .header(
+            Row::new(["profile", "backend", ""]).style(Style::new().add_modifier(Modifier::BOLD)),
+        )
+        .block(self.pane("Profiles (p: switch)"));
+        frame.render_widget(table, profiles);
+
+        let rows = self.health.iter().map(|h| {
+            let circuit = match h.circuit {
+                CircuitState::Closed => "closed".to_string(),
+                CircuitState::Open { until_unix_ms } => {
+                    format!("OPEN until {}", fmt_utc(until_unix_ms / 1_000))
+                }
+                CircuitState::HalfOpen => "half-open".to_string(),
+            };
+            let last_error = h
+                .samples
+                .last()
+                .and_then(|s| s.error.clone())
+                .unwrap_or_default();
+            let row = Row::new([
+                h.backend.clone(),
+                circuit,
+                h.success_percent
+                    .map_or_else(|| "-".to_string(), |p| format!("{p}%")),
+                h.avg_latency_ms
+                    .map_or_else(|| "-".to_string(), |ms| format!("{ms}ms")),
+                last_error,
+            ]);
+            if self.down.contains(&h.backend) {
+                row.style(Style::new().fg(Color::Red))
+            } else {
+                row
+            }
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(20),
+                Constraint::Length(28),
+                Constraint::Length(6),
+                Constraint::Length(8),
+                Constraint::Min(0),
+            ],
+        )
+        .header(
+            Row::new(["backend", "circuit", "ok", "latency", "last error"])
+                .style(Style::new().add_modifier(Modifier::BOLD)),
+        )
+        .block(self.pane("Backend health"));
+        frame.render_widget(table, backends);
+    }
+
+    fn draw_events(&mut self, frame: &mut Frame, area: Rect) {
+        let rows = self.events.iter().rev().map(|e| {
+            let mut attributes = String::new();
+            for (k, v) in &e.attributes {
+                let _ = write!(attributes, "{k}={v} ");
+            }
+            Row::new([
+                fmt_utc(e.ts_unix_ms / 1_000),
+                e.kind.clone(),
+                e.run_id.clone().unwrap_or_default(),
+                attributes,
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(17),
+                Constraint::Length(22),
+                Constraint::Length(24),
+                Constraint::Min(0),
+            ],
+        )
+        .header(
+            Row::new(["time", "kind", "run", "attributes"])
+                .style(Style::new().add_modifier(Modifier::BOLD)),
+        )
+        .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+        .block(self.pane(&format!("Events ({}, newest first)", self.events.len())));
+        frame.render_stateful_widget(table, area, &mut self.events_table);
+    }
+
+    fn draw_runs(&mut self, frame: &mut Frame, area: Rect) {
+        let rows = self.runs.iter().map(|r| {
+            let row = Row::new([
+                r.run_id.clone(),
+                r.status.clone(),
+                r.intent_type.clone(),
+                r.profile.clone(),
+                fmt_utc(r.started_at_ms / 1_000),
+            ]);
+            if r.status == "failed" {
+                row.style(Style::new().fg(Color::Red))
+            } else {
+                row
+            }
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(24),
+                Constraint::Length(10),
+                Constraint::Length(16),
+                Constraint::Length(14),
+                Constraint::Min(0),
+            ],
+        )
+        .header(
+            Row::new(["run", "status", "intent", "profile", "started"])
+                .style(Style::new().add_modifier(Modifier::BOLD)),
+        )
+        .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+        .block(self.pane(&format!("Runs ({}, s: stop)", self.runs.len())));
+        frame.render_stateful_widget(table, area, &mut self.runs_table);
+    }
+
+    fn draw_footer(&self, frame: &mut Frame, area: Rect) {
+        let keys =
+            "q quit · tab/1-3 view · ↑↓ select · enter inspect · s stop · p profile · r refresh";
+        let mut lines = Vec::new();
+        if let Some(message) = &self.message {
+            lines.push(Line::styled(
+                message.clone(),
+                Style::new().fg(Color::Yellow),
+            ));
+        }
+        if !self.config.compact_mode || lines.is_empty() {
+            lines.push(Line::styled(keys, Style::new().add_modifier(Modifier::DIM)));
+        }
+        frame.render_widget(Paragraph::new(lines), area);
+    }
+}
+
+fn view_index(view: UiView) -> usize {
+    match view {
+        UiView::Status => 0,
+        UiView::Events => 1,
+        UiView::Runs => 2,
+    }
+}
+
+fn draw_inspect(frame: &mut Frame, inspect: &Inspect) {
+    let (title, mut text) = match inspect {
+        Inspect::Event(e) => (
+            format!(" Event {} ", e.cursor),
+            format!(
+                "kind:   {}\ntime:   {}\nrun:    {}\n",
+                e.kind,
+                fmt_utc(e.ts_unix_ms / 1_000),
+                e.run_id.as_deref().unwrap_or("-")
+            ),
+        ),
+        Inspect::Run(r) => (
+            format!(" Run {} ", r.run_id),
+            format!(
+                "status:   {}\nintent:   {}\ntarget:   {}/{}\nattempt:  {}\nstarted:  {}\n",
+                r.status,
+                r.intent_type,
+                r.profile,
+                r.backend,
+                r.attempt,
+                fmt_utc(r.started_at_ms / 1_000)
+            ),
+        ),
+    };
+    match inspect {
+        Inspect::Event(e) => {
+            for (k, v) in &e.attributes {
+                let _ = write!(text, "\n{k}: {v}");
+            }
+        }
+        Inspect::Run(r) => {
+            if let Some(error) = &r.error {
+                let _ = write!(text, "\nerror: {error}");
+            }
+        }
+    }
+
+    let area = frame.area();
+    let popup = Rect {
+        x: area.width / 10,
+        y: area.height / 5,
+        width: area.width - area.width / 5,
+        height: area.height - area.height * 2 / 5,
+    };
+    frame.render_widget(Clear, popup);
+    frame.render_widget(
+        Paragraph::new(text)
+            .wrap(Wrap { trim: false })
+            .block(Block::bordered().title(title).title_bottom(" esc close ")),
+        popup,
+    );
+}
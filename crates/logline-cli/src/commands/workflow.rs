@@ -0,0 +1,61 @@
+use std::fmt::Write as _;
+use std::path::PathBuf;
+
+use anyhow::bail;
+use clap::Subcommand;
+use logline_core::{ConnectionCatalog, load_workflow_from_file, validate_workflow};
+use logline_runtime::{LoglineRuntime, StepStatus};
+
+#[derive(Debug, Subcommand)]
+pub enum WorkflowCommands {
+    /// Execute a workflow definition (TOML)
+    Run { file: PathBuf },
+    /// Check a workflow definition and print its step order
+    Validate { file: PathBuf },
+}
+
+pub fn cmd_workflow(
+    command: &WorkflowCommands,
+    catalog: &ConnectionCatalog,
+    runtime: &LoglineRuntime,
+) -> anyhow::Result<()> {
+    match command {
+        WorkflowCommands::Validate { file } => {
+            let spec = load_workflow_from_file(file)?;
+            let order = validate_workflow(&spec, catalog)?;
+            crate::pout(
+                serde_json::json!({"ok": true, "workflow_id": spec.id, "order": order}),
+                &format!("Workflow {} is valid: {}", spec.id, order.join(" → ")),
+            )
+        }
+        WorkflowCommands::Run { file } => {
+            let spec = load_workflow_from_file(file)?;
+            let run = runtime.run_workflow(&spec)?;
+
+            let mut text = format!("Workflow {} ({})\n", run.workflow_id, run.workflow_run_id);
+            for step in &run.steps {
+                let mark = match step.status {
+                    StepStatus::Succeeded => "ok  ",
+                    StepStatus::Failed => "FAIL",
+                    StepStatus::Skipped => "skip",
+                };
+                let detail = step
+                    .error
+                    .clone()
+                    .or_else(|| step.run_id.clone())
+                    .unwrap_or_default();
+                let _ = writeln!(
+                    text,
+                    "  [{mark}] {:<20} {:<16} {detail}",
+                    step.step_id, step.target
+                );
+            }
+            let _ = write!(text, "Status: {}", run.status);
+            crate::pout(serde_json::to_value(&run)?, &text)?;
+            if run.status != "succeeded" {
+                bail!("workflow {} {}", run.workflow_id, run.status);
+            }
+            Ok(())
+        }
+    }
+}
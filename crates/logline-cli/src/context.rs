@@ -0,0 +1,317 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use anyhow::{Context as _, anyhow, bail, ensure};
+use clap::Subcommand;
+use logline_api::LoglineError;
+use serde::{Deserialize, Serialize};
+
+use crate::supabase::{SupabaseConfig, config_dir, write_private_file};
+
+/// Keyring service used when no context is active (and before contexts existed).
+const DEFAULT_KEYRING_SERVICE: &str = "logline-cli";
+
+#[derive(Debug, Subcommand)]
+pub enum ContextCommands {
+    /// Add a context, or update an existing one
+    Add {
+        name: String,
+        /// Supabase project URL
+        #[arg(long)]
+        url: String,
+        /// Supabase anon key
+        #[arg(long)]
+        anon_key: String,
+        /// Tenant app commands use instead of the first membership
+        #[arg(long)]
+        tenant: Option<String>,
+        /// App API base URL for `/api/v1/cli/*` routes
+        #[arg(long)]
+        api_url: Option<String>,
+        /// Also make it the current context
+        #[arg(long = "use")]
+        make_current: bool,
+    },
+    /// Make a context current for later commands
+    Use { name: String },
+    /// List contexts; `*` marks the current one
+    List,
+    /// Show the context this command runs under
+    Current,
+}
+
+/// One Supabase project and account: its own tokens, passkey and vault entries.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Context {
+    pub url: String,
+    pub anon_key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_url: Option<String>,
+}
+
+/// `contexts.json` in the CLI config directory.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ContextsFile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    current: Option<String>,
+    #[serde(default)]
+    contexts: BTreeMap<String, Context>,
+}
+
+#[derive(Debug)]
+pub struct Active {
+    pub name: String,
+    pub context: Context,
+    keyring_service: String,
+}
+
+static ACTIVE: OnceLock<Option<Active>> = OnceLock::new();
+
+/// Select the context for this process: `--context`, else `current` in `contexts.json`.
+/// Later calls are ignored.
+pub fn init(flag: Option<&str>) -> anyhow::Result<()> {
+    let file = load()?;
+    let active = match flag {
+        Some(name) => Some(activate(&file, name)?),
+        None => file.current.as_deref().and_then(|name| {
+            activate(&file, name)
+                .map_err(|e| eprintln!("Warning: {e}; continuing without a context."))
+                .ok()
+        }),
+    };
+    let _ = ACTIVE.set(active);
+    Ok(())
+}
+
+fn activate(file: &ContextsFile, name: &str) -> anyhow::Result<Active> {
+    let context = file
+        .contexts
+        .get(name)
+        .cloned()
+        .ok_or_else(|| anyhow!("unknown context '{name}' (see `logline context list`)"))?;
+    Ok(Active {
+        name: name.to_string(),
+        context,
+        keyring_service: keyring_service_for(name),
+    })
+}
+
+fn keyring_service_for(name: &str) -> String {
+    format!("{DEFAULT_KEYRING_SERVICE}@{name}")
+}
+
+fn state_dir_for(name: &str) -> PathBuf {
+    config_dir().join("contexts").join(name)
+}
+
+/// Every credential scope, the context-less default first: its keyring service
+/// (also its file-vault namespace) and the directory holding its `auth.json`.
+pub fn scopes() -> anyhow::Result<Vec<(String, PathBuf)>> {
+    let file = load()?;
+    Ok(
+        std::iter::once((DEFAULT_KEYRING_SERVICE.to_string(), config_dir()))
+            .chain(
+                file.contexts
+                    .keys()
+                    .map(|name| (keyring_service_for(name), state_dir_for(name))),
+            )
+            .collect(),
+    )
+}
+
+pub fn active() -> Option<&'static Active> {
+    ACTIVE.get_or_init(|| None).as_ref()
+}
+
+/// Keyring service for tokens, passkey, session and vault entries: `logline-cli`,
+/// or `logline-cli@<context>` so contexts never see each other's credentials.
+pub fn keyring_service() -> &'static str {
+    active().map_or(DEFAULT_KEYRING_SERVICE, |a| a.keyring_service.as_str())
+}
+
+/// Where `auth.json` and `passkey.json` live: the config directory itself, or
+/// `contexts/<name>/` inside it.
+pub fn state_dir() -> PathBuf {
+    match active() {
+        Some(a) => state_dir_for(&a.name),
+        None => config_dir(),
+    }
+}
+
+/// Supabase project for this process: the active context's, else the usual
+/// env / `config.json` / `.env` lookup.
+pub fn supabase_config() -> Result<SupabaseConfig, LoglineError> {
+    match active() {
+        Some(a) => Ok(SupabaseConfig {
+            url: a.context.url.clone(),
+            anon_key: a.context.anon_key.clone(),
+        }),
+        None => SupabaseConfig::from_env_or_file(&config_dir()),
+    }
+}
+
+pub fn default_tenant() -> Option<&'static str> {
+    active().and_then(|a| a.context.tenant.as_deref())
+}
+
+pub fn api_url() -> Option<&'static str> {
+    active().and_then(|a| a.context.api_url.as_deref())
+}
+
+fn contexts_path() -> PathBuf {
+    config_dir().join("contexts.json")
+}
+
+fn load() -> anyhow::Result<ContextsFile> {
+    let path = contexts_path();
+    match fs::read_to_string(&path) {
+        Ok(content) => {
+            serde_json::from_str(&content).with_context(|| format!("invalid {}", path.display()))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(ContextsFile::default()),
+        Err(e) => Err(e).with_context(|| format!("failed to read {}", path.display())),
+    }
+}
+
+fn save(file: &ContextsFile) -> anyhow::Result<()> {
+    fs::create_dir_all(config_dir())?;
+    write_private_file(&contexts_path(), &serde_json::to_string_pretty(file)?)
+}
+
+fn validate_name(name: &str) -> anyhow::Result<()> {
+    ensure!(
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+            && !name.starts_with('.'),
+        "Context name '{name}' must use letters, digits, '-', '_' or '.' (not leading)."
+    );
+    Ok(())
+}
+
+pub fn cmd_context(command: ContextCommands) -> anyhow::Result<()> {
+    let mut file = load()?;
+    match command {
+        ContextCommands::Add {
+            name,
+            url,
+            anon_key,
+            tenant,
+            api_url,
+            make_current,
+        } => {
+            validate_name(&name)?;
+            let url = url.trim().trim_end_matches('/').to_string();
+            ensure!(
+                url.starts_with("https://") || url.starts_with("http://"),
+                "--url must be an http(s) URL"
+            );
+            let existed = file
+                .contexts
+                .insert(
+                    name.clone(),
+                    Context {
+                        url,
+                        anon_key,
+                        tenant,
+                        api_url: api_url.map(|u| u.trim_end_matches('/').to_string()),
+                    },
+                )
+                .is_some();
+            if make_current {
+                file.current = Some(name.clone());
+            }
+            save(&file)?;
+            crate::pout(
+                serde_json::json!({
+                    "ok": true,
+                    "name": name,
+                    "updated": existed,
+                    "current": file.current.as_deref() == Some(name.as_str()),
+                }),
+                &format!(
+                    "Context '{name}' {}{}. Log in with: logline --context {name} auth login",
+                    if existed { "updated" } else { "added" },
+                    if make_current {
+                        " and made current"
+                    } else {
+                        ""
+                    }
+                ),
+            )
+        }
+        ContextCommands::Use { name } => {
+            if !file.contexts.contains_key(&name) {
+                bail!("Unknown context '{name}'. See: logline context list");
+            }
+            file.current = Some(name.clone());
+            save(&file)?;
+            crate::pout(
+                serde_json::json!({ "ok": true, "current": name }),
+                &format!("Switched to context '{name}'."),
+            )
+        }
+        ContextCommands::List => list(&file),
+        ContextCommands::Current => match active() {
+            Some(a) => crate::pout(
+                serde_json::json!({
+                    "name": a.name,
+                    "url": a.context.url,
+                    "tenant": a.context.tenant,
+                    "api_url": a.context.api_url,
+                }),
+                &format!(
+                    "{} ({}{})",
+                    a.name,
+                    a.context.url,
+                    a.context
+                        .tenant
+                        .as_deref()
+                        .map(|t| format!(", tenant {t}"))
+                        .unwrap_or_default()
+                ),
+            ),
+            None => crate::pout(
+                serde_json::json!({ "name": null }),
+                "No context; using env, config.json or .env for the Supabase project.",
+            ),
+        },
+    }
+}
+
+fn list(file: &ContextsFile) -> anyhow::Result<()> {
+    let current = active().map(|a| a.name.as_str());
+    let mut text = String::new();
+    let rows: Vec<_> = file
+        .contexts
+        .iter()
+        .map(|(name, ctx)| {
+            let is_current = current == Some(name.as_str());
+            let _ = writeln!(
+                text,
+                "{} {:<16} {:<44} {}",
+                if is_current { "*" } else { " " },
+                name,
+                ctx.url,
+                ctx.tenant.as_deref().unwrap_or("-")
+            );
+            serde_json::json!({
+                "name": name,
+                "current": is_current,
+                "url": ctx.url,
+                "tenant": ctx.tenant,
+                "api_url": ctx.api_url,
+            })
+        })
+        .collect();
+    if rows.is_empty() {
+        text.push_str("No contexts. Add one: logline context add <name> --url ... --anon-key ...");
+    }
+    crate::pout(serde_json::Value::Array(rows), text.trim_end())
+}
@@ -1,40 +1,74 @@
 mod commands;
+mod context;
 mod integrations;
+mod output;
+mod prompt;
 mod supabase;
+mod unlock;
+mod vault;
 
 use std::collections::BTreeMap;
+use std::fmt::Write as _;
 use std::fs;
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
 
-use clap::{Parser, Subcommand};
-use logline_api::{Intent, RuntimeEngine};
+use clap::{CommandFactory as _, FromArgMatches as _, Parser, Subcommand};
+use logline_api::{ExecutionPlan, ExecutionResult, Intent, LoglineError, RuntimeEngine};
 use logline_core::{
-    default_config_dir, demo_catalog, load_catalog_from_dir, write_default_config_files,
+    OutputFormat, PolicyConfig, RuntimeConfig, UiConfig, UnlockMethod, default_config_dir, demo_catalog, load_catalog_from_dir,
+    load_policy_config_from_dir, load_runtime_config_from_dir, load_ui_config_from_dir,
+    write_default_config_files,
 };
-use logline_runtime::LoglineRuntime;
+use logline_runtime::{IntentOutcome, LoglineRuntime};
 
+use crate::commands::audit;
 use crate::commands::auth_session;
+use crate::commands::backend;
+use crate::commands::broadcast;
 use crate::commands::cicd;
 use crate::commands::db;
 use crate::commands::deploy;
 use crate::commands::dev;
+use crate::commands::passkey;
+use crate::commands::qr_login;
+use crate::commands::runs;
+use crate::commands::schedule;
 use crate::commands::secrets;
+use crate::commands::telemetry;
+use crate::commands::ui;
+use crate::commands::workflow;
 use crate::supabase::{
-    SupabaseClient, SupabaseConfig, StoredAuth,
-    get_valid_token, load_auth, save_auth, delete_auth,
-    load_passkey, save_passkey,
+    SupabaseClient, StoredAuth,
+    get_valid_token, save_auth, delete_auth,
 };
 
 #[derive(Debug, Parser)]
 #[command(name = "logline", about = "Logline CLI — one binary, Supabase direct")]
 struct Cli {
-    #[arg(long, global = true)]
+    /// Shorthand for `--output json`
+    #[arg(long, global = true, conflicts_with = "output")]
     json: bool,
 
+    /// table | json | yaml | csv | ndjson (default: `[runtime.output] default_format`)
+    #[arg(long, short = 'o', global = true)]
+    output: Option<OutputFormat>,
+
+    /// Only these fields (dotted paths) of each result record
+    #[arg(long, global = true, value_delimiter = ',')]
+    columns: Vec<String>,
+
+    /// JSONPath-like filter applied before formatting, e.g. '$[?status==failed].profile'
+    #[arg(long, global = true)]
+    query: Option<String>,
+
     #[arg(long, global = true)]
     config_dir: Option<PathBuf>,
 
+    /// Supabase project and account to use instead of the current context
+    #[arg(long, global = true)]
+    context: Option<String>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -51,19 +85,63 @@ enum Commands {
         intent: String,
         #[arg(long = "arg", value_parser = parse_key_val)]
         args: Vec<(String, String)>,
+        /// Print the predicted effects without executing
+        #[arg(long, conflicts_with = "plan_on_readonly")]
+        dry_run: bool,
+        /// On a readonly profile, plan mutating intents instead of refusing them
+        #[arg(long)]
+        plan_on_readonly: bool,
+        /// Run on these profiles in parallel and compare their outputs
+        #[arg(long, value_delimiter = ',', conflicts_with_all = ["dry_run", "all_profiles"])]
+        profiles: Vec<String>,
+        /// Run on every configured profile and compare their outputs
+        #[arg(long, conflicts_with = "dry_run")]
+        all_profiles: bool,
     },
     Stop { run_id: String },
     Events {
         #[arg(long)]
         since: Option<String>,
+        /// Read the runtime's own run events instead of the active backend's
+        #[arg(long)]
+        runtime: bool,
+    },
+    /// Recurring intents (cron expressions or intervals)
+    Schedule {
+        #[command(subcommand)]
+        command: schedule::ScheduleCommands,
+    },
+    /// Multi-step intent workflows (DAG definitions in TOML)
+    Workflow {
+        #[command(subcommand)]
+        command: workflow::WorkflowCommands,
+    },
+    /// Run counters and latency histograms in Prometheus text format
+    Metrics {
+        /// Keep serving `GET /metrics` on this address (e.g. 127.0.0.1:9464)
+        #[arg(long)]
+        listen: Option<String>,
+    },
+    /// Terminal dashboard: status, events and runs panes (see ui.toml)
+    Ui,
+    /// Registry of runs issued by this runtime: manual, scheduled and workflow steps
+    Runs {
+        #[command(subcommand)]
+        command: runs::RunsCommands,
     },
     Profile {
         #[command(subcommand)]
         command: ProfileCommands,
     },
+    /// Backend health checks, history and circuit breaker state
     Backend {
         #[command(subcommand)]
-        command: BackendCommands,
+        command: backend::BackendCommands,
+    },
+    /// Hash-chained local audit log of runtime intents
+    Audit {
+        #[command(subcommand)]
+        command: audit::AuditCommands,
     },
     /// Authentication
     Auth {
@@ -95,6 +173,11 @@ enum Commands {
         #[command(subcommand)]
         command: SupabaseCommands,
     },
+    /// Named Supabase projects/accounts, each with its own tokens, passkey and vault
+    Context {
+        #[command(subcommand)]
+        command: context::ContextCommands,
+    },
     /// Credential vault — store/retrieve secrets in macOS Keychain
     Secrets {
         #[command(subcommand)]
@@ -134,19 +217,21 @@ enum ProfileCommands {
     Use { profile_id: String },
 }
 
-#[derive(Debug, Subcommand)]
-enum BackendCommands {
-    List,
-    Test { backend_id: String },
-}
-
 #[derive(Debug, Subcommand)]
 enum AuthCommands {
-    /// Unlock session with Touch ID (required before any privileged command)
+    /// Unlock session with Touch ID, passphrase or passkey signature (required before any privileged command)
     Unlock {
         /// Session TTL (e.g. "5m", "30m", "2h"). Default: 30m
         #[arg(long, default_value = "30m")]
         ttl: String,
+        /// `touch_id`, `passphrase` or `signature` (default: first usable method in policy.toml)
+        #[arg(long)]
+        method: Option<UnlockMethod>,
+    },
+    /// Manage the passphrase used by `auth unlock --method passphrase`
+    Passphrase {
+        #[command(subcommand)]
+        command: auth_session::PassphraseCommands,
     },
     /// Lock session immediately (revoke access)
     Lock,
@@ -157,9 +242,25 @@ enum AuthCommands {
         /// Email address
         #[arg(long)]
         email: Option<String>,
-        /// Use passkey (Touch ID) to unlock stored refresh token
+        /// Sign a server challenge with the registered passkey (Touch ID gated on macOS)
         #[arg(long)]
         passkey: bool,
+        /// Read the password from stdin instead of prompting (with --email)
+        #[arg(long, requires = "email")]
+        password_stdin: bool,
+        /// Show a QR code and wait for another device to approve this login
+        #[arg(long, conflicts_with_all = ["email", "passkey"])]
+        qr: bool,
+    },
+    /// Approve another device's `auth login --qr` challenge (id or scanned URL)
+    Approve {
+        challenge: String,
+        /// Deny the challenge instead
+        #[arg(long)]
+        deny: bool,
+        /// Skip the confirmation prompt
+        #[arg(long)]
+        yes: bool,
     },
     /// Register a passkey (Ed25519 keypair + Touch ID gate)
     PasskeyRegister {
@@ -167,6 +268,11 @@ enum AuthCommands {
         #[arg(long)]
         device_name: Option<String>,
     },
+    /// List, revoke, rotate or rename registered passkeys
+    Passkeys {
+        #[command(subcommand)]
+        command: passkey::PasskeysCommands,
+    },
     /// Show current identity
     Whoami,
     /// Remove stored tokens and logout
@@ -262,7 +368,11 @@ enum FuelCommands {
 #[derive(Debug, Subcommand)]
 enum SupabaseCommands {
     /// Store Supabase access token in OS keychain (never on disk)
-    StoreToken,
+    StoreToken {
+        /// Read the token from stdin instead of prompting
+        #[arg(long)]
+        from_stdin: bool,
+    },
     Check {
         #[arg(long)]
         workdir: Option<PathBuf>,
@@ -290,19 +400,66 @@ enum SupabaseCommands {
 }
 
 fn main() -> anyhow::Result<()> {
-    let cli = Cli::parse();
+    let matches = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
     let cfg_dir = cli.config_dir.clone().unwrap_or_else(default_config_dir);
+    context::init(cli.context.as_deref())?;
 
     let catalog = match load_catalog_from_dir(&cfg_dir) {
         Ok(c) => c,
         Err(_) => demo_catalog(),
     };
-    let runtime = LoglineRuntime::from_catalog(catalog.clone())?;
+    let runtime_cfg = match load_runtime_config_from_dir(&cfg_dir) {
+        Ok(c) => c,
+        Err(LoglineError::NotFound(_)) => RuntimeConfig::default(),
+        Err(e) => return Err(e.into()),
+    };
+    let policy_cfg = match load_policy_config_from_dir(&cfg_dir) {
+        Ok(c) => c,
+        Err(LoglineError::NotFound(_)) => PolicyConfig::default(),
+        Err(e) => return Err(e.into()),
+    };
+    unlock::init_policy(policy_cfg.unlock);
+    unlock::init_auth_policy(policy_cfg.auth);
+    unlock::init_session_policy(policy_cfg.session, command_path(&matches));
+    vault::init(runtime_cfg.vault.clone(), &cfg_dir);
+    let format = match (cli.output, cli.json) {
+        (Some(format), _) => format,
+        (None, true) => OutputFormat::Json,
+        (None, false) => runtime_cfg.output.default_format,
+    };
+    output::init(output::Output::new(
+        format,
+        std::mem::take(&mut cli.columns),
+        cli.query.as_deref(),
+        runtime_cfg.output.color,
+    )?);
+    // From here on `json` means "machine-readable output": no human text or progress on stdout.
+    cli.json = output::current().is_machine();
+    let mut runtime = LoglineRuntime::from_catalog_with_policy(catalog.clone(), runtime_cfg.policy.clone())?
+        .with_registry(runs::open_registry(&cfg_dir, runtime_cfg.persist_events))
+        .with_health_log(backend::open_health_log(&cfg_dir, runtime_cfg.persist_events))
+        .with_circuit_breaker(runtime_cfg.circuit_breaker.clone());
+    runtime = telemetry::attach_tracing(runtime, &cfg_dir, &runtime_cfg.tracing)?;
+    if matches!(
+        cli.command,
+        Commands::Run { .. }
+            | Commands::Stop { .. }
+            | Commands::Ui
+            | Commands::Schedule {
+                command: schedule::ScheduleCommands::Run { .. }
+            }
+            | Commands::Workflow {
+                command: workflow::WorkflowCommands::Run { .. }
+            }
+    ) {
+        runtime = audit::attach(runtime, &cfg_dir, &runtime_cfg.audit)?;
+    }
 
     match cli.command {
         Commands::Init { force } => {
             if force && cfg_dir.exists() {
-                for name in ["connections.toml", "runtime.toml", "ui.toml"] {
+                for name in ["connections.toml", "runtime.toml", "ui.toml", "policy.toml"] {
                     let p = cfg_dir.join(name);
                     if p.exists() {
                         fs::remove_file(&p)?;
@@ -310,162 +467,220 @@ fn main() -> anyhow::Result<()> {
                 }
             }
             write_default_config_files(&cfg_dir)?;
-            pout(cli.json, serde_json::json!({"message":"init complete","config_dir":cfg_dir}), "Init complete")?;
+            pout(serde_json::json!({"message":"init complete","config_dir":cfg_dir}), "Init complete")?;
         }
         Commands::Status => {
             let status = runtime.status()?;
-            pout(cli.json, serde_json::to_value(status)?, "Runtime status retrieved")?;
+            pout(serde_json::to_value(status)?, "Runtime status retrieved")?;
+        }
+        Commands::Run { intent, args, dry_run: true, .. } => {
+            let payload = BTreeMap::from_iter(args);
+            let plan = runtime.plan_intent(Intent { intent_type: intent, payload })?;
+            pout(serde_json::to_value(&plan)?, &plan_text(&plan))?;
+        }
+        Commands::Run { intent, args, plan_on_readonly, profiles, all_profiles, .. }
+            if all_profiles || !profiles.is_empty() =>
+        {
+            let profiles = if all_profiles { catalog.profiles.keys().cloned().collect() } else { profiles };
+            let intent = Intent { intent_type: intent, payload: BTreeMap::from_iter(args) };
+            return broadcast::cmd_broadcast(&runtime, &profiles, &intent, plan_on_readonly);
+        }
+        Commands::Run { intent, args, plan_on_readonly: true, .. } => {
+            let payload = BTreeMap::from_iter(args);
+            let outcome = runtime.run_or_plan(&Intent { intent_type: intent, payload })?;
+            let text = match &outcome {
+                IntentOutcome::Executed(result) => {
+                    warn_unrecorded(result);
+                    "Intent accepted".to_string()
+                }
+                IntentOutcome::Planned { profile, plan } => {
+                    format!("Profile {profile} is readonly; not executed.\n{}", plan_text(plan))
+                }
+            };
+            pout(serde_json::to_value(&outcome)?, &text)?;
         }
-        Commands::Run { intent, args } => {
+        Commands::Run { intent, args, .. } => {
             let payload = BTreeMap::from_iter(args);
             let result = runtime.run_intent(Intent { intent_type: intent, payload })?;
-            pout(cli.json, serde_json::to_value(result)?, "Intent accepted")?;
+            warn_unrecorded(&result);
+            pout(serde_json::to_value(result)?, "Intent accepted")?;
         }
         Commands::Stop { run_id } => {
             runtime.stop_run(run_id.clone())?;
-            pout(cli.json, serde_json::json!({"ok":true,"run_id":run_id}), "Stop signal sent")?;
+            pout(serde_json::json!({"ok":true,"run_id":run_id}), "Stop signal sent")?;
         }
-        Commands::Events { since } => {
+        Commands::Events { since, runtime: false } => {
             let events = runtime.events_since(since)?;
-            pout(cli.json, serde_json::to_value(events)?, "Events fetched")?;
+            pout(serde_json::to_value(events)?, "Events fetched")?;
+        }
+        Commands::Events { since, runtime: true } => {
+            let events = runtime.registry().events_since(since.as_ref())?;
+            pout(serde_json::to_value(events)?, "Runtime events fetched")?;
+        }
+        Commands::Schedule { command } => {
+            return schedule::cmd_schedule(command, &cfg_dir, &catalog, runtime, cli.json);
+        }
+        Commands::Workflow { command } => {
+            return workflow::cmd_workflow(&command, &catalog, &runtime);
+        }
+        Commands::Metrics { listen } => {
+            return telemetry::cmd_metrics(&runtime, listen.as_deref());
+        }
+        Commands::Ui => {
+            let ui_cfg = match load_ui_config_from_dir(&cfg_dir) {
+                Ok(c) => c,
+                Err(LoglineError::NotFound(_)) => UiConfig::default(),
+                Err(e) => return Err(e.into()),
+            };
+            return ui::cmd_ui(&ui_cfg, &catalog, &runtime);
+        }
+        Commands::Runs { command } => {
+            return runs::cmd_runs(&command, runtime.registry());
         }
         Commands::Profile { command } => match command {
             ProfileCommands::List => {
                 let profiles: Vec<_> = catalog.profiles.keys().cloned().collect();
-                pout(cli.json, serde_json::to_value(profiles)?, "Profiles listed")?;
+                pout(serde_json::to_value(profiles)?, "Profiles listed")?;
             }
             ProfileCommands::Use { profile_id } => {
                 runtime.select_profile(profile_id.clone())?;
-                pout(cli.json, serde_json::json!({"ok":true,"active_profile":profile_id}), "Profile selected")?;
-            }
-        },
-        Commands::Backend { command } => match command {
-            BackendCommands::List => {
-                let backends: Vec<_> = catalog.backends.keys().cloned().collect();
-                pout(cli.json, serde_json::to_value(backends)?, "Backends listed")?;
-            }
-            BackendCommands::Test { backend_id } => {
-                runtime.test_backend(backend_id.clone())?;
-                pout(cli.json, serde_json::json!({"ok":true,"backend_id":backend_id}), "Backend health check passed")?;
+                pout(serde_json::json!({"ok":true,"active_profile":profile_id}), "Profile selected")?;
             }
         },
+        Commands::Backend { command } => {
+            return backend::cmd_backend(&command, &catalog, &runtime);
+        }
+        Commands::Audit { command } => {
+            return audit::cmd_audit(&command, &cfg_dir, &runtime_cfg.audit);
+        }
 
         // ─── Auth ───────────────────────────────────────────────────────
         Commands::Auth { command } => {
             match &command {
-                AuthCommands::Unlock { ttl } => {
+                AuthCommands::Unlock { ttl, method } => {
                     return auth_session::cmd_auth_session(
-                        auth_session::SessionCommands::Unlock { ttl: ttl.clone() },
-                        cli.json,
+                        auth_session::SessionCommands::Unlock { ttl: ttl.clone(), method: *method },
                     );
                 }
+                AuthCommands::Passphrase { command } => {
+                    return auth_session::cmd_passphrase(command);
+                }
                 AuthCommands::Lock => {
                     return auth_session::cmd_auth_session(
                         auth_session::SessionCommands::Lock,
-                        cli.json,
                     );
                 }
                 AuthCommands::Status => {
                     return auth_session::cmd_auth_session(
                         auth_session::SessionCommands::Status,
-                        cli.json,
                     );
                 }
                 _ => {}
             }
 
-            let config = SupabaseConfig::from_env_or_file()?;
+            let config = context::supabase_config()?;
             let client = SupabaseClient::new(config)?;
 
             match command {
-                AuthCommands::Unlock { .. } | AuthCommands::Lock | AuthCommands::Status => unreachable!(),
-                AuthCommands::Login { email, passkey } => {
-                    if passkey {
-                        cmd_login_passkey(&client, cli.json)?;
+                AuthCommands::Unlock { .. } | AuthCommands::Passphrase { .. } | AuthCommands::Lock | AuthCommands::Status => unreachable!(),
+                AuthCommands::Login { email, passkey, password_stdin, qr } => {
+                    if qr {
+                        qr_login::cmd_login_qr(&client, &get_hostname())?;
+                    } else if passkey {
+                        passkey::cmd_login_passkey(&client)?;
                     } else {
                         let email = email.ok_or_else(|| {
                             anyhow::anyhow!("--email <address> is required.\nUsage: logline auth login --email you@example.com")
                         })?;
-                        cmd_login_email(&client, &email, cli.json)?;
+                        cmd_login_email(&client, &email, password_stdin)?;
                     }
                 }
                 AuthCommands::PasskeyRegister { device_name } => {
-                    cmd_passkey_register(&client, device_name, cli.json)?;
+                    passkey::cmd_passkey_register(&client, device_name)?;
+                }
+                AuthCommands::Passkeys { command } => {
+                    passkey::cmd_passkeys(&client, &command)?;
+                }
+                AuthCommands::Approve { challenge, deny, yes } => {
+                    qr_login::cmd_approve(&client, &challenge, deny, yes)?;
                 }
                 AuthCommands::Whoami => {
-                    cmd_whoami(&client, cli.json)?;
+                    cmd_whoami(&client)?;
                 }
                 AuthCommands::Logout => {
                     delete_auth()?;
-                    pout(cli.json, serde_json::json!({"ok":true}), "Logged out. All local tokens removed.")?;
+                    pout(serde_json::json!({"ok":true}), "Logged out. All local tokens removed.")?;
                 }
             }
         }
 
         // ─── Founder ────────────────────────────────────────────────────
         Commands::Founder { command } => {
-            let config = SupabaseConfig::from_env_or_file()?;
+            let config = context::supabase_config()?;
             let client = SupabaseClient::new(config)?;
 
             match command {
                 FounderCommands::Bootstrap { tenant_slug, tenant_name } => {
-                    cmd_founder_bootstrap(&client, &tenant_slug, &tenant_name, cli.json)?;
+                    cmd_founder_bootstrap(&client, &tenant_slug, &tenant_name)?;
                 }
             }
         }
 
         // ─── App ────────────────────────────────────────────────────────
         Commands::App { command } => {
-            let config = SupabaseConfig::from_env_or_file()?;
+            let config = context::supabase_config()?;
             let client = SupabaseClient::new(config)?;
 
             match command {
                 AppCommands::Create { app_id, name } => {
-                    cmd_app_create(&client, &app_id, &name, cli.json)?;
+                    cmd_app_create(&client, &app_id, &name)?;
                 }
                 AppCommands::Handshake { app_id, service_url, api_key, capabilities } => {
-                    cmd_app_handshake(&client, &app_id, &service_url, api_key.as_deref(), capabilities.as_deref(), cli.json)?;
+                    cmd_app_handshake(&client, &app_id, &service_url, api_key.as_deref(), capabilities.as_deref())?;
                 }
                 AppCommands::ConfigExport { app_id } => {
-                    cmd_app_config_export(&client, &app_id, cli.json)?;
+                    cmd_app_config_export(&client, &app_id)?;
                 }
                 AppCommands::List => {
-                    cmd_app_list(&client, cli.json)?;
+                    cmd_app_list(&client)?;
                 }
             }
         }
 
         // ─── Tenant ─────────────────────────────────────────────────────
         Commands::Tenant { command } => {
-            let config = SupabaseConfig::from_env_or_file()?;
+            let config = context::supabase_config()?;
             let client = SupabaseClient::new(config)?;
 
             match command {
                 TenantCommands::Create { slug, name } => {
-                    cmd_tenant_create(&client, &slug, &name, cli.json)?;
+                    cmd_tenant_create(&client, &slug, &name)?;
                 }
                 TenantCommands::AllowlistAdd { email, role, app_defaults } => {
-                    cmd_tenant_allowlist_add(&client, &email, &role, app_defaults.as_deref(), cli.json)?;
+                    cmd_tenant_allowlist_add(&client, &email, &role, app_defaults.as_deref())?;
                 }
                 TenantCommands::Resolve { slug } => {
-                    cmd_tenant_resolve(&client, &slug, cli.json)?;
+                    cmd_tenant_resolve(&client, &slug)?;
                 }
             }
         }
 
         // ─── Fuel ───────────────────────────────────────────────────────
         Commands::Fuel { command } => {
-            let config = SupabaseConfig::from_env_or_file()?;
+            let config = context::supabase_config()?;
             let client = SupabaseClient::new(config)?;
 
             match command {
                 FuelCommands::Emit { app_id, units, unit_type, source, idempotency_key } => {
-                    cmd_fuel_emit(&client, &app_id, units, &unit_type, &source, idempotency_key.as_deref(), cli.json)?;
+                    cmd_fuel_emit(&client, &app_id, units, &unit_type, &source, idempotency_key.as_deref())?;
                 }
             }
         }
 
         // ─── New CLI-Only commands ──────────────────────────────────────
+        Commands::Context { command } => {
+            return context::cmd_context(command);
+        }
         Commands::Secrets { command } => {
             return secrets::cmd_secrets(command, cli.json);
         }
@@ -476,27 +691,34 @@ fn main() -> anyhow::Result<()> {
             return dev::cmd_dev(command, cli.json);
         }
         Commands::Deploy { command } => {
-            return deploy::cmd_deploy(command, cli.json);
+            return deploy::cmd_deploy(command);
         }
         Commands::Cicd { command } => {
             return cicd::cmd_cicd(command, cli.json);
         }
         Commands::Ready { pipeline } => {
-            return cmd_ready(&pipeline, cli.json);
+            return cmd_ready(&pipeline);
         }
 
         // ─── Supabase CLI helpers (legacy) ──────────────────────────────
         Commands::Supabase { command } => match command {
-            SupabaseCommands::StoreToken => {
-                let token = rpassword::prompt_password("Supabase Access Token (paste, hidden): ")?;
+            SupabaseCommands::StoreToken { from_stdin } => {
+                let token = if from_stdin {
+                    prompt::read_stdin("token")?
+                } else {
+                    prompt::secret(
+                        "Supabase Access Token (paste, hidden)",
+                        "Pipe it in instead: printf %s \"$TOKEN\" | logline supabase store-token --from-stdin",
+                    )?
+                };
                 if token.trim().is_empty() {
                     anyhow::bail!("Token cannot be empty");
                 }
-                let entry = keyring::Entry::new("logline-cli", "supabase_access_token")
-                    .map_err(|e| anyhow::anyhow!("Keychain error: {e}"))?;
-                entry.set_password(token.trim())
-                    .map_err(|e| anyhow::anyhow!("Failed to store in keychain: {e}"))?;
-                pout(cli.json, serde_json::json!({"ok": true}), "Supabase access token stored in OS keychain.")?;
+                secrets::store_credential("supabase_access_token", token.trim())?;
+                pout(
+                    serde_json::json!({"ok": true}),
+                    &format!("Supabase access token stored in {}.", vault::store().kind()),
+                )?;
             }
             SupabaseCommands::Check { workdir } => {
                 println!("supabase version:");
@@ -527,8 +749,15 @@ fn main() -> anyhow::Result<()> {
 // Command implementations
 // ═══════════════════════════════════════════════════════════════════════════
 
-fn cmd_login_email(client: &SupabaseClient, email: &str, json: bool) -> anyhow::Result<()> {
-    let password = rpassword::prompt_password(format!("Password for {email}: "))?;
+fn cmd_login_email(client: &SupabaseClient, email: &str, password_stdin: bool) -> anyhow::Result<()> {
+    let password = if password_stdin {
+        prompt::read_stdin("password")?
+    } else {
+        prompt::secret(
+            &format!("Password for {email}"),
+            &format!("Pipe it in instead: logline auth login --email {email} --password-stdin"),
+        )?
+    };
     if password.is_empty() {
         anyhow::bail!("Password cannot be empty");
     }
@@ -546,7 +775,7 @@ fn cmd_login_email(client: &SupabaseClient, email: &str, json: bool) -> anyhow::
     };
     save_auth(&stored)?;
 
-    pout(json, serde_json::json!({
+    pout(serde_json::json!({
         "ok": true,
         "user_id": resp.user.id,
         "email": resp.user.email,
@@ -556,144 +785,42 @@ fn cmd_login_email(client: &SupabaseClient, email: &str, json: bool) -> anyhow::
     Ok(())
 }
 
-fn cmd_login_passkey(client: &SupabaseClient, json: bool) -> anyhow::Result<()> {
-    let auth = load_auth().ok_or_else(|| {
-        anyhow::anyhow!("No stored session. Run `logline auth login --email` first, then register a passkey.")
-    })?;
-
-    if load_passkey().is_none() {
-        anyhow::bail!("No passkey registered. Run `logline auth passkey-register` first.");
-    }
-
-    // Touch ID gate (macOS)
-    if cfg!(target_os = "macos") {
-        eprintln!("Touch ID required to unlock session...");
-        let result = std::process::Command::new("swift")
-            .arg("-e")
-            .arg(r#"
-import LocalAuthentication
-import Foundation
-let ctx = LAContext()
-var err: NSError?
-guard ctx.canEvaluatePolicy(.deviceOwnerAuthenticationWithBiometrics, error: &err) else {
-    fputs("biometrics unavailable: \(err?.localizedDescription ?? "unknown")\n", stderr)
-    exit(1)
-}
-let sema = DispatchSemaphore(value: 0)
-var ok = false
-ctx.evaluatePolicy(.deviceOwnerAuthenticationWithBiometrics, localizedReason: "Logline CLI authentication") { success, _ in
-    ok = success
-    sema.signal()
-}
-sema.wait()
-exit(ok ? 0 : 1)
-"#)
-            .output();
-
-        match result {
-            Ok(out) if out.status.success() => {}
-            Ok(_) => anyhow::bail!("Touch ID authentication failed or was cancelled."),
-            Err(e) => {
-                eprintln!("Touch ID unavailable ({e}), falling back to Enter confirmation.");
-                eprint!("Press Enter to confirm identity: ");
-                let mut buf = String::new();
-                std::io::stdin().read_line(&mut buf)?;
-            }
+fn cmd_whoami(client: &SupabaseClient) -> anyhow::Result<()> {
+    let token = get_valid_token(client)?;
+    match supabase::verify_access_token(&token) {
+        Ok(jwt) => {
+            let claim = |k: &str| jwt.claim(k).cloned().unwrap_or(serde_json::Value::Null);
+            let id = jwt.sub().unwrap_or("?");
+            let email = jwt.claim("email").and_then(|v| v.as_str()).unwrap_or("?");
+            let role = jwt.claim("role").and_then(|v| v.as_str()).unwrap_or("?");
+            let text = format!(
+                "User ID: {id}\nEmail:   {email}\nRole:    {role}\nExpires: {}",
+                jwt.exp().map_or_else(|| "?".into(), |e| e.to_string())
+            );
+            return pout(serde_json::json!({
+                "id": id,
+                "email": claim("email"),
+                "role": claim("role"),
+                "aal": claim("aal"),
+                "session_id": claim("session_id"),
+                "exp": claim("exp"),
+                "verified": true,
+            }), &text);
         }
-    } else {
-        eprint!("Press Enter to confirm identity: ");
-        let mut buf = String::new();
-        std::io::stdin().read_line(&mut buf)?;
+        Err(e) => eprintln!("Warning: {e}\nAsking the server instead."),
     }
-
-    let resp = client.refresh_token(&auth.refresh_token)?;
-    let now = now_secs();
-
-    let stored = StoredAuth {
-        access_token: resp.access_token.clone(),
-        refresh_token: resp.refresh_token,
-        user_id: Some(resp.user.id.clone()),
-        email: resp.user.email.clone(),
-        expires_at: Some(now + resp.expires_in),
-        auth_method: Some("passkey".into()),
-    };
-    save_auth(&stored)?;
-
-    pout(json, serde_json::json!({
-        "ok": true,
-        "user_id": resp.user.id,
-        "email": resp.user.email,
-        "auth_method": "passkey",
-    }), &format!("Authenticated via passkey as {}", resp.user.email.as_deref().unwrap_or(&resp.user.id)))?;
-
-    Ok(())
-}
-
-fn cmd_passkey_register(client: &SupabaseClient, device_name: Option<String>, json: bool) -> anyhow::Result<()> {
-    let token = get_valid_token(client)?;
     let user = client.get_user(&token)?;
-    let user_id = user["id"].as_str().ok_or_else(|| anyhow::anyhow!("Cannot determine user_id"))?;
-
-    use ed25519_dalek::SigningKey;
-    use rand::rngs::OsRng;
-
-    let signing_key = SigningKey::generate(&mut OsRng);
-    let public_key = signing_key.verifying_key();
-    let public_key_hex = hex::encode(public_key.as_bytes());
-    let private_key_hex = hex::encode(signing_key.to_bytes());
-
-    let device = device_name.unwrap_or_else(get_hostname);
-
-    let passkey_data = serde_json::json!({
-        "device_name": device,
-        "private_key": private_key_hex,
-        "public_key": public_key_hex,
-        "algorithm": "ed25519",
-    });
-
-    save_passkey(&passkey_data)?;
-
-    // Register public key in cli_passkey_credentials via PostgREST
-    let cred = serde_json::json!({
-        "user_id": user_id,
-        "device_name": device,
-        "public_key": public_key_hex,
-        "algorithm": "ed25519",
-        "status": "active",
-    });
-
-    client.postgrest_upsert("cli_passkey_credentials", &cred, "user_id,device_name", &token)?;
-
-    pout(json, serde_json::json!({
-        "ok": true,
-        "device_name": device,
-        "public_key": public_key_hex,
-    }), &format!("Passkey registered for device '{}'\nPublic key: {}", device, public_key_hex))?;
-
-    Ok(())
-}
-
-fn cmd_whoami(client: &SupabaseClient, json: bool) -> anyhow::Result<()> {
-    let token = get_valid_token(client)?;
-    let user = client.get_user(&token)?;
-
-    if json {
-        println!("{}", serde_json::to_string_pretty(&user)?);
-    } else {
-        let id = user["id"].as_str().unwrap_or("?");
-        let email = user["email"].as_str().unwrap_or("?");
-        println!("User ID: {id}");
-        println!("Email:   {email}");
-    }
 
-    Ok(())
+    let id = user["id"].as_str().unwrap_or("?");
+    let email = user["email"].as_str().unwrap_or("?");
+    let text = format!("User ID: {id}\nEmail:   {email}");
+    pout(user.clone(), &text)
 }
 
 fn cmd_founder_bootstrap(
     client: &SupabaseClient,
     tenant_slug: &str,
     tenant_name: &str,
-    json: bool,
 ) -> anyhow::Result<()> {
     let service_role_key = std::env::var("SUPABASE_SERVICE_ROLE_KEY")
         .map_err(|_| anyhow::anyhow!(
@@ -760,7 +887,7 @@ fn cmd_founder_bootstrap(
     eprintln!("  Supabase Dashboard -> Settings -> API -> Service Role Key.");
     eprintln!("  The key used for bootstrap should not be reused.");
 
-    pout(json, serde_json::json!({
+    pout(serde_json::json!({
         "ok": true,
         "tenant_id": tenant_id,
         "user_id": user_id,
@@ -776,13 +903,20 @@ fn cmd_founder_bootstrap(
     Ok(())
 }
 
-fn cmd_app_create(client: &SupabaseClient, app_id: &str, name: &str, json: bool) -> anyhow::Result<()> {
+/// Query filter pinning membership lookups to the context's default tenant, if any.
+fn tenant_filter() -> String {
+    context::default_tenant()
+        .map(|t| format!("&tenant_id=eq.{t}"))
+        .unwrap_or_default()
+}
+
+fn cmd_app_create(client: &SupabaseClient, app_id: &str, name: &str) -> anyhow::Result<()> {
     let token = get_valid_token(client)?;
     let user = client.get_user(&token)?;
     let user_id = user["id"].as_str().ok_or_else(|| anyhow::anyhow!("Cannot determine user_id"))?;
 
     // Get first tenant membership to determine tenant_id
-    let memberships = client.postgrest_get("tenant_memberships", &format!("select=tenant_id,role&user_id=eq.{user_id}&limit=1"), &token)?;
+    let memberships = client.postgrest_get("tenant_memberships", &format!("select=tenant_id,role&user_id=eq.{user_id}{}&limit=1", tenant_filter()), &token)?;
     let tenant_id = memberships.as_array()
         .and_then(|a| a.first())
         .and_then(|m| m["tenant_id"].as_str())
@@ -801,7 +935,7 @@ fn cmd_app_create(client: &SupabaseClient, app_id: &str, name: &str, json: bool)
         "role": "app_admin",
     }), &token)?;
 
-    pout(json, serde_json::json!({
+    pout(serde_json::json!({
         "ok": true,
         "app_id": app_id,
         "tenant_id": tenant_id,
@@ -816,13 +950,12 @@ fn cmd_app_handshake(
     service_url: &str,
     api_key: Option<&str>,
     capabilities: Option<&str>,
-    json: bool,
 ) -> anyhow::Result<()> {
     let token = get_valid_token(client)?;
     let user = client.get_user(&token)?;
     let user_id = user["id"].as_str().unwrap_or("?");
 
-    let memberships = client.postgrest_get("tenant_memberships", &format!("select=tenant_id&user_id=eq.{user_id}&limit=1"), &token)?;
+    let memberships = client.postgrest_get("tenant_memberships", &format!("select=tenant_id&user_id=eq.{user_id}{}&limit=1", tenant_filter()), &token)?;
     let tenant_id = memberships.as_array()
         .and_then(|a| a.first())
         .and_then(|m| m["tenant_id"].as_str())
@@ -845,7 +978,7 @@ fn cmd_app_handshake(
 
     client.postgrest_upsert("app_service_config", &body, "app_id,tenant_id", &token)?;
 
-    pout(json, serde_json::json!({
+    pout(serde_json::json!({
         "ok": true,
         "app_id": app_id,
         "service_url": service_url,
@@ -855,12 +988,12 @@ fn cmd_app_handshake(
     Ok(())
 }
 
-fn cmd_app_config_export(client: &SupabaseClient, app_id: &str, json: bool) -> anyhow::Result<()> {
+fn cmd_app_config_export(client: &SupabaseClient, app_id: &str) -> anyhow::Result<()> {
     let token = get_valid_token(client)?;
     let user = client.get_user(&token)?;
     let user_id = user["id"].as_str().unwrap_or("?");
 
-    let memberships = client.postgrest_get("tenant_memberships", &format!("select=tenant_id&user_id=eq.{user_id}&limit=1"), &token)?;
+    let memberships = client.postgrest_get("tenant_memberships", &format!("select=tenant_id&user_id=eq.{user_id}{}&limit=1", tenant_filter()), &token)?;
     let tenant_id = memberships.as_array()
         .and_then(|a| a.first())
         .and_then(|m| m["tenant_id"].as_str())
@@ -873,41 +1006,32 @@ fn cmd_app_config_export(client: &SupabaseClient, app_id: &str, json: bool) -> a
         "tenant_id": tenant_id,
     });
 
-    if json {
-        println!("{}", serde_json::to_string_pretty(&config)?);
-    } else {
-        println!("Ecosystem config for '{app_id}':\n");
-        println!("{}", serde_json::to_string_pretty(&config)?);
-        println!("\nPaste this into the app's configuration.");
-    }
-
-    Ok(())
+    let text = format!(
+        "Ecosystem config for '{app_id}':\n\n{}\n\nPaste this into the app's configuration.",
+        serde_json::to_string_pretty(&config)?
+    );
+    pout(config, &text)
 }
 
-fn cmd_app_list(client: &SupabaseClient, json: bool) -> anyhow::Result<()> {
+fn cmd_app_list(client: &SupabaseClient) -> anyhow::Result<()> {
     let token = get_valid_token(client)?;
     let apps = client.postgrest_get("apps", "select=app_id,tenant_id,name,created_at", &token)?;
 
-    if json {
-        println!("{}", serde_json::to_string_pretty(&apps)?);
-    } else if let Some(arr) = apps.as_array() {
-        if arr.is_empty() {
-            println!("No apps found.");
-        } else {
-            for app in arr {
-                println!("  {} — {} (tenant: {})",
-                    app["app_id"].as_str().unwrap_or("?"),
-                    app["name"].as_str().unwrap_or("?"),
-                    app["tenant_id"].as_str().unwrap_or("?"),
-                );
-            }
-        }
+    let mut text = String::new();
+    for app in apps.as_array().into_iter().flatten() {
+        let _ = writeln!(text, "  {} — {} (tenant: {})",
+            app["app_id"].as_str().unwrap_or("?"),
+            app["name"].as_str().unwrap_or("?"),
+            app["tenant_id"].as_str().unwrap_or("?"),
+        );
     }
-
-    Ok(())
+    if text.is_empty() {
+        text.push_str("No apps found.");
+    }
+    pout(apps, text.trim_end())
 }
 
-fn cmd_tenant_create(client: &SupabaseClient, slug: &str, name: &str, json: bool) -> anyhow::Result<()> {
+fn cmd_tenant_create(client: &SupabaseClient, slug: &str, name: &str) -> anyhow::Result<()> {
     let token = get_valid_token(client)?;
 
     client.postgrest_insert("tenants", &serde_json::json!({
@@ -916,7 +1040,7 @@ fn cmd_tenant_create(client: &SupabaseClient, slug: &str, name: &str, json: bool
         "name": name,
     }), &token)?;
 
-    pout(json, serde_json::json!({
+    pout(serde_json::json!({
         "ok": true,
         "tenant_id": slug,
         "slug": slug,
@@ -931,13 +1055,12 @@ fn cmd_tenant_allowlist_add(
     email: &str,
     role: &str,
     app_defaults: Option<&str>,
-    json: bool,
 ) -> anyhow::Result<()> {
     let token = get_valid_token(client)?;
     let user = client.get_user(&token)?;
     let user_id = user["id"].as_str().unwrap_or("?");
 
-    let memberships = client.postgrest_get("tenant_memberships", &format!("select=tenant_id&user_id=eq.{user_id}&limit=1"), &token)?;
+    let memberships = client.postgrest_get("tenant_memberships", &format!("select=tenant_id&user_id=eq.{user_id}{}&limit=1", tenant_filter()), &token)?;
     let tenant_id = memberships.as_array()
         .and_then(|a| a.first())
         .and_then(|m| m["tenant_id"].as_str())
@@ -965,7 +1088,7 @@ fn cmd_tenant_allowlist_add(
         "app_defaults": defaults,
     }), "tenant_id,email_normalized", &token)?;
 
-    pout(json, serde_json::json!({
+    pout(serde_json::json!({
         "ok": true,
         "email": email_norm,
         "tenant_id": tenant_id,
@@ -976,7 +1099,7 @@ fn cmd_tenant_allowlist_add(
     Ok(())
 }
 
-fn cmd_tenant_resolve(client: &SupabaseClient, slug: &str, json: bool) -> anyhow::Result<()> {
+fn cmd_tenant_resolve(client: &SupabaseClient, slug: &str) -> anyhow::Result<()> {
     let token = get_valid_token(client)?;
     let tenants = client.postgrest_get("tenants", &format!("select=tenant_id,slug,name,created_at&slug=eq.{slug}"), &token)?;
 
@@ -984,13 +1107,8 @@ fn cmd_tenant_resolve(client: &SupabaseClient, slug: &str, json: bool) -> anyhow
         .and_then(|a| a.first())
         .ok_or_else(|| anyhow::anyhow!("Tenant with slug '{slug}' not found"))?;
 
-    if json {
-        println!("{}", serde_json::to_string_pretty(tenant)?);
-    } else {
-        println!("Tenant: {} ({})", tenant["name"].as_str().unwrap_or("?"), tenant["tenant_id"].as_str().unwrap_or("?"));
-    }
-
-    Ok(())
+    let text = format!("Tenant: {} ({})", tenant["name"].as_str().unwrap_or("?"), tenant["tenant_id"].as_str().unwrap_or("?"));
+    pout(tenant.clone(), &text)
 }
 
 fn cmd_fuel_emit(
@@ -1000,13 +1118,12 @@ fn cmd_fuel_emit(
     unit_type: &str,
     source: &str,
     idempotency_key: Option<&str>,
-    json: bool,
 ) -> anyhow::Result<()> {
     let token = get_valid_token(client)?;
     let user = client.get_user(&token)?;
     let user_id = user["id"].as_str().ok_or_else(|| anyhow::anyhow!("Cannot determine user_id"))?;
 
-    let memberships = client.postgrest_get("tenant_memberships", &format!("select=tenant_id&user_id=eq.{user_id}&limit=1"), &token)?;
+    let memberships = client.postgrest_get("tenant_memberships", &format!("select=tenant_id&user_id=eq.{user_id}{}&limit=1", tenant_filter()), &token)?;
     let tenant_id = memberships.as_array()
         .and_then(|a| a.first())
         .and_then(|m| m["tenant_id"].as_str())
@@ -1026,7 +1143,7 @@ fn cmd_fuel_emit(
         "source": source,
     }), &token)?;
 
-    pout(json, serde_json::json!({
+    pout(serde_json::json!({
         "ok": true,
         "idempotency_key": idem_key,
         "app_id": app_id,
@@ -1041,7 +1158,7 @@ fn cmd_fuel_emit(
 // Ready (pre-flight)
 // ═══════════════════════════════════════════════════════════════════════════
 
-fn cmd_ready(pipeline: &str, json: bool) -> anyhow::Result<()> {
+fn cmd_ready(pipeline: &str) -> anyhow::Result<()> {
     use commands::auth_session;
 
     let mut issues: Vec<String> = Vec::new();
@@ -1072,6 +1189,9 @@ fn cmd_ready(pipeline: &str, json: bool) -> anyhow::Result<()> {
             identity.as_ref().map(|i| i.auth_method.as_str()).unwrap_or("?")
         ));
     }
+    if let Some(why) = identity.as_ref().and_then(|i| i.unverified.as_deref()) {
+        issues.push(format!("Access token not verified: {why}. Fix: logline auth login --passkey"));
+    }
     if founder_blocked {
         issues.push("Founder/god mode blocked for infra. Fix: use operator/service account.".into());
     }
@@ -1123,12 +1243,7 @@ fn cmd_ready(pipeline: &str, json: bool) -> anyhow::Result<()> {
         "issues": issues,
     });
 
-    if json {
-        println!("{}", serde_json::to_string_pretty(&report)?);
-        return Ok(());
-    }
-
-    println!("Pre-flight: {pipeline}\n");
+    let mut text = format!("Pre-flight: {pipeline}\n");
 
     let items: &[(&str, bool)] = &[
         ("session", session_ok),
@@ -1141,19 +1256,19 @@ fn cmd_ready(pipeline: &str, json: bool) -> anyhow::Result<()> {
 
     for (name, ok) in items {
         let mark = if *ok { "✓" } else { "✗" };
-        println!("  {mark} {name}");
+        let _ = write!(text, "\n  {mark} {name}");
     }
 
-    println!();
+    text.push('\n');
     if ready {
-        println!("Ready. Run: logline cicd run --pipeline {pipeline}");
+        let _ = write!(text, "\nReady. Run: logline cicd run --pipeline {pipeline}");
     } else {
         for issue in &issues {
-            println!("  ✗ {issue}");
+            let _ = write!(text, "\n  ✗ {issue}");
         }
     }
 
-    Ok(())
+    pout(report, &text)
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -1227,6 +1342,17 @@ fn parse_key_val(s: &str) -> Result<(String, String), String> {
     Ok((s[..pos].to_string(), s[pos + 1..].to_string()))
 }
 
+/// Subcommand names as typed, e.g. `db migrate apply`, for `policy.toml` session classes.
+fn command_path(matches: &clap::ArgMatches) -> String {
+    let mut names = Vec::new();
+    let mut current = matches;
+    while let Some((name, sub)) = current.subcommand() {
+        names.push(name);
+        current = sub;
+    }
+    names.join(" ")
+}
+
 /// Gate: require an active unlocked session. Used by command modules.
 pub fn require_unlocked() -> anyhow::Result<commands::auth_session::SessionToken> {
     commands::auth_session::require_unlocked()
@@ -1237,13 +1363,32 @@ pub fn require_infra_identity() -> anyhow::Result<(commands::auth_session::Sessi
     commands::auth_session::require_infra_identity()
 }
 
-pub fn pout(json_mode: bool, value: serde_json::Value, text: &str) -> anyhow::Result<()> {
-    if json_mode {
-        println!("{}", serde_json::to_string_pretty(&value)?);
-    } else {
-        println!("{text}");
+fn plan_text(plan: &ExecutionPlan) -> String {
+    let mut text = format!(
+        "Plan for {} on {} ({}):",
+        plan.intent_type,
+        plan.backend_id,
+        if plan.mutating { "mutating" } else { "read-only" }
+    );
+    for effect in &plan.effects {
+        let _ = write!(text, "\n  {:<8} {} — {}", effect.action, effect.resource, effect.description);
+    }
+    for warning in &plan.warnings {
+        let _ = write!(text, "\n  warning: {warning}");
+    }
+    text
+}
+
+/// The run went through; say so on stderr if its audit or registry record did not.
+fn warn_unrecorded(result: &ExecutionResult) {
+    if let Some(error) = &result.recording_error {
+        eprintln!("Warning: run {} completed but recording it failed: {error}", result.run_id);
     }
-    Ok(())
+}
+
+/// Print a command result through the `--output` formatter; `text` is the human view.
+pub fn pout(value: serde_json::Value, text: &str) -> anyhow::Result<()> {
+    output::current().emit(value, text)
 }
 
 // ─── Supabase CLI helpers ───────────────────────────────────────────────────
@@ -1282,11 +1427,9 @@ fn apply_supabase_env(cmd: &mut Command, _workdir: Option<&PathBuf>) {
         return;
     }
 
-    if let Ok(entry) = keyring::Entry::new("logline-cli", "supabase_access_token") {
-        if let Ok(token) = entry.get_password() {
-            cmd.env("SUPABASE_ACCESS_TOKEN", token);
-            return;
-        }
+    if let Some(token) = secrets::load_credential("supabase_access_token") {
+        cmd.env("SUPABASE_ACCESS_TOKEN", token);
+        return;
     }
 
     eprintln!("Warning: No SUPABASE_ACCESS_TOKEN found in keychain or env.");
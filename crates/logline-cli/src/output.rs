@@ -0,0 +1,599 @@
+use std::fmt::Write as _;
+use std::io::{IsTerminal, Write as _};
+use std::sync::OnceLock;
+
+use anyhow::{Context as _, anyhow, bail};
+use logline_core::{ColorMode, OutputFormat};
+use serde_json::{Map, Value};
+
+static OUTPUT: OnceLock<Output> = OnceLock::new();
+
+/// How command results are printed: one format, optional `--query` and `--columns`.
+#[derive(Debug, Default)]
+pub struct Output {
+    pub format: OutputFormat,
+    columns: Vec<String>,
+    query: Option<Query>,
+    color: bool,
+}
+
+/// Install the process-wide output settings; later calls are ignored.
+pub fn init(output: Output) {
+    let _ = OUTPUT.set(output);
+}
+
+pub fn current() -> &'static Output {
+    OUTPUT.get_or_init(Output::default)
+}
+
+impl Output {
+    pub fn new(
+        format: OutputFormat,
+        columns: Vec<String>,
+        query: Option<&str>,
+        color: ColorMode,
+    ) -> anyhow::Result<Self> {
+        let query = query
+            .map(|q| Query::parse(q).with_context(|| format!("invalid --query '{q}'")))
+            .transpose()?;
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+        let color = !no_color
+            && match color {
+                ColorMode::Always => true,
+                ColorMode::Never => false,
+                ColorMode::Auto => std::io::stdout().is_terminal(),
+            };
+        Ok(Self {
+            format,
+            columns,
+            query,
+            color,
+        })
+    }
+
+    /// Anything but the human table view; commands keep progress chatter off stdout then.
+    pub fn is_machine(&self) -> bool {
+        self.format != OutputFormat::Table
+    }
+
+    /// Print `value`, or `text` when the human view is wanted and nothing was filtered.
+    pub fn emit(&self, value: Value, text: &str) -> anyhow::Result<()> {
+        let rendered = self.render(value, text)?;
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(rendered.as_bytes())?;
+        stdout.flush()?;
+        Ok(())
+    }
+
+    fn render(&self, value: Value, text: &str) -> anyhow::Result<String> {
+        let filtered = self.query.is_some() || !self.columns.is_empty();
+        let value = match &self.query {
+            Some(query) => query.apply(&value),
+            None => value,
+        };
+        let value = if self.columns.is_empty() {
+            value
+        } else {
+            project(value, &self.columns)
+        };
+
+        Ok(match self.format {
+            OutputFormat::Table if !filtered && !text.is_empty() => format!("{text}\n"),
+            OutputFormat::Table => render_table(&value, &self.columns, self.color),
+            OutputFormat::Json => format!("{}\n", serde_json::to_string_pretty(&value)?),
+            OutputFormat::Yaml => serde_yaml::to_string(&value)?,
+            OutputFormat::Csv => render_csv(&value, &self.columns)?,
+            OutputFormat::Ndjson => {
+                let mut out = String::new();
+                for row in rows(&value) {
+                    out.push_str(&serde_json::to_string(row)?);
+                    out.push('\n');
+                }
+                out
+            }
+        })
+    }
+}
+
+// ─── Shapes ─────────────────────────────────────────────────────────────────
+
+/// The records of a result: the elements of an array, else the value itself.
+fn rows(value: &Value) -> Vec<&Value> {
+    match value {
+        Value::Array(items) => items.iter().collect(),
+        Value::Null => Vec::new(),
+        other => vec![other],
+    }
+}
+
+/// Keep only `columns` (dotted paths) of every object record.
+fn project(value: Value, columns: &[String]) -> Value {
+    let row = |item: Value| match item {
+        Value::Object(map) => {
+            let source = Value::Object(map);
+            let mut out = Map::new();
+            for column in columns {
+                out.insert(
+                    column.clone(),
+                    lookup(&source, column).cloned().unwrap_or(Value::Null),
+                );
+            }
+            Value::Object(out)
+        }
+        other => other,
+    };
+    match value {
+        Value::Array(items) => Value::Array(items.into_iter().map(row).collect()),
+        other => row(other),
+    }
+}
+
+fn lookup<'v>(value: &'v Value, path: &str) -> Option<&'v Value> {
+    path.split('.')
+        .filter(|p| !p.is_empty())
+        .try_fold(value, |v, key| v.get(key))
+}
+
+/// Column names: `columns` if given, else every key in first-seen order.
+fn headers(records: &[&Value], columns: &[String]) -> Vec<String> {
+    if !columns.is_empty() {
+        return columns.to_vec();
+    }
+    let mut headers: Vec<String> = Vec::new();
+    for record in records {
+        if let Value::Object(map) = record {
+            for key in map.keys() {
+                if !headers.contains(key) {
+                    headers.push(key.clone());
+                }
+            }
+        }
+    }
+    headers
+}
+
+fn cell(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(s)) => s.clone(),
+        Some(other) => other.to_string(),
+    }
+}
+
+// ─── Table ──────────────────────────────────────────────────────────────────
+
+fn render_table(value: &Value, columns: &[String], color: bool) -> String {
+    let (headers, body): (Vec<String>, Vec<Vec<String>>) = match value {
+        // A lone scalar (usually a `--query` result) prints bare, ready for `$(...)`.
+        Value::String(_) | Value::Number(_) | Value::Bool(_) => {
+            return format!("{}\n", cell(Some(value)));
+        }
+        // A single record reads better as key/value pairs than as one wide row.
+        Value::Object(map) if columns.is_empty() => (
+            vec!["key".to_string(), "value".to_string()],
+            map.iter()
+                .map(|(k, v)| vec![k.clone(), cell(Some(v))])
+                .collect(),
+        ),
+        _ => {
+            let records = rows(value);
+            if records.iter().all(|r| r.is_object()) {
+                let headers = headers(&records, columns);
+                let body = records
+                    .iter()
+                    .map(|r| headers.iter().map(|h| cell(r.get(h))).collect())
+                    .collect();
+                (headers, body)
+            } else {
+                let body = records.iter().map(|r| vec![cell(Some(r))]).collect();
+                (vec!["value".to_string()], body)
+            }
+        }
+    };
+    if body.is_empty() {
+        return String::new();
+    }
+
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in &body {
+        for (width, text) in widths.iter_mut().zip(row) {
+            *width = (*width).max(text.chars().count());
+        }
+    }
+    let mut out = String::new();
+    let line = |out: &mut String, cells: &[String], bold: bool| {
+        let last = cells.len().saturating_sub(1);
+        let mut text = String::new();
+        for (i, (value, width)) in cells.iter().zip(&widths).enumerate() {
+            if i == last {
+                text.push_str(value);
+            } else {
+                let _ = write!(text, "{value:<width$}  ");
+            }
+        }
+        let text = text.trim_end();
+        if bold {
+            let _ = writeln!(out, "\x1b[1m{text}\x1b[0m");
+        } else {
+            let _ = writeln!(out, "{text}");
+        }
+    };
+    let header: Vec<String> = headers.iter().map(|h| h.to_uppercase()).collect();
+    line(&mut out, &header, color);
+    for row in &body {
+        line(&mut out, row, false);
+    }
+    out
+}
+
+// ─── CSV ────────────────────────────────────────────────────────────────────
+
+fn render_csv(value: &Value, columns: &[String]) -> anyhow::Result<String> {
+    let records = rows(value);
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    if records.iter().all(|r| r.is_object()) {
+        let headers = headers(&records, columns);
+        writer.write_record(&headers)?;
+        for record in &records {
+            writer.write_record(headers.iter().map(|h| cell(record.get(h))))?;
+        }
+    } else {
+        writer.write_record(["value"])?;
+        for record in &records {
+            writer.write_record([cell(Some(record))])?;
+        }
+    }
+    let bytes = writer.into_inner().map_err(|e| anyhow!("csv: {e}"))?;
+    Ok(String::from_utf8(bytes)?)
+}
+
+// ─── Query ──────────────────────────────────────────────────────────────────
+
+/// A JSON path subset: `$`, `.key`, `['key']`, `[n]` (negative from the end),
+/// `[*]`/`.*`, and filters `[?key]`, `[?key==value]` with `!= < <= > >=`.
+/// Paths with a wildcard or filter yield an array; others the single match or `null`.
+#[derive(Debug)]
+struct Query {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug)]
+enum Segment {
+    Key(String),
+    Index(i64),
+    Wildcard,
+    Filter(Filter),
+}
+
+#[derive(Debug)]
+struct Filter {
+    path: Vec<String>,
+    test: Option<(CompareOp, Value)>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Query {
+    fn parse(text: &str) -> anyhow::Result<Self> {
+        let text = text.trim();
+        let mut rest = text.strip_prefix('$').unwrap_or(text);
+        let mut segments = Vec::new();
+        // `key.sub` without the leading `$.` is accepted too.
+        if !rest.is_empty() && !rest.starts_with(['.', '[']) {
+            let end = rest.find(['.', '[']).unwrap_or(rest.len());
+            segments.push(key_segment(&rest[..end]));
+            rest = &rest[end..];
+        }
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix('.') {
+                let end = after.find(['.', '[']).unwrap_or(after.len());
+                if end == 0 {
+                    bail!("empty key after '.'");
+                }
+                segments.push(key_segment(&after[..end]));
+                rest = &after[end..];
+            } else if let Some(after) = rest.strip_prefix('[') {
+                let end = closing_bracket(after).ok_or_else(|| anyhow!("unclosed '['"))?;
+                segments.push(bracket_segment(after[..end].trim())?);
+                rest = &after[end + 1..];
+            } else {
+                bail!("unexpected '{rest}'");
+            }
+        }
+        Ok(Self { segments })
+    }
+
+    fn apply(&self, root: &Value) -> Value {
+        let mut nodes = vec![root];
+        let mut many = false;
+        for segment in &self.segments {
+            let mut next = Vec::new();
+            for node in nodes {
+                match segment {
+                    Segment::Key(key) => next.extend(node.get(key)),
+                    Segment::Index(i) => {
+                        if let Value::Array(items) = node {
+                            let len = i64::try_from(items.len()).unwrap_or(i64::MAX);
+                            let at = if *i < 0 { len + i } else { *i };
+                            next.extend(usize::try_from(at).ok().and_then(|at| items.get(at)));
+                        }
+                    }
+                    Segment::Wildcard => next.extend(children(node)),
+                    Segment::Filter(filter) => {
+                        next.extend(children(node).into_iter().filter(|c| filter.matches(c)));
+                    }
+                }
+            }
+            many |= matches!(segment, Segment::Wildcard | Segment::Filter(_));
+            nodes = next;
+        }
+        if many {
+            Value::Array(nodes.into_iter().cloned().collect())
+        } else {
+            nodes.first().map_or(Value::Null, |v| (*v).clone())
+        }
+    }
+}
+
+impl Filter {
+    fn matches(&self, value: &Value) -> bool {
+        let found = self
+            .path
+            .iter()
+            .try_fold(value, |v, key| v.get(key.as_str()));
+        match (&self.test, found) {
+            (None, found) => !matches!(found, None | Some(Value::Null | Value::Bool(false))),
+            (Some((CompareOp::Ne, expected)), found) => found != Some(expected),
+            (Some((op, expected)), Some(found)) => compare(*op, found, expected),
+            (Some(_), None) => false,
+        }
+    }
+}
+
+fn compare(op: CompareOp, found: &Value, expected: &Value) -> bool {
+    let ordering = match (found, expected) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64().partial_cmp(&b.as_f64()),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        _ => None,
+    };
+    match op {
+        CompareOp::Eq => found == expected,
+        CompareOp::Ne => found != expected,
+        CompareOp::Lt => ordering.is_some_and(std::cmp::Ordering::is_lt),
+        CompareOp::Le => ordering.is_some_and(std::cmp::Ordering::is_le),
+        CompareOp::Gt => ordering.is_some_and(std::cmp::Ordering::is_gt),
+        CompareOp::Ge => ordering.is_some_and(std::cmp::Ordering::is_ge),
+    }
+}
+
+fn children(node: &Value) -> Vec<&Value> {
+    match node {
+        Value::Array(items) => items.iter().collect(),
+        Value::Object(map) => map.values().collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn key_segment(key: &str) -> Segment {
+    if key == "*" {
+        Segment::Wildcard
+    } else {
+        Segment::Key(key.to_string())
+    }
+}
+
+/// Index of the `]` closing a bracket, skipping quoted text.
+fn closing_bracket(text: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (None, '\'' | '"') => quote = Some(c),
+            (None, ']') => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn bracket_segment(inner: &str) -> anyhow::Result<Segment> {
+    if inner == "*" {
+        return Ok(Segment::Wildcard);
+    }
+    if let Some(expr) = inner.strip_prefix('?') {
+        let expr = expr.trim();
+        let expr = expr
+            .strip_prefix('(')
+            .and_then(|e| e.strip_suffix(')'))
+            .unwrap_or(expr);
+        return parse_filter(expr.trim()).map(Segment::Filter);
+    }
+    if let Some(key) = unquote(inner) {
+        return Ok(Segment::Key(key.to_string()));
+    }
+    inner
+        .parse::<i64>()
+        .map(Segment::Index)
+        .map_err(|_| anyhow!("expected an index, '*', a quoted key or a ?filter in [{inner}]"))
+}
+
+fn parse_filter(expr: &str) -> anyhow::Result<Filter> {
+    const OPS: [(&str, CompareOp); 6] = [
+        ("==", CompareOp::Eq),
+        ("!=", CompareOp::Ne),
+        ("<=", CompareOp::Le),
+        (">=", CompareOp::Ge),
+        ("<", CompareOp::Lt),
+        (">", CompareOp::Gt),
+    ];
+    let found = OPS
+        .iter()
+        .filter_map(|(token, op)| expr.find(token).map(|at| (at, *token, *op)))
+        .min_by_key(|(at, token, _)| (*at, std::cmp::Reverse(token.len())));
+    let (left, test) = match found {
+        Some((at, token, op)) => (
+            &expr[..at],
+            Some((op, literal(expr[at + token.len()..].trim()))),
+        ),
+        None => (expr, None),
+    };
+    let left = left.trim();
+    let left = left.strip_prefix('@').unwrap_or(left);
+    let path: Vec<String> = left
+        .split('.')
+        .filter(|p| !p.is_empty())
+        .map(str::to_string)
+        .collect();
+    if path.is_empty() {
+        bail!("filter '{expr}' needs a key, e.g. [?status=='failed']");
+    }
+    Ok(Filter { path, test })
+}
+
+fn unquote(text: &str) -> Option<&str> {
+    ['\'', '"'].into_iter().find_map(|q| {
+        text.strip_prefix(q)
+            .and_then(|t| t.strip_suffix(q))
+            .filter(|_| text.len() >= 2)
+    })
+}
+
+/// A filter operand: quoted string, JSON number/bool/null, else a bare string.
+fn literal(text: &str) -> Value {
+    if let Some(s) = unquote(text) {
+        return Value::String(s.to_string());
+    }
+    serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn query(text: &str, value: &Value) -> Value {
+        Query::parse(text).unwrap().apply(value)
+    }
+
+    #[test]
+    fn queries_select_keys_indexes_wildcards_and_filters() {
+        let data = json!({
+            "meta": { "name": "prod" },
+            "runs": [
+                { "id": "a", "status": "failed", "ms": 10 },
+                { "id": "b", "status": "ok", "ms": 30 },
+            ],
+        });
+        assert_eq!(query("$.meta.name", &data), json!("prod"));
+        assert_eq!(query("meta.name", &data), json!("prod"));
+        assert_eq!(query("$['meta'][\"name\"]", &data), json!("prod"));
+        assert_eq!(query("$.runs[-1].id", &data), json!("b"));
+        assert_eq!(query("$.runs[*].id", &data), json!(["a", "b"]));
+        assert_eq!(query("$.runs.*.id", &data), json!(["a", "b"]));
+        assert_eq!(query("$.runs[?status=='failed'].id", &data), json!(["a"]));
+        assert_eq!(query("$.runs[?(@.ms > 15)].id", &data), json!(["b"]));
+        assert_eq!(query("$.runs[?status!='ok'].ms", &data), json!([10]));
+        assert_eq!(query("$.runs[?missing]", &data), json!([]));
+        assert_eq!(query("$.missing.deeper", &data), Value::Null);
+        assert_eq!(query("$.runs[5]", &data), Value::Null);
+        assert_eq!(query("$", &data), data);
+    }
+
+    #[test]
+    fn invalid_queries_are_rejected() {
+        for text in [
+            "$.",
+            "$..runs",
+            "$.runs[0",
+            "$.runs[x]",
+            "$.runs[?]",
+            "$.runs[?=='a']",
+        ] {
+            assert!(Query::parse(text).is_err(), "{text}");
+        }
+        let err = Output::new(
+            OutputFormat::Json,
+            Vec::new(),
+            Some("$.a["),
+            ColorMode::Never,
+        )
+        .unwrap_err();
+        assert!(
+            format!("{err:#}").contains("invalid --query '$.a['"),
+            "{err:#}"
+        );
+    }
+
+    #[test]
+    fn project_keeps_dotted_columns_and_nulls_missing_ones() {
+        let columns = ["id".to_string(), "meta.region".into(), "absent".into()];
+        let value = json!([
+            { "id": 1, "meta": { "region": "eu", "zone": "a" }, "extra": true },
+            { "id": 2 },
+            "not a record",
+        ]);
+        assert_eq!(
+            project(value, &columns),
+            json!([
+                { "id": 1, "meta.region": "eu", "absent": null },
+                { "id": 2, "meta.region": null, "absent": null },
+                "not a record",
+            ])
+        );
+        assert_eq!(
+            project(json!({ "id": 3, "meta": "flat" }), &columns),
+            json!({ "id": 3, "meta.region": null, "absent": null })
+        );
+    }
+
+    #[test]
+    fn csv_quotes_commas_quotes_and_newlines() {
+        let value = json!([
+            { "name": "a,b", "note": "say \"hi\"", "body": "line 1\nline 2" },
+            { "name": "plain", "count": 2 },
+        ]);
+        assert_eq!(
+            render_csv(&value, &[]).unwrap(),
+            "body,name,note,count\n\
+             \"line 1\nline 2\",\"a,b\",\"say \"\"hi\"\"\",\n\
+             ,plain,,2\n"
+        );
+        assert_eq!(
+            render_csv(&json!([1, "x,y"]), &[]).unwrap(),
+            "value\n1\n\"x,y\"\n"
+        );
+    }
+
+    #[test]
+    fn table_columns_fit_the_widest_cell() {
+        let value = json!([
+            { "name": "héllo", "status": "running" },
+            { "name": "a", "status": "x" },
+        ]);
+        assert_eq!(
+            render_table(&value, &[], false),
+            "NAME   STATUS\n\
+             héllo  running\n\
+             a      x\n"
+        );
+        assert_eq!(
+            render_table(&value, &["status".to_string()], false),
+            "STATUS\nrunning\nx\n"
+        );
+        assert_eq!(
+            render_table(&json!({ "k": "v", "longer": 1 }), &[], false),
+            "KEY     VALUE\nk       v\nlonger  1\n"
+        );
+        assert_eq!(render_table(&json!("bare"), &[], false), "bare\n");
+        assert_eq!(render_table(&json!([]), &[], false), "");
+        assert!(render_table(&value, &[], true).starts_with("\x1b[1mNAME   STATUS\x1b[0m\n"));
+    }
+}
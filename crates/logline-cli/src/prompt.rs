@@ -0,0 +1,78 @@
+use std::fmt;
+use std::io::{IsTerminal, Read as _};
+
+/// Returned instead of prompting when nobody can answer: `LOGLINE_NON_INTERACTIVE`
+/// is set (the CI/CD runner sets it for child commands) or stdin is not a terminal.
+#[derive(Debug)]
+pub struct NonInteractive {
+    /// What the prompt was asking for.
+    pub prompt: String,
+    /// Why prompting is off.
+    pub reason: &'static str,
+    /// The flag or stdin alternative to use instead.
+    pub instead: String,
+}
+
+impl fmt::Display for NonInteractive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "refusing to prompt ({}): {}\n{}",
+            self.reason, self.prompt, self.instead
+        )
+    }
+}
+
+impl std::error::Error for NonInteractive {}
+
+/// Why prompts are disabled, or `None` when a person is at the terminal.
+pub fn non_interactive_reason() -> Option<&'static str> {
+    let forced = std::env::var("LOGLINE_NON_INTERACTIVE")
+        .is_ok_and(|v| !matches!(v.trim(), "" | "0" | "false" | "no"));
+    if forced {
+        Some("LOGLINE_NON_INTERACTIVE is set")
+    } else if !std::io::stdin().is_terminal() {
+        Some("stdin is not a terminal")
+    } else {
+        None
+    }
+}
+
+/// Fail fast with [`NonInteractive`] unless prompting is possible.
+pub fn ensure_interactive(prompt: &str, instead: &str) -> Result<(), NonInteractive> {
+    match non_interactive_reason() {
+        None => Ok(()),
+        Some(reason) => Err(NonInteractive {
+            prompt: prompt.to_string(),
+            reason,
+            instead: instead.to_string(),
+        }),
+    }
+}
+
+/// Hidden input, never echoed.
+pub fn secret(prompt: &str, instead: &str) -> anyhow::Result<String> {
+    ensure_interactive(prompt, instead)?;
+    Ok(rpassword::prompt_password(format!("{prompt}: "))?)
+}
+
+/// Yes/no question on stderr; anything but `y` or `yes` is a no.
+pub fn confirm(question: &str, instead: &str) -> anyhow::Result<bool> {
+    ensure_interactive(question, instead)?;
+    eprint!("{question} [y/N] ");
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(
+        answer.trim().to_ascii_lowercase().as_str(),
+        "y" | "yes"
+    ))
+}
+
+/// The whole of stdin minus the trailing newline, for `--from-stdin` style flags.
+pub fn read_stdin(what: &str) -> anyhow::Result<String> {
+    let mut value = String::new();
+    std::io::stdin().read_to_string(&mut value)?;
+    let value = value.trim_end_matches(['\r', '\n']);
+    anyhow::ensure!(!value.is_empty(), "{what} on stdin is empty");
+    Ok(value.to_string())
+}
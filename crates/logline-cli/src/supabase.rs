@@ -1,59 +1,12 @@
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::time::Duration;
+use std::sync::OnceLock;
 
-use anyhow::{bail, Context};
-use reqwest::blocking::{Client, Response};
+use anyhow::bail;
+use logline_auth::{ClaimValidator, JwksSet, JwtVerifier, VerifiedJwt, VerifyOptions};
+pub use logline_supabase::{SupabaseClient, SupabaseConfig};
 use serde::{Deserialize, Serialize};
 
-// ─── Config ─────────────────────────────────────────────────────────────────
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SupabaseConfig {
-    pub url: String,
-    pub anon_key: String,
-}
-
-impl SupabaseConfig {
-    pub fn from_env_or_file() -> anyhow::Result<Self> {
-        if let (Ok(url), Ok(key)) = (
-            std::env::var("NEXT_PUBLIC_SUPABASE_URL"),
-            std::env::var("NEXT_PUBLIC_SUPABASE_ANON_KEY"),
-        ) {
-            if !url.is_empty() && !key.is_empty() {
-                return Ok(Self { url, anon_key: key });
-            }
-        }
-
-        let config_path = config_dir().join("config.json");
-        if config_path.exists() {
-            let content = fs::read_to_string(&config_path)
-                .context("Failed to read config.json")?;
-            return serde_json::from_str(&content)
-                .context("Invalid config.json format");
-        }
-
-        for filename in [".env.local", ".env"] {
-            let path = std::env::current_dir()
-                .unwrap_or_else(|_| PathBuf::from("."))
-                .join(filename);
-            if let Ok(content) = fs::read_to_string(&path) {
-                let url = parse_env_value(&content, "NEXT_PUBLIC_SUPABASE_URL");
-                let key = parse_env_value(&content, "NEXT_PUBLIC_SUPABASE_ANON_KEY");
-                if let (Some(url), Some(key)) = (url, key) {
-                    return Ok(Self { url, anon_key: key });
-                }
-            }
-        }
-
-        bail!(
-            "Supabase config not found.\n\
-             Set NEXT_PUBLIC_SUPABASE_URL and NEXT_PUBLIC_SUPABASE_ANON_KEY env vars,\n\
-             or create ~/.config/logline/config.json with {{\"url\": \"...\", \"anon_key\": \"...\"}}"
-        )
-    }
-}
-
 // ─── Stored auth tokens ─────────────────────────────────────────────────────
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -73,15 +26,17 @@ pub fn config_dir() -> PathBuf {
         .join("logline")
 }
 
+pub const AUTH_FILE: &str = "auth.json";
+pub const PASSKEY_FILE: &str = "passkey.json";
+
 pub fn auth_path() -> PathBuf {
-    config_dir().join("auth.json")
+    crate::context::state_dir().join(AUTH_FILE)
 }
 
-const KEYRING_SERVICE: &str = "logline-cli";
 const KEYRING_AUTH_USER: &str = "auth_tokens";
 const KEYRING_PASSKEY_USER: &str = "passkey_ed25519";
 
-fn write_private_file(path: &Path, contents: &str) -> anyhow::Result<()> {
+pub fn write_private_file(path: &Path, contents: &str) -> anyhow::Result<()> {
     #[cfg(unix)]
     {
         use std::os::unix::fs::OpenOptionsExt;
@@ -98,12 +53,13 @@ fn write_private_file(path: &Path, contents: &str) -> anyhow::Result<()> {
     Ok(())
 }
 
+fn file_vault() -> bool {
+    crate::vault::store().kind() == logline_core::VaultBackendKind::File
+}
+
 pub fn load_auth() -> Option<StoredAuth> {
-    // Try Keychain first
-    if let Some(json) = keyring::Entry::new(KEYRING_SERVICE, KEYRING_AUTH_USER)
-        .ok()
-        .and_then(|e| e.get_password().ok())
-    {
+    // Try the vault first
+    if let Some(json) = crate::commands::secrets::load_credential(KEYRING_AUTH_USER) {
         return serde_json::from_str(&json).ok();
     }
     // Fall back to protected config directory
@@ -115,16 +71,19 @@ pub fn load_auth() -> Option<StoredAuth> {
 pub fn save_auth(auth: &StoredAuth) -> anyhow::Result<()> {
     let json = serde_json::to_string(auth)?;
 
+    // The encrypted vault is the only copy; no plaintext file next to it
+    if file_vault() {
+        return crate::commands::secrets::store_credential(KEYRING_AUTH_USER, &json);
+    }
+
     // Try Keychain (may silently fail on unsigned binaries)
-    let keychain_ok = keyring::Entry::new(KEYRING_SERVICE, KEYRING_AUTH_USER)
-        .ok()
-        .and_then(|e| e.set_password(&json).ok())
-        .is_some();
+    let keychain_ok = crate::commands::secrets::store_credential(KEYRING_AUTH_USER, &json).is_ok();
 
     // Always write to protected config dir as reliable storage
-    let dir = config_dir();
-    fs::create_dir_all(&dir)?;
-    let path = dir.join("auth.json");
+    let path = auth_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
     write_private_file(&path, &json)?;
 
     if !keychain_ok {
@@ -135,264 +94,155 @@ pub fn save_auth(auth: &StoredAuth) -> anyhow::Result<()> {
 }
 
 pub fn delete_auth() -> anyhow::Result<()> {
-    if let Ok(entry) = keyring::Entry::new(KEYRING_SERVICE, KEYRING_AUTH_USER) {
-        let _ = entry.delete_credential();
-    }
+    let _ = crate::commands::secrets::delete_credential(KEYRING_AUTH_USER);
     let path = auth_path();
     if path.exists() {
         fs::remove_file(path)?;
     }
+    delete_passkey()
+}
 
-    if let Ok(entry) = keyring::Entry::new(KEYRING_SERVICE, KEYRING_PASSKEY_USER) {
-        let _ = entry.delete_credential();
-    }
-    let passkey_path = config_dir().join("passkey.json");
+pub fn delete_passkey() -> anyhow::Result<()> {
+    let _ = crate::commands::secrets::delete_credential(KEYRING_PASSKEY_USER);
+    let passkey_path = passkey_path();
     if passkey_path.exists() {
         fs::remove_file(passkey_path)?;
     }
     Ok(())
 }
 
+pub fn passkey_path() -> PathBuf {
+    crate::context::state_dir().join(PASSKEY_FILE)
+}
+
 pub fn load_passkey() -> Option<serde_json::Value> {
-    if let Some(json) = keyring::Entry::new(KEYRING_SERVICE, KEYRING_PASSKEY_USER)
-        .ok()
-        .and_then(|e| e.get_password().ok())
-    {
+    if let Some(json) = crate::commands::secrets::load_credential(KEYRING_PASSKEY_USER) {
         return serde_json::from_str(&json).ok();
     }
-    let path = config_dir().join("passkey.json");
-    let content = fs::read_to_string(&path).ok()?;
+    let content = fs::read_to_string(passkey_path()).ok()?;
     serde_json::from_str(&content).ok()
 }
 
 pub fn save_passkey(data: &serde_json::Value) -> anyhow::Result<()> {
     let json = serde_json::to_string(data)?;
 
-    let _keychain_ok = keyring::Entry::new(KEYRING_SERVICE, KEYRING_PASSKEY_USER)
-        .ok()
-        .and_then(|e| e.set_password(&json).ok())
-        .is_some();
+    if file_vault() {
+        return crate::commands::secrets::store_credential(KEYRING_PASSKEY_USER, &json);
+    }
 
-    let dir = config_dir();
-    fs::create_dir_all(&dir)?;
-    let path = dir.join("passkey.json");
-    write_private_file(&path, &json)?;
+    let _keychain_ok = crate::commands::secrets::store_credential(KEYRING_PASSKEY_USER, &json).is_ok();
+
+    fs::create_dir_all(crate::context::state_dir())?;
+    write_private_file(&passkey_path(), &json)?;
 
     Ok(())
 }
 
-// ─── Supabase HTTP client ───────────────────────────────────────────────────
-
-pub struct SupabaseClient {
-    pub config: SupabaseConfig,
-    http: Client,
-}
+// ─── Offline token verification ─────────────────────────────────────────────
 
-impl SupabaseClient {
-    pub fn new(config: SupabaseConfig) -> anyhow::Result<Self> {
-        let http = Client::builder()
-            .timeout(Duration::from_secs(15))
-            .build()?;
-        Ok(Self { config, http })
-    }
+const JWKS_FILE: &str = "jwks.json";
+/// How long a saved JWKS is used before it is fetched again (when online).
+const JWKS_MAX_AGE_SECS: u64 = 24 * 3600;
 
-    // ── Auth endpoints ──────────────────────────────────────────────────
-
-    pub fn login_email(&self, email: &str, password: &str) -> anyhow::Result<AuthTokenResponse> {
-        let url = format!(
-            "{}/auth/v1/token?grant_type=password",
-            self.config.url
-        );
-        let resp = self
-            .http
-            .post(&url)
-            .header("apikey", &self.config.anon_key)
-            .header("Content-Type", "application/json")
-            .json(&serde_json::json!({ "email": email, "password": password }))
-            .send()?;
-
-        if resp.status().is_success() {
-            Ok(resp.json()?)
-        } else {
-            let status = resp.status();
-            let body = resp.text().unwrap_or_default();
-            bail!("Login failed ({status}): {body}")
-        }
-    }
+/// The project's signing keys as last fetched, so tokens verify without a round-trip.
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedJwks {
+    url: String,
+    fetched_at: u64,
+    jwks: JwksSet,
+}
 
-    pub fn refresh_token(&self, refresh_token: &str) -> anyhow::Result<AuthTokenResponse> {
-        let url = format!(
-            "{}/auth/v1/token?grant_type=refresh_token",
-            self.config.url
-        );
-        let resp = self
-            .http
-            .post(&url)
-            .header("apikey", &self.config.anon_key)
-            .header("Content-Type", "application/json")
-            .json(&serde_json::json!({ "refresh_token": refresh_token }))
-            .send()?;
-
-        if resp.status().is_success() {
-            Ok(resp.json()?)
-        } else {
-            let status = resp.status();
-            let body = resp.text().unwrap_or_default();
-            bail!("Token refresh failed ({status}): {body}")
-        }
+fn verify_options(config: &SupabaseConfig) -> VerifyOptions {
+    let issuer = format!("{}/auth/v1", config.url.trim_end_matches('/'));
+    VerifyOptions {
+        jwks_url: format!("{issuer}/.well-known/jwks.json"),
+        issuer: Some(issuer),
+        audience: Some("authenticated".into()),
+        validators: vec![ClaimValidator::required("sub")],
+        ..Default::default()
     }
+}
 
-    pub fn get_user(&self, access_token: &str) -> anyhow::Result<serde_json::Value> {
-        let url = format!("{}/auth/v1/user", self.config.url);
-        let resp = self
-            .http
-            .get(&url)
-            .header("apikey", &self.config.anon_key)
-            .header("Authorization", format!("Bearer {access_token}"))
-            .send()?;
-
-        if resp.status().is_success() {
-            Ok(resp.json()?)
-        } else {
-            let status = resp.status();
-            let body = resp.text().unwrap_or_default();
-            bail!("Get user failed ({status}): {body}")
+/// One verifier per process, so every check shares its JWKS cache (freshness,
+/// unknown-`kid` refetches, stale fallback). `jwks.json` only seeds that cache,
+/// so a new process does not refetch keys it saved less than a day ago.
+fn verifier() -> &'static JwtVerifier {
+    static VERIFIER: OnceLock<JwtVerifier> = OnceLock::new();
+    VERIFIER.get_or_init(|| {
+        let verifier = JwtVerifier::default();
+        let saved = fs::read_to_string(crate::context::state_dir().join(JWKS_FILE))
+            .ok()
+            .and_then(|c| serde_json::from_str::<SavedJwks>(&c).ok());
+        if let Some(saved) = saved {
+            let age = now_secs().saturating_sub(saved.fetched_at);
+            if age < JWKS_MAX_AGE_SECS {
+                verifier.cache().store(&saved.url, &saved.jwks, None, JWKS_MAX_AGE_SECS - age);
+            }
         }
-    }
+        verifier
+    })
+}
 
-    // ── PostgREST endpoints ─────────────────────────────────────────────
-
-    pub fn postgrest_get(
-        &self,
-        table: &str,
-        query: &str,
-        access_token: &str,
-    ) -> anyhow::Result<serde_json::Value> {
-        let url = format!("{}/rest/v1/{}?{}", self.config.url, table, query);
-        let resp = self.postgrest_request("GET", &url, access_token, None)?;
-        Ok(resp.json()?)
-    }
+fn save_jwks(url: &str, jwks: JwksSet) -> anyhow::Result<()> {
+    let entry = SavedJwks { url: url.to_string(), fetched_at: now_secs(), jwks };
+    let dir = crate::context::state_dir();
+    fs::create_dir_all(&dir)?;
+    write_private_file(&dir.join(JWKS_FILE), &serde_json::to_string(&entry)?)
+}
 
-    pub fn postgrest_insert(
-        &self,
-        table: &str,
-        body: &serde_json::Value,
-        access_token: &str,
-    ) -> anyhow::Result<serde_json::Value> {
-        let url = format!("{}/rest/v1/{}", self.config.url, table);
-        let resp = self.postgrest_request("POST", &url, access_token, Some(body))?;
-        Ok(resp.json().unwrap_or(serde_json::json!({"ok": true})))
-    }
+/// The project publishes no asymmetric signing keys (it still signs tokens with a
+/// shared HS256 secret), so its tokens cannot be verified locally at all.
+#[derive(Debug)]
+pub struct NoSigningKeys {
+    pub jwks_url: String,
+}
 
-    pub fn postgrest_upsert(
-        &self,
-        table: &str,
-        body: &serde_json::Value,
-        on_conflict: &str,
-        access_token: &str,
-    ) -> anyhow::Result<serde_json::Value> {
-        let url = format!(
-            "{}/rest/v1/{}?on_conflict={}",
-            self.config.url, table, on_conflict
-        );
-        let resp = self
-            .http
-            .post(&url)
-            .header("apikey", &self.config.anon_key)
-            .header("Authorization", format!("Bearer {access_token}"))
-            .header("Content-Type", "application/json")
-            .header("Prefer", "resolution=merge-duplicates,return=representation")
-            .json(body)
-            .send()?;
-
-        if resp.status().is_success() {
-            Ok(resp.json().unwrap_or(serde_json::json!({"ok": true})))
-        } else {
-            let status = resp.status();
-            let body_text = resp.text().unwrap_or_default();
-            bail!("PostgREST upsert {table} failed ({status}): {body_text}")
-        }
+impl std::fmt::Display for NoSigningKeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} lists no signing keys; the project signs tokens with a shared secret. \
+             Enable asymmetric JWT signing keys in Supabase to verify tokens locally.",
+            self.jwks_url
+        )
     }
+}
 
-    fn postgrest_request(
-        &self,
-        method: &str,
-        url: &str,
-        access_token: &str,
-        body: Option<&serde_json::Value>,
-    ) -> anyhow::Result<Response> {
-        let mut req = match method {
-            "POST" => self.http.post(url),
-            "PATCH" => self.http.patch(url),
-            "DELETE" => self.http.delete(url),
-            _ => self.http.get(url),
-        };
-
-        req = req
-            .header("apikey", &self.config.anon_key)
-            .header("Authorization", format!("Bearer {access_token}"))
-            .header("Prefer", "return=representation");
-
-        if let Some(b) = body {
-            req = req.header("Content-Type", "application/json").json(b);
-        }
-
-        let resp = req.send()?;
-
-        if resp.status().is_success() {
-            Ok(resp)
-        } else {
-            let status = resp.status();
-            let body_text = resp.text().unwrap_or_default();
-            bail!("PostgREST request failed ({status}): {body_text}")
-        }
+impl std::error::Error for NoSigningKeys {}
+
+/// Verify a Supabase access token (signature, `exp`, issuer, audience, `sub`) against the
+/// project's published signing keys. Only the first call per day needs the network.
+pub fn verify_access_token(token: &str) -> anyhow::Result<VerifiedJwt> {
+    let opts = verify_options(&crate::context::supabase_config()?);
+    let url = opts.jwks_url.clone();
+    let verifier = verifier();
+    let before = verifier.cache().stats();
+
+    let jwks = verifier
+        .load_jwks_blocking(&url, &opts)
+        .map_err(|e| anyhow::anyhow!("cannot fetch {url}: {e}"))?;
+    if jwks.keys.iter().all(|k| k.kty == "oct") {
+        return Err(NoSigningKeys { jwks_url: url }.into());
     }
-
-    // ── Service-role operations (bootstrap only) ────────────────────────
-
-    pub fn service_role_insert(
-        &self,
-        table: &str,
-        body: &serde_json::Value,
-        service_role_key: &str,
-    ) -> anyhow::Result<serde_json::Value> {
-        let url = format!("{}/rest/v1/{}", self.config.url, table);
-        let resp = self
-            .http
-            .post(&url)
-            .header("apikey", &self.config.anon_key)
-            .header("Authorization", format!("Bearer {service_role_key}"))
-            .header("Content-Type", "application/json")
-            .header("Prefer", "resolution=merge-duplicates,return=representation")
-            .json(body)
-            .send()?;
-
-        if resp.status().is_success() {
-            Ok(resp.json().unwrap_or(serde_json::json!({"ok": true})))
-        } else {
-            let status = resp.status();
-            let text = resp.text().unwrap_or_default();
-            bail!("Service-role insert into {table} failed ({status}): {text}")
+    let result = verifier
+        .verify_with_jwks_url_blocking(token, opts.clone())
+        .map_err(|e| anyhow::anyhow!("access token rejected: {e}"));
+
+    // Persist only keys that were actually fetched, not a stale fallback.
+    let after = verifier.cache().stats();
+    if after.misses > before.misses && after.stale_served == before.stale_served {
+        if let Ok(jwks) = verifier.load_jwks_blocking(&url, &opts) {
+            let _ = save_jwks(&url, jwks);
         }
     }
+    result
 }
 
-// ─── Auth token response ────────────────────────────────────────────────────
-
-#[derive(Debug, Deserialize)]
-pub struct AuthTokenResponse {
-    pub access_token: String,
-    pub refresh_token: String,
-    pub expires_in: u64,
-    #[allow(dead_code)]
-    pub token_type: String,
-    pub user: AuthUser,
-}
-
-#[derive(Debug, Deserialize)]
-pub struct AuthUser {
-    pub id: String,
-    pub email: Option<String>,
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
 }
 
 // ─── Token management with auto-refresh ─────────────────────────────────────
@@ -437,31 +287,3 @@ pub fn get_valid_token(client: &SupabaseClient) -> anyhow::Result<String> {
         }
     }
 }
-
-// ─── Helpers ────────────────────────────────────────────────────────────────
-
-fn parse_env_value(content: &str, key: &str) -> Option<String> {
-    for raw_line in content.lines() {
-        let line = raw_line.trim();
-        if line.is_empty() || line.starts_with('#') {
-            continue;
-        }
-        let mut parts = line.splitn(2, '=');
-        let k = parts.next()?.trim();
-        let v = parts.next()?.trim();
-        if k != key {
-            continue;
-        }
-        let unquoted = if (v.starts_with('"') && v.ends_with('"'))
-            || (v.starts_with('\'') && v.ends_with('\''))
-        {
-            v[1..v.len().saturating_sub(1)].trim()
-        } else {
-            v
-        };
-        if !unquoted.is_empty() {
-            return Some(unquoted.to_string());
-        }
-    }
-    None
-}
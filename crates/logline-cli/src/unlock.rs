@@ -0,0 +1,507 @@
+use std::sync::OnceLock;
+
+use anyhow::{anyhow, bail, ensure};
+use argon2::Argon2;
+use argon2::password_hash::rand_core::{OsRng, RngCore as _};
+use argon2::password_hash::{PasswordHash, PasswordHasher as _, PasswordVerifier as _, SaltString};
+use ed25519_dalek::{Signer as _, SigningKey, VerifyingKey};
+use logline_core::{AuthPolicy, CommandClass, SessionPolicy, UnlockMethod, UnlockPolicy};
+
+use crate::commands::secrets;
+
+/// Keyring entry holding the Argon2id PHC string for the unlock passphrase.
+const PASSPHRASE_HASH_KEY: &str = "unlock_passphrase_argon2";
+
+const UNLOCK_INSTEAD: &str = "Unlock from a terminal before the pipeline starts \
+     (e.g. `logline auth unlock --ttl 2h`); later commands reuse the session until it expires.";
+
+static POLICY: OnceLock<UnlockPolicy> = OnceLock::new();
+
+/// Install the `[unlock]` section of `policy.toml`; later calls are ignored.
+pub fn init_policy(policy: UnlockPolicy) {
+    let _ = POLICY.set(policy);
+}
+
+pub fn policy() -> &'static UnlockPolicy {
+    POLICY.get_or_init(UnlockPolicy::default)
+}
+
+static AUTH_POLICY: OnceLock<AuthPolicy> = OnceLock::new();
+
+/// Install the `[auth]` section of `policy.toml`; later calls are ignored.
+pub fn init_auth_policy(policy: AuthPolicy) {
+    let _ = AUTH_POLICY.set(policy);
+}
+
+pub fn auth_policy() -> &'static AuthPolicy {
+    AUTH_POLICY.get_or_init(AuthPolicy::default)
+}
+
+static SESSION_POLICY: OnceLock<(SessionPolicy, String)> = OnceLock::new();
+
+/// Install the `[session]` section of `policy.toml` and the command path this
+/// process runs (e.g. `db migrate apply`); later calls are ignored.
+pub fn init_session_policy(policy: SessionPolicy, command: String) {
+    let _ = SESSION_POLICY.set((policy, command));
+}
+
+/// The running command and the sensitivity class `policy.toml` puts it in, if any.
+pub fn command_class() -> Option<(&'static str, CommandClass<'static>)> {
+    let (policy, command) = SESSION_POLICY.get()?;
+    policy
+        .class_for(command)
+        .map(|class| (command.as_str(), class))
+}
+
+/// One way of proving presence before `logline auth unlock` opens a session.
+pub trait UnlockProvider {
+    fn method(&self) -> UnlockMethod;
+
+    /// Why this provider cannot be used on this machine, if it cannot.
+    fn unavailable(&self) -> Option<String>;
+
+    /// Challenge the user; `Ok` means the session may open.
+    fn unlock(&self) -> anyhow::Result<()>;
+
+    /// Whether passing `unlock` shows a person is present, not just that a
+    /// local file is readable.
+    fn checks_presence(&self) -> bool {
+        true
+    }
+}
+
+pub fn provider(method: UnlockMethod) -> Box<dyn UnlockProvider> {
+    match method {
+        UnlockMethod::TouchId => Box::new(TouchIdProvider),
+        UnlockMethod::Passphrase => Box::new(PassphraseProvider),
+        UnlockMethod::Signature => Box::new(SignatureProvider),
+    }
+}
+
+/// Provider lookup; the real one is [`provider`], tests pass fakes.
+type Providers<'a> = &'a dyn Fn(UnlockMethod) -> Box<dyn UnlockProvider>;
+
+/// Run `method`, or the first allowed provider usable here, and return the one that passed.
+pub fn unlock(method: Option<UnlockMethod>) -> anyhow::Result<UnlockMethod> {
+    unlock_with(policy(), method, &provider)
+}
+
+fn unlock_with(
+    policy: &UnlockPolicy,
+    method: Option<UnlockMethod>,
+    providers: Providers<'_>,
+) -> anyhow::Result<UnlockMethod> {
+    if let Some(method) = method {
+        ensure!(
+            policy.allowed.contains(&method),
+            "Unlock method {method} is not allowed by policy.toml (allowed: {}).",
+            join(&policy.allowed)
+        );
+        let provider = providers(method);
+        if let Some(why) = provider.unavailable() {
+            bail!("Cannot unlock with {method}: {why}.");
+        }
+        provider.unlock()?;
+        return Ok(provider.method());
+    }
+
+    first_usable(&policy.allowed, providers)
+}
+
+/// Step-up check for a policy class: run the first of `methods` that is usable
+/// here. When the class names none, only `unlock.infra` methods qualify, so a
+/// step-up is never satisfied by the presence-free `signature` check by default.
+pub fn step_up(methods: &[UnlockMethod]) -> anyhow::Result<UnlockMethod> {
+    step_up_with(policy(), methods, &provider)
+}
+
+fn step_up_with(
+    policy: &UnlockPolicy,
+    methods: &[UnlockMethod],
+    providers: Providers<'_>,
+) -> anyhow::Result<UnlockMethod> {
+    let methods = if methods.is_empty() { &policy.infra } else { methods };
+    let candidates: Vec<_> = methods
+        .iter()
+        .copied()
+        .filter(|m| policy.allowed.contains(m))
+        .collect();
+    ensure!(
+        !candidates.is_empty(),
+        "No unlock method qualifies for step-up (policy.toml unlock.infra is empty)."
+    );
+    first_usable(&candidates, providers)
+}
+
+/// Whether a session opened or last stepped up by `method` saw a person, not
+/// just a readable passkey file.
+pub fn checked_presence(method: &str) -> bool {
+    method
+        .parse::<UnlockMethod>()
+        .is_ok_and(|m| provider(m).checks_presence())
+}
+
+/// Gate for `auth passphrase set` when no session vouches for presence: a fresh
+/// check with an allowed method that proves it (never `signature`). The current
+/// passphrase counts once one is set.
+pub fn require_presence_for_new_passphrase() -> anyhow::Result<()> {
+    require_presence_for_new_passphrase_with(policy(), &provider)
+}
+
+fn require_presence_for_new_passphrase_with(
+    policy: &UnlockPolicy,
+    providers: Providers<'_>,
+) -> anyhow::Result<()> {
+    let candidates: Vec<_> = policy
+        .allowed
+        .iter()
+        .copied()
+        .filter(|&m| {
+            let provider = providers(m);
+            provider.checks_presence()
+                && (m != UnlockMethod::Passphrase || provider.unavailable().is_none())
+        })
+        .collect();
+    first_usable(&candidates, providers).map(|_| ()).map_err(|e| {
+        anyhow!(
+            "Setting the unlock passphrase needs a presence check (touch_id, or the current passphrase).\n\
+             A session opened by `signature` does not count.\n{e}"
+        )
+    })
+}
+
+fn first_usable(methods: &[UnlockMethod], providers: Providers<'_>) -> anyhow::Result<UnlockMethod> {
+    let mut reasons = Vec::new();
+    for &method in methods {
+        let provider = providers(method);
+        match provider.unavailable() {
+            None => {
+                provider.unlock()?;
+                return Ok(provider.method());
+            }
+            Some(why) => reasons.push(format!("  {}: {why}", provider.method())),
+        }
+    }
+    bail!(
+        "No allowed unlock method is usable here:\n{}",
+        reasons.join("\n")
+    )
+}
+
+pub fn join(methods: &[UnlockMethod]) -> String {
+    methods
+        .iter()
+        .map(|m| m.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Gate for infra commands: the session must have been opened by a method `unlock.infra` accepts.
+pub fn require_infra_method(opened_by: &str) -> anyhow::Result<()> {
+    let infra = &policy().infra;
+    let accepted = opened_by
+        .parse::<UnlockMethod>()
+        .is_ok_and(|m| infra.contains(&m));
+    ensure!(
+        accepted,
+        "Infra commands need a session unlocked with one of: {} (policy.toml unlock.infra).\n\
+         This session was opened by {opened_by}.\n\
+         Fix: logline auth lock && logline auth unlock --method {}",
+        join(infra),
+        infra.first().map_or("passphrase", |m| m.as_str())
+    );
+    Ok(())
+}
+
+// ─── Touch ID ───────────────────────────────────────────────────────────────
+
+pub struct TouchIdProvider;
+
+impl UnlockProvider for TouchIdProvider {
+    fn method(&self) -> UnlockMethod {
+        UnlockMethod::TouchId
+    }
+
+    fn unavailable(&self) -> Option<String> {
+        (!cfg!(target_os = "macos")).then(|| "Touch ID needs macOS".to_string())
+    }
+
+    fn unlock(&self) -> anyhow::Result<()> {
+        crate::prompt::ensure_interactive("Touch ID", UNLOCK_INSTEAD)?;
+
+        eprintln!("Touch ID required...");
+        let result = std::process::Command::new("swift")
+            .arg("-e")
+            .arg(
+                r#"
+import LocalAuthentication
+import Foundation
+let ctx = LAContext()
+var err: NSError?
+guard ctx.canEvaluatePolicy(.deviceOwnerAuthenticationWithBiometrics, error: &err) else {
+    fputs("biometrics unavailable: \(err?.localizedDescription ?? "unknown")\n", stderr)
+    exit(1)
+}
+let sema = DispatchSemaphore(value: 0)
+var ok = false
+ctx.evaluatePolicy(.deviceOwnerAuthenticationWithBiometrics, localizedReason: "Logline CLI — unlock session") { success, _ in
+    ok = success
+    sema.signal()
+}
+sema.wait()
+exit(ok ? 0 : 1)
+"#,
+            )
+            .output();
+
+        match result {
+            Ok(out) if out.status.success() => Ok(()),
+            Ok(_) => bail!("Touch ID authentication failed or was cancelled."),
+            Err(e) => bail!(
+                "Touch ID unavailable ({e}). Use another method: logline auth unlock --method passphrase"
+            ),
+        }
+    }
+}
+
+// ─── Passphrase ─────────────────────────────────────────────────────────────
+
+pub struct PassphraseProvider;
+
+impl UnlockProvider for PassphraseProvider {
+    fn method(&self) -> UnlockMethod {
+        UnlockMethod::Passphrase
+    }
+
+    fn unavailable(&self) -> Option<String> {
+        secrets::load_credential(PASSPHRASE_HASH_KEY)
+            .is_none()
+            .then(|| "no passphrase set (run `logline auth passphrase set`)".to_string())
+    }
+
+    fn unlock(&self) -> anyhow::Result<()> {
+        let stored = secrets::load_credential(PASSPHRASE_HASH_KEY).ok_or_else(|| {
+            anyhow!("No unlock passphrase set. Run `logline auth passphrase set`.")
+        })?;
+        let hash = PasswordHash::new(&stored)
+            .map_err(|e| anyhow!("Stored passphrase hash is unreadable ({e}); set it again."))?;
+        let passphrase = crate::prompt::secret("Unlock passphrase (hidden)", UNLOCK_INSTEAD)?;
+        Argon2::default()
+            .verify_password(passphrase.as_bytes(), &hash)
+            .map_err(|_| anyhow!("Wrong passphrase."))
+    }
+}
+
+/// Hash `passphrase` with Argon2id and a fresh salt, and store it in the keyring.
+pub fn set_passphrase(passphrase: &str) -> anyhow::Result<()> {
+    ensure!(
+        passphrase.chars().count() >= 12,
+        "Passphrase must be at least 12 characters."
+    );
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = Argon2::default()
+        .hash_password(passphrase.as_bytes(), &salt)
+        .map_err(|e| anyhow!("Failed to hash passphrase: {e}"))?;
+    secrets::store_credential(PASSPHRASE_HASH_KEY, &hash.to_string())
+}
+
+pub fn has_passphrase() -> bool {
+    secrets::load_credential(PASSPHRASE_HASH_KEY).is_some()
+}
+
+pub fn clear_passphrase() -> anyhow::Result<bool> {
+    secrets::delete_credential(PASSPHRASE_HASH_KEY)
+}
+
+// ─── Passkey signature ──────────────────────────────────────────────────────
+
+/// Signs a fresh random challenge with the local passkey and checks it against
+/// the registered public key. That only proves the passkey file (or keyring
+/// entry) is readable by this user; it does not prove a person is present, which
+/// is why `signature` is not in the default `unlock.infra`.
+pub struct SignatureProvider;
+
+impl UnlockProvider for SignatureProvider {
+    fn method(&self) -> UnlockMethod {
+        UnlockMethod::Signature
+    }
+
+    fn unavailable(&self) -> Option<String> {
+        crate::supabase::load_passkey()
+            .is_none()
+            .then(|| "no passkey registered (run `logline auth passkey-register`)".to_string())
+    }
+
+    fn checks_presence(&self) -> bool {
+        false
+    }
+
+    fn unlock(&self) -> anyhow::Result<()> {
+        let passkey = crate::supabase::load_passkey().ok_or_else(|| {
+            anyhow!("No passkey registered. Run `logline auth passkey-register`.")
+        })?;
+        let signing_key = SigningKey::from_bytes(&key_bytes(&passkey, "private_key")?);
+        let registered = VerifyingKey::from_bytes(&key_bytes(&passkey, "public_key")?)
+            .map_err(|e| anyhow!("Registered passkey public key is invalid: {e}"))?;
+        ensure!(
+            signing_key.verifying_key() == registered,
+            "Passkey private key does not match its registered public key."
+        );
+
+        let mut challenge = [0u8; 32];
+        OsRng.fill_bytes(&mut challenge);
+        let signature = signing_key.sign(&challenge);
+        registered
+            .verify_strict(&challenge, &signature)
+            .map_err(|e| anyhow!("Passkey signature check failed: {e}"))
+    }
+}
+
+pub fn key_bytes(passkey: &serde_json::Value, field: &str) -> anyhow::Result<[u8; 32]> {
+    let hex_key = passkey[field]
+        .as_str()
+        .ok_or_else(|| anyhow!("Passkey is missing {field}."))?;
+    hex::decode(hex_key)?
+        .try_into()
+        .map_err(|_| anyhow!("Passkey {field} is not 32 bytes."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Scripted provider: usable unless `missing`, and passing unless `fails`.
+    struct Fake {
+        method: UnlockMethod,
+        missing: bool,
+        fails: bool,
+    }
+
+    impl UnlockProvider for Fake {
+        fn method(&self) -> UnlockMethod {
+            self.method
+        }
+
+        fn unavailable(&self) -> Option<String> {
+            self.missing.then(|| "not here".to_string())
+        }
+
+        fn unlock(&self) -> anyhow::Result<()> {
+            ensure!(!self.fails, "{} refused", self.method);
+            Ok(())
+        }
+
+        fn checks_presence(&self) -> bool {
+            self.method != UnlockMethod::Signature
+        }
+    }
+
+    /// Providers where only `usable` are available and `failing` refuse.
+    fn fakes(
+        usable: &'static [UnlockMethod],
+        failing: &'static [UnlockMethod],
+    ) -> impl Fn(UnlockMethod) -> Box<dyn UnlockProvider> {
+        move |method| {
+            Box::new(Fake {
+                method,
+                missing: !usable.contains(&method),
+                fails: failing.contains(&method),
+            })
+        }
+    }
+
+    const ALL: &[UnlockMethod] = &[
+        UnlockMethod::TouchId,
+        UnlockMethod::Passphrase,
+        UnlockMethod::Signature,
+    ];
+
+    #[test]
+    fn unlock_runs_the_named_method_or_the_first_usable_one() {
+        let policy = UnlockPolicy::default();
+        let usable = fakes(&[UnlockMethod::Passphrase, UnlockMethod::Signature], &[]);
+
+        assert_eq!(unlock_with(&policy, None, &usable).unwrap(), UnlockMethod::Passphrase);
+        assert_eq!(
+            unlock_with(&policy, Some(UnlockMethod::Signature), &usable).unwrap(),
+            UnlockMethod::Signature
+        );
+        let err = unlock_with(&policy, Some(UnlockMethod::TouchId), &usable).unwrap_err();
+        assert!(err.to_string().contains("not here"), "{err}");
+
+        let refused = fakes(ALL, &[UnlockMethod::TouchId]);
+        assert!(unlock_with(&policy, None, &refused).is_err());
+
+        let strict = UnlockPolicy {
+            allowed: vec![UnlockMethod::Passphrase],
+            infra: vec![UnlockMethod::Passphrase],
+        };
+        let err = unlock_with(&strict, Some(UnlockMethod::Signature), &fakes(ALL, &[])).unwrap_err();
+        assert!(err.to_string().contains("not allowed"), "{err}");
+        assert!(unlock_with(&strict, None, &fakes(&[UnlockMethod::Signature], &[])).is_err());
+    }
+
+    #[test]
+    fn step_up_defaults_to_infra_methods_and_skips_disallowed_ones() {
+        let policy = UnlockPolicy::default();
+        let signature_only = fakes(&[UnlockMethod::Signature], &[]);
+
+        assert!(step_up_with(&policy, &[], &signature_only).is_err());
+        assert_eq!(
+            step_up_with(&policy, &[], &fakes(ALL, &[])).unwrap(),
+            UnlockMethod::TouchId
+        );
+        assert_eq!(
+            step_up_with(&policy, &[UnlockMethod::Signature], &signature_only).unwrap(),
+            UnlockMethod::Signature
+        );
+
+        let no_infra = UnlockPolicy {
+            allowed: vec![UnlockMethod::Passphrase],
+            infra: Vec::new(),
+        };
+        let err = step_up_with(&no_infra, &[], &fakes(ALL, &[])).unwrap_err();
+        assert!(err.to_string().contains("unlock.infra is empty"), "{err}");
+        assert!(step_up_with(&no_infra, &[UnlockMethod::TouchId], &fakes(ALL, &[])).is_err());
+    }
+
+    #[test]
+    fn a_new_passphrase_needs_a_check_that_proves_presence() {
+        let policy = UnlockPolicy::default();
+
+        let err = require_presence_for_new_passphrase_with(
+            &policy,
+            &fakes(&[UnlockMethod::Signature], &[]),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("signature"), "{err}");
+
+        assert!(
+            require_presence_for_new_passphrase_with(&policy, &fakes(&[UnlockMethod::TouchId], &[]))
+                .is_ok()
+        );
+        // Replacing a passphrase may prove presence with the current one.
+        assert!(
+            require_presence_for_new_passphrase_with(
+                &policy,
+                &fakes(&[UnlockMethod::Passphrase, UnlockMethod::Signature], &[]),
+            )
+            .is_ok()
+        );
+        assert!(
+            require_presence_for_new_passphrase_with(
+                &policy,
+                &fakes(&[UnlockMethod::TouchId], &[UnlockMethod::TouchId]),
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn only_presence_checking_methods_vouch_for_a_session() {
+        assert!(checked_presence("passphrase"));
+        assert!(checked_presence("touch_id"));
+        assert!(!checked_presence("signature"));
+        assert!(!checked_presence(""));
+    }
+}
@@ -0,0 +1,567 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use anyhow::{Context as _, anyhow, bail, ensure};
+use argon2::password_hash::rand_core::{OsRng, RngCore as _};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD as B64;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use logline_core::{VaultBackendKind, VaultConfig};
+use logline_runtime::LockFile;
+use serde::{Deserialize, Serialize};
+
+use crate::commands::secrets;
+
+/// Entries the CLI writes for itself, next to the `secrets` keys. The OS keyring
+/// cannot enumerate, so listing and migration probe these names.
+pub const INTERNAL_KEYS: &[&str] = &[
+    "auth_tokens",
+    "passkey_ed25519",
+    "logline_session",
+    "unlock_passphrase_argon2",
+];
+
+const VAULT_AAD: &[u8] = b"logline-vault/v1";
+const VAULT_LOCK_STALE: Duration = Duration::from_secs(60);
+const VAULT_LOCK_WAIT: Duration = Duration::from_secs(30);
+/// Ceilings on the KDF parameters a vault file may ask for, so an edited file
+/// cannot make every command allocate gigabytes or spin for minutes. Well above
+/// the Argon2 defaults (19 MiB, 2 passes, 1 lane) the CLI writes.
+const MAX_M_COST_KIB: u32 = 1024 * 1024;
+const MAX_T_COST: u32 = 16;
+const MAX_P_COST: u32 = 16;
+
+const VAULT_INSTEAD: &str = "Set LOGLINE_VAULT_PASSPHRASE, or point [runtime.vault] key_file \
+     (or LOGLINE_VAULT_KEY_FILE) at a key file.";
+
+/// Somewhere credentials live. Keys are scoped to the active context.
+pub trait CredentialStore: Send + Sync {
+    fn kind(&self) -> VaultBackendKind;
+
+    fn get(&self, key: &str) -> anyhow::Result<Option<String>>;
+
+    fn set(&self, key: &str, value: &str) -> anyhow::Result<()>;
+
+    /// `Ok(false)` when there was nothing to delete.
+    fn delete(&self, key: &str) -> anyhow::Result<bool>;
+
+    /// Keys that hold a value.
+    fn keys(&self) -> anyhow::Result<Vec<String>>;
+
+    /// The same backend scoped to another context's keyring service instead of
+    /// the active one, e.g. to migrate every context at once.
+    fn scoped(&self, namespace: &str) -> Box<dyn CredentialStore>;
+}
+
+struct Settings {
+    config: VaultConfig,
+    cfg_dir: PathBuf,
+}
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+static STORE: OnceLock<Box<dyn CredentialStore>> = OnceLock::new();
+
+/// Install the `[runtime.vault]` settings; later calls are ignored.
+pub fn init(config: VaultConfig, cfg_dir: &Path) {
+    let _ = SETTINGS.set(Settings {
+        config,
+        cfg_dir: cfg_dir.to_path_buf(),
+    });
+}
+
+/// The configured backend (the OS keyring unless `[runtime.vault]` says otherwise).
+pub fn store() -> &'static dyn CredentialStore {
+    STORE
+        .get_or_init(|| {
+            let kind = SETTINGS
+                .get()
+                .map_or(VaultBackendKind::Keyring, |s| s.config.backend);
+            open(kind)
+        })
+        .as_ref()
+}
+
+/// A backend of `kind` with the configured settings, e.g. the other side of a migration.
+pub fn open(kind: VaultBackendKind) -> Box<dyn CredentialStore> {
+    match kind {
+        VaultBackendKind::Keyring => Box::new(KeyringStore { service: None }),
+        VaultBackendKind::File => {
+            let (config, cfg_dir) = SETTINGS.get().map_or_else(
+                || (VaultConfig::default(), crate::supabase::config_dir()),
+                |s| (s.config.clone(), s.cfg_dir.clone()),
+            );
+            let key_file = std::env::var("LOGLINE_VAULT_KEY_FILE")
+                .ok()
+                .filter(|p| !p.trim().is_empty())
+                .or(config.key_file);
+            Box::new(FileVault {
+                path: cfg_dir.join(&config.path),
+                key_file: key_file.map(|p| cfg_dir.join(p)),
+                namespace: None,
+                key: Arc::default(),
+            })
+        }
+    }
+}
+
+fn known_keys() -> impl Iterator<Item = &'static str> {
+    secrets::ALL_KEYS.iter().chain(INTERNAL_KEYS).copied()
+}
+
+// ─── OS keyring ─────────────────────────────────────────────────────────────
+
+pub struct KeyringStore {
+    /// Keyring service; the active context's when `None`.
+    service: Option<String>,
+}
+
+impl KeyringStore {
+    fn entry(&self, key: &str) -> anyhow::Result<keyring::Entry> {
+        let service = match &self.service {
+            Some(service) => service,
+            None => crate::context::keyring_service(),
+        };
+        keyring::Entry::new(service, key).map_err(|e| anyhow!("Keychain error: {e}"))
+    }
+}
+
+impl CredentialStore for KeyringStore {
+    fn kind(&self) -> VaultBackendKind {
+        VaultBackendKind::Keyring
+    }
+
+    fn get(&self, key: &str) -> anyhow::Result<Option<String>> {
+        // A missing or broken keyring reads as empty, as it always has.
+        Ok(self.entry(key).ok().and_then(|e| e.get_password().ok()))
+    }
+
+    fn set(&self, key: &str, value: &str) -> anyhow::Result<()> {
+        self.entry(key)?
+            .set_password(value)
+            .map_err(|e| anyhow!("Failed to store '{key}' in keychain: {e}"))
+    }
+
+    fn delete(&self, key: &str) -> anyhow::Result<bool> {
+        match self.entry(key)?.delete_credential() {
+            Ok(()) => Ok(true),
+            Err(keyring::Error::NoEntry) => Ok(false),
+            Err(e) => bail!("Failed to delete '{key}': {e}"),
+        }
+    }
+
+    fn keys(&self) -> anyhow::Result<Vec<String>> {
+        Ok(known_keys()
+            .filter(|k| self.get(k).ok().flatten().is_some())
+            .map(str::to_string)
+            .collect())
+    }
+
+    fn scoped(&self, namespace: &str) -> Box<dyn CredentialStore> {
+        Box::new(Self {
+            service: Some(namespace.to_string()),
+        })
+    }
+}
+
+// ─── Encrypted file ─────────────────────────────────────────────────────────
+
+/// One file for every context: a JSON envelope around an XChaCha20-Poly1305
+/// ciphertext of `{ namespace: { key: value } }`, keyed by Argon2id over a
+/// passphrase or key file. Every write re-encrypts with a fresh nonce and
+/// replaces the file atomically, under `<vault>.lock` so concurrent writers
+/// do not drop each other's changes.
+pub struct FileVault {
+    path: PathBuf,
+    key_file: Option<PathBuf>,
+    /// Namespace read and written; the active context's keyring service when `None`.
+    namespace: Option<String>,
+    /// The last derived key, so a process derives (and prompts) at most once;
+    /// shared with [`CredentialStore::scoped`] copies.
+    key: Arc<Mutex<Option<Sealing>>>,
+}
+
+/// A derived key and the salt and Argon2 parameters it was derived with.
+#[derive(Clone)]
+struct Sealing {
+    salt: [u8; 16],
+    params: Params,
+    key: [u8; 32],
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Envelope {
+    version: u32,
+    kdf: String,
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+type Namespaces = BTreeMap<String, BTreeMap<String, String>>;
+
+impl FileVault {
+    fn namespace(&self) -> &str {
+        match &self.namespace {
+            Some(namespace) => namespace,
+            None => crate::context::keyring_service(),
+        }
+    }
+
+    fn secret(&self, creating: bool) -> anyhow::Result<Vec<u8>> {
+        if let Some(path) = &self.key_file {
+            let bytes = fs::read(path)
+                .with_context(|| format!("failed to read vault key file {}", path.display()))?;
+            ensure!(
+                bytes.len() >= 32,
+                "vault key file {} must hold at least 32 bytes",
+                path.display()
+            );
+            return Ok(bytes);
+        }
+        if let Ok(passphrase) = std::env::var("LOGLINE_VAULT_PASSPHRASE") {
+            if !passphrase.is_empty() {
+                return Ok(passphrase.into_bytes());
+            }
+        }
+        let passphrase = crate::prompt::secret("Vault passphrase (hidden)", VAULT_INSTEAD)?;
+        if creating {
+            let again = crate::prompt::secret("Repeat vault passphrase (hidden)", VAULT_INSTEAD)?;
+            ensure!(passphrase == again, "Passphrases do not match.");
+            ensure!(
+                passphrase.chars().count() >= 12,
+                "Vault passphrase must be at least 12 characters."
+            );
+        }
+        Ok(passphrase.into_bytes())
+    }
+
+    fn derive(&self, salt: [u8; 16], params: Params, creating: bool) -> anyhow::Result<Sealing> {
+        let mut cached = self
+            .key
+            .lock()
+            .map_err(|_| anyhow!("vault lock poisoned"))?;
+        if let Some(sealing) = cached.as_ref() {
+            if sealing.salt == salt && sealing.params == params {
+                return Ok(sealing.clone());
+            }
+        }
+        let secret = self.secret(creating)?;
+        let mut key = [0u8; 32];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params.clone())
+            .hash_password_into(&secret, &salt, &mut key)
+            .map_err(|e| anyhow!("vault key derivation failed: {e}"))?;
+        let sealing = Sealing { salt, params, key };
+        *cached = Some(sealing.clone());
+        Ok(sealing)
+    }
+
+    /// Decrypted contents plus the key to re-encrypt with; empty for a new vault.
+    fn load(&self) -> anyhow::Result<(Namespaces, Sealing)> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let mut salt = [0u8; 16];
+                OsRng.fill_bytes(&mut salt);
+                let sealing = self.derive(salt, Params::default(), true)?;
+                return Ok((Namespaces::new(), sealing));
+            }
+            Err(e) => {
+                return Err(e).with_context(|| format!("failed to read {}", self.path.display()));
+            }
+        };
+
+        let envelope: Envelope = serde_json::from_str(&content)
+            .with_context(|| format!("{} is not a logline vault", self.path.display()))?;
+        ensure!(
+            envelope.version == 1 && envelope.kdf == "argon2id",
+            "{}: unsupported vault version {} ({})",
+            self.path.display(),
+            envelope.version,
+            envelope.kdf
+        );
+        ensure!(
+            envelope.m_cost <= MAX_M_COST_KIB
+                && envelope.t_cost <= MAX_T_COST
+                && envelope.p_cost <= MAX_P_COST,
+            "{}: KDF parameters m_cost={} t_cost={} p_cost={} exceed the limits \
+             ({MAX_M_COST_KIB} KiB, {MAX_T_COST}, {MAX_P_COST})",
+            self.path.display(),
+            envelope.m_cost,
+            envelope.t_cost,
+            envelope.p_cost
+        );
+        let salt: [u8; 16] = decode(&envelope.salt, "salt")?;
+        let nonce: [u8; 24] = decode(&envelope.nonce, "nonce")?;
+        let params = Params::new(envelope.m_cost, envelope.t_cost, envelope.p_cost, None)
+            .map_err(|e| anyhow!("{}: bad KDF parameters: {e}", self.path.display()))?;
+        let sealing = self.derive(salt, params, false)?;
+
+        let ciphertext = B64.decode(&envelope.ciphertext)?;
+        let plaintext = XChaCha20Poly1305::new(&sealing.key.into())
+            .decrypt(
+                &XNonce::from(nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad: VAULT_AAD,
+                },
+            )
+            .map_err(|_| {
+                anyhow!(
+                    "Cannot open vault {}: wrong passphrase or key file.",
+                    self.path.display()
+                )
+            })?;
+        Ok((serde_json::from_slice(&plaintext)?, sealing))
+    }
+
+    fn save(&self, entries: &Namespaces, sealing: &Sealing) -> anyhow::Result<()> {
+        let mut nonce = [0u8; 24];
+        OsRng.fill_bytes(&mut nonce);
+        let plaintext = serde_json::to_vec(entries)?;
+        let ciphertext = XChaCha20Poly1305::new(&sealing.key.into())
+            .encrypt(
+                &XNonce::from(nonce),
+                Payload {
+                    msg: &plaintext,
+                    aad: VAULT_AAD,
+                },
+            )
+            .map_err(|_| anyhow!("vault encryption failed"))?;
+        let envelope = Envelope {
+            version: 1,
+            kdf: "argon2id".to_string(),
+            m_cost: sealing.params.m_cost(),
+            t_cost: sealing.params.t_cost(),
+            p_cost: sealing.params.p_cost(),
+            salt: B64.encode(sealing.salt),
+            nonce: B64.encode(nonce),
+            ciphertext: B64.encode(ciphertext),
+        };
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp = self.path.with_extension("enc.tmp");
+        crate::supabase::write_private_file(&tmp, &serde_json::to_string_pretty(&envelope)?)?;
+        fs::rename(&tmp, &self.path)
+            .with_context(|| format!("failed to replace {}", self.path.display()))
+    }
+
+    fn update<T>(&self, f: impl FnOnce(&mut BTreeMap<String, String>) -> T) -> anyhow::Result<T> {
+        let mut lock = self.path.as_os_str().to_os_string();
+        lock.push(".lock");
+        let _lock = LockFile::acquire(Path::new(&lock), VAULT_LOCK_STALE, VAULT_LOCK_WAIT)?;
+
+        let (mut entries, sealing) = self.load()?;
+        let namespace = entries.entry(self.namespace().to_string()).or_default();
+        let result = f(namespace);
+        entries.retain(|_, keys| !keys.is_empty());
+        self.save(&entries, &sealing)?;
+        Ok(result)
+    }
+}
+
+impl CredentialStore for FileVault {
+    fn kind(&self) -> VaultBackendKind {
+        VaultBackendKind::File
+    }
+
+    fn get(&self, key: &str) -> anyhow::Result<Option<String>> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+        let (mut entries, _) = self.load()?;
+        Ok(entries
+            .remove(self.namespace())
+            .and_then(|mut keys| keys.remove(key)))
+    }
+
+    fn set(&self, key: &str, value: &str) -> anyhow::Result<()> {
+        self.update(|keys| {
+            keys.insert(key.to_string(), value.to_string());
+        })
+    }
+
+    fn delete(&self, key: &str) -> anyhow::Result<bool> {
+        if !self.path.exists() {
+            return Ok(false);
+        }
+        self.update(|keys| keys.remove(key).is_some())
+    }
+
+    fn keys(&self) -> anyhow::Result<Vec<String>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let (entries, _) = self.load()?;
+        Ok(entries
+            .get(self.namespace())
+            .map(|keys| keys.keys().cloned().collect())
+            .unwrap_or_default())
+    }
+
+    fn scoped(&self, namespace: &str) -> Box<dyn CredentialStore> {
+        Box::new(Self {
+            path: self.path.clone(),
+            key_file: self.key_file.clone(),
+            namespace: Some(namespace.to_string()),
+            key: Arc::clone(&self.key),
+        })
+    }
+}
+
+fn decode<const N: usize>(value: &str, what: &str) -> anyhow::Result<[u8; N]> {
+    B64.decode(value)?
+        .try_into()
+        .map_err(|_| anyhow!("vault {what} must be {N} bytes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("logline-vault-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// A fresh handle (no cached key) on `dir/vault.enc`, keyed by `dir/<key_name>`.
+    fn vault(dir: &Path, key_name: &str) -> FileVault {
+        let key_file = dir.join(key_name);
+        if !key_file.exists() {
+            fs::write(
+                &key_file,
+                format!("{key_name}-0123456789abcdef0123456789abcdef"),
+            )
+            .unwrap();
+        }
+        FileVault {
+            path: dir.join("vault.enc"),
+            key_file: Some(key_file),
+            namespace: None,
+            key: Arc::default(),
+        }
+    }
+
+    fn envelope(dir: &Path) -> Envelope {
+        serde_json::from_str(&fs::read_to_string(dir.join("vault.enc")).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn values_roundtrip_through_a_fresh_handle() {
+        let dir = temp_dir("roundtrip");
+        let writer = vault(&dir, "key");
+        writer.set("auth_tokens", "{\"a\":1}").unwrap();
+        writer.set("logline_session", "s").unwrap();
+        assert!(writer.delete("logline_session").unwrap());
+
+        let reader = vault(&dir, "key");
+        assert_eq!(
+            reader.get("auth_tokens").unwrap().as_deref(),
+            Some("{\"a\":1}")
+        );
+        assert_eq!(reader.get("logline_session").unwrap(), None);
+        assert_eq!(reader.keys().unwrap(), ["auth_tokens"]);
+        assert!(!dir.join("vault.enc.lock").exists());
+    }
+
+    #[test]
+    fn wrong_key_and_tampered_ciphertext_are_refused() {
+        let dir = temp_dir("refused");
+        vault(&dir, "key").set("auth_tokens", "secret").unwrap();
+
+        let err = vault(&dir, "other-key").get("auth_tokens").unwrap_err();
+        assert!(
+            err.to_string().contains("wrong passphrase or key file"),
+            "{err}"
+        );
+
+        let mut tampered = envelope(&dir);
+        let mut ciphertext = B64.decode(&tampered.ciphertext).unwrap();
+        ciphertext[0] ^= 1;
+        tampered.ciphertext = B64.encode(ciphertext);
+        fs::write(
+            dir.join("vault.enc"),
+            serde_json::to_string(&tampered).unwrap(),
+        )
+        .unwrap();
+        let err = vault(&dir, "key").get("auth_tokens").unwrap_err();
+        assert!(
+            err.to_string().contains("wrong passphrase or key file"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn rewrites_keep_the_parameters_the_key_was_derived_with() {
+        let dir = temp_dir("params");
+        let creator = vault(&dir, "key");
+        let params = Params::new(8 * 1024, 1, 1, None).unwrap();
+        let sealing = creator.derive([7; 16], params, false).unwrap();
+        creator.save(&Namespaces::new(), &sealing).unwrap();
+
+        vault(&dir, "key").set("auth_tokens", "secret").unwrap();
+        let written = envelope(&dir);
+        assert_eq!(
+            (written.m_cost, written.t_cost, written.p_cost),
+            (8 * 1024, 1, 1)
+        );
+        assert_eq!(
+            vault(&dir, "key").get("auth_tokens").unwrap().as_deref(),
+            Some("secret")
+        );
+    }
+
+    #[test]
+    fn oversized_kdf_parameters_are_refused_before_deriving() {
+        let dir = temp_dir("kdf-limits");
+        vault(&dir, "key").set("auth_tokens", "secret").unwrap();
+
+        let mut inflated = envelope(&dir);
+        inflated.m_cost = MAX_M_COST_KIB + 1;
+        fs::write(
+            dir.join("vault.enc"),
+            serde_json::to_string(&inflated).unwrap(),
+        )
+        .unwrap();
+        let err = vault(&dir, "key").get("auth_tokens").unwrap_err();
+        assert!(err.to_string().contains("exceed the limits"), "{err}");
+    }
+
+    #[test]
+    fn scoped_handles_see_only_their_namespace() {
+        let dir = temp_dir("scoped");
+        let base = vault(&dir, "key");
+        base.scoped("logline-cli@a")
+            .set("github_token", "a")
+            .unwrap();
+        base.scoped("logline-cli@b")
+            .set("github_token", "b")
+            .unwrap();
+
+        let reader = vault(&dir, "key");
+        assert_eq!(
+            reader
+                .scoped("logline-cli@a")
+                .get("github_token")
+                .unwrap()
+                .as_deref(),
+            Some("a")
+        );
+        assert_eq!(
+            reader.scoped("logline-cli@b").keys().unwrap(),
+            ["github_token"]
+        );
+        assert!(reader.scoped("logline-cli@c").keys().unwrap().is_empty());
+    }
+}
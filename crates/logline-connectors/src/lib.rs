@@ -1,10 +1,21 @@
+mod plugin;
+mod process;
+
 use std::collections::BTreeMap;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use logline_api::{
-    BackendCapabilities, BackendConfig, BackendConnector, ConnectorFactory, DomainEvent,
-    EventCursor, ExecutionResult, Intent, LoglineError, RunId, SecretStore,
+    BackendCapabilities, BackendConfig, BackendConnector, BackendKind, ConnectorFactory,
+    DomainEvent, EventCursor, ExecutionPlan, ExecutionResult, HealthReport, Intent, LoglineError,
+    PlannedEffect, RunId, SecretStore,
+};
+
+pub use logline_supabase::SupabaseConnector;
+pub use plugin::{
+    PLUGIN_PROTOCOL_VERSIONS, PeerInfo, PluginConnector, PluginInfo, PluginTransport, serve_plugin,
 };
+pub use process::ProcessConnector;
 
 pub struct EnvSecretStore;
 
@@ -36,18 +47,19 @@ impl BackendConnector for HttpLikeConnector {
             supports_streaming: true,
             supports_write: true,
             supports_history: true,
+            supports_plan: true,
         }
     }
 
-    fn health(&self) -> Result<(), LoglineError> {
+    fn health(&self) -> Result<HealthReport, LoglineError> {
         if self.base_url.is_empty() {
             return Err(LoglineError::Connection("base_url is empty".to_string()));
         }
-        Ok(())
+        Ok(HealthReport::default())
     }
 
     fn execute(&self, intent: &Intent) -> Result<ExecutionResult, LoglineError> {
-        let run_id = format!("run-{}", now_ms());
+        let run_id = format!("run-{}", next_run_seq());
         let mut output = BTreeMap::new();
         output.insert("backend".to_string(), self.id.clone());
         output.insert("intent_type".to_string(), intent.intent_type.clone());
@@ -57,6 +69,36 @@ impl BackendConnector for HttpLikeConnector {
             run_id,
             status: "accepted".to_string(),
             output,
+            recording_error: None,
+        })
+    }
+
+    fn plan(&self, intent: &Intent) -> Result<ExecutionPlan, LoglineError> {
+        let mutating = !is_read_intent(&intent.intent_type);
+        let resource = format!(
+            "{}/intents/{}",
+            self.base_url.trim_end_matches('/'),
+            intent.intent_type
+        );
+        let mut warnings = Vec::new();
+        if self.base_url.is_empty() {
+            warnings.push("base_url is empty; execution would fail".to_string());
+        }
+        let keys: Vec<&str> = intent.payload.keys().map(String::as_str).collect();
+        Ok(ExecutionPlan {
+            backend_id: self.id.clone(),
+            intent_type: intent.intent_type.clone(),
+            mutating,
+            effects: vec![PlannedEffect {
+                action: if mutating { "submit" } else { "read" }.to_string(),
+                resource,
+                description: if keys.is_empty() {
+                    "call without payload".to_string()
+                } else {
+                    format!("call with payload keys: {}", keys.join(", "))
+                },
+            }],
+            warnings,
         })
     }
 
@@ -82,22 +124,73 @@ impl BackendConnector for HttpLikeConnector {
     }
 }
 
-#[derive(Default)]
-pub struct DefaultConnectorFactory;
+pub struct DefaultConnectorFactory {
+    stop_grace: Duration,
+}
+
+impl Default for DefaultConnectorFactory {
+    fn default() -> Self {
+        Self {
+            stop_grace: Duration::from_secs(15),
+        }
+    }
+}
+
+impl DefaultConnectorFactory {
+    /// How long process connectors wait after SIGTERM before SIGKILL
+    /// (`[policy] stop_grace_seconds`).
+    #[must_use]
+    pub fn with_stop_grace(mut self, grace: Duration) -> Self {
+        self.stop_grace = grace;
+        self
+    }
+}
 
 impl ConnectorFactory for DefaultConnectorFactory {
     fn build(
         &self,
         cfg: &BackendConfig,
-        _secrets: &dyn SecretStore,
+        secrets: &dyn SecretStore,
     ) -> Result<Box<dyn BackendConnector>, LoglineError> {
-        Ok(Box::new(HttpLikeConnector::new(
-            cfg.backend_id.clone(),
-            cfg.base_url.clone(),
-        )))
+        Ok(match cfg.kind {
+            BackendKind::Http => Box::new(HttpLikeConnector::new(
+                cfg.backend_id.clone(),
+                cfg.base_url.clone(),
+            )),
+            BackendKind::Process => {
+                Box::new(ProcessConnector::new(cfg).with_stop_grace(self.stop_grace))
+            }
+            BackendKind::Plugin => Box::new(PluginConnector::new(cfg, secrets)?),
+            BackendKind::Supabase => Box::new(SupabaseConnector::new(cfg, secrets)),
+        })
     }
 }
 
+/// Intent types that only read, judged by their leading verb (`get`, `list.runs`, ...).
+fn is_read_intent(intent_type: &str) -> bool {
+    const READ_VERBS: [&str; 10] = [
+        "get", "list", "read", "describe", "status", "show", "query", "fetch", "ping", "health",
+    ];
+    let verb = intent_type
+        .split(['.', '_', '-', ':', '/'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    READ_VERBS.contains(&verb.as_str())
+}
+
+/// Millisecond timestamp, bumped when needed so parallel runs get distinct ids.
+fn next_run_seq() -> u64 {
+    static LAST: AtomicU64 = AtomicU64::new(0);
+    let now = u64::try_from(now_ms()).unwrap_or(u64::MAX);
+    let prev = LAST
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |last| {
+            Some(now.max(last + 1))
+        })
+        .unwrap_or(now);
+    now.max(prev + 1)
+}
+
 fn now_ms() -> u128 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
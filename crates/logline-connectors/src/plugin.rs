@@ -0,0 +1,722 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use logline_api::{
+    AuthMode, BackendCapabilities, BackendConfig, BackendConnector, DomainEvent, EventCursor,
+    ExecutionPlan, ExecutionResult, HealthReport, Intent, LoglineError, RunId, SecretStore,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+/// Protocol versions this build can speak, oldest first.
+pub const PLUGIN_PROTOCOL_VERSIONS: &[u32] = &[1];
+
+/// JSON-RPC error codes for each `LoglineError` kind; see `PLUGIN_PROTOCOL.md`.
+const CODE_INTERNAL: i64 = -32000;
+const CODE_VALIDATION: i64 = -32001;
+const CODE_AUTH: i64 = -32002;
+const CODE_CONNECTION: i64 = -32003;
+const CODE_CONFLICT: i64 = -32004;
+const CODE_NOT_FOUND: i64 = -32005;
+const CODE_METHOD_NOT_FOUND: i64 = -32601;
+const CODE_INVALID_PARAMS: i64 = -32602;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Request {
+    jsonrpc: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<u64>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Response {
+    jsonrpc: String,
+    id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl From<&LoglineError> for RpcError {
+    fn from(e: &LoglineError) -> Self {
+        let (code, message) = match e {
+            LoglineError::Validation(m) => (CODE_VALIDATION, m),
+            LoglineError::Auth(m) => (CODE_AUTH, m),
+            LoglineError::Connection(m) => (CODE_CONNECTION, m),
+            LoglineError::Conflict(m) => (CODE_CONFLICT, m),
+            LoglineError::NotFound(m) => (CODE_NOT_FOUND, m),
+            LoglineError::Internal(m) => (CODE_INTERNAL, m),
+        };
+        Self {
+            code,
+            message: message.clone(),
+        }
+    }
+}
+
+impl RpcError {
+    fn into_logline(self, backend_id: &str, method: &str) -> LoglineError {
+        let message = self.message;
+        match self.code {
+            CODE_VALIDATION | CODE_INVALID_PARAMS => LoglineError::Validation(message),
+            CODE_AUTH => LoglineError::Auth(message),
+            CODE_CONNECTION => LoglineError::Connection(message),
+            CODE_CONFLICT => LoglineError::Conflict(message),
+            CODE_NOT_FOUND => LoglineError::NotFound(message),
+            CODE_METHOD_NOT_FOUND => LoglineError::Validation(format!(
+                "plugin for backend {backend_id} does not support {method}"
+            )),
+            _ => LoglineError::Internal(message),
+        }
+    }
+}
+
+/// Parameters of the `initialize` handshake.
+#[derive(Debug, Serialize, Deserialize)]
+struct InitializeParams {
+    protocol_versions: Vec<u32>,
+    host: PeerInfo,
+    backend: BackendConfig,
+    /// The resolved `auth.secret_ref`, when the backend has one.
+    secret: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeerInfo {
+    pub name: String,
+    pub version: String,
+}
+
+/// What a plugin reported during the handshake.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginInfo {
+    pub protocol_version: u32,
+    pub plugin: PeerInfo,
+    pub capabilities: BackendCapabilities,
+}
+
+/// Reader and writer of one plugin connection, plus the child to reap on shutdown.
+pub type PluginTransport = (Box<dyn Read + Send>, Box<dyn Write + Send>, Option<Child>);
+
+type Pending = Arc<Mutex<HashMap<u64, mpsc::Sender<Result<Value, RpcError>>>>>;
+type Connect = Box<dyn Fn() -> Result<PluginTransport, LoglineError> + Send + Sync>;
+
+/// A backend implemented by an external program speaking JSON-RPC 2.0 over stdio.
+///
+/// The plugin is started on first use and kept for the life of the connector;
+/// if it exits, the next call starts it again. Requests carry ids, so a plugin
+/// may answer them out of order.
+pub struct PluginConnector {
+    cfg: BackendConfig,
+    secret: Result<Option<String>, String>,
+    connect: Connect,
+    session: Mutex<Option<Arc<Session>>>,
+}
+
+struct Session {
+    info: PluginInfo,
+    writer: Mutex<Box<dyn Write + Send>>,
+    pending: Pending,
+    next_id: AtomicU64,
+    alive: Arc<AtomicBool>,
+    child: Mutex<Option<Child>>,
+}
+
+impl PluginConnector {
+    /// Spawn `cfg.plugin.executable` on first use; its stderr passes through.
+    pub fn new(cfg: &BackendConfig, secrets: &dyn SecretStore) -> Result<Self, LoglineError> {
+        let spec = cfg.plugin.clone().ok_or_else(|| {
+            LoglineError::Validation(format!(
+                "plugin backend {} needs plugin.executable",
+                cfg.backend_id
+            ))
+        })?;
+        Ok(Self::with_transport(cfg, secrets, move || {
+            let mut child = Command::new(&spec.executable)
+                .args(&spec.args)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::inherit())
+                .spawn()
+                .map_err(|e| {
+                    LoglineError::Connection(format!("failed to start {}: {e}", spec.executable))
+                })?;
+            let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+                return Err(LoglineError::Internal(format!(
+                    "{} started without stdio pipes",
+                    spec.executable
+                )));
+            };
+            Ok((Box::new(stdout), Box::new(stdin), Some(child)))
+        }))
+    }
+
+    /// Talk to a plugin over streams opened by `connect`, e.g. a socket.
+    pub fn with_transport<F>(cfg: &BackendConfig, secrets: &dyn SecretStore, connect: F) -> Self
+    where
+        F: Fn() -> Result<PluginTransport, LoglineError> + Send + Sync + 'static,
+    {
+        // A missing secret only fails the backend once it is used, not the whole runtime.
+        let secret = match cfg.auth.mode {
+            AuthMode::None => Ok(None),
+            _ if cfg.auth.secret_ref.is_empty() => Ok(None),
+            _ => secrets
+                .get(&cfg.auth.secret_ref)
+                .map(Some)
+                .map_err(|e| e.to_string()),
+        };
+        Self {
+            cfg: cfg.clone(),
+            secret,
+            connect: Box::new(connect),
+            session: Mutex::new(None),
+        }
+    }
+
+    /// The handshake result, starting the plugin if needed.
+    pub fn info(&self) -> Result<PluginInfo, LoglineError> {
+        Ok(self.session()?.info.clone())
+    }
+
+    fn session(&self) -> Result<Arc<Session>, LoglineError> {
+        let mut guard = self
+            .session
+            .lock()
+            .map_err(|_| LoglineError::Internal("plugin session poisoned".to_string()))?;
+        if let Some(session) = guard.as_ref().filter(|s| s.alive.load(Ordering::SeqCst)) {
+            return Ok(Arc::clone(session));
+        }
+        let secret = self.secret.clone().map_err(LoglineError::Auth)?;
+        let session = Arc::new(Session::start(&self.connect, &self.cfg, secret)?);
+        *guard = Some(Arc::clone(&session));
+        Ok(session)
+    }
+
+    fn call<T: for<'de> Deserialize<'de>>(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> Result<T, LoglineError> {
+        let session = self.session()?;
+        let timeout = Duration::from_millis(self.cfg.request_timeout_ms);
+        let value = session.call(&self.cfg.backend_id, method, params, timeout)?;
+        serde_json::from_value(value).map_err(|e| {
+            LoglineError::Internal(format!(
+                "plugin for backend {} sent an invalid {method} result: {e}",
+                self.cfg.backend_id
+            ))
+        })
+    }
+}
+
+impl Session {
+    fn start(
+        connect: &Connect,
+        cfg: &BackendConfig,
+        secret: Option<String>,
+    ) -> Result<Self, LoglineError> {
+        let (reader, writer, child) = connect()?;
+        let pending: Pending = Arc::new(Mutex::new(HashMap::new()));
+        let alive = Arc::new(AtomicBool::new(true));
+        spawn_reader(reader, Arc::clone(&pending), Arc::clone(&alive));
+
+        let mut session = Self {
+            info: PluginInfo {
+                protocol_version: 0,
+                plugin: PeerInfo {
+                    name: String::new(),
+                    version: String::new(),
+                },
+                capabilities: BackendCapabilities {
+                    supports_streaming: false,
+                    supports_write: false,
+                    supports_history: false,
+                    supports_plan: false,
+                },
+            },
+            writer: Mutex::new(writer),
+            pending,
+            next_id: AtomicU64::new(1),
+            alive,
+            child: Mutex::new(child),
+        };
+        let params = InitializeParams {
+            protocol_versions: PLUGIN_PROTOCOL_VERSIONS.to_vec(),
+            host: PeerInfo {
+                name: "logline".to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+            },
+            backend: cfg.clone(),
+            secret,
+        };
+        let value = session.call(
+            &cfg.backend_id,
+            "initialize",
+            json!(params),
+            Duration::from_millis(cfg.connect_timeout_ms),
+        )?;
+        let info: PluginInfo = serde_json::from_value(value).map_err(|e| {
+            LoglineError::Connection(format!(
+                "plugin for backend {} sent an invalid handshake: {e}",
+                cfg.backend_id
+            ))
+        })?;
+        if !PLUGIN_PROTOCOL_VERSIONS.contains(&info.protocol_version) {
+            return Err(LoglineError::Connection(format!(
+                "plugin {} for backend {} speaks protocol {}, this build supports {PLUGIN_PROTOCOL_VERSIONS:?}",
+                info.plugin.name, cfg.backend_id, info.protocol_version
+            )));
+        }
+        session.info = info;
+        Ok(session)
+    }
+
+    fn send(&self, request: &Request) -> Result<(), LoglineError> {
+        let mut line = serde_json::to_string(request)
+            .map_err(|e| LoglineError::Internal(format!("failed to encode request: {e}")))?;
+        line.push('\n');
+        let mut writer = self
+            .writer
+            .lock()
+            .map_err(|_| LoglineError::Internal("plugin writer poisoned".to_string()))?;
+        writer
+            .write_all(line.as_bytes())
+            .and_then(|()| writer.flush())
+            .map_err(|e| {
+                self.alive.store(false, Ordering::SeqCst);
+                LoglineError::Connection(format!("plugin stream closed: {e}"))
+            })
+    }
+
+    fn call(
+        &self,
+        backend_id: &str,
+        method: &str,
+        params: Value,
+        timeout: Duration,
+    ) -> Result<Value, LoglineError> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (tx, rx) = mpsc::channel();
+        self.pending
+            .lock()
+            .map_err(|_| LoglineError::Internal("plugin requests poisoned".to_string()))?
+            .insert(id, tx);
+        self.send(&Request {
+            jsonrpc: "2.0".to_string(),
+            id: Some(id),
+            method: method.to_string(),
+            params,
+        })?;
+
+        match rx.recv_timeout(timeout) {
+            Ok(result) => result.map_err(|e| e.into_logline(backend_id, method)),
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                if let Ok(mut pending) = self.pending.lock() {
+                    pending.remove(&id);
+                }
+                Err(LoglineError::Connection(format!(
+                    "plugin for backend {backend_id} did not answer {method} within {}ms",
+                    timeout.as_millis()
+                )))
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => Err(LoglineError::Connection(format!(
+                "plugin for backend {backend_id} exited during {method}"
+            ))),
+        }
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        let _ = self.send(&Request {
+            jsonrpc: "2.0".to_string(),
+            id: None,
+            method: "shutdown".to_string(),
+            params: Value::Null,
+        });
+        // Closing stdin is the second hint; kill the plugin if it still lingers.
+        if let Ok(mut writer) = self.writer.lock() {
+            *writer = Box::new(std::io::sink());
+        }
+        let Ok(mut child) = self.child.lock() else {
+            return;
+        };
+        if let Some(child) = child.as_mut() {
+            let deadline = Instant::now() + Duration::from_secs(1);
+            while Instant::now() < deadline {
+                if matches!(child.try_wait(), Ok(Some(_))) {
+                    return;
+                }
+                thread::sleep(Duration::from_millis(20));
+            }
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+/// Route responses to waiting callers; when the stream ends, fail them all.
+fn spawn_reader(reader: Box<dyn Read + Send>, pending: Pending, alive: Arc<AtomicBool>) {
+    thread::spawn(move || {
+        for line in BufReader::new(reader).lines() {
+            let Ok(line) = line else { break };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let Ok(response) = serde_json::from_str::<Response>(&line) else {
+                continue;
+            };
+            let Some(id) = response.id else { continue };
+            let result = match (response.result, response.error) {
+                (_, Some(error)) => Err(error),
+                (result, None) => Ok(result.unwrap_or(Value::Null)),
+            };
+            if let Some(tx) = pending.lock().ok().and_then(|mut p| p.remove(&id)) {
+                let _ = tx.send(result);
+            }
+        }
+        alive.store(false, Ordering::SeqCst);
+        if let Ok(mut pending) = pending.lock() {
+            pending.clear();
+        }
+    });
+}
+
+impl BackendConnector for PluginConnector {
+    fn id(&self) -> &str {
+        &self.cfg.backend_id
+    }
+
+    /// Reported by the plugin; nothing is supported while it cannot be started.
+    fn capabilities(&self) -> BackendCapabilities {
+        self.session().map_or(
+            BackendCapabilities {
+                supports_streaming: false,
+                supports_write: false,
+                supports_history: false,
+                supports_plan: false,
+            },
+            |s| s.info.capabilities.clone(),
+        )
+    }
+
+    /// Plugins that answer `null` get a report with only the round-trip latency.
+    fn health(&self) -> Result<HealthReport, LoglineError> {
+        let started = Instant::now();
+        let report: Option<HealthReport> = self.call("health", Value::Null)?;
+        let mut report = report.unwrap_or_default();
+        if report.latency_ms == 0 {
+            report.latency_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+        }
+        Ok(report)
+    }
+
+    fn execute(&self, intent: &Intent) -> Result<ExecutionResult, LoglineError> {
+        self.call("execute", json!({ "intent": intent }))
+    }
+
+    fn plan(&self, intent: &Intent) -> Result<ExecutionPlan, LoglineError> {
+        if !self.capabilities().supports_plan {
+            return Err(LoglineError::Validation(format!(
+                "backend {} cannot plan intent {}",
+                self.cfg.backend_id, intent.intent_type
+            )));
+        }
+        self.call("plan", json!({ "intent": intent }))
+    }
+
+    fn stop(&self, run_id: &RunId) -> Result<(), LoglineError> {
+        self.call::<Value>("stop", json!({ "run_id": run_id }))
+            .map(|_| ())
+    }
+
+    fn events_since(&self, cursor: Option<&EventCursor>) -> Result<Vec<DomainEvent>, LoglineError> {
+        self.call("events_since", json!({ "cursor": cursor }))
+    }
+}
+
+/// Serve a connector as a plugin over `input`/`output` (normally stdin/stdout)
+/// until the host sends `shutdown` or closes the stream.
+///
+/// `build` runs once, during the `initialize` handshake, with the backend's
+/// config and resolved secret. Requests are answered in order.
+pub fn serve_plugin<B>(
+    plugin: &PeerInfo,
+    build: B,
+    input: impl BufRead,
+    mut output: impl Write,
+) -> Result<(), LoglineError>
+where
+    B: FnOnce(BackendConfig, Option<String>) -> Result<Box<dyn BackendConnector>, LoglineError>,
+{
+    let mut build = Some(build);
+    let mut connector: Option<Box<dyn BackendConnector>> = None;
+    for line in input.lines() {
+        let line = line.map_err(|e| LoglineError::Connection(format!("stdin closed: {e}")))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let request: Request = match serde_json::from_str(&line) {
+            Ok(request) => request,
+            Err(e) => {
+                respond(
+                    &mut output,
+                    None,
+                    Err(RpcError {
+                        code: -32700,
+                        message: format!("parse error: {e}"),
+                    }),
+                )?;
+                continue;
+            }
+        };
+        if request.method == "shutdown" {
+            if request.id.is_some() {
+                respond(&mut output, request.id, Ok(Value::Null))?;
+            }
+            return Ok(());
+        }
+
+        let result = if request.method == "initialize" {
+            match build.take() {
+                Some(build) => initialize(plugin, build, request.params).map(|(conn, info)| {
+                    connector = Some(conn);
+                    info
+                }),
+                None => Err(RpcError {
+                    code: CODE_VALIDATION,
+                    message: "initialize was already attempted".to_string(),
+                }),
+            }
+        } else if let Some(conn) = connector.as_deref() {
+            dispatch(conn, &request.method, &request.params)
+        } else {
+            Err(RpcError {
+                code: CODE_VALIDATION,
+                message: "initialize must be the first request".to_string(),
+            })
+        };
+        if request.id.is_some() {
+            respond(&mut output, request.id, result)?;
+        }
+    }
+    Ok(())
+}
+
+fn initialize<B>(
+    plugin: &PeerInfo,
+    build: B,
+    params: Value,
+) -> Result<(Box<dyn BackendConnector>, Value), RpcError>
+where
+    B: FnOnce(BackendConfig, Option<String>) -> Result<Box<dyn BackendConnector>, LoglineError>,
+{
+    let params: InitializeParams = serde_json::from_value(params).map_err(|e| RpcError {
+        code: CODE_INVALID_PARAMS,
+        message: format!("invalid initialize params: {e}"),
+    })?;
+    let version = params
+        .protocol_versions
+        .iter()
+        .filter(|v| PLUGIN_PROTOCOL_VERSIONS.contains(v))
+        .max()
+        .copied()
+        .ok_or_else(|| RpcError {
+            code: CODE_INVALID_PARAMS,
+            message: format!(
+                "no common protocol version: host offers {:?}, plugin supports {PLUGIN_PROTOCOL_VERSIONS:?}",
+                params.protocol_versions
+            ),
+        })?;
+    let connector = build(params.backend, params.secret).map_err(|e| RpcError::from(&e))?;
+    let info = PluginInfo {
+        protocol_version: version,
+        plugin: plugin.clone(),
+        capabilities: connector.capabilities(),
+    };
+    Ok((connector, json!(info)))
+}
+
+fn dispatch(conn: &dyn BackendConnector, method: &str, params: &Value) -> Result<Value, RpcError> {
+    fn param<T: for<'de> Deserialize<'de>>(params: &Value, key: &str) -> Result<T, RpcError> {
+        serde_json::from_value(params.get(key).cloned().unwrap_or(Value::Null)).map_err(|e| {
+            RpcError {
+                code: CODE_INVALID_PARAMS,
+                message: format!("invalid {key}: {e}"),
+            }
+        })
+    }
+    fn encode<T: Serialize>(result: Result<T, LoglineError>) -> Result<Value, RpcError> {
+        result.map_err(|e| RpcError::from(&e)).and_then(|v| {
+            serde_json::to_value(v).map_err(|e| RpcError {
+                code: CODE_INTERNAL,
+                message: e.to_string(),
+            })
+        })
+    }
+
+    match method {
+        "health" => encode(conn.health()),
+        "execute" => encode(conn.execute(&param(params, "intent")?)),
+        "plan" => encode(conn.plan(&param(params, "intent")?)),
+        "stop" => encode(conn.stop(&param(params, "run_id")?)),
+        "events_since" => {
+            let cursor: Option<EventCursor> = param(params, "cursor")?;
+            encode(conn.events_since(cursor.as_ref()))
+        }
+        _ => Err(RpcError {
+            code: CODE_METHOD_NOT_FOUND,
+            message: format!("unknown method {method}"),
+        }),
+    }
+}
+
+fn respond(
+    output: &mut impl Write,
+    id: Option<u64>,
+    result: Result<Value, RpcError>,
+) -> Result<(), LoglineError> {
+    let (result, error) = match result {
+        Ok(value) => (Some(value), None),
+        Err(error) => (None, Some(error)),
+    };
+    let mut line = serde_json::to_string(&Response {
+        jsonrpc: "2.0".to_string(),
+        id,
+        result,
+        error,
+    })
+    .map_err(|e| LoglineError::Internal(format!("failed to encode response: {e}")))?;
+    line.push('\n');
+    output
+        .write_all(line.as_bytes())
+        .and_then(|()| output.flush())
+        .map_err(|e| LoglineError::Connection(format!("stdout closed: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::os::unix::net::UnixStream;
+
+    use logline_api::{BackendAuth, BackendKind};
+
+    use super::*;
+    use crate::{EnvSecretStore, HttpLikeConnector};
+
+    fn plugin_config() -> BackendConfig {
+        BackendConfig {
+            backend_id: "foo".to_string(),
+            kind: BackendKind::Plugin,
+            base_url: "http://foo.internal".to_string(),
+            auth: BackendAuth {
+                mode: AuthMode::None,
+                secret_ref: String::new(),
+            },
+            connect_timeout_ms: 2_000,
+            request_timeout_ms: 2_000,
+            extra_headers: BTreeMap::new(),
+            commands: BTreeMap::new(),
+            plugin: None,
+            supabase: None,
+        }
+    }
+
+    /// Each connect starts an in-process plugin serving `HttpLikeConnector` over a socket pair.
+    fn connect_in_process() -> Result<PluginTransport, LoglineError> {
+        let (host, plugin) =
+            UnixStream::pair().map_err(|e| LoglineError::Internal(e.to_string()))?;
+        let plugin_out = plugin
+            .try_clone()
+            .map_err(|e| LoglineError::Internal(e.to_string()))?;
+        thread::spawn(move || {
+            serve_plugin(
+                &PeerInfo {
+                    name: "logline-connector-foo".to_string(),
+                    version: "0.0.1".to_string(),
+                },
+                |cfg, _secret| {
+                    Ok(
+                        Box::new(HttpLikeConnector::new(cfg.backend_id, cfg.base_url))
+                            as Box<dyn BackendConnector>,
+                    )
+                },
+                BufReader::new(plugin),
+                plugin_out,
+            )
+        });
+        let host_out = host
+            .try_clone()
+            .map_err(|e| LoglineError::Internal(e.to_string()))?;
+        Ok((Box::new(host), Box::new(host_out), None))
+    }
+
+    #[test]
+    fn host_and_plugin_round_trip_the_connector_surface() {
+        let conn =
+            PluginConnector::with_transport(&plugin_config(), &EnvSecretStore, connect_in_process);
+
+        let info = conn.info().unwrap();
+        assert_eq!(info.protocol_version, 1);
+        assert_eq!(info.plugin.name, "logline-connector-foo");
+        assert!(conn.capabilities().supports_plan);
+        conn.health().unwrap();
+
+        let intent = Intent {
+            intent_type: "deploy".to_string(),
+            payload: BTreeMap::new(),
+        };
+        let result = conn.execute(&intent).unwrap();
+        assert_eq!(result.output["target"], "http://foo.internal");
+        assert!(conn.plan(&intent).unwrap().mutating);
+        conn.stop(&result.run_id).unwrap();
+        assert_eq!(
+            conn.events_since(Some(&"7".to_string())).unwrap()[0].attributes["since"],
+            "7"
+        );
+    }
+
+    #[test]
+    fn plugin_rejects_unknown_protocol_versions() {
+        let request = json!({
+            "jsonrpc": "2.0", "id": 1, "method": "initialize",
+            "params": {
+                "protocol_versions": [99],
+                "host": {"name": "logline", "version": "9"},
+                "backend": plugin_config(),
+                "secret": null,
+            },
+        });
+        let mut out = Vec::new();
+        serve_plugin(
+            &PeerInfo {
+                name: "p".to_string(),
+                version: "1".to_string(),
+            },
+            |_, _| Err(LoglineError::Internal("must not build".to_string())),
+            format!("{request}\n").as_bytes(),
+            &mut out,
+        )
+        .unwrap();
+
+        let response: Response = serde_json::from_slice(&out).unwrap();
+        let error = response.error.unwrap();
+        assert_eq!(error.code, CODE_INVALID_PARAMS);
+        assert!(error.message.contains("no common protocol version"));
+    }
+}
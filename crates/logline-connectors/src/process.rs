@@ -0,0 +1,698 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::ffi::OsString;
+use std::fs;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use logline_api::{
+    BackendCapabilities, BackendConfig, BackendConnector, DomainEvent, EventCursor, ExecutionPlan,
+    ExecutionResult, HealthReport, Intent, LoglineError, PlannedEffect, ProcessCommand, RunId,
+};
+
+use crate::{next_run_seq, now_ms};
+
+/// Events kept in memory for `events_since`; older ones are dropped.
+const MAX_EVENTS: usize = 10_000;
+/// Stdout lines of this form become entries of the run's `output` map.
+const OUTPUT_PREFIX: &str = "::output ";
+const POLL: Duration = Duration::from_millis(20);
+/// How long output may keep arriving after the process exits. A background
+/// child that inherited the pipes would otherwise hold `execute` open.
+const OUTPUT_DRAIN: Duration = Duration::from_secs(1);
+
+/// Runs intents as local subprocesses, one configured command per intent type.
+///
+/// Each process starts with a cleared environment (only `PATH`, the command's
+/// `env` and `LOGLINE_*` variables are set), no stdin, and a scratch working
+/// directory unless the command names one. Stdout and stderr lines are
+/// published as `process.stdout` / `process.stderr` events. Each process
+/// leads its own process group, and timeouts and `stop` signal the whole
+/// group. Every run keeps its pid under `runs_dir/<run_id>` (a 0700 directory
+/// in `XDG_RUNTIME_DIR`, else the config directory), so `stop` also works from
+/// another `logline` process.
+pub struct ProcessConnector {
+    id: String,
+    commands: BTreeMap<String, ProcessCommand>,
+    runs_dir: PathBuf,
+    stop_grace: Duration,
+    events: Arc<Mutex<EventLog>>,
+}
+
+#[derive(Default)]
+struct EventLog {
+    next: u64,
+    events: VecDeque<DomainEvent>,
+}
+
+impl EventLog {
+    fn push(&mut self, kind: &str, run_id: &str, attributes: BTreeMap<String, String>) {
+        self.next += 1;
+        self.events.push_back(DomainEvent {
+            cursor: self.next.to_string(),
+            ts_unix_ms: i64::try_from(now_ms()).unwrap_or(i64::MAX),
+            kind: kind.to_string(),
+            run_id: Some(run_id.to_string()),
+            attributes,
+        });
+        if self.events.len() > MAX_EVENTS {
+            self.events.pop_front();
+        }
+    }
+}
+
+impl ProcessConnector {
+    pub fn new(cfg: &BackendConfig) -> Self {
+        Self {
+            id: cfg.backend_id.clone(),
+            commands: cfg.commands.clone(),
+            runs_dir: default_runs_root().join(&cfg.backend_id),
+            stop_grace: Duration::from_secs(15),
+            events: Arc::new(Mutex::new(EventLog::default())),
+        }
+    }
+
+    /// Where pid files and scratch directories live; created private (0700).
+    #[must_use]
+    pub fn with_runs_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.runs_dir = dir.into();
+        self
+    }
+
+    /// How long `stop` waits after SIGTERM before sending SIGKILL.
+    #[must_use]
+    pub fn with_stop_grace(mut self, grace: Duration) -> Self {
+        self.stop_grace = grace;
+        self
+    }
+
+    fn command(&self, intent_type: &str) -> Result<&ProcessCommand, LoglineError> {
+        self.commands.get(intent_type).ok_or_else(|| {
+            LoglineError::Validation(format!(
+                "backend {} has no command for intent {intent_type}",
+                self.id
+            ))
+        })
+    }
+
+    fn run_dir(&self, run_id: &str) -> Result<PathBuf, LoglineError> {
+        if run_id.is_empty() || run_id.contains(['/', '\\']) || run_id.starts_with('.') {
+            return Err(LoglineError::Validation(format!("invalid run id {run_id}")));
+        }
+        Ok(self.runs_dir.join(run_id))
+    }
+
+    fn record(&self, kind: &str, run_id: &str, attributes: BTreeMap<String, String>) {
+        if let Ok(mut log) = self.events.lock() {
+            log.push(kind, run_id, attributes);
+        }
+    }
+
+    /// Forward each line of `stream` as an event; stdout also collects `::output` lines.
+    fn pump(
+        &self,
+        stream: impl Read + Send + 'static,
+        kind: &'static str,
+        run_id: &str,
+        output: Option<Arc<Mutex<BTreeMap<String, String>>>>,
+    ) -> thread::JoinHandle<()> {
+        let events = Arc::clone(&self.events);
+        let run_id = run_id.to_string();
+        thread::spawn(move || {
+            for line in BufReader::new(stream).lines() {
+                let Ok(line) = line else { break };
+                if let (Some(output), Some(rest)) = (&output, line.strip_prefix(OUTPUT_PREFIX)) {
+                    if let (Some((k, v)), Ok(mut output)) = (rest.split_once('='), output.lock()) {
+                        output.insert(k.trim().to_string(), v.to_string());
+                    }
+                }
+                if let Ok(mut log) = events.lock() {
+                    log.push(kind, &run_id, BTreeMap::from([("line".to_string(), line)]));
+                }
+            }
+        })
+    }
+
+    /// The process for one run: cleared environment, no stdin, piped output, and
+    /// its own process group.
+    fn command_line(
+        &self,
+        command: &ProcessCommand,
+        args: &[String],
+        intent: &Intent,
+        run_id: &str,
+        work_dir: &Path,
+    ) -> Command {
+        let mut cmd = Command::new(&command.program);
+        cmd.args(args)
+            .env_clear()
+            .env("PATH", search_path())
+            .envs(&command.env)
+            .env("LOGLINE_BACKEND", &self.id)
+            .env("LOGLINE_INTENT", &intent.intent_type)
+            .env("LOGLINE_RUN_ID", run_id)
+            .current_dir(work_dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        for (key, value) in &intent.payload {
+            cmd.env(payload_var(key), value);
+        }
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut cmd, 0);
+        cmd
+    }
+
+    /// Poll `child` until it exits, terminating it once `timeout` has passed.
+    fn wait(
+        &self,
+        child: &mut Child,
+        timeout: Option<Duration>,
+    ) -> Result<(ExitStatus, bool), LoglineError> {
+        let started = Instant::now();
+        let mut timed_out = false;
+        loop {
+            match child.try_wait() {
+                Ok(Some(status)) => return Ok((status, timed_out)),
+                Ok(None) => {}
+                Err(e) => {
+                    return Err(LoglineError::Internal(format!(
+                        "failed to wait for process {}: {e}",
+                        child.id()
+                    )));
+                }
+            }
+            if !timed_out && timeout.is_some_and(|t| started.elapsed() >= t) {
+                timed_out = true;
+                let pid = child.id();
+                terminate(pid, self.stop_grace, || {
+                    matches!(child.try_wait(), Ok(Some(_))) && !signal(pid, "0")
+                });
+            }
+            thread::sleep(POLL);
+        }
+    }
+}
+
+impl BackendConnector for ProcessConnector {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities {
+            supports_streaming: true,
+            supports_write: true,
+            supports_history: false,
+            supports_plan: true,
+        }
+    }
+
+    /// Every configured program must resolve; there is no server to report a version.
+    fn health(&self) -> Result<HealthReport, LoglineError> {
+        let started = Instant::now();
+        let path = search_path();
+        for (intent_type, command) in &self.commands {
+            if find_program(&command.program, &path).is_none() {
+                return Err(LoglineError::Connection(format!(
+                    "program {} for intent {intent_type} not found",
+                    command.program
+                )));
+            }
+        }
+        Ok(HealthReport {
+            latency_ms: u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX),
+            ..HealthReport::default()
+        })
+    }
+
+    fn execute(&self, intent: &Intent) -> Result<ExecutionResult, LoglineError> {
+        let command = self.command(&intent.intent_type)?;
+        let args = render_args(&command.args, &intent.payload)?;
+        let run_id = format!("proc-{}", next_run_seq());
+        let run_dir = self.run_dir(&run_id)?;
+        create_private_dir(&self.runs_dir)?;
+        let work_dir = command
+            .working_dir
+            .as_ref()
+            .map_or_else(|| run_dir.join("work"), PathBuf::from);
+        for dir in [&run_dir, &work_dir] {
+            fs::create_dir_all(dir).map_err(|e| {
+                LoglineError::Internal(format!("failed to create {}: {e}", dir.display()))
+            })?;
+        }
+
+        let mut cmd = self.command_line(command, &args, intent, &run_id, &work_dir);
+        let mut child = cmd.spawn().map_err(|e| {
+            let _ = fs::remove_dir_all(&run_dir);
+            LoglineError::Connection(format!("failed to start {}: {e}", command.program))
+        })?;
+
+        let pid = child.id();
+        let _ = fs::write(run_dir.join("pid"), pid.to_string());
+        self.record(
+            "process.started",
+            &run_id,
+            BTreeMap::from([
+                ("program".to_string(), command.program.clone()),
+                ("pid".to_string(), pid.to_string()),
+            ]),
+        );
+
+        let output = Arc::new(Mutex::new(BTreeMap::new()));
+        let mut pumps = Vec::new();
+        if let Some(stdout) = child.stdout.take() {
+            pumps.push(self.pump(stdout, "process.stdout", &run_id, Some(Arc::clone(&output))));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            pumps.push(self.pump(stderr, "process.stderr", &run_id, None));
+        }
+        let waited = self.wait(&mut child, command.timeout_seconds.map(Duration::from_secs));
+        let drained = Instant::now() + OUTPUT_DRAIN;
+        while pumps.iter().any(|p| !p.is_finished()) && Instant::now() < drained {
+            thread::sleep(POLL);
+        }
+        // Pumps still running are left to end when the last writer closes.
+        for pump in pumps.into_iter().filter(thread::JoinHandle::is_finished) {
+            let _ = pump.join();
+        }
+        let stop_requested = run_dir.join("stop").exists();
+        let _ = fs::remove_dir_all(&run_dir);
+        let (status, timed_out) = waited?;
+
+        let mut output = output
+            .lock()
+            .map(|o| o.clone())
+            .map_err(|_| LoglineError::Internal("process output poisoned".to_string()))?;
+        let exit = match status.code() {
+            Some(code) => ("exit_code".to_string(), code.to_string()),
+            None => ("signal".to_string(), "terminated".to_string()),
+        };
+        self.record("process.exited", &run_id, BTreeMap::from([exit.clone()]));
+        output.insert(exit.0, exit.1);
+
+        if stop_requested {
+            return Ok(ExecutionResult {
+                run_id,
+                status: "stopped".to_string(),
+                output,
+                recording_error: None,
+            });
+        }
+        if timed_out {
+            return Err(LoglineError::Connection(format!(
+                "{} timed out after {}s",
+                command.program,
+                command.timeout_seconds.unwrap_or_default()
+            )));
+        }
+        if !status.success() {
+            return Err(LoglineError::Internal(format!(
+                "{} exited with {status}",
+                command.program
+            )));
+        }
+        Ok(ExecutionResult {
+            run_id,
+            status: "succeeded".to_string(),
+            output,
+            recording_error: None,
+        })
+    }
+
+    fn plan(&self, intent: &Intent) -> Result<ExecutionPlan, LoglineError> {
+        let command = self.command(&intent.intent_type)?;
+        let mut warnings = Vec::new();
+        let args = render_args(&command.args, &intent.payload).unwrap_or_else(|e| {
+            warnings.push(e.to_string());
+            command.args.clone()
+        });
+        if find_program(&command.program, &search_path()).is_none() {
+            warnings.push(format!("program {} not found", command.program));
+        }
+        let mut line = command.program.clone();
+        for arg in &args {
+            line.push(' ');
+            line.push_str(arg);
+        }
+        Ok(ExecutionPlan {
+            backend_id: self.id.clone(),
+            intent_type: intent.intent_type.clone(),
+            mutating: !command.read_only,
+            effects: vec![PlannedEffect {
+                action: if command.read_only { "read" } else { "exec" }.to_string(),
+                resource: command.program.clone(),
+                description: format!(
+                    "run `{line}` in {}",
+                    command
+                        .working_dir
+                        .as_deref()
+                        .unwrap_or("a scratch directory")
+                ),
+            }],
+            warnings,
+        })
+    }
+
+    fn stop(&self, run_id: &RunId) -> Result<(), LoglineError> {
+        let run_dir = self.run_dir(run_id)?;
+        let pid: u32 = fs::read_to_string(run_dir.join("pid"))
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+            .ok_or_else(|| {
+                LoglineError::NotFound(format!("run {run_id} is not running on {}", self.id))
+            })?;
+        fs::write(run_dir.join("stop"), b"").map_err(|e| {
+            LoglineError::Internal(format!("failed to mark {run_id} as stopping: {e}"))
+        })?;
+        self.record(
+            "process.stopping",
+            run_id,
+            BTreeMap::from([("pid".to_string(), pid.to_string())]),
+        );
+        terminate(pid, self.stop_grace, || !signal(pid, "0"));
+        Ok(())
+    }
+
+    fn events_since(&self, cursor: Option<&EventCursor>) -> Result<Vec<DomainEvent>, LoglineError> {
+        let after = match cursor {
+            Some(c) => c.parse::<u64>().map_err(|_| {
+                LoglineError::Validation(format!("invalid cursor {c} for backend {}", self.id))
+            })?,
+            None => 0,
+        };
+        let log = self
+            .events
+            .lock()
+            .map_err(|_| LoglineError::Internal("process events poisoned".to_string()))?;
+        Ok(log
+            .events
+            .iter()
+            .filter(|e| e.cursor.parse::<u64>().is_ok_and(|seq| seq > after))
+            .cloned()
+            .collect())
+    }
+}
+
+/// SIGTERM to the process group led by `pid`, then SIGKILL if `exited` is
+/// still false after `grace`.
+fn terminate(pid: u32, grace: Duration, mut exited: impl FnMut() -> bool) {
+    if !signal(pid, "TERM") {
+        return;
+    }
+    let deadline = Instant::now() + grace;
+    while Instant::now() < deadline {
+        if exited() {
+            return;
+        }
+        thread::sleep(POLL);
+    }
+    if !exited() {
+        signal(pid, "KILL");
+    }
+}
+
+/// Signal the process group led by `pid` through `kill(1)`, which keeps this
+/// crate free of `unsafe`. `"0"` only checks whether any member is alive.
+fn signal(pid: u32, name: &str) -> bool {
+    Command::new("kill")
+        .arg(format!("-{name}"))
+        .arg("--")
+        .arg(format!("-{pid}"))
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+/// Replace `{{key}}` placeholders with payload values.
+fn render_args(
+    args: &[String],
+    payload: &BTreeMap<String, String>,
+) -> Result<Vec<String>, LoglineError> {
+    args.iter()
+        .map(|arg| {
+            let mut rendered = String::new();
+            let mut rest = arg.as_str();
+            while let Some(start) = rest.find("{{") {
+                let Some(len) = rest[start + 2..].find("}}") else {
+                    break;
+                };
+                let key = rest[start + 2..start + 2 + len].trim();
+                let value = payload.get(key).ok_or_else(|| {
+                    LoglineError::Validation(format!("payload is missing {key} for argument {arg}"))
+                })?;
+                rendered.push_str(&rest[..start]);
+                rendered.push_str(value);
+                rest = &rest[start + 4 + len..];
+            }
+            rendered.push_str(rest);
+            Ok(rendered)
+        })
+        .collect()
+}
+
+/// `LOGLINE_PAYLOAD_<KEY>`, with the key upper-cased and other characters as `_`.
+fn payload_var(key: &str) -> String {
+    let key: String = key
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("LOGLINE_PAYLOAD_{key}")
+}
+
+/// `XDG_RUNTIME_DIR` (per user, cleared at logout), else the CLI config
+/// directory; never the shared temp dir, where other users could plant or read
+/// pid files.
+fn default_runs_root() -> PathBuf {
+    dirs::runtime_dir()
+        .or_else(dirs::config_dir)
+        .map_or_else(std::env::temp_dir, |dir| dir.join("logline"))
+        .join("process")
+}
+
+fn create_private_dir(dir: &Path) -> Result<(), LoglineError> {
+    let failed = |e: std::io::Error| {
+        LoglineError::Internal(format!("failed to create {}: {e}", dir.display()))
+    };
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{DirBuilderExt as _, PermissionsExt as _};
+        builder.mode(0o700);
+        builder.create(dir).map_err(failed)?;
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o700)).map_err(failed)
+    }
+    #[cfg(not(unix))]
+    builder.create(dir).map_err(failed)
+}
+
+fn search_path() -> OsString {
+    std::env::var_os("PATH").unwrap_or_else(|| OsString::from("/usr/local/bin:/usr/bin:/bin"))
+}
+
+fn find_program(program: &str, path: &OsString) -> Option<PathBuf> {
+    if program.contains('/') {
+        return Path::new(program).is_file().then(|| PathBuf::from(program));
+    }
+    std::env::split_paths(path)
+        .map(|dir| dir.join(program))
+        .find(|candidate| candidate.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use logline_api::{AuthMode, BackendAuth, BackendKind};
+
+    use super::*;
+
+    fn connector(name: &str, commands: &[(&str, &str, &[&str])]) -> ProcessConnector {
+        let cfg = BackendConfig {
+            backend_id: "local-proc".to_string(),
+            kind: BackendKind::Process,
+            base_url: String::new(),
+            auth: BackendAuth {
+                mode: AuthMode::None,
+                secret_ref: String::new(),
+            },
+            connect_timeout_ms: 2_000,
+            request_timeout_ms: 10_000,
+            extra_headers: BTreeMap::new(),
+            commands: commands
+                .iter()
+                .map(|(intent, program, args)| {
+                    (
+                        (*intent).to_string(),
+                        ProcessCommand {
+                            program: (*program).to_string(),
+                            args: args.iter().map(ToString::to_string).collect(),
+                            ..ProcessCommand::default()
+                        },
+                    )
+                })
+                .collect(),
+            plugin: None,
+            supabase: None,
+        };
+        let dir = std::env::temp_dir().join(format!("logline-proc-{name}-{}", std::process::id()));
+        ProcessConnector::new(&cfg)
+            .with_runs_dir(dir)
+            .with_stop_grace(Duration::from_secs(2))
+    }
+
+    fn intent(intent_type: &str, payload: &[(&str, &str)]) -> Intent {
+        Intent {
+            intent_type: intent_type.to_string(),
+            payload: payload
+                .iter()
+                .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn execute_streams_lines_and_collects_outputs() {
+        let conn = connector(
+            "exec",
+            &[(
+                "build",
+                "sh",
+                &[
+                    "-c",
+                    "echo building {{target}}; echo \"::output artifact=$LOGLINE_PAYLOAD_TARGET.tar\"; echo warn >&2; [ -z \"$HOME\" ]",
+                ],
+            )],
+        );
+
+        let result = conn
+            .execute(&intent("build", &[("target", "web")]))
+            .unwrap();
+        assert_eq!(result.status, "succeeded");
+        assert_eq!(result.output["artifact"], "web.tar");
+        assert_eq!(result.output["exit_code"], "0");
+
+        let events = conn.events_since(None).unwrap();
+        let lines: Vec<(&str, &str)> = events
+            .iter()
+            .filter_map(|e| Some((e.kind.as_str(), e.attributes.get("line")?.as_str())))
+            .collect();
+        assert!(lines.contains(&("process.stdout", "building web")));
+        assert!(lines.contains(&("process.stderr", "warn")));
+        assert_eq!(events.last().unwrap().kind, "process.exited");
+
+        let err = conn.execute(&intent("build", &[])).unwrap_err();
+        assert!(matches!(err, LoglineError::Validation(_)));
+    }
+
+    #[test]
+    fn stop_terminates_a_running_process() {
+        let conn = Arc::new(connector(
+            "stop",
+            &[(
+                "serve",
+                "sh",
+                &["-c", "trap 'exit 3' TERM; while :; do sleep 0.05; done"],
+            )],
+        ));
+        let runner = Arc::clone(&conn);
+        let handle = thread::spawn(move || runner.execute(&intent("serve", &[])));
+
+        let run_id = loop {
+            let started = conn.events_since(None).unwrap();
+            if let Some(e) = started.iter().find(|e| e.kind == "process.started") {
+                break e.run_id.clone().unwrap();
+            }
+            thread::sleep(POLL);
+        };
+        conn.stop(&run_id).unwrap();
+
+        let result = handle.join().unwrap().unwrap();
+        assert_eq!(result.status, "stopped");
+        assert_eq!(result.output["exit_code"], "3");
+        assert!(matches!(conn.stop(&run_id), Err(LoglineError::NotFound(_))));
+    }
+
+    #[test]
+    fn background_children_do_not_hold_execute_open() {
+        let conn = connector(
+            "drain",
+            &[("spawn", "sh", &["-c", "sleep 5 & echo started"])],
+        );
+        let started = Instant::now();
+        let result = conn.execute(&intent("spawn", &[])).unwrap();
+        assert_eq!(result.status, "succeeded");
+        assert!(
+            started.elapsed() < Duration::from_secs(4),
+            "{:?}",
+            started.elapsed()
+        );
+    }
+
+    #[test]
+    fn stop_signals_the_whole_process_group() {
+        let dir = std::env::temp_dir().join(format!("logline-proc-group-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let pid_file = dir.join("grandchild");
+        let conn = Arc::new(connector(
+            "group",
+            &[(
+                "serve",
+                "sh",
+                &["-c", "sleep 30 & echo $! > {{pid_file}}; wait"],
+            )],
+        ));
+        let runner = Arc::clone(&conn);
+        let payload = pid_file.display().to_string();
+        let handle =
+            thread::spawn(move || runner.execute(&intent("serve", &[("pid_file", &payload)])));
+
+        let grandchild = loop {
+            if let Some(pid) = fs::read_to_string(&pid_file)
+                .ok()
+                .and_then(|s| s.trim().parse::<u32>().ok())
+            {
+                break pid;
+            }
+            thread::sleep(POLL);
+        };
+        let run_id = conn
+            .events_since(None)
+            .unwrap()
+            .into_iter()
+            .find(|e| e.kind == "process.started")
+            .and_then(|e| e.run_id)
+            .unwrap();
+        conn.stop(&run_id).unwrap();
+
+        assert_eq!(handle.join().unwrap().unwrap().status, "stopped");
+        let alive = Command::new("kill")
+            .args(["-0", &grandchild.to_string()])
+            .stderr(Stdio::null())
+            .status()
+            .unwrap()
+            .success();
+        assert!(!alive, "grandchild {grandchild} survived stop");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn runs_dir_is_private() {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        let conn = connector("private", &[("noop", "true", &[])]);
+        conn.execute(&intent("noop", &[])).unwrap();
+        let mode = fs::metadata(&conn.runs_dir).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+    }
+}
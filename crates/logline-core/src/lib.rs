@@ -2,9 +2,39 @@ use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use logline_api::{AuthMode, BackendAuth, BackendConfig, LoglineError};
+use logline_api::{
+    AuthMode, BackendAuth, BackendConfig, BackendKind, LoglineError, PluginSpec, ProcessCommand,
+    SupabaseOp, SupabaseSpec,
+};
 use serde::{Deserialize, Serialize};
 
+mod policy_config;
+mod runtime_config;
+mod schedule;
+mod ui_config;
+mod workflow;
+
+pub use policy_config::{
+    AuthPolicy, CommandClass, PolicyConfig, SessionClass, SessionPolicy, UnlockMethod,
+    UnlockPolicy, load_policy_config_from_dir, load_policy_config_from_file,
+};
+pub use runtime_config::{
+    AuditConfig, AuditSinkKind, CircuitBreakerConfig, ColorMode, OutputConfig, OutputFormat,
+    RuntimeConfig, SpanExporterKind, TracingConfig, VaultBackendKind, VaultConfig,
+    load_runtime_config_from_dir, load_runtime_config_from_file,
+};
+pub use schedule::{
+    CronExpr, MissedRunPolicy, ScheduleSpec, ScheduleTrigger, civil_from_days, parse_interval,
+    validate_schedule,
+};
+pub use ui_config::{
+    NotificationConfig, UiConfig, UiView, load_ui_config_from_dir, load_ui_config_from_file,
+};
+pub use workflow::{
+    StepFailurePolicy, StepRef, StepRefField, StepTarget, WorkflowSpec, WorkflowStep, ancestors,
+    load_workflow_from_file, render_step_payload, validate_workflow,
+};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RuntimePolicy {
     pub max_concurrent_runs: usize,
@@ -44,6 +74,71 @@ pub fn validate_catalog(catalog: &ConnectionCatalog) -> Result<(), LoglineError>
             )));
         }
     }
+    for (id, backend) in &catalog.backends {
+        match backend.kind {
+            BackendKind::Http if backend.base_url.is_empty() => {
+                return Err(LoglineError::Validation(format!(
+                    "backend {id} needs a base_url"
+                )));
+            }
+            BackendKind::Http => {}
+            BackendKind::Process => {
+                if backend.commands.is_empty() {
+                    return Err(LoglineError::Validation(format!(
+                        "process backend {id} has no commands"
+                    )));
+                }
+                if let Some((intent, _)) = backend
+                    .commands
+                    .iter()
+                    .find(|(_, c)| c.program.trim().is_empty())
+                {
+                    return Err(LoglineError::Validation(format!(
+                        "backend {id} command for {intent} has no program"
+                    )));
+                }
+            }
+            BackendKind::Plugin => {
+                if backend
+                    .plugin
+                    .as_ref()
+                    .is_none_or(|p| p.executable.trim().is_empty())
+                {
+                    return Err(LoglineError::Validation(format!(
+                        "plugin backend {id} needs plugin.executable"
+                    )));
+                }
+            }
+            BackendKind::Supabase => validate_supabase(id, backend)?,
+        }
+    }
+    Ok(())
+}
+
+fn validate_supabase(id: &str, backend: &BackendConfig) -> Result<(), LoglineError> {
+    if backend.base_url.is_empty() {
+        return Err(LoglineError::Validation(format!(
+            "supabase backend {id} needs base_url (the project URL)"
+        )));
+    }
+    let Some(spec) = &backend.supabase else {
+        return Err(LoglineError::Validation(format!(
+            "supabase backend {id} needs a [backends.{id}.supabase] table"
+        )));
+    };
+    for (intent, op) in &spec.intents {
+        let missing = match op.op {
+            SupabaseOp::Update | SupabaseOp::Delete if op.filter.is_none() => Some("filter"),
+            SupabaseOp::Upsert if op.on_conflict.is_none() => Some("on_conflict"),
+            _ if op.target.trim().is_empty() => Some("target"),
+            _ => None,
+        };
+        if let Some(field) = missing {
+            return Err(LoglineError::Validation(format!(
+                "backend {id} intent {intent} needs {field}"
+            )));
+        }
+    }
     Ok(())
 }
 
@@ -51,6 +146,7 @@ pub fn demo_catalog() -> ConnectionCatalog {
     let backend_id = "local-main".to_string();
     let backend = BackendConfig {
         backend_id: backend_id.clone(),
+        kind: BackendKind::Http,
         base_url: "http://127.0.0.1:8787".to_string(),
         auth: logline_api::BackendAuth {
             mode: logline_api::AuthMode::ApiKey,
@@ -59,6 +155,9 @@ pub fn demo_catalog() -> ConnectionCatalog {
         connect_timeout_ms: 2_000,
         request_timeout_ms: 10_000,
         extra_headers: BTreeMap::new(),
+        commands: BTreeMap::new(),
+        plugin: None,
+        supabase: None,
     };
 
     let profile = Profile {
@@ -88,13 +187,31 @@ struct RawProfile {
 
 #[derive(Debug, Deserialize)]
 struct RawBackend {
+    #[serde(default)]
+    kind: BackendKind,
+    #[serde(default)]
     base_url: String,
-    auth_mode: AuthMode,
+    auth_mode: Option<AuthMode>,
+    #[serde(default)]
     secret_ref: String,
+    #[serde(default = "default_connect_timeout_ms")]
     connect_timeout_ms: u64,
+    #[serde(default = "default_request_timeout_ms")]
     request_timeout_ms: u64,
     #[serde(default)]
     extra_headers: BTreeMap<String, String>,
+    #[serde(default)]
+    commands: BTreeMap<String, ProcessCommand>,
+    plugin: Option<PluginSpec>,
+    supabase: Option<SupabaseSpec>,
+}
+
+fn default_connect_timeout_ms() -> u64 {
+    2_000
+}
+
+fn default_request_timeout_ms() -> u64 {
+    10_000
 }
 
 pub fn default_config_dir() -> PathBuf {
@@ -136,22 +253,37 @@ pub fn load_catalog_from_file(path: &Path) -> Result<ConnectionCatalog, LoglineE
         .backends
         .into_iter()
         .map(|(id, b)| {
-            (
+            // Local processes and plugins may need no credentials.
+            let mode = match (b.auth_mode, b.kind) {
+                (Some(mode), _) => mode,
+                (None, BackendKind::Process | BackendKind::Plugin) => AuthMode::None,
+                (None, BackendKind::Http | BackendKind::Supabase) => {
+                    return Err(LoglineError::Validation(format!(
+                        "backend {id} in {} needs an auth_mode",
+                        path.display()
+                    )));
+                }
+            };
+            Ok((
                 id.clone(),
                 BackendConfig {
                     backend_id: id,
+                    kind: b.kind,
                     base_url: b.base_url,
                     auth: BackendAuth {
-                        mode: b.auth_mode,
+                        mode,
                         secret_ref: b.secret_ref,
                     },
                     connect_timeout_ms: b.connect_timeout_ms,
                     request_timeout_ms: b.request_timeout_ms,
                     extra_headers: b.extra_headers,
+                    commands: b.commands,
+                    plugin: b.plugin,
+                    supabase: b.supabase,
                 },
-            )
+            ))
         })
-        .collect();
+        .collect::<Result<_, LoglineError>>()?;
 
     let catalog = ConnectionCatalog { profiles, backends };
     validate_catalog(&catalog)?;
@@ -162,7 +294,7 @@ pub fn write_default_config_files(dir: &Path) -> Result<(), LoglineError> {
     fs::create_dir_all(dir)
         .map_err(|e| LoglineError::Internal(format!("failed to create {}: {e}", dir.display())))?;
 
-    let files: [(&str, &str); 3] = [
+    let files: [(&str, &str); 4] = [
         (
             "connections.toml",
             include_str!("../../../docs/logline-cli/examples/connections.toml.example"),
@@ -175,6 +307,10 @@ pub fn write_default_config_files(dir: &Path) -> Result<(), LoglineError> {
             "ui.toml",
             include_str!("../../../docs/logline-cli/examples/ui.toml.example"),
         ),
+        (
+            "policy.toml",
+            include_str!("../../../docs/logline-cli/examples/policy.toml.example"),
+        ),
     ];
 
     for (name, body) in files {
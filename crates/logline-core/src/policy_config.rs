@@ -0,0 +1,280 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use logline_api::LoglineError;
+use serde::{Deserialize, Serialize};
+
+/// A way to prove presence before `logline auth unlock` opens a session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnlockMethod {
+    /// macOS biometrics via `LocalAuthentication`.
+    TouchId,
+    /// A passphrase checked against an Argon2id hash in the OS keyring.
+    Passphrase,
+    /// A fresh local challenge signed with the registered Ed25519 passkey.
+    Signature,
+}
+
+impl UnlockMethod {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::TouchId => "touch_id",
+            Self::Passphrase => "passphrase",
+            Self::Signature => "signature",
+        }
+    }
+}
+
+impl std::fmt::Display for UnlockMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for UnlockMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "touch_id" | "touch-id" => Ok(Self::TouchId),
+            "passphrase" => Ok(Self::Passphrase),
+            "signature" => Ok(Self::Signature),
+            other => Err(format!(
+                "unknown unlock method '{other}' (expected touch_id, passphrase or signature)"
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnlockPolicy {
+    /// Methods `logline auth unlock` accepts, tried in this order when none is named.
+    pub allowed: Vec<UnlockMethod>,
+    /// Methods whose sessions may run infra commands (deploy, cicd, db migrate).
+    pub infra: Vec<UnlockMethod>,
+}
+
+impl Default for UnlockPolicy {
+    fn default() -> Self {
+        Self {
+            allowed: vec![
+                UnlockMethod::TouchId,
+                UnlockMethod::Passphrase,
+                UnlockMethod::Signature,
+            ],
+            infra: vec![UnlockMethod::TouchId, UnlockMethod::Passphrase],
+        }
+    }
+}
+
+/// Limits a sensitivity class puts on the session running one of its commands.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionClass {
+    /// Refuse sessions opened by `auth unlock` longer ago than this, whatever their TTL.
+    pub max_session_age_minutes: Option<u64>,
+    /// Login methods (`auth.json` `auth_method`, e.g. `passkey`) accepted; empty accepts any.
+    #[serde(default)]
+    pub auth_method: Vec<String>,
+    /// Unlock methods the last presence check must have used; empty accepts any allowed one.
+    #[serde(default)]
+    pub unlock_method: Vec<UnlockMethod>,
+    /// Re-prompt (step up) when the last presence check is older than this; the
+    /// step-up uses `unlock_method`, or `unlock.infra` when that is empty.
+    pub fresh_unlock_minutes: Option<u64>,
+}
+
+/// `[session]`: named classes and the commands assigned to them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionPolicy {
+    #[serde(default)]
+    pub classes: BTreeMap<String, SessionClass>,
+    /// Command path (`"deploy"`, `"db migrate"`, `"secrets get"`) to class name.
+    #[serde(default)]
+    pub commands: BTreeMap<String, String>,
+}
+
+/// The `[session.commands]` entry a command falls under.
+#[derive(Debug, Clone, Copy)]
+pub struct CommandClass<'a> {
+    /// The matching `[session.commands]` key.
+    pub rule: &'a str,
+    pub name: &'a str,
+    pub class: &'a SessionClass,
+}
+
+impl SessionPolicy {
+    /// Class for a space-separated command path; the longest matching key wins,
+    /// so `"db"` covers `db migrate apply` unless `"db migrate"` is also listed.
+    pub fn class_for(&self, command: &str) -> Option<CommandClass<'_>> {
+        self.commands
+            .iter()
+            .filter(|(rule, _)| {
+                command == rule.as_str()
+                    || command
+                        .strip_prefix(rule.as_str())
+                        .is_some_and(|rest| rest.starts_with(' '))
+            })
+            .max_by_key(|(rule, _)| rule.len())
+            .and_then(|(rule, name)| {
+                self.classes
+                    .get(name)
+                    .map(|class| CommandClass { rule, name, class })
+            })
+    }
+}
+
+/// `[auth]`: how far the stored login is trusted.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthPolicy {
+    /// Let infra commands run on access tokens that cannot be verified locally
+    /// because the project publishes no signing keys (the server still confirms
+    /// the user). Off unless set.
+    #[serde(default)]
+    pub allow_unverified_tokens: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PolicyConfig {
+    pub unlock: UnlockPolicy,
+    #[serde(default)]
+    pub session: SessionPolicy,
+    #[serde(default)]
+    pub auth: AuthPolicy,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RawPolicyFile {
+    #[serde(default)]
+    unlock: RawUnlock,
+    #[serde(default)]
+    session: SessionPolicy,
+    #[serde(default)]
+    auth: AuthPolicy,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RawUnlock {
+    allowed: Option<Vec<UnlockMethod>>,
+    infra: Option<Vec<UnlockMethod>>,
+}
+
+pub fn load_policy_config_from_dir(dir: &Path) -> Result<PolicyConfig, LoglineError> {
+    load_policy_config_from_file(&dir.join("policy.toml"))
+}
+
+pub fn load_policy_config_from_file(path: &Path) -> Result<PolicyConfig, LoglineError> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| LoglineError::NotFound(format!("failed to read {}: {e}", path.display())))?;
+    parse_policy_config(&content)
+        .map_err(|e| LoglineError::Validation(format!("{}: {e}", path.display())))
+}
+
+fn parse_policy_config(content: &str) -> Result<PolicyConfig, String> {
+    let raw: RawPolicyFile = toml::from_str(content).map_err(|e| format!("invalid TOML: {e}"))?;
+
+    let defaults = UnlockPolicy::default();
+    let allowed = raw.unlock.allowed.unwrap_or(defaults.allowed);
+    // Without an explicit list, infra takes the default strong methods that are allowed.
+    let infra = raw.unlock.infra.unwrap_or_else(|| {
+        defaults
+            .infra
+            .into_iter()
+            .filter(|m| allowed.contains(m))
+            .collect()
+    });
+    if allowed.is_empty() {
+        return Err("unlock.allowed must name at least one method".to_string());
+    }
+    if let Some(method) = infra.iter().find(|m| !allowed.contains(m)) {
+        return Err(format!(
+            "unlock.infra lists {method}, which unlock.allowed does not"
+        ));
+    }
+
+    for (name, class) in &raw.session.classes {
+        if let Some(method) = class.unlock_method.iter().find(|m| !allowed.contains(m)) {
+            return Err(format!(
+                "session.classes.{name}.unlock_method lists {method}, which unlock.allowed does not"
+            ));
+        }
+    }
+    for (command, class) in &raw.session.commands {
+        if !raw.session.classes.contains_key(class) {
+            return Err(format!(
+                "session.commands.\"{command}\" names unknown class '{class}'"
+            ));
+        }
+    }
+
+    Ok(PolicyConfig {
+        unlock: UnlockPolicy { allowed, infra },
+        session: raw.session,
+        auth: raw.auth,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn infra_methods_must_be_allowed() {
+        let err = parse_policy_config(
+            "[unlock]\nallowed = [\"passphrase\"]\ninfra = [\"passphrase\", \"signature\"]\n",
+        )
+        .unwrap_err();
+        assert!(err.contains("signature"), "{err}");
+
+        let policy =
+            parse_policy_config("[unlock]\nallowed = [\"signature\", \"passphrase\"]\n").unwrap();
+        assert_eq!(policy.unlock.infra, vec![UnlockMethod::Passphrase]);
+        assert_eq!(parse_policy_config("").unwrap().unlock.allowed.len(), 3);
+    }
+
+    #[test]
+    fn unverified_tokens_need_an_explicit_opt_in() {
+        assert!(
+            !parse_policy_config("")
+                .unwrap()
+                .auth
+                .allow_unverified_tokens
+        );
+        let policy = parse_policy_config("[auth]\nallow_unverified_tokens = true\n").unwrap();
+        assert!(policy.auth.allow_unverified_tokens);
+    }
+
+    #[test]
+    fn commands_resolve_to_the_longest_matching_class() {
+        let policy = parse_policy_config(
+            r#"
+[session.classes.critical]
+max_session_age_minutes = 60
+auth_method = ["passkey"]
+fresh_unlock_minutes = 5
+
+[session.classes.sensitive]
+unlock_method = ["passphrase"]
+
+[session.commands]
+"db" = "sensitive"
+"db migrate" = "critical"
+"deploy" = "critical"
+"#,
+        )
+        .unwrap()
+        .session;
+
+        let hit = policy.class_for("db migrate apply").unwrap();
+        assert_eq!((hit.rule, hit.name), ("db migrate", "critical"));
+        assert_eq!(hit.class.fresh_unlock_minutes, Some(5));
+        assert_eq!(policy.class_for("db verify-rls").unwrap().name, "sensitive");
+        assert_eq!(policy.class_for("deploy").unwrap().name, "critical");
+        assert!(policy.class_for("deployments").is_none());
+        assert!(policy.class_for("secrets get").is_none());
+
+        let err = parse_policy_config("[session.commands]\n\"deploy\" = \"nope\"\n").unwrap_err();
+        assert!(err.contains("nope"), "{err}");
+    }
+}
@@ -0,0 +1,374 @@
+use std::fs;
+use std::path::Path;
+
+use logline_api::LoglineError;
+use serde::{Deserialize, Serialize};
+
+use crate::RuntimePolicy;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditSinkKind {
+    Local,
+    Remote,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditConfig {
+    pub enabled: bool,
+    pub sink: AuditSinkKind,
+    pub retention_days: u32,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            sink: AuditSinkKind::Local,
+            retention_days: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpanExporterKind {
+    File,
+    OtlpHttp,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TracingConfig {
+    pub enabled: bool,
+    pub exporter: SpanExporterKind,
+    /// File exporter target; relative paths resolve against the config dir.
+    pub path: String,
+    /// OTLP/HTTP traces endpoint of a local collector.
+    pub endpoint: String,
+}
+
+impl Default for TracingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            exporter: SpanExporterKind::File,
+            path: "spans.jsonl".to_string(),
+            endpoint: "http://127.0.0.1:4318/v1/traces".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputFormat {
+    /// Each command's human-readable view; a generated table with `--columns`/`--query`.
+    #[default]
+    Table,
+    Json,
+    Yaml,
+    Csv,
+    Ndjson,
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(Self::Table),
+            "json" => Ok(Self::Json),
+            "yaml" => Ok(Self::Yaml),
+            "csv" => Ok(Self::Csv),
+            "ndjson" => Ok(Self::Ndjson),
+            _ => Err(format!(
+                "unknown output format '{s}' (expected table, json, yaml, csv or ndjson)"
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorMode {
+    /// Color when stdout is a terminal and `NO_COLOR` is unset.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OutputConfig {
+    pub default_format: OutputFormat,
+    pub color: ColorMode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VaultBackendKind {
+    /// The OS keyring (macOS Keychain, Secret Service, Windows Credential Manager).
+    #[default]
+    Keyring,
+    /// An encrypted file, for machines without a usable keyring.
+    File,
+}
+
+impl VaultBackendKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Keyring => "keyring",
+            Self::File => "file",
+        }
+    }
+}
+
+impl std::fmt::Display for VaultBackendKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for VaultBackendKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keyring" => Ok(Self::Keyring),
+            "file" => Ok(Self::File),
+            _ => Err(format!(
+                "unknown vault backend '{s}' (expected keyring or file)"
+            )),
+        }
+    }
+}
+
+/// Where credentials (tokens, passkey, session, `secrets`) are kept.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultConfig {
+    pub backend: VaultBackendKind,
+    /// File backend location; relative paths resolve against the config dir.
+    pub path: String,
+    /// File whose contents derive the vault key instead of a passphrase.
+    pub key_file: Option<String>,
+}
+
+impl Default for VaultConfig {
+    fn default() -> Self {
+        Self {
+            backend: VaultBackendKind::Keyring,
+            path: "vault.enc".to_string(),
+            key_file: None,
+        }
+    }
+}
+
+/// Per-backend circuit breaking, driven by health probes and run outcomes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircuitBreakerConfig {
+    pub enabled: bool,
+    /// Consecutive failures that open the circuit.
+    pub failure_threshold: u32,
+    /// How long an open circuit rejects runs before letting one through.
+    pub cooldown_seconds: u64,
+    /// Health samples kept per backend.
+    pub history: usize,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            failure_threshold: 5,
+            cooldown_seconds: 30,
+            history: 50,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuntimeConfig {
+    pub policy: RuntimePolicy,
+    pub persist_events: bool,
+    pub audit: AuditConfig,
+    pub tracing: TracingConfig,
+    pub circuit_breaker: CircuitBreakerConfig,
+    pub output: OutputConfig,
+    pub vault: VaultConfig,
+}
+
+impl Default for RuntimeConfig {
+    fn default() -> Self {
+        Self {
+            policy: RuntimePolicy::default(),
+            persist_events: true,
+            audit: AuditConfig::default(),
+            tracing: TracingConfig::default(),
+            circuit_breaker: CircuitBreakerConfig::default(),
+            output: OutputConfig::default(),
+            vault: VaultConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RawRuntimeFile {
+    #[serde(default)]
+    runtime: RawRuntime,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RawRuntime {
+    max_concurrent_runs: Option<usize>,
+    default_queue_capacity: Option<usize>,
+    stop_grace_seconds: Option<u64>,
+    persist_events: Option<bool>,
+    #[serde(default)]
+    audit: RawAudit,
+    #[serde(default)]
+    tracing: RawTracing,
+    #[serde(default)]
+    circuit_breaker: RawCircuitBreaker,
+    #[serde(default)]
+    output: RawOutput,
+    #[serde(default)]
+    vault: RawVault,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RawAudit {
+    enabled: Option<bool>,
+    sink: Option<AuditSinkKind>,
+    retention_days: Option<u32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RawTracing {
+    enabled: Option<bool>,
+    exporter: Option<SpanExporterKind>,
+    path: Option<String>,
+    endpoint: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RawCircuitBreaker {
+    enabled: Option<bool>,
+    failure_threshold: Option<u32>,
+    cooldown_seconds: Option<u64>,
+    history: Option<usize>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RawOutput {
+    default_format: Option<OutputFormat>,
+    color: Option<ColorMode>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RawVault {
+    backend: Option<VaultBackendKind>,
+    path: Option<String>,
+    key_file: Option<String>,
+}
+
+pub fn load_runtime_config_from_dir(dir: &Path) -> Result<RuntimeConfig, LoglineError> {
+    load_runtime_config_from_file(&dir.join("runtime.toml"))
+}
+
+pub fn load_runtime_config_from_file(path: &Path) -> Result<RuntimeConfig, LoglineError> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| LoglineError::NotFound(format!("failed to read {}: {e}", path.display())))?;
+    let raw: RawRuntimeFile = toml::from_str(&content).map_err(|e| {
+        LoglineError::Validation(format!("invalid TOML in {}: {e}", path.display()))
+    })?;
+
+    let defaults = RuntimeConfig::default();
+    let policy = RuntimePolicy {
+        max_concurrent_runs: raw
+            .runtime
+            .max_concurrent_runs
+            .unwrap_or(defaults.policy.max_concurrent_runs),
+        default_queue_capacity: raw
+            .runtime
+            .default_queue_capacity
+            .unwrap_or(defaults.policy.default_queue_capacity),
+        stop_grace_seconds: raw
+            .runtime
+            .stop_grace_seconds
+            .unwrap_or(defaults.policy.stop_grace_seconds),
+    };
+    if policy.max_concurrent_runs == 0 {
+        return Err(LoglineError::Validation(format!(
+            "{}: runtime.max_concurrent_runs must be at least 1",
+            path.display()
+        )));
+    }
+
+    let audit = AuditConfig {
+        enabled: raw.runtime.audit.enabled.unwrap_or(defaults.audit.enabled),
+        sink: raw.runtime.audit.sink.unwrap_or(defaults.audit.sink),
+        retention_days: raw
+            .runtime
+            .audit
+            .retention_days
+            .unwrap_or(defaults.audit.retention_days),
+    };
+
+    let breaker = &raw.runtime.circuit_breaker;
+    let circuit_breaker = CircuitBreakerConfig {
+        enabled: breaker.enabled.unwrap_or(defaults.circuit_breaker.enabled),
+        failure_threshold: breaker
+            .failure_threshold
+            .unwrap_or(defaults.circuit_breaker.failure_threshold),
+        cooldown_seconds: breaker
+            .cooldown_seconds
+            .unwrap_or(defaults.circuit_breaker.cooldown_seconds),
+        history: breaker.history.unwrap_or(defaults.circuit_breaker.history),
+    };
+    if circuit_breaker.failure_threshold == 0 || circuit_breaker.history == 0 {
+        return Err(LoglineError::Validation(format!(
+            "{}: runtime.circuit_breaker.failure_threshold and history must be at least 1",
+            path.display()
+        )));
+    }
+
+    Ok(RuntimeConfig {
+        policy,
+        persist_events: raw
+            .runtime
+            .persist_events
+            .unwrap_or(defaults.persist_events),
+        audit,
+        tracing: TracingConfig {
+            enabled: raw
+                .runtime
+                .tracing
+                .enabled
+                .unwrap_or(defaults.tracing.enabled),
+            exporter: raw
+                .runtime
+                .tracing
+                .exporter
+                .unwrap_or(defaults.tracing.exporter),
+            path: raw.runtime.tracing.path.unwrap_or(defaults.tracing.path),
+            endpoint: raw
+                .runtime
+                .tracing
+                .endpoint
+                .unwrap_or(defaults.tracing.endpoint),
+        },
+        circuit_breaker,
+        output: OutputConfig {
+            default_format: raw
+                .runtime
+                .output
+                .default_format
+                .unwrap_or(defaults.output.default_format),
+            color: raw.runtime.output.color.unwrap_or(defaults.output.color),
+        },
+        vault: VaultConfig {
+            backend: raw.runtime.vault.backend.unwrap_or(defaults.vault.backend),
+            path: raw.runtime.vault.path.unwrap_or(defaults.vault.path),
+            key_file: raw.runtime.vault.key_file,
+        },
+    })
+}
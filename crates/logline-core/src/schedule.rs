@@ -0,0 +1,306 @@
+use std::collections::BTreeSet;
+
+use logline_api::{Intent, LoglineError, ProfileId};
+use serde::{Deserialize, Serialize};
+
+/// How far ahead `next_after` searches before giving up on an expression
+/// that can never match (e.g. `0 0 31 2 *`).
+const MAX_SEARCH_DAYS: i64 = 366 * 5;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScheduleTrigger {
+    Cron { expr: String },
+    Interval { every_seconds: u64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MissedRunPolicy {
+    /// Drop slots that were missed by more than the misfire grace window.
+    Skip,
+    /// Fire every missed slot (bounded by `max_catch_up`) once the scheduler is back.
+    CatchUp,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleSpec {
+    pub id: String,
+    pub trigger: ScheduleTrigger,
+    pub intent: Intent,
+    pub profile: ProfileId,
+    pub missed_runs: MissedRunPolicy,
+    #[serde(default)]
+    pub allow_overlap: bool,
+    #[serde(default)]
+    pub paused: bool,
+    #[serde(default = "default_misfire_grace_seconds")]
+    pub misfire_grace_seconds: u64,
+    #[serde(default = "default_max_catch_up")]
+    pub max_catch_up: usize,
+}
+
+fn default_misfire_grace_seconds() -> u64 {
+    60
+}
+
+fn default_max_catch_up() -> usize {
+    10
+}
+
+impl ScheduleSpec {
+    /// First fire time strictly after `after_unix_secs`.
+    pub fn next_fire_after(&self, after_unix_secs: i64) -> Result<i64, LoglineError> {
+        match &self.trigger {
+            ScheduleTrigger::Cron { expr } => CronExpr::parse(expr)?
+                .next_after(after_unix_secs)
+                .ok_or_else(|| {
+                    LoglineError::Validation(format!("cron expression '{expr}' never fires"))
+                }),
+            ScheduleTrigger::Interval { every_seconds } => {
+                let every = i64::try_from(*every_seconds).unwrap_or(i64::MAX);
+                Ok(after_unix_secs.saturating_add(every))
+            }
+        }
+    }
+}
+
+pub fn validate_schedule(spec: &ScheduleSpec) -> Result<(), LoglineError> {
+    if spec.id.is_empty()
+        || !spec
+            .id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(LoglineError::Validation(format!(
+            "invalid schedule id '{}': use letters, digits, '-' or '_'",
+            spec.id
+        )));
+    }
+    if spec.intent.intent_type.is_empty() {
+        return Err(LoglineError::Validation(format!(
+            "schedule {} has an empty intent type",
+            spec.id
+        )));
+    }
+    match &spec.trigger {
+        ScheduleTrigger::Cron { expr } => {
+            CronExpr::parse(expr)?;
+        }
+        ScheduleTrigger::Interval { every_seconds } => {
+            if *every_seconds < 60 {
+                return Err(LoglineError::Validation(format!(
+                    "schedule {} interval must be at least 60 seconds",
+                    spec.id
+                )));
+            }
+        }
+    }
+    if spec.max_catch_up == 0 {
+        return Err(LoglineError::Validation(format!(
+            "schedule {} max_catch_up must be at least 1",
+            spec.id
+        )));
+    }
+    Ok(())
+}
+
+/// Parse durations like `90s`, `15m`, `2h`, `1d`.
+pub fn parse_interval(s: &str) -> Result<u64, LoglineError> {
+    let s = s.trim().to_lowercase();
+    let invalid =
+        || LoglineError::Validation(format!("invalid interval '{s}': use e.g. 15m, 2h, 1d"));
+    let (num, unit) = s.split_at(s.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?);
+    let n: u64 = num.parse().map_err(|_| invalid())?;
+    let factor = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3_600,
+        "d" => 86_400,
+        _ => return Err(invalid()),
+    };
+    n.checked_mul(factor).ok_or_else(invalid)
+}
+
+/// Five-field cron expression (minute hour day-of-month month day-of-week), evaluated in UTC.
+///
+/// Supports `*`, lists, ranges, steps (`*/5`, `1-10/2`) and the `@hourly`,
+/// `@daily`, `@weekly`, `@monthly`, `@yearly` shorthands. As in Vixie cron,
+/// when both day fields are restricted a day matches if either does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronExpr {
+    minutes: BTreeSet<u32>,
+    hours: BTreeSet<u32>,
+    days_of_month: BTreeSet<u32>,
+    months: BTreeSet<u32>,
+    days_of_week: BTreeSet<u32>,
+    dom_restricted: bool,
+    dow_restricted: bool,
+}
+
+impl CronExpr {
+    pub fn parse(expr: &str) -> Result<Self, LoglineError> {
+        let expanded = match expr.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(LoglineError::Validation(format!(
+                "cron expression '{expr}' must have 5 fields (minute hour dom month dow)"
+            )));
+        }
+
+        let mut days_of_week = parse_field(fields[4], 0, 7, expr)?;
+        if days_of_week.remove(&7) {
+            days_of_week.insert(0);
+        }
+
+        Ok(Self {
+            minutes: parse_field(fields[0], 0, 59, expr)?,
+            hours: parse_field(fields[1], 0, 23, expr)?,
+            days_of_month: parse_field(fields[2], 1, 31, expr)?,
+            months: parse_field(fields[3], 1, 12, expr)?,
+            days_of_week,
+            dom_restricted: fields[2] != "*",
+            dow_restricted: fields[4] != "*",
+        })
+    }
+
+    /// First matching minute strictly after `after_unix_secs`.
+    pub fn next_after(&self, after_unix_secs: i64) -> Option<i64> {
+        let start = after_unix_secs.div_euclid(60) * 60 + 60;
+        let start_day = start.div_euclid(86_400);
+        let start_minute_of_day = u32::try_from(start.rem_euclid(86_400) / 60).ok()?;
+
+        for day in start_day..start_day + MAX_SEARCH_DAYS {
+            if !self.day_matches(day) {
+                continue;
+            }
+            let floor = if day == start_day {
+                start_minute_of_day
+            } else {
+                0
+            };
+            for &hour in &self.hours {
+                for &minute in &self.minutes {
+                    let minute_of_day = hour * 60 + minute;
+                    if minute_of_day >= floor {
+                        return Some(day * 86_400 + i64::from(minute_of_day) * 60);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn day_matches(&self, days_since_epoch: i64) -> bool {
+        let (_, month, day) = civil_from_days(days_since_epoch);
+        if !self.months.contains(&month) {
+            return false;
+        }
+        // 1970-01-01 was a Thursday (4).
+        let weekday = u32::try_from((days_since_epoch + 4).rem_euclid(7)).unwrap_or(0);
+        let dom = self.days_of_month.contains(&day);
+        let dow = self.days_of_week.contains(&weekday);
+        match (self.dom_restricted, self.dow_restricted) {
+            (true, true) => dom || dow,
+            (true, false) => dom,
+            (false, true) => dow,
+            (false, false) => true,
+        }
+    }
+}
+
+fn parse_field(field: &str, min: u32, max: u32, expr: &str) -> Result<BTreeSet<u32>, LoglineError> {
+    let invalid = |part: &str| {
+        LoglineError::Validation(format!(
+            "cron expression '{expr}': invalid field '{part}' (allowed {min}-{max})"
+        ))
+    };
+
+    let mut values = BTreeSet::new();
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((r, s)) => (r, s.parse::<u32>().map_err(|_| invalid(part))?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            return Err(invalid(part));
+        }
+        let (lo, hi) = if range == "*" {
+            (min, max)
+        } else if let Some((a, b)) = range.split_once('-') {
+            (
+                a.parse().map_err(|_| invalid(part))?,
+                b.parse().map_err(|_| invalid(part))?,
+            )
+        } else {
+            let v: u32 = range.parse().map_err(|_| invalid(part))?;
+            // `5/15` means "from 5, every 15" like `5-max/15`.
+            if part.contains('/') { (v, max) } else { (v, v) }
+        };
+        if lo < min || hi > max || lo > hi {
+            return Err(invalid(part));
+        }
+        values.extend((lo..=hi).step_by(step as usize));
+    }
+    Ok(values)
+}
+
+/// Gregorian (year, month, day) for a count of days since 1970-01-01.
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = u32::try_from(doy - (153 * mp + 2) / 5 + 1).unwrap_or(1);
+    let month = u32::try_from(if mp < 10 { mp + 3 } else { mp - 9 }).unwrap_or(1);
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2026-03-02T10:07:00Z, a Monday.
+    const MON_1007: i64 = 1_772_446_020;
+
+    #[test]
+    fn civil_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(MON_1007 / 86_400), (2026, 3, 2));
+    }
+
+    #[test]
+    fn cron_steps_and_ranges() {
+        let every_15 = CronExpr::parse("*/15 * * * *").unwrap();
+        assert_eq!(every_15.next_after(MON_1007), Some(MON_1007 + 8 * 60));
+
+        let weekdays_9 = CronExpr::parse("0 9 * * 1-5").unwrap();
+        // Already past 09:00 on Monday, so Tuesday 09:00.
+        assert_eq!(
+            weekdays_9.next_after(MON_1007),
+            Some(MON_1007 + 86_400 - 67 * 60)
+        );
+
+        assert!(CronExpr::parse("61 * * * *").is_err());
+        assert!(CronExpr::parse("* * *").is_err());
+        assert_eq!(CronExpr::parse("0 0 31 2 *").unwrap().next_after(0), None);
+    }
+
+    #[test]
+    fn intervals() {
+        assert_eq!(parse_interval("15m").unwrap(), 900);
+        assert_eq!(parse_interval("1d").unwrap(), 86_400);
+        assert!(parse_interval("m").is_err());
+        assert!(parse_interval("10x").is_err());
+    }
+}
@@ -0,0 +1,119 @@
+use std::fs;
+use std::path::Path;
+
+use logline_api::LoglineError;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UiView {
+    #[default]
+    Status,
+    Events,
+    Runs,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationConfig {
+    pub enabled: bool,
+    pub on_run_failure: bool,
+    pub on_backend_disconnect: bool,
+    /// Program and arguments run for every notification, with the message
+    /// appended as the last argument. Empty: terminal bell and status line only.
+    pub command: Vec<String>,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            on_run_failure: true,
+            on_backend_disconnect: true,
+            command: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UiConfig {
+    pub default_view: UiView,
+    pub refresh_ms: u64,
+    pub compact_mode: bool,
+    pub notifications: NotificationConfig,
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            default_view: UiView::Status,
+            refresh_ms: 3000,
+            compact_mode: true,
+            notifications: NotificationConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RawUiFile {
+    #[serde(default)]
+    ui: RawUi,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RawUi {
+    default_view: Option<UiView>,
+    refresh_ms: Option<u64>,
+    compact_mode: Option<bool>,
+    #[serde(default)]
+    notifications: RawNotifications,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RawNotifications {
+    enabled: Option<bool>,
+    on_run_failure: Option<bool>,
+    on_backend_disconnect: Option<bool>,
+    command: Option<Vec<String>>,
+}
+
+pub fn load_ui_config_from_dir(dir: &Path) -> Result<UiConfig, LoglineError> {
+    load_ui_config_from_file(&dir.join("ui.toml"))
+}
+
+pub fn load_ui_config_from_file(path: &Path) -> Result<UiConfig, LoglineError> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| LoglineError::NotFound(format!("failed to read {}: {e}", path.display())))?;
+    let raw: RawUiFile = toml::from_str(&content).map_err(|e| {
+        LoglineError::Validation(format!("invalid TOML in {}: {e}", path.display()))
+    })?;
+
+    let defaults = UiConfig::default();
+    let refresh_ms = raw.ui.refresh_ms.unwrap_or(defaults.refresh_ms);
+    if refresh_ms < 100 {
+        return Err(LoglineError::Validation(format!(
+            "{}: ui.refresh_ms must be at least 100",
+            path.display()
+        )));
+    }
+    let notifications = raw.ui.notifications;
+
+    Ok(UiConfig {
+        default_view: raw.ui.default_view.unwrap_or(defaults.default_view),
+        refresh_ms,
+        compact_mode: raw.ui.compact_mode.unwrap_or(defaults.compact_mode),
+        notifications: NotificationConfig {
+            enabled: notifications
+                .enabled
+                .unwrap_or(defaults.notifications.enabled),
+            on_run_failure: notifications
+                .on_run_failure
+                .unwrap_or(defaults.notifications.on_run_failure),
+            on_backend_disconnect: notifications
+                .on_backend_disconnect
+                .unwrap_or(defaults.notifications.on_backend_disconnect),
+            command: notifications
+                .command
+                .unwrap_or(defaults.notifications.command),
+        },
+    })
+}
@@ -0,0 +1,426 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fs;
+use std::path::Path;
+
+use logline_api::{BackendId, LoglineError, ProfileId};
+use serde::{Deserialize, Serialize};
+
+use crate::ConnectionCatalog;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowSpec {
+    pub id: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(rename = "step", default)]
+    pub steps: Vec<WorkflowStep>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowStep {
+    pub id: String,
+    pub intent: String,
+    /// Values may reference earlier steps, e.g. `${steps.build.output.artifact}`.
+    #[serde(default)]
+    pub payload: BTreeMap<String, String>,
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    #[serde(default)]
+    pub on_failure: StepFailurePolicy,
+    /// Extra attempts after a connection failure, for intents the backend plans
+    /// as non-mutating; anything else runs once.
+    #[serde(default)]
+    pub retries: u32,
+    /// Fan out across these profiles (mutually exclusive with `backends`).
+    #[serde(default)]
+    pub profiles: Vec<ProfileId>,
+    /// Fan out across these backends, each under a profile that uses it (a
+    /// readonly one if any).
+    #[serde(default)]
+    pub backends: Vec<BackendId>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepFailurePolicy {
+    /// Stop starting new steps; the workflow fails.
+    #[default]
+    Abort,
+    /// Skip steps that depend on this one; unrelated branches keep running.
+    SkipDependents,
+    /// Record the failure and carry on as if the step had succeeded.
+    Continue,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(tag = "type", content = "id", rename_all = "snake_case")]
+pub enum StepTarget {
+    Active,
+    Profile(ProfileId),
+    Backend(BackendId),
+}
+
+impl StepTarget {
+    pub fn label(&self) -> String {
+        match self {
+            Self::Active => "active".to_string(),
+            Self::Profile(id) => id.clone(),
+            Self::Backend(id) => format!("backend:{id}"),
+        }
+    }
+}
+
+impl WorkflowStep {
+    pub fn targets(&self) -> Vec<StepTarget> {
+        if !self.profiles.is_empty() {
+            self.profiles
+                .iter()
+                .cloned()
+                .map(StepTarget::Profile)
+                .collect()
+        } else if !self.backends.is_empty() {
+            self.backends
+                .iter()
+                .cloned()
+                .map(StepTarget::Backend)
+                .collect()
+        } else {
+            vec![StepTarget::Active]
+        }
+    }
+}
+
+/// A `${steps.<id>[@<target>].<field>}` reference inside a payload value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepRef {
+    pub step: String,
+    /// Profile id or `backend:<id>` of a fanned-out step; `None` means the first target.
+    pub target: Option<String>,
+    pub field: StepRefField,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StepRefField {
+    Output(String),
+    RunId,
+    Status,
+}
+
+impl StepRef {
+    fn parse(inner: &str) -> Option<Self> {
+        let rest = inner.strip_prefix("steps.")?;
+        let (head, field) = rest.split_once('.')?;
+        let (step, target) = match head.split_once('@') {
+            Some((s, t)) => (s, Some(t.to_string())),
+            None => (head, None),
+        };
+        let field = match field {
+            "run_id" => StepRefField::RunId,
+            "status" => StepRefField::Status,
+            other => StepRefField::Output(other.strip_prefix("output.")?.to_string()),
+        };
+        if step.is_empty() {
+            return None;
+        }
+        Some(Self {
+            step: step.to_string(),
+            target,
+            field,
+        })
+    }
+}
+
+/// Split a payload value into literal text and step references.
+fn scan_refs(value: &str) -> Result<Vec<Result<StepRef, &str>>, String> {
+    let mut parts = Vec::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        if start > 0 {
+            parts.push(Err(&rest[..start]));
+        }
+        let after = &rest[start + 2..];
+        let end = after
+            .find('}')
+            .ok_or_else(|| format!("unterminated reference in '{value}'"))?;
+        let inner = &after[..end];
+        parts.push(Ok(StepRef::parse(inner).ok_or_else(|| {
+            format!("invalid reference '${{{inner}}}': expected steps.<id>.output.<key>, .run_id or .status")
+        })?));
+        rest = &after[end + 1..];
+    }
+    if !rest.is_empty() {
+        parts.push(Err(rest));
+    }
+    Ok(parts)
+}
+
+/// Substitute step references in `step.payload`; `resolve` returns `None` for unknown values.
+pub fn render_step_payload(
+    step: &WorkflowStep,
+    resolve: impl Fn(&StepRef) -> Option<String>,
+) -> Result<BTreeMap<String, String>, LoglineError> {
+    let mut payload = BTreeMap::new();
+    for (key, value) in &step.payload {
+        let mut rendered = String::new();
+        for part in scan_refs(value).map_err(LoglineError::Validation)? {
+            match part {
+                Err(literal) => rendered.push_str(literal),
+                Ok(r) => {
+                    let v = resolve(&r).ok_or_else(|| {
+                        LoglineError::Validation(format!(
+                            "step {}: payload '{key}' references {} which has no value",
+                            step.id,
+                            describe_ref(&r)
+                        ))
+                    })?;
+                    rendered.push_str(&v);
+                }
+            }
+        }
+        payload.insert(key.clone(), rendered);
+    }
+    Ok(payload)
+}
+
+fn describe_ref(r: &StepRef) -> String {
+    let target = r
+        .target
+        .as_ref()
+        .map(|t| format!("@{t}"))
+        .unwrap_or_default();
+    match &r.field {
+        StepRefField::Output(key) => format!("steps.{}{target}.output.{key}", r.step),
+        StepRefField::RunId => format!("steps.{}{target}.run_id", r.step),
+        StepRefField::Status => format!("steps.{}{target}.status", r.step),
+    }
+}
+
+/// Check the workflow against `catalog` and return its steps in dependency order.
+pub fn validate_workflow(
+    spec: &WorkflowSpec,
+    catalog: &ConnectionCatalog,
+) -> Result<Vec<String>, LoglineError> {
+    let invalid = |msg: String| LoglineError::Validation(format!("workflow {}: {msg}", spec.id));
+
+    if spec.steps.is_empty() {
+        return Err(invalid("no steps defined".to_string()));
+    }
+    let mut ids = BTreeSet::new();
+    for step in &spec.steps {
+        if !ids.insert(step.id.as_str()) {
+            return Err(invalid(format!("duplicate step id '{}'", step.id)));
+        }
+    }
+
+    for step in &spec.steps {
+        if step.intent.is_empty() {
+            return Err(invalid(format!("step {} has an empty intent", step.id)));
+        }
+        if !step.profiles.is_empty() && !step.backends.is_empty() {
+            return Err(invalid(format!(
+                "step {} sets both profiles and backends",
+                step.id
+            )));
+        }
+        for p in &step.profiles {
+            if !catalog.profiles.contains_key(p) {
+                return Err(invalid(format!(
+                    "step {} targets unknown profile {p}",
+                    step.id
+                )));
+            }
+        }
+        for b in &step.backends {
+            if !catalog.backends.contains_key(b) {
+                return Err(invalid(format!(
+                    "step {} targets unknown backend {b}",
+                    step.id
+                )));
+            }
+        }
+        let mut deps = BTreeSet::new();
+        for dep in &step.depends_on {
+            if !ids.contains(dep.as_str()) {
+                return Err(invalid(format!(
+                    "step {} depends on unknown step {dep}",
+                    step.id
+                )));
+            }
+            if !deps.insert(dep.as_str()) {
+                return Err(invalid(format!(
+                    "step {} lists dependency {dep} more than once",
+                    step.id
+                )));
+            }
+        }
+    }
+
+    let order = topological_order(spec).ok_or_else(|| invalid("dependency cycle".to_string()))?;
+
+    // References must point at ancestors, otherwise the value may not exist yet.
+    for step in &spec.steps {
+        let ancestors = ancestors(spec, &step.id);
+        for value in step.payload.values() {
+            for part in scan_refs(value).map_err(|e| invalid(format!("step {}: {e}", step.id)))? {
+                let Ok(r) = part else { continue };
+                if !ancestors.contains(r.step.as_str()) {
+                    return Err(invalid(format!(
+                        "step {} references {} but does not depend on {}",
+                        step.id,
+                        describe_ref(&r),
+                        r.step
+                    )));
+                }
+            }
+        }
+    }
+
+    Ok(order)
+}
+
+fn topological_order(spec: &WorkflowSpec) -> Option<Vec<String>> {
+    let mut indegree: BTreeMap<&str, usize> = spec
+        .steps
+        .iter()
+        .map(|s| (s.id.as_str(), s.depends_on.len()))
+        .collect();
+    let mut ready: VecDeque<&str> = spec
+        .steps
+        .iter()
+        .filter(|s| s.depends_on.is_empty())
+        .map(|s| s.id.as_str())
+        .collect();
+
+    let mut order = Vec::with_capacity(spec.steps.len());
+    while let Some(id) = ready.pop_front() {
+        order.push(id.to_string());
+        for step in spec
+            .steps
+            .iter()
+            .filter(|s| s.depends_on.iter().any(|d| d == id))
+        {
+            let n = indegree.get_mut(step.id.as_str())?;
+            *n -= 1;
+            if *n == 0 {
+                ready.push_back(step.id.as_str());
+            }
+        }
+    }
+    (order.len() == spec.steps.len()).then_some(order)
+}
+
+/// Every step `step_id` transitively depends on.
+pub fn ancestors<'a>(spec: &'a WorkflowSpec, step_id: &str) -> BTreeSet<&'a str> {
+    let by_id: BTreeMap<&str, &WorkflowStep> =
+        spec.steps.iter().map(|s| (s.id.as_str(), s)).collect();
+    let mut seen = BTreeSet::new();
+    let mut stack: Vec<&str> = by_id
+        .get(step_id)
+        .map(|s| s.depends_on.iter().map(String::as_str).collect())
+        .unwrap_or_default();
+    while let Some(id) = stack.pop() {
+        if let Some((&key, step)) = by_id.get_key_value(id) {
+            if seen.insert(key) {
+                stack.extend(step.depends_on.iter().map(String::as_str));
+            }
+        }
+    }
+    seen
+}
+
+pub fn load_workflow_from_file(path: &Path) -> Result<WorkflowSpec, LoglineError> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| LoglineError::NotFound(format!("failed to read {}: {e}", path.display())))?;
+    toml::from_str(&content)
+        .map_err(|e| LoglineError::Validation(format!("invalid TOML in {}: {e}", path.display())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::demo_catalog;
+
+    fn spec(toml_src: &str) -> WorkflowSpec {
+        toml::from_str(toml_src).unwrap()
+    }
+
+    #[test]
+    fn orders_steps_and_rejects_cycles() {
+        let wf = spec(
+            r#"
+            id = "release"
+            [[step]]
+            id = "deploy"
+            intent = "deploy"
+            depends_on = ["build"]
+            payload = { artifact = "${steps.build.output.artifact}", note = "from ${steps.build.run_id}" }
+            [[step]]
+            id = "build"
+            intent = "build"
+            "#,
+        );
+        assert_eq!(
+            validate_workflow(&wf, &demo_catalog()).unwrap(),
+            ["build", "deploy"]
+        );
+
+        let rendered = render_step_payload(&wf.steps[0], |r| match &r.field {
+            StepRefField::Output(k) if k == "artifact" => Some("a.tgz".to_string()),
+            StepRefField::RunId => Some("run-7".to_string()),
+            _ => None,
+        })
+        .unwrap();
+        assert_eq!(rendered["artifact"], "a.tgz");
+        assert_eq!(rendered["note"], "from run-7");
+
+        let cyclic = spec(
+            r#"
+            id = "loop"
+            [[step]]
+            id = "a"
+            intent = "x"
+            depends_on = ["b"]
+            [[step]]
+            id = "b"
+            intent = "x"
+            depends_on = ["a"]
+            "#,
+        );
+        assert!(validate_workflow(&cyclic, &demo_catalog()).is_err());
+
+        let repeated = spec(
+            r#"
+            id = "twice"
+            [[step]]
+            id = "a"
+            intent = "x"
+            [[step]]
+            id = "b"
+            intent = "x"
+            depends_on = ["a", "a"]
+            "#,
+        );
+        let err = validate_workflow(&repeated, &demo_catalog())
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("more than once"), "{err}");
+    }
+
+    #[test]
+    fn references_must_point_at_dependencies() {
+        let wf = spec(
+            r#"
+            id = "bad"
+            [[step]]
+            id = "a"
+            intent = "x"
+            [[step]]
+            id = "b"
+            intent = "x"
+            payload = { v = "${steps.a.output.v}" }
+            "#,
+        );
+        let err = validate_workflow(&wf, &demo_catalog()).unwrap_err();
+        assert!(err.to_string().contains("does not depend on a"));
+    }
+}
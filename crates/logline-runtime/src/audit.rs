@@ -0,0 +1,412 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use logline_api::{BackendId, LoglineError, ProfileId, RunId};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::lockfile::LockFile;
+
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+const LOG_LOCK_STALE: Duration = Duration::from_secs(30);
+const LOG_LOCK_WAIT: Duration = Duration::from_secs(10);
+
+/// What happened, before it is chained into the log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub ts_unix_ms: i64,
+    pub principal: String,
+    pub profile: ProfileId,
+    pub backend: BackendId,
+    pub action: String,
+    pub intent_type: Option<String>,
+    pub payload_sha256: Option<String>,
+    pub run_id: Option<RunId>,
+    pub result: String,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// One line of the audit log: the entry plus its position in the hash chain.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub seq: u64,
+    #[serde(flatten)]
+    pub entry: AuditEntry,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+impl AuditRecord {
+    fn compute_hash(&self) -> Result<String, LoglineError> {
+        let mut unsigned = self.clone();
+        unsigned.hash = String::new();
+        let body = serde_json::to_vec(&unsigned)
+            .map_err(|e| LoglineError::Internal(format!("failed to encode audit record: {e}")))?;
+        Ok(hex_digest(&body))
+    }
+}
+
+pub trait AuditSink: Send + Sync {
+    fn append(&self, entry: AuditEntry) -> Result<AuditRecord, LoglineError>;
+}
+
+/// Marks where the chain starts after older records were pruned.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AuditAnchor {
+    seq: u64,
+    hash: String,
+}
+
+/// Append-only JSON-lines audit log on the local filesystem.
+///
+/// Appends from any number of handles and processes (the scheduler and a manual
+/// `logline run`, say) extend one chain: each takes `<log>.lock` and re-reads the
+/// tail under it.
+pub struct LocalAuditLog {
+    path: PathBuf,
+}
+
+impl LocalAuditLog {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn read_tail(&self) -> Result<(u64, String), LoglineError> {
+        let records = read_records(&self.path)?;
+        if let Some(last) = records.last() {
+            return Ok((last.seq, last.hash.clone()));
+        }
+        let anchor = read_anchor(&self.path)?;
+        Ok(anchor.map_or_else(|| (0, GENESIS_HASH.to_string()), |a| (a.seq, a.hash)))
+    }
+}
+
+impl AuditSink for LocalAuditLog {
+    fn append(&self, entry: AuditEntry) -> Result<AuditRecord, LoglineError> {
+        let _lock = lock_log(&self.path)?;
+        let (last_seq, last_hash) = self.read_tail()?;
+
+        let mut record = AuditRecord {
+            seq: last_seq + 1,
+            entry,
+            prev_hash: last_hash,
+            hash: String::new(),
+        };
+        record.hash = record.compute_hash()?;
+
+        let line = serde_json::to_string(&record)
+            .map_err(|e| LoglineError::Internal(format!("failed to encode audit record: {e}")))?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| {
+                LoglineError::Internal(format!("failed to open {}: {e}", self.path.display()))
+            })?;
+        writeln!(file, "{line}").map_err(|e| {
+            LoglineError::Internal(format!("failed to write {}: {e}", self.path.display()))
+        })?;
+        Ok(record)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditVerification {
+    pub ok: bool,
+    pub records: usize,
+    pub first_seq: Option<u64>,
+    pub last_seq: Option<u64>,
+    pub broken_at_seq: Option<u64>,
+    pub reason: Option<String>,
+}
+
+/// Walk the whole chain and report the first record that does not line up.
+pub fn verify_audit_log(path: &Path) -> Result<AuditVerification, LoglineError> {
+    let records = read_records(path)?;
+    let (mut expected_seq, mut expected_prev) =
+        read_anchor(path)?.map_or_else(|| (1, GENESIS_HASH.to_string()), |a| (a.seq + 1, a.hash));
+
+    let mut report = AuditVerification {
+        ok: true,
+        records: records.len(),
+        first_seq: records.first().map(|r| r.seq),
+        last_seq: records.last().map(|r| r.seq),
+        broken_at_seq: None,
+        reason: None,
+    };
+
+    for record in &records {
+        let failure = if record.seq != expected_seq {
+            Some(format!("expected seq {expected_seq}, found {}", record.seq))
+        } else if record.prev_hash != expected_prev {
+            Some("prev_hash does not match the preceding record".to_string())
+        } else if record.compute_hash()? != record.hash {
+            Some("record contents do not match its hash".to_string())
+        } else {
+            None
+        };
+
+        if let Some(reason) = failure {
+            report.ok = false;
+            report.broken_at_seq = Some(record.seq);
+            report.reason = Some(reason);
+            return Ok(report);
+        }
+
+        expected_seq = record.seq + 1;
+        expected_prev.clone_from(&record.hash);
+    }
+
+    Ok(report)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditPruneReport {
+    pub removed: usize,
+    pub kept: usize,
+    pub anchor_seq: Option<u64>,
+}
+
+/// Drop records older than `retention_days`, keeping the chain verifiable
+/// by anchoring it at the last removed record.
+pub fn prune_audit_log(
+    path: &Path,
+    retention_days: u32,
+    now_unix_ms: i64,
+) -> Result<AuditPruneReport, LoglineError> {
+    let _lock = lock_log(path)?;
+    let verification = verify_audit_log(path)?;
+    if !verification.ok {
+        return Err(LoglineError::Conflict(format!(
+            "refusing to prune a broken audit chain (seq {}: {})",
+            verification.broken_at_seq.unwrap_or_default(),
+            verification.reason.unwrap_or_default()
+        )));
+    }
+
+    let cutoff = now_unix_ms - i64::from(retention_days) * 86_400_000;
+    let records = read_records(path)?;
+    let split = records
+        .iter()
+        .position(|r| r.entry.ts_unix_ms >= cutoff)
+        .unwrap_or(records.len());
+    if split == 0 {
+        return Ok(AuditPruneReport {
+            removed: 0,
+            kept: records.len(),
+            anchor_seq: read_anchor(path)?.map(|a| a.seq),
+        });
+    }
+
+    let (removed, kept) = records.split_at(split);
+    let last_removed = &removed[removed.len() - 1];
+    let anchor = AuditAnchor {
+        seq: last_removed.seq,
+        hash: last_removed.hash.clone(),
+    };
+
+    let mut body = String::new();
+    for record in kept {
+        let line = serde_json::to_string(record)
+            .map_err(|e| LoglineError::Internal(format!("failed to encode audit record: {e}")))?;
+        body.push_str(&line);
+        body.push('\n');
+    }
+    let anchor_json = serde_json::to_string(&anchor)
+        .map_err(|e| LoglineError::Internal(format!("failed to encode audit anchor: {e}")))?;
+
+    // Anchor first: a crash between the two writes leaves extra records, never a gap.
+    write_atomic(&anchor_path(path), &anchor_json)?;
+    write_atomic(path, &body)?;
+
+    Ok(AuditPruneReport {
+        removed: removed.len(),
+        kept: kept.len(),
+        anchor_seq: Some(anchor.seq),
+    })
+}
+
+pub fn payload_digest(payload: &impl Serialize) -> Option<String> {
+    serde_json::to_vec(payload).ok().map(|b| hex_digest(&b))
+}
+
+fn read_records(path: &Path) -> Result<Vec<AuditRecord>, LoglineError> {
+    let content = match fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(LoglineError::Internal(format!(
+                "failed to read {}: {e}",
+                path.display()
+            )));
+        }
+    };
+
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line).map_err(|e| {
+                LoglineError::Validation(format!(
+                    "{} line {}: malformed audit record: {e}",
+                    path.display(),
+                    i + 1
+                ))
+            })
+        })
+        .collect()
+}
+
+/// Held across reading the tail and writing, so appends and prunes never interleave.
+/// A sibling file rather than the log itself, which pruning replaces.
+fn lock_log(path: &Path) -> Result<LockFile, LoglineError> {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".lock");
+    LockFile::acquire(&path.with_file_name(name), LOG_LOCK_STALE, LOG_LOCK_WAIT)
+}
+
+fn anchor_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".anchor");
+    path.with_file_name(name)
+}
+
+fn read_anchor(path: &Path) -> Result<Option<AuditAnchor>, LoglineError> {
+    let anchor_path = anchor_path(path);
+    match fs::read_to_string(&anchor_path) {
+        Ok(c) => serde_json::from_str(&c).map(Some).map_err(|e| {
+            LoglineError::Validation(format!("invalid {}: {e}", anchor_path.display()))
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(LoglineError::Internal(format!(
+            "failed to read {}: {e}",
+            anchor_path.display()
+        ))),
+    }
+}
+
+fn write_atomic(path: &Path, body: &str) -> Result<(), LoglineError> {
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp = path.with_file_name(tmp_name);
+    fs::write(&tmp, body)
+        .map_err(|e| LoglineError::Internal(format!("failed to write {}: {e}", tmp.display())))?;
+    fs::rename(&tmp, path)
+        .map_err(|e| LoglineError::Internal(format!("failed to replace {}: {e}", path.display())))
+}
+
+fn hex_digest(bytes: &[u8]) -> String {
+    use std::fmt::Write as _;
+
+    Sha256::digest(bytes)
+        .iter()
+        .fold(String::with_capacity(64), |mut out, b| {
+            let _ = write!(out, "{b:02x}");
+            out
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(ts_unix_ms: i64) -> AuditEntry {
+        AuditEntry {
+            ts_unix_ms,
+            principal: "ops@example.com".to_string(),
+            profile: "local".to_string(),
+            backend: "local-main".to_string(),
+            action: "run_intent".to_string(),
+            intent_type: Some("deploy".to_string()),
+            payload_sha256: None,
+            run_id: Some("run-1".to_string()),
+            result: "accepted".to_string(),
+            error: None,
+            duration_ms: 3,
+        }
+    }
+
+    fn temp_log(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("logline-audit-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("audit.log")
+    }
+
+    #[test]
+    fn chain_verifies_and_detects_tampering() {
+        let path = temp_log("tamper");
+        let log = LocalAuditLog::new(path.clone());
+        for ts in 1..=3 {
+            log.append(entry(ts)).unwrap();
+        }
+        assert!(verify_audit_log(&path).unwrap().ok);
+
+        let content = fs::read_to_string(&path).unwrap();
+        fs::write(
+            &path,
+            content.replacen("ops@example.com", "mallory@example.com", 1),
+        )
+        .unwrap();
+        let report = verify_audit_log(&path).unwrap();
+        assert!(!report.ok);
+        assert_eq!(report.broken_at_seq, Some(1));
+    }
+
+    #[test]
+    fn prune_keeps_chain_verifiable() {
+        let path = temp_log("prune");
+        let log = LocalAuditLog::new(path.clone());
+        let day = 86_400_000;
+        for ts in [0, day, 10 * day] {
+            log.append(entry(ts)).unwrap();
+        }
+
+        let report = prune_audit_log(&path, 5, 11 * day).unwrap();
+        assert_eq!(
+            (report.removed, report.kept, report.anchor_seq),
+            (2, 1, Some(2))
+        );
+        assert!(verify_audit_log(&path).unwrap().ok);
+
+        // A fresh handle continues the chain from the surviving tail.
+        LocalAuditLog::new(path.clone())
+            .append(entry(11 * day))
+            .unwrap();
+        let verification = verify_audit_log(&path).unwrap();
+        assert!(verification.ok);
+        assert_eq!(verification.last_seq, Some(4));
+    }
+
+    #[test]
+    fn concurrent_handles_extend_one_chain() {
+        let path = temp_log("concurrent");
+        let writers: Vec<_> = (0..4)
+            .map(|_| {
+                // Separate handles, as separate processes would have.
+                let log = LocalAuditLog::new(path.clone());
+                std::thread::spawn(move || {
+                    for ts in 0..25 {
+                        log.append(entry(ts)).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let verification = verify_audit_log(&path).unwrap();
+        assert!(verification.ok, "{:?}", verification.reason);
+        assert_eq!(verification.records, 100);
+        assert_eq!(verification.last_seq, Some(100));
+    }
+}
@@ -0,0 +1,201 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use logline_api::{BackendId, Intent, LoglineError, ProfileId, RunId};
+use serde::Serialize;
+
+use crate::plan::IntentOutcome;
+use crate::registry::RunSource;
+use crate::{LoglineRuntime, now_unix_ms};
+
+/// What one profile returned for a broadcast intent.
+#[derive(Debug, Clone, Serialize)]
+pub struct BroadcastTarget {
+    pub profile: ProfileId,
+    pub backend: BackendId,
+    pub outcome: Option<IntentOutcome>,
+    pub error: Option<String>,
+}
+
+/// Comparison of the `output` maps of every target that executed.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OutputDiff {
+    /// Keys with the same value on every compared target.
+    pub identical: BTreeMap<String, String>,
+    /// Keys whose value differs between targets; `None` means the key was absent.
+    pub differing: BTreeMap<String, BTreeMap<ProfileId, Option<String>>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BroadcastRun {
+    pub broadcast_id: RunId,
+    pub intent_type: String,
+    pub targets: Vec<BroadcastTarget>,
+    pub diff: OutputDiff,
+}
+
+impl BroadcastRun {
+    pub fn failed(&self) -> usize {
+        self.targets.iter().filter(|t| t.error.is_some()).count()
+    }
+}
+
+impl LoglineRuntime {
+    /// Run `intent` on every profile in `profiles` in parallel, within
+    /// `max_concurrent_runs`. Failures are reported per target; with
+    /// `plan_on_readonly`, readonly profiles return a plan instead of an error.
+    pub fn broadcast(
+        &self,
+        profiles: &[ProfileId],
+        intent: &Intent,
+        plan_on_readonly: bool,
+    ) -> Result<BroadcastRun, LoglineError> {
+        if profiles.is_empty() {
+            return Err(LoglineError::Validation(
+                "broadcast needs at least one profile".to_string(),
+            ));
+        }
+        let mut seen = BTreeSet::new();
+        let mut targets = Vec::with_capacity(profiles.len());
+        for id in profiles {
+            let profile = self
+                .catalog
+                .profiles
+                .get(id)
+                .ok_or_else(|| LoglineError::NotFound(format!("profile {id} not found")))?;
+            if !seen.insert(id) {
+                return Err(LoglineError::Validation(format!(
+                    "profile {id} listed twice"
+                )));
+            }
+            targets.push((id.clone(), profile.backend_id.clone()));
+        }
+
+        let broadcast_id = format!("bc-{}", now_unix_ms());
+        let results: Mutex<BTreeMap<usize, BroadcastTarget>> = Mutex::new(BTreeMap::new());
+        let next = AtomicUsize::new(0);
+        let workers = self.policy.max_concurrent_runs.clamp(1, targets.len());
+
+        thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| {
+                    loop {
+                        let i = next.fetch_add(1, Ordering::SeqCst);
+                        let Some((profile, backend)) = targets.get(i) else {
+                            break;
+                        };
+                        let source = RunSource::Broadcast {
+                            broadcast_id: broadcast_id.clone(),
+                        };
+                        let result = if plan_on_readonly {
+                            self.run_or_plan_on(profile.clone(), backend.clone(), intent, source)
+                        } else {
+                            self.dispatch(profile.clone(), backend.clone(), intent, source, 1)
+                                .map(IntentOutcome::Executed)
+                        };
+                        let target = BroadcastTarget {
+                            profile: profile.clone(),
+                            backend: backend.clone(),
+                            error: result.as_ref().err().map(ToString::to_string),
+                            outcome: result.ok(),
+                        };
+                        if let Ok(mut results) = results.lock() {
+                            results.insert(i, target);
+                        }
+                    }
+                });
+            }
+        });
+
+        let targets: Vec<BroadcastTarget> = results
+            .into_inner()
+            .map_err(|_| LoglineError::Internal("broadcast results poisoned".to_string()))?
+            .into_values()
+            .collect();
+        Ok(BroadcastRun {
+            broadcast_id,
+            intent_type: intent.intent_type.clone(),
+            diff: diff_outputs(&targets),
+            targets,
+        })
+    }
+}
+
+/// Compare outputs of the targets that executed; planned and failed targets are left out.
+pub fn diff_outputs(targets: &[BroadcastTarget]) -> OutputDiff {
+    let outputs: Vec<(&ProfileId, &BTreeMap<String, String>)> = targets
+        .iter()
+        .filter_map(|t| match &t.outcome {
+            Some(IntentOutcome::Executed(result)) => Some((&t.profile, &result.output)),
+            _ => None,
+        })
+        .collect();
+    let keys: BTreeSet<&String> = outputs.iter().flat_map(|(_, o)| o.keys()).collect();
+
+    let mut diff = OutputDiff::default();
+    for key in keys {
+        let values: BTreeMap<ProfileId, Option<String>> = outputs
+            .iter()
+            .map(|(profile, output)| ((*profile).clone(), output.get(key).cloned()))
+            .collect();
+        let distinct: BTreeSet<&Option<String>> = values.values().collect();
+        match distinct.into_iter().collect::<Vec<_>>().as_slice() {
+            [Some(value)] => {
+                diff.identical.insert(key.clone(), value.clone());
+            }
+            _ => {
+                diff.differing.insert(key.clone(), values);
+            }
+        }
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use logline_api::ExecutionResult;
+
+    use super::*;
+
+    fn executed(profile: &str, output: &[(&str, &str)]) -> BroadcastTarget {
+        BroadcastTarget {
+            profile: profile.to_string(),
+            backend: format!("{profile}-api"),
+            outcome: Some(IntentOutcome::Executed(ExecutionResult {
+                run_id: format!("run-{profile}"),
+                status: "accepted".to_string(),
+                output: output
+                    .iter()
+                    .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+                    .collect(),
+                recording_error: None,
+            })),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn diff_separates_identical_and_differing_keys() {
+        let failed = BroadcastTarget {
+            profile: "prod".to_string(),
+            backend: "prod-api".to_string(),
+            outcome: None,
+            error: Some("connection error: down".to_string()),
+        };
+        let diff = diff_outputs(&[
+            executed("local", &[("version", "3"), ("rows", "10")]),
+            executed(
+                "staging",
+                &[("version", "3"), ("rows", "12"), ("note", "x")],
+            ),
+            failed,
+        ]);
+
+        assert_eq!(diff.identical.get("version").map(String::as_str), Some("3"));
+        assert_eq!(diff.differing["rows"]["staging"].as_deref(), Some("12"));
+        assert_eq!(diff.differing["note"]["local"], None);
+        assert!(!diff.differing["rows"].contains_key("prod"));
+    }
+}
@@ -0,0 +1,512 @@
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use logline_api::{BackendId, HealthReport, LoglineError};
+use logline_core::CircuitBreakerConfig;
+use serde::{Deserialize, Serialize};
+
+use crate::lockfile::LockFile;
+use crate::{LoglineRuntime, now_unix_ms};
+
+const LOG_LOCK_STALE: Duration = Duration::from_secs(30);
+const LOG_LOCK_WAIT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthSource {
+    /// An explicit health check, e.g. `logline backend test`.
+    Probe,
+    /// A run that failed to reach the backend, or the first run to succeed after that.
+    Run,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthSample {
+    pub backend: BackendId,
+    pub ts_unix_ms: i64,
+    pub source: HealthSource,
+    pub ok: bool,
+    pub latency_ms: u64,
+    pub report: Option<HealthReport>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum CircuitState {
+    Closed,
+    /// Runs fail fast until `until_unix_ms`.
+    Open {
+        until_unix_ms: i64,
+    },
+    /// The cooldown is over; the next run or probe decides.
+    HalfOpen,
+}
+
+/// Rolling health of one backend, derived from its recent samples.
+#[derive(Debug, Clone, Serialize)]
+pub struct BackendHealth {
+    pub backend: BackendId,
+    pub circuit: CircuitState,
+    pub consecutive_failures: u32,
+    /// Share of successful samples, 0-100; `None` without samples.
+    pub success_percent: Option<u8>,
+    pub avg_latency_ms: Option<u64>,
+    /// Oldest first.
+    pub samples: Vec<HealthSample>,
+}
+
+impl BackendHealth {
+    fn from_samples(
+        backend: &str,
+        samples: Vec<HealthSample>,
+        breaker: &CircuitBreakerConfig,
+        now_ms: i64,
+    ) -> Self {
+        let consecutive_failures = samples.iter().rev().take_while(|s| !s.ok).count();
+        let consecutive_failures = u32::try_from(consecutive_failures).unwrap_or(u32::MAX);
+        let circuit = match samples.last() {
+            Some(last) if breaker.enabled && consecutive_failures >= breaker.failure_threshold => {
+                let cooldown_ms = i64::try_from(breaker.cooldown_seconds.saturating_mul(1_000))
+                    .unwrap_or(i64::MAX);
+                let until_unix_ms = last.ts_unix_ms.saturating_add(cooldown_ms);
+                if now_ms < until_unix_ms {
+                    CircuitState::Open { until_unix_ms }
+                } else {
+                    CircuitState::HalfOpen
+                }
+            }
+            _ => CircuitState::Closed,
+        };
+
+        let ok: Vec<&HealthSample> = samples.iter().filter(|s| s.ok).collect();
+        let success_percent = (!samples.is_empty())
+            .then(|| u8::try_from(ok.len() * 100 / samples.len()).unwrap_or(100));
+        let avg_latency_ms = (!ok.is_empty()).then(|| {
+            ok.iter().map(|s| s.latency_ms).sum::<u64>() / u64::try_from(ok.len()).unwrap_or(1)
+        });
+
+        Self {
+            backend: backend.to_string(),
+            circuit,
+            consecutive_failures,
+            success_percent,
+            avg_latency_ms,
+            samples,
+        }
+    }
+}
+
+/// Result of probing one backend as part of `probe_all`.
+#[derive(Debug, Clone, Serialize)]
+pub struct BackendProbe {
+    pub backend: BackendId,
+    pub report: Option<HealthReport>,
+    pub error: Option<String>,
+}
+
+/// Append-only health samples, one JSON object per line, shared by every
+/// process using the same config dir. Compacted to the newest samples per
+/// backend once a backend has twice as many as the breaker keeps. Appends and
+/// compaction hold `<log>.lock`, so a compaction cannot drop another process's
+/// sample.
+pub struct HealthLog {
+    path: Option<PathBuf>,
+    memory: Mutex<Vec<HealthSample>>,
+}
+
+impl HealthLog {
+    pub fn persistent(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: Some(path.into()),
+            memory: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            memory: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Append `sample`, then drop all but the newest `keep` samples per backend
+    /// if its backend has grown past `2 * keep`.
+    pub fn record(&self, sample: &HealthSample, keep: usize) -> Result<(), LoglineError> {
+        let Some(path) = &self.path else {
+            let mut memory = self
+                .memory
+                .lock()
+                .map_err(|_| LoglineError::Internal("health log poisoned".to_string()))?;
+            memory.push(sample.clone());
+            *memory = retain_newest(std::mem::take(&mut *memory), keep);
+            return Ok(());
+        };
+
+        let mut line = serde_json::to_string(sample)
+            .map_err(|e| LoglineError::Internal(format!("failed to encode health sample: {e}")))?;
+        line.push('\n');
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                LoglineError::Internal(format!("failed to create {}: {e}", parent.display()))
+            })?;
+        }
+        let _lock = lock_log(path)?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| {
+                LoglineError::Internal(format!("failed to open {}: {e}", path.display()))
+            })?;
+        file.write_all(line.as_bytes()).map_err(|e| {
+            LoglineError::Internal(format!("failed to write {}: {e}", path.display()))
+        })?;
+
+        let history = self.history()?;
+        let count = history
+            .iter()
+            .filter(|s| s.backend == sample.backend)
+            .count();
+        if count <= keep.saturating_mul(2) {
+            return Ok(());
+        }
+        let mut content = String::new();
+        for kept in retain_newest(history, keep) {
+            content.push_str(&serde_json::to_string(&kept).map_err(|e| {
+                LoglineError::Internal(format!("failed to encode health sample: {e}"))
+            })?);
+            content.push('\n');
+        }
+        let tmp = path.with_extension("jsonl.tmp");
+        fs::write(&tmp, content)
+            .and_then(|()| fs::rename(&tmp, path))
+            .map_err(|e| {
+                LoglineError::Internal(format!("failed to compact {}: {e}", path.display()))
+            })
+    }
+
+    /// The newest `keep` samples of `backend`, oldest first.
+    pub fn samples(&self, backend: &str, keep: usize) -> Result<Vec<HealthSample>, LoglineError> {
+        let mut samples: Vec<HealthSample> = self
+            .history()?
+            .into_iter()
+            .filter(|s| s.backend == backend)
+            .collect();
+        let excess = samples.len().saturating_sub(keep);
+        samples.drain(..excess);
+        Ok(samples)
+    }
+
+    fn history(&self) -> Result<Vec<HealthSample>, LoglineError> {
+        let Some(path) = &self.path else {
+            return Ok(self
+                .memory
+                .lock()
+                .map_err(|_| LoglineError::Internal("health log poisoned".to_string()))?
+                .clone());
+        };
+
+        let content = match fs::read_to_string(path) {
+            Ok(c) => c,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(LoglineError::Internal(format!(
+                    "failed to read {}: {e}",
+                    path.display()
+                )));
+            }
+        };
+
+        content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(n, line)| {
+                serde_json::from_str(line).map_err(|e| {
+                    LoglineError::Validation(format!("{}:{}: {e}", path.display(), n + 1))
+                })
+            })
+            .collect()
+    }
+}
+
+/// Keep the newest `keep` samples of every backend, in their original order.
+fn retain_newest(samples: Vec<HealthSample>, keep: usize) -> Vec<HealthSample> {
+    let mut remaining: BTreeMap<BackendId, usize> = BTreeMap::new();
+    for sample in &samples {
+        *remaining.entry(sample.backend.clone()).or_default() += 1;
+    }
+    samples
+        .into_iter()
+        .filter(|s| {
+            let left = remaining.get_mut(&s.backend).map_or(0, |n| {
+                *n -= 1;
+                *n
+            });
+            left < keep
+        })
+        .collect()
+}
+
+impl LoglineRuntime {
+    /// Check `backend_id` now and add the outcome to its health history.
+    /// Probes go through even while the circuit is open; a passing probe closes it.
+    pub fn probe_backend(&self, backend_id: &str) -> Result<HealthReport, LoglineError> {
+        let connector = self
+            .connectors
+            .get(backend_id)
+            .ok_or_else(|| LoglineError::NotFound(format!("backend {backend_id} not loaded")))?;
+        let started = Instant::now();
+        let result = connector.health().map(|mut report| {
+            if report.latency_ms == 0 {
+                report.latency_ms = elapsed_ms(started);
+            }
+            report
+        });
+        self.record_health(&HealthSample {
+            backend: backend_id.to_string(),
+            ts_unix_ms: now_unix_ms(),
+            source: HealthSource::Probe,
+            ok: result.is_ok(),
+            latency_ms: result
+                .as_ref()
+                .map_or_else(|_| elapsed_ms(started), |r| r.latency_ms),
+            report: result.as_ref().ok().cloned(),
+            error: result.as_ref().err().map(ToString::to_string),
+        })?;
+        result
+    }
+
+    /// Probe every loaded backend in parallel, one thread each.
+    pub fn probe_all(&self) -> Vec<BackendProbe> {
+        thread::scope(|scope| {
+            let handles: Vec<_> = self
+                .connectors
+                .keys()
+                .map(|id| (id, scope.spawn(|| self.probe_backend(id))))
+                .collect();
+            handles
+                .into_iter()
+                .map(|(id, handle)| {
+                    let result = handle.join().unwrap_or_else(|_| {
+                        Err(LoglineError::Internal(format!("probe of {id} panicked")))
+                    });
+                    BackendProbe {
+                        backend: id.clone(),
+                        error: result.as_ref().err().map(ToString::to_string),
+                        report: result.ok(),
+                    }
+                })
+                .collect()
+        })
+    }
+
+    /// Health history and circuit state of `backend_id`.
+    pub fn backend_health(&self, backend_id: &str) -> Result<BackendHealth, LoglineError> {
+        if !self.connectors.contains_key(backend_id) {
+            return Err(LoglineError::NotFound(format!(
+                "backend {backend_id} not loaded"
+            )));
+        }
+        let samples = self
+            .health
+            .samples(backend_id, self.circuit_breaker.history)?;
+        Ok(BackendHealth::from_samples(
+            backend_id,
+            samples,
+            &self.circuit_breaker,
+            now_unix_ms(),
+        ))
+    }
+
+    /// Fail fast while `backend_id`'s circuit is open.
+    pub(crate) fn check_circuit(&self, backend_id: &str) -> Result<(), LoglineError> {
+        if !self.circuit_breaker.enabled {
+            return Ok(());
+        }
+        let health = self.backend_health(backend_id)?;
+        match health.circuit {
+            CircuitState::Open { until_unix_ms } => Err(LoglineError::Connection(format!(
+                "circuit open for backend {backend_id} after {} consecutive failures; \
+                 retrying in {}s (run `logline backend test {backend_id}` to check it now)",
+                health.consecutive_failures,
+                ((until_unix_ms - now_unix_ms()).max(0) + 999) / 1_000
+            ))),
+            CircuitState::Closed | CircuitState::HalfOpen => Ok(()),
+        }
+    }
+
+    /// Feed a run that reached the connector into the backend's health: connection
+    /// failures count against the breaker, and the first success afterwards resets it.
+    pub(crate) fn record_run_health<T>(
+        &self,
+        backend_id: &str,
+        result: &Result<T, LoglineError>,
+        latency_ms: u64,
+    ) -> Result<(), LoglineError> {
+        let error = match result {
+            Err(e @ LoglineError::Connection(_)) => Some(e.to_string()),
+            Err(_) => return Ok(()),
+            Ok(_) => {
+                let last = self.health.samples(backend_id, 1)?;
+                if last.first().is_none_or(|s| s.ok) {
+                    return Ok(());
+                }
+                None
+            }
+        };
+        self.record_health(&HealthSample {
+            backend: backend_id.to_string(),
+            ts_unix_ms: now_unix_ms(),
+            source: HealthSource::Run,
+            ok: error.is_none(),
+            latency_ms,
+            report: None,
+            error,
+        })
+    }
+
+    fn record_health(&self, sample: &HealthSample) -> Result<(), LoglineError> {
+        self.health.record(sample, self.circuit_breaker.history)
+    }
+}
+
+fn lock_log(path: &Path) -> Result<LockFile, LoglineError> {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".lock");
+    LockFile::acquire(&path.with_file_name(name), LOG_LOCK_STALE, LOG_LOCK_WAIT)
+}
+
+fn elapsed_ms(started: Instant) -> u64 {
+    u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use logline_core::demo_catalog;
+    use logline_testkit::{Fault, MockConnectorFactory, MockSecretStore, Step};
+
+    use super::*;
+    use crate::RunSource;
+
+    #[test]
+    fn repeated_failures_open_the_circuit_and_a_probe_closes_it() {
+        let factory = MockConnectorFactory::new();
+        let runtime = LoglineRuntime::from_catalog_with_factory(
+            demo_catalog(),
+            &factory,
+            &MockSecretStore::new(),
+        )
+        .unwrap()
+        .with_circuit_breaker(CircuitBreakerConfig {
+            failure_threshold: 2,
+            ..CircuitBreakerConfig::default()
+        });
+        let backend = factory.backend("local-main").unwrap();
+        backend.push(Step::intent("deploy").fail(Fault::Connection("reset".to_string())));
+        backend.fail_health(Fault::Connection("refused".to_string()));
+
+        let intent = logline_api::Intent {
+            intent_type: "deploy".to_string(),
+            payload: BTreeMap::new(),
+        };
+        assert!(
+            runtime
+                .run_intent_on("local", &intent, RunSource::Manual)
+                .is_err()
+        );
+        assert!(runtime.probe_backend("local-main").is_err());
+
+        let health = runtime.backend_health("local-main").unwrap();
+        assert_eq!(health.consecutive_failures, 2);
+        assert!(matches!(health.circuit, CircuitState::Open { .. }));
+        let err = runtime
+            .run_intent_on("local", &intent, RunSource::Manual)
+            .unwrap_err();
+        assert!(matches!(err, LoglineError::Connection(msg) if msg.contains("circuit open")));
+        backend.assert_executed(&["deploy"]);
+
+        backend.restore_health();
+        runtime.probe_backend("local-main").unwrap();
+        let health = runtime.backend_health("local-main").unwrap();
+        assert_eq!(health.circuit, CircuitState::Closed);
+        assert_eq!(health.success_percent, Some(33));
+    }
+
+    fn sample(backend: &str, ts_unix_ms: i64) -> HealthSample {
+        HealthSample {
+            backend: backend.to_string(),
+            ts_unix_ms,
+            source: HealthSource::Probe,
+            ok: true,
+            latency_ms: 1,
+            report: None,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn compaction_keeps_the_newest_samples_per_backend() {
+        let dir = std::env::temp_dir().join(format!("logline-health-{}", now_unix_ms()));
+        let log = HealthLog::persistent(dir.join("health.jsonl"));
+        log.record(&sample("b", 0), 2).unwrap();
+        for ts in 1..=5 {
+            log.record(&sample("a", ts), 2).unwrap();
+        }
+
+        let kept: Vec<i64> = log
+            .samples("a", 10)
+            .unwrap()
+            .iter()
+            .map(|s| s.ts_unix_ms)
+            .collect();
+        assert_eq!(kept, vec![4, 5]);
+        assert_eq!(log.samples("b", 10).unwrap().len(), 1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn concurrent_handles_do_not_lose_samples_to_compaction() {
+        let dir = std::env::temp_dir().join(format!("logline-health-race-{}", now_unix_ms()));
+        let path = dir.join("health.jsonl");
+        let writers: Vec<_> = ["a", "b", "c", "d"]
+            .into_iter()
+            .map(|backend| {
+                // Separate handles, as separate processes would have.
+                let log = HealthLog::persistent(path.clone());
+                thread::spawn(move || {
+                    for ts in 0..200 {
+                        log.record(&sample(backend, ts), 3).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let log = HealthLog::persistent(path);
+        for backend in ["a", "b", "c", "d"] {
+            let kept: Vec<i64> = log
+                .samples(backend, 10)
+                .unwrap()
+                .iter()
+                .map(|s| s.ts_unix_ms)
+                .collect();
+            let expected: Vec<i64> = (200 - i64::try_from(kept.len()).unwrap()..200).collect();
+            assert!(kept.len() >= 3, "{backend}: {kept:?}");
+            assert_eq!(kept, expected, "{backend}");
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,31 +1,86 @@
 use std::sync::RwLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use logline_api::{
     BackendConfig, BackendConnector, BackendId, ConnectorFactory, DomainEvent, EventCursor,
-    ExecutionResult, Intent, LoglineError, ProfileId, RunId, RuntimeEngine, RuntimeStatus,
-    SecretStore,
+    ExecutionPlan, ExecutionResult, HealthReport, Intent, LoglineError, ProfileId, RunId,
+    RuntimeEngine, RuntimeStatus, SecretStore,
 };
 use logline_connectors::{DefaultConnectorFactory, EnvSecretStore};
-use logline_core::{ConnectionCatalog, validate_catalog};
+use logline_core::{CircuitBreakerConfig, ConnectionCatalog, RuntimePolicy, validate_catalog};
+
+mod audit;
+mod broadcast;
+mod health;
+mod lockfile;
+mod metrics;
+mod plan;
+mod registry;
+mod scheduler;
+mod slots;
+mod spans;
+mod workflow;
+
+pub use audit::{
+    AuditEntry, AuditPruneReport, AuditRecord, AuditSink, AuditVerification, LocalAuditLog,
+    payload_digest, prune_audit_log, verify_audit_log,
+};
+pub use broadcast::{BroadcastRun, BroadcastTarget, OutputDiff, diff_outputs};
+pub use health::{
+    BackendHealth, BackendProbe, CircuitState, HealthLog, HealthSample, HealthSource,
+};
+pub use lockfile::LockFile;
+pub use metrics::{LATENCY_BUCKETS_MS, MetricsSnapshot, RunSeries};
+pub use plan::IntentOutcome;
+pub use registry::{RunRecord, RunRegistry, RunSource};
+pub use scheduler::{
+    DispatchedSlots, ScheduleEntry, ScheduleStore, Scheduler, SkippedSlots, TickReport,
+};
+pub use spans::{FileSpanExporter, Span, SpanExporter, SpanKind, otlp_json};
+pub use workflow::{StepRun, StepStatus, WorkflowRun};
+
+use crate::slots::RunSlots;
+use crate::spans::now_unix_nanos;
 
 struct RuntimeState {
     active_profile: ProfileId,
     active_backend: BackendId,
-    running_jobs: usize,
 }
 
 pub struct LoglineRuntime {
     catalog: ConnectionCatalog,
     connectors: std::collections::BTreeMap<BackendId, Box<dyn BackendConnector>>,
     state: RwLock<RuntimeState>,
+    audit: Option<Box<dyn AuditSink>>,
+    principal: String,
+    registry: RunRegistry,
+    health: HealthLog,
+    circuit_breaker: CircuitBreakerConfig,
+    policy: RuntimePolicy,
+    slots: RunSlots,
+    spans: Option<Box<dyn SpanExporter>>,
+    span_export_failures: AtomicU64,
+    recording_failures: AtomicU64,
 }
 
 impl LoglineRuntime {
     pub fn from_catalog(catalog: ConnectionCatalog) -> Result<Self, LoglineError> {
+        Self::from_catalog_with_policy(catalog, RuntimePolicy::default())
+    }
+
+    /// [`Self::from_catalog`] with `policy` applied, including to the connectors
+    /// it builds (process backends use `stop_grace_seconds`).
+    pub fn from_catalog_with_policy(
+        catalog: ConnectionCatalog,
+        policy: RuntimePolicy,
+    ) -> Result<Self, LoglineError> {
         validate_catalog(&catalog)?;
 
         let secrets = EnvSecretStore;
-        Self::from_catalog_with_factory(catalog, &DefaultConnectorFactory, &secrets)
+        let factory = DefaultConnectorFactory::default()
+            .with_stop_grace(Duration::from_secs(policy.stop_grace_seconds));
+        Ok(Self::from_catalog_with_factory(catalog, &factory, &secrets)?.with_policy(policy))
     }
 
     pub fn from_catalog_with_factory(
@@ -61,14 +116,218 @@ impl LoglineRuntime {
             state: RwLock::new(RuntimeState {
                 active_profile: first_profile,
                 active_backend,
-                running_jobs: 0,
             }),
+            audit: None,
+            principal: "anonymous".to_string(),
+            registry: RunRegistry::in_memory(),
+            health: HealthLog::in_memory(),
+            circuit_breaker: CircuitBreakerConfig::default(),
+            slots: RunSlots::new(&RuntimePolicy::default()),
+            policy: RuntimePolicy::default(),
+            spans: None,
+            span_export_failures: AtomicU64::new(0),
+            recording_failures: AtomicU64::new(0),
         })
     }
+
+    /// Record every run/stop issued through this runtime, attributed to `principal`.
+    #[must_use]
+    pub fn with_audit(mut self, sink: Box<dyn AuditSink>, principal: impl Into<String>) -> Self {
+        self.audit = Some(sink);
+        self.principal = principal.into();
+        self
+    }
+
+    /// Record runs in `registry` instead of the default in-memory one.
+    #[must_use]
+    pub fn with_registry(mut self, registry: RunRegistry) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    /// Keep backend health samples in `log` instead of the default in-memory one.
+    #[must_use]
+    pub fn with_health_log(mut self, log: HealthLog) -> Self {
+        self.health = log;
+        self
+    }
+
+    /// Apply the circuit breaker settings from `runtime.toml`.
+    #[must_use]
+    pub fn with_circuit_breaker(mut self, config: CircuitBreakerConfig) -> Self {
+        self.circuit_breaker = config;
+        self
+    }
+
+    /// Apply concurrency limits from `runtime.toml`.
+    #[must_use]
+    pub fn with_policy(mut self, policy: RuntimePolicy) -> Self {
+        self.slots = RunSlots::new(&policy);
+        self.policy = policy;
+        self
+    }
+
+    /// Export run and connector spans through `exporter`.
+    #[must_use]
+    pub fn with_span_exporter(mut self, exporter: Box<dyn SpanExporter>) -> Self {
+        self.spans = Some(exporter);
+        self
+    }
+
+    pub fn policy(&self) -> &RuntimePolicy {
+        &self.policy
+    }
+
+    pub fn registry(&self) -> &RunRegistry {
+        &self.registry
+    }
+
+    /// Run `intent` on `profile_id` without changing the active profile.
+    pub fn run_intent_on(
+        &self,
+        profile_id: &str,
+        intent: &Intent,
+        source: RunSource,
+    ) -> Result<ExecutionResult, LoglineError> {
+        let profile = self
+            .catalog
+            .profiles
+            .get(profile_id)
+            .ok_or_else(|| LoglineError::NotFound(format!("profile {profile_id} not found")))?;
+        self.dispatch(
+            profile_id.to_string(),
+            profile.backend_id.clone(),
+            intent,
+            source,
+            1,
+        )
+    }
+
+    fn dispatch(
+        &self,
+        profile_id: ProfileId,
+        backend_id: BackendId,
+        intent: &Intent,
+        source: RunSource,
+        attempt: u32,
+    ) -> Result<ExecutionResult, LoglineError> {
+        let connector = self
+            .connectors
+            .get(&backend_id)
+            .ok_or_else(|| LoglineError::NotFound(format!("backend {backend_id} not loaded")))?;
+
+        let mut entry = self.audit_entry(profile_id.clone(), backend_id.clone(), "run_intent");
+        entry.intent_type = Some(intent.intent_type.clone());
+        entry.payload_sha256 = payload_digest(&intent.payload);
+        let started_at_ms = now_unix_ms();
+        let started = Instant::now();
+        let started_unix_nanos = now_unix_nanos();
+
+        let mut call = None;
+        let result = self
+            .check_readonly(&profile_id, &backend_id, intent)
+            .and_then(|()| self.check_circuit(&backend_id))
+            .and_then(|()| {
+                let _slot = self.slots.acquire()?;
+                let call_started = now_unix_nanos();
+                let result = connector.execute(intent);
+                call = Some((call_started, now_unix_nanos()));
+                result
+            });
+        let health = match call {
+            Some((from, to)) => {
+                self.record_run_health(&backend_id, &result, to.saturating_sub(from) / 1_000_000)
+            }
+            None => Ok(()),
+        };
+
+        let record = RunRecord {
+            run_id: result
+                .as_ref()
+                .map_or_else(|_| format!("failed-{started_at_ms}"), |r| r.run_id.clone()),
+            source,
+            profile: profile_id,
+            backend: backend_id,
+            intent_type: intent.intent_type.clone(),
+            status: result
+                .as_ref()
+                .map_or_else(|_| "failed".to_string(), |r| r.status.clone()),
+            attempt,
+            started_at_ms,
+            finished_at_ms: Some(now_unix_ms()),
+            error: result.as_ref().err().map(ToString::to_string),
+        };
+        self.export_run_spans(&record, started_unix_nanos, call);
+
+        if let Ok(r) = &result {
+            entry.run_id = Some(r.run_id.clone());
+        }
+        let recorded = health
+            .and_then(|()| self.registry.record(&record))
+            .and_then(|()| {
+                self.record_audit(entry, started, result.as_ref().map(|r| r.status.as_str()))
+            });
+        match (result, recorded) {
+            (result, Ok(())) => result,
+            // The backend already ran it: an error now would read as a failed run
+            // and invite a second one.
+            (Ok(mut r), Err(e)) => {
+                self.recording_failures.fetch_add(1, Ordering::Relaxed);
+                r.recording_error = Some(e.to_string());
+                Ok(r)
+            }
+            (Err(_), Err(e)) => Err(e),
+        }
+    }
+
+    fn active_target(&self) -> Result<(ProfileId, BackendId), LoglineError> {
+        let guard = self
+            .state
+            .read()
+            .map_err(|_| LoglineError::Internal("runtime state poisoned".to_string()))?;
+        Ok((guard.active_profile.clone(), guard.active_backend.clone()))
+    }
+
+    fn record_audit(
+        &self,
+        mut entry: AuditEntry,
+        started: Instant,
+        outcome: Result<&str, &LoglineError>,
+    ) -> Result<(), LoglineError> {
+        let Some(sink) = &self.audit else {
+            return Ok(());
+        };
+        entry.duration_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+        match outcome {
+            Ok(status) => entry.result = status.to_string(),
+            Err(e) => {
+                entry.result = "error".to_string();
+                entry.error = Some(e.to_string());
+            }
+        }
+        sink.append(entry).map(|_| ())
+    }
+
+    fn audit_entry(&self, profile: ProfileId, backend: BackendId, action: &str) -> AuditEntry {
+        AuditEntry {
+            ts_unix_ms: now_unix_ms(),
+            principal: self.principal.clone(),
+            profile,
+            backend,
+            action: action.to_string(),
+            intent_type: None,
+            payload_sha256: None,
+            run_id: None,
+            result: String::new(),
+            error: None,
+            duration_ms: 0,
+        }
+    }
 }
 
 impl RuntimeEngine for LoglineRuntime {
     fn status(&self) -> Result<RuntimeStatus, LoglineError> {
+        let slots = self.slots.counts()?;
         let guard = self
             .state
             .read()
@@ -76,40 +335,41 @@ impl RuntimeEngine for LoglineRuntime {
         Ok(RuntimeStatus {
             active_profile: guard.active_profile.clone(),
             active_backend: guard.active_backend.clone(),
-            running_jobs: guard.running_jobs,
-            queue_depth: 0,
+            running_jobs: slots.running,
+            queue_depth: slots.queued,
         })
     }
 
     fn run_intent(&self, intent: Intent) -> Result<ExecutionResult, LoglineError> {
-        let backend_id = {
-            let guard = self
-                .state
-                .read()
-                .map_err(|_| LoglineError::Internal("runtime state poisoned".to_string()))?;
-            guard.active_backend.clone()
-        };
+        let (profile_id, backend_id) = self.active_target()?;
+        self.dispatch(profile_id, backend_id, &intent, RunSource::Manual, 1)
+    }
 
-        let connector = self
-            .connectors
-            .get(&backend_id)
-            .ok_or_else(|| LoglineError::NotFound(format!("backend {backend_id} not loaded")))?;
-        connector.execute(&intent)
+    fn plan_intent(&self, intent: Intent) -> Result<ExecutionPlan, LoglineError> {
+        let (profile_id, backend_id) = self.active_target()?;
+        self.plan_audited(profile_id, &backend_id, &intent)
     }
 
     fn stop_run(&self, run_id: RunId) -> Result<(), LoglineError> {
-        let backend_id = {
-            let guard = self
-                .state
-                .read()
-                .map_err(|_| LoglineError::Internal("runtime state poisoned".to_string()))?;
-            guard.active_backend.clone()
-        };
+        let (profile_id, backend_id) = self.active_target()?;
         let connector = self
             .connectors
             .get(&backend_id)
             .ok_or_else(|| LoglineError::NotFound(format!("backend {backend_id} not loaded")))?;
-        connector.stop(&run_id)
+
+        let mut entry = self.audit_entry(profile_id, backend_id, "stop_run");
+        entry.run_id = Some(run_id.clone());
+        let started = Instant::now();
+        let result = connector.stop(&run_id);
+        if result.is_ok() {
+            if let Some(mut record) = self.registry.get(&run_id)? {
+                record.status = "stopped".to_string();
+                record.finished_at_ms = Some(now_unix_ms());
+                self.registry.record(&record)?;
+            }
+        }
+        self.record_audit(entry, started, result.as_ref().map(|()| "stopped"))?;
+        result
     }
 
     fn events_since(&self, cursor: Option<EventCursor>) -> Result<Vec<DomainEvent>, LoglineError> {
@@ -127,12 +387,8 @@ impl RuntimeEngine for LoglineRuntime {
         connector.events_since(cursor.as_ref())
     }
 
-    fn test_backend(&self, backend_id: BackendId) -> Result<(), LoglineError> {
-        let connector = self
-            .connectors
-            .get(&backend_id)
-            .ok_or_else(|| LoglineError::NotFound(format!("backend {backend_id} not loaded")))?;
-        connector.health()
+    fn test_backend(&self, backend_id: BackendId) -> Result<HealthReport, LoglineError> {
+        self.probe_backend(&backend_id)
     }
 
     fn select_profile(&self, profile_id: ProfileId) -> Result<(), LoglineError> {
@@ -166,3 +422,78 @@ fn build_connector(
 ) -> Result<Box<dyn BackendConnector>, LoglineError> {
     factory.build(cfg, secrets)
 }
+
+fn now_unix_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| i64::try_from(d.as_millis()).unwrap_or(i64::MAX))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use logline_core::demo_catalog;
+    use logline_testkit::{MockConnectorFactory, MockSecretStore, Step};
+
+    use super::*;
+
+    #[test]
+    fn stop_reaches_the_backend_and_marks_the_run() {
+        let factory = MockConnectorFactory::new();
+        let runtime = LoglineRuntime::from_catalog_with_factory(
+            demo_catalog(),
+            &factory,
+            &MockSecretStore::new(),
+        )
+        .unwrap();
+        let backend = factory.backend("local-main").unwrap();
+        backend
+            .push(Step::intent("export").run_id("run-export"))
+            .slow_stop(Duration::from_millis(20));
+
+        let intent = Intent {
+            intent_type: "export".to_string(),
+            payload: std::collections::BTreeMap::new(),
+        };
+        runtime.run_intent(intent).unwrap();
+        runtime.stop_run("run-export".to_string()).unwrap();
+
+        backend.assert_stopped("run-export");
+        let record = runtime.registry().get("run-export").unwrap().unwrap();
+        assert_eq!(record.status, "stopped");
+    }
+
+    struct FailingAudit;
+
+    impl AuditSink for FailingAudit {
+        fn append(&self, _entry: AuditEntry) -> Result<AuditRecord, LoglineError> {
+            Err(LoglineError::Internal("disk full".to_string()))
+        }
+    }
+
+    #[test]
+    fn audit_failure_is_reported_beside_a_successful_run() {
+        let factory = MockConnectorFactory::new();
+        let runtime = LoglineRuntime::from_catalog_with_factory(
+            demo_catalog(),
+            &factory,
+            &MockSecretStore::new(),
+        )
+        .unwrap()
+        .with_audit(Box::new(FailingAudit), "ops");
+        factory
+            .backend("local-main")
+            .unwrap()
+            .push(Step::intent("export").run_id("run-export"));
+
+        let intent = Intent {
+            intent_type: "export".to_string(),
+            payload: std::collections::BTreeMap::new(),
+        };
+        let result = runtime.run_intent(intent).unwrap();
+        assert_eq!(result.run_id, "run-export");
+        assert!(result.recording_error.as_deref().is_some_and(|e| e.contains("disk full")));
+        assert_eq!(runtime.metrics().unwrap().recording_failures, 1);
+    }
+}
@@ -0,0 +1,177 @@
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use logline_api::LoglineError;
+
+/// Advisory cross-process lock: an exclusively created file, removed on drop.
+///
+/// A lock whose file is older than `stale_after` is assumed to belong to a
+/// process that died without cleaning up, and is reclaimed.
+pub struct LockFile {
+    path: PathBuf,
+    /// Written into the file so drop only removes a lock this handle owns.
+    token: String,
+}
+
+impl LockFile {
+    pub fn try_acquire(path: &Path, stale_after: Duration) -> Result<Option<Self>, LoglineError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                LoglineError::Internal(format!("failed to create {}: {e}", parent.display()))
+            })?;
+        }
+
+        for _ in 0..2 {
+            match OpenOptions::new().write(true).create_new(true).open(path) {
+                Ok(mut file) => {
+                    let token = unique_token();
+                    let _ = writeln!(file, "{token}");
+                    return Ok(Some(Self {
+                        path: path.to_path_buf(),
+                        token,
+                    }));
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    let Some(seen) = stale_mtime(path, stale_after) else {
+                        return Ok(None);
+                    };
+                    if !reclaim(path, seen) {
+                        return Ok(None);
+                    }
+                }
+                Err(e) => {
+                    return Err(LoglineError::Internal(format!(
+                        "failed to create lock {}: {e}",
+                        path.display()
+                    )));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn acquire(
+        path: &Path,
+        stale_after: Duration,
+        wait: Duration,
+    ) -> Result<Self, LoglineError> {
+        let deadline = Instant::now() + wait;
+        loop {
+            if let Some(lock) = Self::try_acquire(path, stale_after)? {
+                return Ok(lock);
+            }
+            if Instant::now() >= deadline {
+                return Err(LoglineError::Conflict(format!(
+                    "{} is held by another process",
+                    path.display()
+                )));
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+    }
+}
+
+impl Drop for LockFile {
+    fn drop(&mut self) {
+        // A holder that overran `stale_after` may have been reclaimed; leave
+        // the new owner's file alone.
+        let owned = fs::read_to_string(&self.path).is_ok_and(|s| s.trim() == self.token);
+        if owned {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+fn unique_token() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    format!("{} {nanos}", std::process::id())
+}
+
+/// The lock file's mtime, if it is older than `stale_after`.
+fn stale_mtime(path: &Path, stale_after: Duration) -> Option<SystemTime> {
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    let age = modified.elapsed().ok()?;
+    (age > stale_after).then_some(modified)
+}
+
+/// Move a stale lock out of the way. Renaming is atomic, so of several
+/// waiters that all saw it stale exactly one gets the file; if what it got is
+/// not the file it judged stale (another waiter already reclaimed it and a new
+/// holder took the lock), it is put back and the reclaim is abandoned.
+fn reclaim(path: &Path, seen: SystemTime) -> bool {
+    let mut aside = path.as_os_str().to_owned();
+    aside.push(format!(".stale-{}", unique_token().replace(' ', "-")));
+    let aside = PathBuf::from(aside);
+    if fs::rename(path, &aside).is_err() {
+        return false;
+    }
+    let same = fs::metadata(&aside)
+        .and_then(|m| m.modified())
+        .is_ok_and(|t| t == seen);
+    if !same {
+        // `hard_link` never overwrites, so a lock created meanwhile wins.
+        let _ = fs::hard_link(&aside, path);
+    }
+    let _ = fs::remove_file(&aside);
+    same
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_lock(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("logline-lockfile-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join("x.lock")
+    }
+
+    fn age(path: &Path, by: Duration) {
+        let file = OpenOptions::new().write(true).open(path).unwrap();
+        file.set_modified(SystemTime::now() - by).unwrap();
+    }
+
+    #[test]
+    fn a_live_lock_is_not_reclaimed() {
+        let path = temp_lock("live");
+        let held = LockFile::try_acquire(&path, Duration::from_secs(60)).unwrap();
+        assert!(held.is_some());
+        assert!(
+            LockFile::try_acquire(&path, Duration::from_secs(60))
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn a_reclaimed_holder_does_not_release_the_new_owner() {
+        let path = temp_lock("reclaim");
+        let slow = LockFile::try_acquire(&path, Duration::from_secs(60))
+            .unwrap()
+            .unwrap();
+        age(&path, Duration::from_secs(120));
+
+        let fresh = LockFile::try_acquire(&path, Duration::from_secs(60))
+            .unwrap()
+            .expect("stale lock is reclaimed");
+        drop(slow);
+        assert!(path.exists(), "the new owner still holds the lock");
+        assert!(
+            LockFile::try_acquire(&path, Duration::from_secs(60))
+                .unwrap()
+                .is_none()
+        );
+
+        drop(fresh);
+        assert!(!path.exists());
+        let leftovers = fs::read_dir(path.parent().unwrap()).unwrap().count();
+        assert_eq!(leftovers, 0, "no stale copies are left behind");
+    }
+}
@@ -0,0 +1,260 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::atomic::Ordering;
+
+use logline_api::{BackendId, LoglineError};
+use serde::Serialize;
+
+use crate::LoglineRuntime;
+use crate::registry::{RunRecord, RunSource};
+
+/// Upper bounds (ms) of the run latency histogram; `+Inf` is implied.
+pub const LATENCY_BUCKETS_MS: [u64; 12] = [
+    5, 10, 25, 50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000, 30_000,
+];
+
+/// Metric name, help text and how to read it off a series.
+type Counter = (&'static str, &'static str, fn(&RunSeries) -> u64);
+
+/// Counters and latency histogram for one backend and intent type.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunSeries {
+    pub backend: BackendId,
+    pub intent_type: String,
+    pub started: u64,
+    pub failed: u64,
+    pub retried: u64,
+    /// Per-bucket (not cumulative) counts for `LATENCY_BUCKETS_MS`, then `+Inf`.
+    pub latency_buckets: Vec<u64>,
+    pub latency_sum_ms: u64,
+    pub latency_count: u64,
+}
+
+impl RunSeries {
+    fn new(backend: &str, intent_type: &str) -> Self {
+        Self {
+            backend: backend.to_string(),
+            intent_type: intent_type.to_string(),
+            started: 0,
+            failed: 0,
+            retried: 0,
+            latency_buckets: vec![0; LATENCY_BUCKETS_MS.len() + 1],
+            latency_sum_ms: 0,
+            latency_count: 0,
+        }
+    }
+
+    fn observe(&mut self, record: &RunRecord) {
+        self.started += 1;
+        if record.status == "failed" {
+            self.failed += 1;
+        }
+        if record.attempt > 1 {
+            self.retried += 1;
+        }
+        if let Some(finished) = record.finished_at_ms {
+            let ms = u64::try_from(finished - record.started_at_ms).unwrap_or(0);
+            let bucket = LATENCY_BUCKETS_MS
+                .iter()
+                .position(|&le| ms <= le)
+                .unwrap_or(LATENCY_BUCKETS_MS.len());
+            self.latency_buckets[bucket] += 1;
+            self.latency_sum_ms += ms;
+            self.latency_count += 1;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricsSnapshot {
+    pub running_jobs: usize,
+    pub queue_depth: usize,
+    pub span_export_failures: u64,
+    pub recording_failures: u64,
+    pub series: Vec<RunSeries>,
+}
+
+impl MetricsSnapshot {
+    /// Prometheus text exposition format (version 0.0.4).
+    pub fn render_prometheus(&self) -> String {
+        let mut out = String::new();
+        let counters: [Counter; 3] = [
+            (
+                "logline_runs_started_total",
+                "Runs dispatched to a backend.",
+                |s| s.started,
+            ),
+            (
+                "logline_runs_failed_total",
+                "Runs that ended in failure.",
+                |s| s.failed,
+            ),
+            (
+                "logline_runs_retried_total",
+                "Runs that were a retry of an earlier attempt.",
+                |s| s.retried,
+            ),
+        ];
+        for (name, help, value) in counters {
+            let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} counter");
+            for s in &self.series {
+                let _ = writeln!(out, "{name}{{{}}} {}", labels(s), value(s));
+            }
+        }
+
+        let name = "logline_run_duration_seconds";
+        let _ = writeln!(
+            out,
+            "# HELP {name} Run latency from dispatch to completion.\n# TYPE {name} histogram"
+        );
+        for s in &self.series {
+            let mut cumulative = 0;
+            for (i, count) in s.latency_buckets.iter().enumerate() {
+                cumulative += count;
+                let le = LATENCY_BUCKETS_MS
+                    .get(i)
+                    .map_or_else(|| "+Inf".to_string(), |&ms| seconds(ms));
+                let _ = writeln!(
+                    out,
+                    "{name}_bucket{{{},le=\"{le}\"}} {cumulative}",
+                    labels(s)
+                );
+            }
+            let _ = writeln!(
+                out,
+                "{name}_sum{{{}}} {}",
+                labels(s),
+                seconds(s.latency_sum_ms)
+            );
+            let _ = writeln!(out, "{name}_count{{{}}} {}", labels(s), s.latency_count);
+        }
+
+        let gauges = [
+            (
+                "logline_running_jobs",
+                "Runs executing in this process.",
+                self.running_jobs,
+            ),
+            (
+                "logline_queue_depth",
+                "Runs waiting for a slot in this process.",
+                self.queue_depth,
+            ),
+        ];
+        for (name, help, value) in gauges {
+            let _ = writeln!(
+                out,
+                "# HELP {name} {help}\n# TYPE {name} gauge\n{name} {value}"
+            );
+        }
+        let name = "logline_span_export_failures_total";
+        let _ = writeln!(
+            out,
+            "# HELP {name} Span batches the exporter rejected.\n# TYPE {name} counter\n{name} {}",
+            self.span_export_failures
+        );
+        let name = "logline_recording_failures_total";
+        let _ = writeln!(
+            out,
+            "# HELP {name} Completed runs whose records could not be written.\n# TYPE {name} counter\n{name} {}",
+            self.recording_failures
+        );
+        out
+    }
+}
+
+impl LoglineRuntime {
+    /// Counters and latencies from the run registry plus this process's live gauges.
+    pub fn metrics(&self) -> Result<MetricsSnapshot, LoglineError> {
+        let mut series: BTreeMap<(&str, &str), RunSeries> = BTreeMap::new();
+        let runs = self.registry.list()?;
+        // Whole-workflow records wrap step runs that are already counted.
+        for run in runs
+            .iter()
+            .filter(|r| !matches!(r.source, RunSource::Workflow { step_id: None, .. }))
+        {
+            series
+                .entry((&run.backend, &run.intent_type))
+                .or_insert_with(|| RunSeries::new(&run.backend, &run.intent_type))
+                .observe(run);
+        }
+
+        let slots = self.slots.counts()?;
+        Ok(MetricsSnapshot {
+            running_jobs: slots.running,
+            queue_depth: slots.queued,
+            span_export_failures: self.span_export_failures.load(Ordering::Relaxed),
+            recording_failures: self.recording_failures.load(Ordering::Relaxed),
+            series: series.into_values().collect(),
+        })
+    }
+}
+
+fn labels(s: &RunSeries) -> String {
+    format!(
+        "backend=\"{}\",intent_type=\"{}\"",
+        escape_label(&s.backend),
+        escape_label(&s.intent_type)
+    )
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn seconds(ms: u64) -> String {
+    format!("{}.{:03}", ms / 1000, ms % 1000)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(run_id: &str, status: &str, attempt: u32, millis: i64) -> RunRecord {
+        RunRecord {
+            run_id: run_id.to_string(),
+            source: RunSource::Manual,
+            profile: "local".to_string(),
+            backend: "local-main".to_string(),
+            intent_type: "deploy".to_string(),
+            status: status.to_string(),
+            attempt,
+            started_at_ms: 1_000,
+            finished_at_ms: Some(1_000 + millis),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn renders_counters_and_cumulative_buckets() {
+        let mut series = RunSeries::new("local-main", "deploy");
+        series.observe(&record("a", "accepted", 1, 7));
+        series.observe(&record("b", "failed", 1, 40));
+        series.observe(&record("c", "accepted", 2, 60_000));
+        let snapshot = MetricsSnapshot {
+            running_jobs: 0,
+            queue_depth: 0,
+            span_export_failures: 0,
+            recording_failures: 0,
+            series: vec![series],
+        };
+
+        let text = snapshot.render_prometheus();
+        let labels = "backend=\"local-main\",intent_type=\"deploy\"";
+        for line in [
+            format!("logline_runs_started_total{{{labels}}} 3"),
+            format!("logline_runs_failed_total{{{labels}}} 1"),
+            format!("logline_runs_retried_total{{{labels}}} 1"),
+            format!("logline_run_duration_seconds_bucket{{{labels},le=\"0.010\"}} 1"),
+            format!("logline_run_duration_seconds_bucket{{{labels},le=\"0.050\"}} 2"),
+            format!("logline_run_duration_seconds_bucket{{{labels},le=\"30.000\"}} 2"),
+            format!("logline_run_duration_seconds_bucket{{{labels},le=\"+Inf\"}} 3"),
+            format!("logline_run_duration_seconds_sum{{{labels}}} 60.047"),
+        ] {
+            assert!(text.lines().any(|l| l == line), "missing {line}\n{text}");
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use std::time::Instant;
+
+use logline_api::{BackendId, ExecutionPlan, ExecutionResult, Intent, LoglineError, ProfileId};
+use serde::Serialize;
+
+use crate::registry::RunSource;
+use crate::{LoglineRuntime, payload_digest};
+
+/// Result of `run_or_plan`: executed, or downgraded to a plan on a readonly profile.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum IntentOutcome {
+    Executed(ExecutionResult),
+    Planned {
+        profile: ProfileId,
+        plan: ExecutionPlan,
+    },
+}
+
+impl LoglineRuntime {
+    /// Plan `intent` on `profile_id` without changing the active profile.
+    pub fn plan_intent_on(
+        &self,
+        profile_id: &str,
+        intent: &Intent,
+    ) -> Result<ExecutionPlan, LoglineError> {
+        let profile = self
+            .catalog
+            .profiles
+            .get(profile_id)
+            .ok_or_else(|| LoglineError::NotFound(format!("profile {profile_id} not found")))?;
+        self.plan_audited(profile_id.to_string(), &profile.backend_id, intent)
+    }
+
+    /// Run `intent` on the active profile, except that a mutating intent on a
+    /// readonly profile is planned instead of refused.
+    pub fn run_or_plan(&self, intent: &Intent) -> Result<IntentOutcome, LoglineError> {
+        let (profile_id, backend_id) = self.active_target()?;
+        self.run_or_plan_on(profile_id, backend_id, intent, RunSource::Manual)
+    }
+
+    pub(crate) fn run_or_plan_on(
+        &self,
+        profile_id: ProfileId,
+        backend_id: BackendId,
+        intent: &Intent,
+        source: RunSource,
+    ) -> Result<IntentOutcome, LoglineError> {
+        if self.is_readonly(&profile_id) {
+            let plan = self.plan_with(&backend_id, intent)?;
+            if plan.mutating {
+                let plan = self.plan_audited(profile_id.clone(), &backend_id, intent)?;
+                return Ok(IntentOutcome::Planned {
+                    profile: profile_id,
+                    plan,
+                });
+            }
+        }
+        self.dispatch(profile_id, backend_id, intent, source, 1)
+            .map(IntentOutcome::Executed)
+    }
+
+    pub(crate) fn plan_audited(
+        &self,
+        profile_id: ProfileId,
+        backend_id: &str,
+        intent: &Intent,
+    ) -> Result<ExecutionPlan, LoglineError> {
+        let mut entry = self.audit_entry(profile_id, backend_id.to_string(), "plan_intent");
+        entry.intent_type = Some(intent.intent_type.clone());
+        entry.payload_sha256 = payload_digest(&intent.payload);
+        let started = Instant::now();
+        let plan = self.plan_with(backend_id, intent);
+        self.record_audit(entry, started, plan.as_ref().map(|_| "planned"))?;
+        plan
+    }
+
+    pub(crate) fn plan_with(
+        &self,
+        backend_id: &str,
+        intent: &Intent,
+    ) -> Result<ExecutionPlan, LoglineError> {
+        let connector = self
+            .connectors
+            .get(backend_id)
+            .ok_or_else(|| LoglineError::NotFound(format!("backend {backend_id} not loaded")))?;
+        if !connector.capabilities().supports_plan {
+            return Err(LoglineError::Validation(format!(
+                "backend {backend_id} does not support planning"
+            )));
+        }
+        connector.plan(intent)
+    }
+
+    fn is_readonly(&self, profile_id: &str) -> bool {
+        self.catalog
+            .profiles
+            .get(profile_id)
+            .is_some_and(|p| p.readonly)
+    }
+
+    /// Refuse intents that may mutate a readonly profile's backend. Intents the
+    /// backend plans as non-mutating go through; unplannable ones are refused.
+    pub(crate) fn check_readonly(
+        &self,
+        profile_id: &str,
+        backend_id: &str,
+        intent: &Intent,
+    ) -> Result<(), LoglineError> {
+        if !self.is_readonly(profile_id) {
+            return Ok(());
+        }
+        match self.plan_with(backend_id, intent) {
+            Ok(plan) if !plan.mutating => Ok(()),
+            Ok(_) => Err(LoglineError::Conflict(format!(
+                "profile {profile_id} is readonly and intent {} would modify backend {backend_id}; plan it instead",
+                intent.intent_type
+            ))),
+            Err(e) => Err(LoglineError::Conflict(format!(
+                "profile {profile_id} is readonly and intent {} cannot be checked: {e}",
+                intent.intent_type
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use logline_api::RuntimeEngine;
+    use logline_core::demo_catalog;
+
+    use super::*;
+
+    fn intent(intent_type: &str) -> Intent {
+        Intent {
+            intent_type: intent_type.to_string(),
+            payload: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn readonly_profiles_refuse_or_downgrade_mutations() {
+        let mut catalog = demo_catalog();
+        catalog.profiles.get_mut("local").unwrap().readonly = true;
+        let runtime = LoglineRuntime::from_catalog(catalog).unwrap();
+
+        let err = runtime.run_intent(intent("deploy")).unwrap_err();
+        assert!(err.to_string().contains("readonly"));
+
+        match runtime.run_or_plan(&intent("deploy")).unwrap() {
+            IntentOutcome::Planned { plan, .. } => assert!(plan.mutating),
+            IntentOutcome::Executed(r) => panic!("executed {}", r.run_id),
+        }
+        assert!(matches!(
+            runtime.run_or_plan(&intent("list.runs")).unwrap(),
+            IntentOutcome::Executed(_)
+        ));
+        assert!(runtime.run_intent(intent("status")).is_ok());
+    }
+}
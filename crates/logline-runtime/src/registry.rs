@@ -0,0 +1,236 @@
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use logline_api::{BackendId, DomainEvent, EventCursor, LoglineError, ProfileId, RunId};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RunSource {
+    Manual,
+    Schedule {
+        schedule_id: String,
+        scheduled_for_unix: i64,
+    },
+    /// A workflow as a whole (`step_id = None`) or one of its step runs.
+    Workflow {
+        workflow_id: String,
+        workflow_run_id: RunId,
+        step_id: Option<String>,
+    },
+    /// One target of an intent broadcast to several profiles.
+    Broadcast {
+        broadcast_id: RunId,
+    },
+}
+
+impl RunSource {
+    /// Add `source` and the ids identifying it to an event or span attribute map.
+    pub(crate) fn insert_attributes(&self, attributes: &mut BTreeMap<String, String>) {
+        match self {
+            Self::Manual => {
+                attributes.insert("source".to_string(), "manual".to_string());
+            }
+            Self::Schedule {
+                schedule_id,
+                scheduled_for_unix,
+            } => {
+                attributes.insert("source".to_string(), "schedule".to_string());
+                attributes.insert("schedule_id".to_string(), schedule_id.clone());
+                attributes.insert(
+                    "scheduled_for_unix".to_string(),
+                    scheduled_for_unix.to_string(),
+                );
+            }
+            Self::Workflow {
+                workflow_id,
+                workflow_run_id,
+                step_id,
+            } => {
+                attributes.insert("source".to_string(), "workflow".to_string());
+                attributes.insert("workflow_id".to_string(), workflow_id.clone());
+                attributes.insert("workflow_run_id".to_string(), workflow_run_id.clone());
+                if let Some(step_id) = step_id {
+                    attributes.insert("step_id".to_string(), step_id.clone());
+                }
+            }
+            Self::Broadcast { broadcast_id } => {
+                attributes.insert("source".to_string(), "broadcast".to_string());
+                attributes.insert("broadcast_id".to_string(), broadcast_id.clone());
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub run_id: RunId,
+    pub source: RunSource,
+    pub profile: ProfileId,
+    pub backend: BackendId,
+    pub intent_type: String,
+    pub status: String,
+    /// 1 for the first try; workflow step retries count up from there.
+    #[serde(default = "first_attempt")]
+    pub attempt: u32,
+    pub started_at_ms: i64,
+    pub finished_at_ms: Option<i64>,
+    pub error: Option<String>,
+}
+
+/// Append-only record of runs issued through the runtime.
+///
+/// Every state change appends a full record; the latest line for a `run_id`
+/// is its current state, and each line doubles as a runtime event.
+pub struct RunRegistry {
+    path: Option<PathBuf>,
+    memory: Mutex<Vec<RunRecord>>,
+}
+
+impl RunRegistry {
+    pub fn persistent(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: Some(path.into()),
+            memory: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            memory: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn record(&self, record: &RunRecord) -> Result<(), LoglineError> {
+        let Some(path) = &self.path else {
+            self.memory
+                .lock()
+                .map_err(|_| LoglineError::Internal("run registry poisoned".to_string()))?
+                .push(record.clone());
+            return Ok(());
+        };
+
+        let mut line = serde_json::to_string(record)
+            .map_err(|e| LoglineError::Internal(format!("failed to encode run record: {e}")))?;
+        line.push('\n');
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                LoglineError::Internal(format!("failed to create {}: {e}", parent.display()))
+            })?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| {
+                LoglineError::Internal(format!("failed to open {}: {e}", path.display()))
+            })?;
+        file.write_all(line.as_bytes())
+            .map_err(|e| LoglineError::Internal(format!("failed to write {}: {e}", path.display())))
+    }
+
+    /// Current state of every run, newest first.
+    pub fn list(&self) -> Result<Vec<RunRecord>, LoglineError> {
+        let mut latest: BTreeMap<RunId, RunRecord> = BTreeMap::new();
+        for record in self.history()? {
+            latest.insert(record.run_id.clone(), record);
+        }
+        let mut runs: Vec<RunRecord> = latest.into_values().collect();
+        runs.sort_by_key(|r| std::cmp::Reverse(r.started_at_ms));
+        Ok(runs)
+    }
+
+    pub fn get(&self, run_id: &str) -> Result<Option<RunRecord>, LoglineError> {
+        Ok(self
+            .history()?
+            .into_iter()
+            .rev()
+            .find(|r| r.run_id == run_id))
+    }
+
+    /// Runtime events after `cursor`; cursors are line offsets into the registry.
+    pub fn events_since(
+        &self,
+        cursor: Option<&EventCursor>,
+    ) -> Result<Vec<DomainEvent>, LoglineError> {
+        let skip = match cursor {
+            Some(c) => c.parse::<usize>().map_err(|_| {
+                LoglineError::Validation(format!("invalid runtime event cursor '{c}'"))
+            })?,
+            None => 0,
+        };
+
+        Ok(self
+            .history()?
+            .into_iter()
+            .enumerate()
+            .skip(skip)
+            .map(|(i, r)| to_event(i + 1, r))
+            .collect())
+    }
+
+    fn history(&self) -> Result<Vec<RunRecord>, LoglineError> {
+        let Some(path) = &self.path else {
+            return Ok(self
+                .memory
+                .lock()
+                .map_err(|_| LoglineError::Internal("run registry poisoned".to_string()))?
+                .clone());
+        };
+
+        let content = match fs::read_to_string(path) {
+            Ok(c) => c,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(LoglineError::Internal(format!(
+                    "failed to read {}: {e}",
+                    path.display()
+                )));
+            }
+        };
+
+        content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(n, line)| {
+                serde_json::from_str(line).map_err(|e| {
+                    LoglineError::Validation(format!("{}:{}: {e}", path.display(), n + 1))
+                })
+            })
+            .collect()
+    }
+}
+
+fn first_attempt() -> u32 {
+    1
+}
+
+fn to_event(cursor: usize, record: RunRecord) -> DomainEvent {
+    let mut attributes = BTreeMap::from([
+        ("profile".to_string(), record.profile),
+        ("backend".to_string(), record.backend),
+        ("intent_type".to_string(), record.intent_type),
+    ]);
+    record.source.insert_attributes(&mut attributes);
+    if let Some(error) = record.error {
+        attributes.insert("error".to_string(), error);
+    }
+
+    DomainEvent {
+        cursor: cursor.to_string(),
+        ts_unix_ms: record.finished_at_ms.unwrap_or(record.started_at_ms),
+        kind: format!("run.{}", record.status),
+        run_id: Some(record.run_id),
+        attributes,
+    }
+}
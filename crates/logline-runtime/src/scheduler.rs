@@ -0,0 +1,444 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use logline_api::{Intent, LoglineError, RunId};
+use logline_core::{CronExpr, MissedRunPolicy, ScheduleSpec, ScheduleTrigger, validate_schedule};
+use serde::{Deserialize, Serialize};
+
+use crate::LoglineRuntime;
+use crate::lockfile::LockFile;
+use crate::registry::RunSource;
+
+/// How long the schedules file lock may be held before it is considered abandoned.
+const STORE_LOCK_STALE: Duration = Duration::from_secs(30);
+const STORE_LOCK_WAIT: Duration = Duration::from_secs(5);
+/// A run lock older than this is treated as left behind by a crashed scheduler.
+const RUN_LOCK_STALE: Duration = Duration::from_secs(6 * 3_600);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleEntry {
+    #[serde(flatten)]
+    pub spec: ScheduleSpec,
+    pub next_due_unix: i64,
+    pub last_fired_unix: Option<i64>,
+    pub last_run_id: Option<RunId>,
+    pub last_status: Option<String>,
+}
+
+/// Schedule definitions and their firing state, persisted as JSON.
+#[derive(Debug, Clone)]
+pub struct ScheduleStore {
+    path: PathBuf,
+}
+
+impl ScheduleStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn list(&self) -> Result<Vec<ScheduleEntry>, LoglineError> {
+        self.load()
+    }
+
+    pub fn add(&self, spec: ScheduleSpec, now_unix: i64) -> Result<ScheduleEntry, LoglineError> {
+        validate_schedule(&spec)?;
+        let next_due_unix = spec.next_fire_after(now_unix)?;
+        self.update(|entries| {
+            if entries.iter().any(|e| e.spec.id == spec.id) {
+                return Err(LoglineError::Conflict(format!(
+                    "schedule {} already exists",
+                    spec.id
+                )));
+            }
+            let entry = ScheduleEntry {
+                spec,
+                next_due_unix,
+                last_fired_unix: None,
+                last_run_id: None,
+                last_status: None,
+            };
+            entries.push(entry.clone());
+            Ok(entry)
+        })
+    }
+
+    pub fn remove(&self, id: &str) -> Result<(), LoglineError> {
+        self.update(|entries| {
+            let before = entries.len();
+            entries.retain(|e| e.spec.id != id);
+            if entries.len() == before {
+                return Err(LoglineError::NotFound(format!("schedule {id} not found")));
+            }
+            Ok(())
+        })
+    }
+
+    /// Pause or resume. Resuming restarts from the next slot after `now_unix`;
+    /// slots that fell inside the pause are never caught up.
+    pub fn set_paused(
+        &self,
+        id: &str,
+        paused: bool,
+        now_unix: i64,
+    ) -> Result<ScheduleEntry, LoglineError> {
+        self.update(|entries| {
+            let entry = entries
+                .iter_mut()
+                .find(|e| e.spec.id == id)
+                .ok_or_else(|| LoglineError::NotFound(format!("schedule {id} not found")))?;
+            if entry.spec.paused && !paused {
+                entry.next_due_unix = entry.spec.next_fire_after(now_unix)?;
+            }
+            entry.spec.paused = paused;
+            Ok(entry.clone())
+        })
+    }
+
+    fn update<T>(
+        &self,
+        f: impl FnOnce(&mut Vec<ScheduleEntry>) -> Result<T, LoglineError>,
+    ) -> Result<T, LoglineError> {
+        let _lock = LockFile::acquire(
+            &self.path.with_extension("lock"),
+            STORE_LOCK_STALE,
+            STORE_LOCK_WAIT,
+        )?;
+        let mut entries = self.load()?;
+        let out = f(&mut entries)?;
+        self.save(&entries)?;
+        Ok(out)
+    }
+
+    fn load(&self) -> Result<Vec<ScheduleEntry>, LoglineError> {
+        match fs::read_to_string(&self.path) {
+            Ok(content) => serde_json::from_str(&content).map_err(|e| {
+                LoglineError::Validation(format!("invalid JSON in {}: {e}", self.path.display()))
+            }),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(LoglineError::Internal(format!(
+                "failed to read {}: {e}",
+                self.path.display()
+            ))),
+        }
+    }
+
+    fn save(&self, entries: &[ScheduleEntry]) -> Result<(), LoglineError> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                LoglineError::Internal(format!("failed to create {}: {e}", parent.display()))
+            })?;
+        }
+        let body = serde_json::to_string_pretty(entries)
+            .map_err(|e| LoglineError::Internal(format!("failed to encode schedules: {e}")))?;
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, body)
+            .and_then(|()| fs::rename(&tmp, &self.path))
+            .map_err(|e| {
+                LoglineError::Internal(format!("failed to write {}: {e}", self.path.display()))
+            })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DispatchedSlots {
+    pub schedule_id: String,
+    pub scheduled_for_unix: Vec<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedSlots {
+    pub schedule_id: String,
+    pub reason: String,
+    pub count: usize,
+    pub first_unix: i64,
+    pub last_unix: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TickReport {
+    pub dispatched: Vec<DispatchedSlots>,
+    pub skipped: Vec<SkippedSlots>,
+}
+
+/// Fires due schedules against a runtime.
+///
+/// There is no background service: a foreground driver (`logline schedule run`)
+/// calls [`Scheduler::tick`] periodically. Each due schedule runs on its own
+/// worker thread; unless `allow_overlap` is set, a schedule whose previous
+/// run is still in flight (in this process or another one) is skipped.
+pub struct Scheduler {
+    store: ScheduleStore,
+    lock_dir: PathBuf,
+    workers: Mutex<Vec<JoinHandle<()>>>,
+}
+
+struct Dispatch {
+    spec: ScheduleSpec,
+    slots: Vec<i64>,
+    lock: Option<LockFile>,
+}
+
+impl Scheduler {
+    pub fn new(store: ScheduleStore, lock_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            store,
+            lock_dir: lock_dir.into(),
+            workers: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn tick(
+        &self,
+        runtime: &Arc<LoglineRuntime>,
+        now_unix: i64,
+    ) -> Result<TickReport, LoglineError> {
+        let mut report = TickReport::default();
+        let dispatches = self.store.update(|entries| {
+            let mut dispatches = Vec::new();
+            for entry in entries.iter_mut() {
+                if entry.spec.paused || entry.next_due_unix > now_unix {
+                    continue;
+                }
+                let (slots, next_due) = match due_slots(entry, now_unix, &mut report) {
+                    Ok(due) => due,
+                    Err(e) => {
+                        report.skipped.push(skipped(
+                            &entry.spec.id,
+                            &e.to_string(),
+                            &[entry.next_due_unix],
+                        ));
+                        continue;
+                    }
+                };
+                entry.next_due_unix = next_due;
+                if slots.is_empty() {
+                    continue;
+                }
+
+                let lock = if entry.spec.allow_overlap {
+                    None
+                } else {
+                    let lock =
+                        LockFile::try_acquire(&self.run_lock_path(&entry.spec.id), RUN_LOCK_STALE)?;
+                    if lock.is_none() {
+                        report.skipped.push(skipped(
+                            &entry.spec.id,
+                            "previous run still in flight",
+                            &slots,
+                        ));
+                        continue;
+                    }
+                    lock
+                };
+
+                entry.last_fired_unix = slots.last().copied();
+                report.dispatched.push(DispatchedSlots {
+                    schedule_id: entry.spec.id.clone(),
+                    scheduled_for_unix: slots.clone(),
+                });
+                dispatches.push(Dispatch {
+                    spec: entry.spec.clone(),
+                    slots,
+                    lock,
+                });
+            }
+            Ok(dispatches)
+        })?;
+
+        let mut workers = self
+            .workers
+            .lock()
+            .map_err(|_| LoglineError::Internal("scheduler state poisoned".to_string()))?;
+        workers.retain(|h| !h.is_finished());
+        for dispatch in dispatches {
+            let runtime = Arc::clone(runtime);
+            let store = self.store.clone();
+            workers.push(thread::spawn(move || {
+                fire(&runtime, &store, &dispatch);
+                drop(dispatch.lock);
+            }));
+        }
+        Ok(report)
+    }
+
+    /// Block until every dispatched run has finished.
+    pub fn wait(&self) {
+        let workers = match self.workers.lock() {
+            Ok(mut guard) => std::mem::take(&mut *guard),
+            Err(_) => return,
+        };
+        for handle in workers {
+            let _ = handle.join();
+        }
+    }
+
+    fn run_lock_path(&self, schedule_id: &str) -> PathBuf {
+        self.lock_dir.join(format!("{schedule_id}.lock"))
+    }
+}
+
+/// Slots to fire now, per the missed-run policy, and the next due time.
+fn due_slots(
+    entry: &ScheduleEntry,
+    now_unix: i64,
+    report: &mut TickReport,
+) -> Result<(Vec<i64>, i64), LoglineError> {
+    let spec = &entry.spec;
+    let keep = match spec.missed_runs {
+        MissedRunPolicy::Skip => 1,
+        MissedRunPolicy::CatchUp => spec.max_catch_up,
+    };
+    // Parse once: a long outage can leave many slots to walk through.
+    let cron = match &spec.trigger {
+        ScheduleTrigger::Cron { expr } => Some(CronExpr::parse(expr)?),
+        ScheduleTrigger::Interval { .. } => None,
+    };
+    let next = |after: i64| match &cron {
+        Some(c) => c.next_after(after).ok_or_else(|| {
+            LoglineError::Validation(format!("schedule {} never fires again", spec.id))
+        }),
+        None => spec.next_fire_after(after),
+    };
+
+    let mut recent: VecDeque<i64> = VecDeque::with_capacity(keep + 1);
+    let mut dropped: Vec<i64> = Vec::new();
+    let mut slot = entry.next_due_unix;
+    while slot <= now_unix {
+        recent.push_back(slot);
+        if recent.len() > keep {
+            dropped.extend(recent.pop_front());
+        }
+        slot = next(slot)?;
+    }
+    let mut slots: Vec<i64> = recent.into();
+
+    if spec.missed_runs == MissedRunPolicy::Skip {
+        let grace = i64::try_from(spec.misfire_grace_seconds).unwrap_or(i64::MAX);
+        if slots
+            .last()
+            .is_some_and(|&latest| now_unix - latest > grace)
+        {
+            dropped.append(&mut slots);
+        }
+    }
+    if !dropped.is_empty() {
+        let reason = match spec.missed_runs {
+            MissedRunPolicy::Skip => "missed (policy: skip)".to_string(),
+            MissedRunPolicy::CatchUp => format!("beyond catch-up limit of {}", spec.max_catch_up),
+        };
+        report.skipped.push(skipped(&spec.id, &reason, &dropped));
+    }
+    Ok((slots, slot))
+}
+
+fn skipped(schedule_id: &str, reason: &str, slots: &[i64]) -> SkippedSlots {
+    SkippedSlots {
+        schedule_id: schedule_id.to_string(),
+        reason: reason.to_string(),
+        count: slots.len(),
+        first_unix: slots.first().copied().unwrap_or_default(),
+        last_unix: slots.last().copied().unwrap_or_default(),
+    }
+}
+
+fn fire(runtime: &LoglineRuntime, store: &ScheduleStore, dispatch: &Dispatch) {
+    let spec = &dispatch.spec;
+    for &slot in &dispatch.slots {
+        let intent = render_intent(&spec.intent, &spec.id, slot);
+        let source = RunSource::Schedule {
+            schedule_id: spec.id.clone(),
+            scheduled_for_unix: slot,
+        };
+        let (run_id, status) = match runtime.run_intent_on(&spec.profile, &intent, source) {
+            Ok(r) => (Some(r.run_id), r.status),
+            Err(e) => (None, format!("error: {e}")),
+        };
+        let _ = store.update(|entries| {
+            if let Some(entry) = entries.iter_mut().find(|e| e.spec.id == spec.id) {
+                entry.last_run_id = run_id;
+                entry.last_status = Some(status);
+            }
+            Ok(())
+        });
+    }
+}
+
+/// Substitute `{{schedule_id}}` and `{{scheduled_for}}` (unix seconds) in payload values.
+fn render_intent(template: &Intent, schedule_id: &str, scheduled_for_unix: i64) -> Intent {
+    let scheduled_for = scheduled_for_unix.to_string();
+    Intent {
+        intent_type: template.intent_type.clone(),
+        payload: template
+            .payload
+            .iter()
+            .map(|(k, v)| {
+                let value = v
+                    .replace("{{schedule_id}}", schedule_id)
+                    .replace("{{scheduled_for}}", &scheduled_for);
+                (k.clone(), value)
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    fn entry(missed_runs: MissedRunPolicy) -> ScheduleEntry {
+        ScheduleEntry {
+            spec: ScheduleSpec {
+                id: "nightly".to_string(),
+                trigger: ScheduleTrigger::Interval { every_seconds: 600 },
+                intent: Intent {
+                    intent_type: "vacuum".to_string(),
+                    payload: BTreeMap::new(),
+                },
+                profile: "local".to_string(),
+                missed_runs,
+                allow_overlap: false,
+                paused: false,
+                misfire_grace_seconds: 60,
+                max_catch_up: 3,
+            },
+            next_due_unix: 1_000,
+            last_fired_unix: None,
+            last_run_id: None,
+            last_status: None,
+        }
+    }
+
+    #[test]
+    fn missed_slots_follow_policy() {
+        // Slots at 1000, 1600, ..., 4600 are all due at 4630.
+        let mut report = TickReport::default();
+        let (slots, next) =
+            due_slots(&entry(MissedRunPolicy::CatchUp), 4_630, &mut report).unwrap();
+        assert_eq!(slots, vec![3_400, 4_000, 4_600]);
+        assert_eq!(next, 5_200);
+        assert_eq!(report.skipped[0].count, 4);
+
+        let mut report = TickReport::default();
+        let (slots, _) = due_slots(&entry(MissedRunPolicy::Skip), 4_630, &mut report).unwrap();
+        assert_eq!(slots, vec![4_600]);
+        assert_eq!(report.skipped[0].count, 6);
+
+        // Latest slot is beyond the misfire grace window: nothing fires.
+        let mut report = TickReport::default();
+        let (slots, next) = due_slots(&entry(MissedRunPolicy::Skip), 4_700, &mut report).unwrap();
+        assert!(slots.is_empty());
+        assert_eq!(next, 5_200);
+        assert_eq!(report.skipped[0].count, 7);
+    }
+}
@@ -0,0 +1,71 @@
+use std::sync::{Condvar, Mutex};
+
+use logline_api::LoglineError;
+use logline_core::RuntimePolicy;
+
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct SlotCounts {
+    pub running: usize,
+    pub queued: usize,
+}
+
+/// Enforces `max_concurrent_runs`; callers beyond it wait in a queue bounded
+/// by `default_queue_capacity` and are rejected once it is full.
+pub(crate) struct RunSlots {
+    counts: Mutex<SlotCounts>,
+    freed: Condvar,
+    max_running: usize,
+    queue_capacity: usize,
+}
+
+impl RunSlots {
+    pub(crate) fn new(policy: &RuntimePolicy) -> Self {
+        Self {
+            counts: Mutex::new(SlotCounts::default()),
+            freed: Condvar::new(),
+            max_running: policy.max_concurrent_runs.max(1),
+            queue_capacity: policy.default_queue_capacity,
+        }
+    }
+
+    pub(crate) fn acquire(&self) -> Result<SlotGuard<'_>, LoglineError> {
+        let poisoned = || LoglineError::Internal("run slots poisoned".to_string());
+        let mut counts = self.counts.lock().map_err(|_| poisoned())?;
+        if counts.running >= self.max_running {
+            if counts.queued >= self.queue_capacity {
+                return Err(LoglineError::Conflict(format!(
+                    "run queue full ({} running, {} queued)",
+                    counts.running, counts.queued
+                )));
+            }
+            counts.queued += 1;
+            counts = self
+                .freed
+                .wait_while(counts, |c| c.running >= self.max_running)
+                .map_err(|_| poisoned())?;
+            counts.queued -= 1;
+        }
+        counts.running += 1;
+        Ok(SlotGuard { slots: self })
+    }
+
+    pub(crate) fn counts(&self) -> Result<SlotCounts, LoglineError> {
+        self.counts
+            .lock()
+            .map(|c| *c)
+            .map_err(|_| LoglineError::Internal("run slots poisoned".to_string()))
+    }
+}
+
+pub(crate) struct SlotGuard<'a> {
+    slots: &'a RunSlots,
+}
+
+impl Drop for SlotGuard<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut counts) = self.slots.counts.lock() {
+            counts.running = counts.running.saturating_sub(1);
+        }
+        self.slots.freed.notify_one();
+    }
+}
@@ -0,0 +1,222 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use logline_api::LoglineError;
+use serde::Serialize;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+
+use crate::LoglineRuntime;
+use crate::registry::RunRecord;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpanKind {
+    Internal,
+    Client,
+}
+
+/// A finished span; `otlp_json` turns a batch into an OTLP/HTTP JSON request body.
+#[derive(Debug, Clone, Serialize)]
+pub struct Span {
+    pub trace_id: String,
+    pub span_id: String,
+    pub parent_span_id: Option<String>,
+    pub name: String,
+    pub kind: SpanKind,
+    pub start_unix_nanos: u64,
+    pub end_unix_nanos: u64,
+    pub attributes: BTreeMap<String, String>,
+    pub error: Option<String>,
+}
+
+pub trait SpanExporter: Send + Sync {
+    fn export(&self, spans: &[Span]) -> Result<(), LoglineError>;
+}
+
+/// Appends one OTLP JSON request per line, ready to replay into a collector.
+pub struct FileSpanExporter {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl FileSpanExporter {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+}
+
+impl SpanExporter for FileSpanExporter {
+    fn export(&self, spans: &[Span]) -> Result<(), LoglineError> {
+        let mut line = otlp_json(spans).to_string();
+        line.push('\n');
+
+        let _guard = self
+            .lock
+            .lock()
+            .map_err(|_| LoglineError::Internal("span exporter poisoned".to_string()))?;
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                LoglineError::Internal(format!("failed to create {}: {e}", parent.display()))
+            })?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut f| f.write_all(line.as_bytes()))
+            .map_err(|e| {
+                LoglineError::Internal(format!("failed to write {}: {e}", self.path.display()))
+            })
+    }
+}
+
+/// `ExportTraceServiceRequest` in the OTLP/JSON encoding.
+pub fn otlp_json(spans: &[Span]) -> Value {
+    let spans: Vec<Value> = spans
+        .iter()
+        .map(|s| {
+            let mut span = json!({
+                "traceId": s.trace_id,
+                "spanId": s.span_id,
+                "name": s.name,
+                "kind": match s.kind {
+                    SpanKind::Internal => 1,
+                    SpanKind::Client => 3,
+                },
+                "startTimeUnixNano": s.start_unix_nanos.to_string(),
+                "endTimeUnixNano": s.end_unix_nanos.to_string(),
+                "attributes": attributes(&s.attributes),
+                "status": match &s.error {
+                    None => json!({"code": 1}),
+                    Some(message) => json!({"code": 2, "message": message}),
+                },
+            });
+            if let Some(parent) = &s.parent_span_id {
+                span["parentSpanId"] = json!(parent);
+            }
+            span
+        })
+        .collect();
+
+    json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": attributes(&BTreeMap::from([(
+                    "service.name".to_string(),
+                    "logline".to_string(),
+                )])),
+            },
+            "scopeSpans": [{
+                "scope": {"name": "logline-runtime", "version": env!("CARGO_PKG_VERSION")},
+                "spans": spans,
+            }],
+        }],
+    })
+}
+
+fn attributes(map: &BTreeMap<String, String>) -> Vec<Value> {
+    map.iter()
+        .map(|(k, v)| json!({"key": k, "value": {"stringValue": v}}))
+        .collect()
+}
+
+/// Start/end of the connector call inside a run, in unix nanoseconds.
+pub(crate) type CallWindow = Option<(u64, u64)>;
+
+impl LoglineRuntime {
+    /// Export the lifecycle span of a finished run and its connector call.
+    /// Export failures are counted in metrics rather than failing the run.
+    pub(crate) fn export_run_spans(
+        &self,
+        record: &RunRecord,
+        started_unix_nanos: u64,
+        call: CallWindow,
+    ) {
+        let Some(exporter) = &self.spans else {
+            return;
+        };
+
+        let trace_id = random_hex(16);
+        let run_span_id = random_hex(8);
+        let mut attrs = BTreeMap::from([
+            ("logline.profile".to_string(), record.profile.clone()),
+            ("logline.backend".to_string(), record.backend.clone()),
+            (
+                "logline.intent_type".to_string(),
+                record.intent_type.clone(),
+            ),
+            ("logline.run_id".to_string(), record.run_id.clone()),
+            ("logline.status".to_string(), record.status.clone()),
+            ("logline.attempt".to_string(), record.attempt.to_string()),
+        ]);
+        let mut source = BTreeMap::new();
+        record.source.insert_attributes(&mut source);
+        attrs.extend(source.into_iter().map(|(k, v)| (format!("logline.{k}"), v)));
+
+        let mut spans = vec![Span {
+            trace_id: trace_id.clone(),
+            span_id: run_span_id.clone(),
+            parent_span_id: None,
+            name: format!("run {}", record.intent_type),
+            kind: SpanKind::Internal,
+            start_unix_nanos: started_unix_nanos,
+            end_unix_nanos: now_unix_nanos(),
+            attributes: attrs,
+            error: record.error.clone(),
+        }];
+        if let Some((start, end)) = call {
+            spans.push(Span {
+                trace_id,
+                span_id: random_hex(8),
+                parent_span_id: Some(run_span_id),
+                name: "connector.execute".to_string(),
+                kind: SpanKind::Client,
+                start_unix_nanos: start,
+                end_unix_nanos: end,
+                attributes: BTreeMap::from([
+                    ("logline.backend".to_string(), record.backend.clone()),
+                    (
+                        "logline.intent_type".to_string(),
+                        record.intent_type.clone(),
+                    ),
+                ]),
+                error: record.error.clone(),
+            });
+        }
+
+        if exporter.export(&spans).is_err() {
+            self.span_export_failures.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+pub(crate) fn now_unix_nanos() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| u64::try_from(d.as_nanos()).unwrap_or(u64::MAX))
+}
+
+/// Unique enough for trace and span ids: hashed time, process id and a counter.
+fn random_hex(bytes: usize) -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let mut hasher = Sha256::new();
+    hasher.update(now_unix_nanos().to_le_bytes());
+    hasher.update(std::process::id().to_le_bytes());
+    hasher.update(COUNTER.fetch_add(1, Ordering::Relaxed).to_le_bytes());
+    hasher.finalize()[..bytes]
+        .iter()
+        .fold(String::with_capacity(bytes * 2), |mut out, b| {
+            let _ = write!(out, "{b:02x}");
+            out
+        })
+}
@@ -0,0 +1,643 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use logline_api::{BackendId, Intent, LoglineError, ProfileId, RunId};
+use logline_core::{
+    StepFailurePolicy, StepRef, StepRefField, StepTarget, WorkflowSpec, WorkflowStep,
+    render_step_payload, validate_workflow,
+};
+use serde::Serialize;
+
+use crate::registry::{RunRecord, RunSource};
+use crate::{LoglineRuntime, now_unix_ms};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Succeeded,
+    Failed,
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StepRun {
+    pub step_id: String,
+    pub target: String,
+    pub status: StepStatus,
+    pub run_id: Option<RunId>,
+    pub attempts: u32,
+    pub output: BTreeMap<String, String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkflowRun {
+    pub workflow_run_id: RunId,
+    pub workflow_id: String,
+    pub status: String,
+    pub started_at_ms: i64,
+    pub finished_at_ms: i64,
+    pub steps: Vec<StepRun>,
+}
+
+enum StepState {
+    Pending,
+    Running { remaining: usize },
+    Done { blocks_dependents: bool },
+}
+
+struct Job {
+    step_index: usize,
+    target_index: usize,
+    target: StepTarget,
+    intent: Intent,
+}
+
+impl LoglineRuntime {
+    /// Execute `spec` as a DAG: a step starts once everything it depends on has
+    /// finished, fan-out targets run in parallel, and at most
+    /// `max_concurrent_runs` step runs are in flight at once.
+    pub fn run_workflow(&self, spec: &WorkflowSpec) -> Result<WorkflowRun, LoglineError> {
+        let order = validate_workflow(spec, &self.catalog)?;
+        let index: BTreeMap<&str, usize> = spec
+            .steps
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (s.id.as_str(), i))
+            .collect();
+        let order: Vec<usize> = order.iter().map(|id| index[id.as_str()]).collect();
+
+        let started_at_ms = now_unix_ms();
+        let workflow_run_id = format!("wf-{}-{started_at_ms}", spec.id);
+        let mut record = self.workflow_record(spec, &workflow_run_id, started_at_ms)?;
+        self.registry.record(&record)?;
+
+        let mut step_states: Vec<StepState> =
+            spec.steps.iter().map(|_| StepState::Pending).collect();
+        let mut results: Vec<Vec<Option<StepRun>>> = spec
+            .steps
+            .iter()
+            .map(|s| vec![None; s.targets().len()])
+            .collect();
+        let mut aborted: Option<String> = None;
+        let max_in_flight = self.policy.max_concurrent_runs.max(1);
+
+        thread::scope(|scope| {
+            let (tx, rx) = mpsc::channel::<(usize, usize, StepRun)>();
+            let mut queue: VecDeque<Job> = VecDeque::new();
+            let mut in_flight = 0usize;
+
+            loop {
+                if aborted.is_none() {
+                    aborted = release_ready_steps(
+                        spec,
+                        &order,
+                        &index,
+                        &mut step_states,
+                        &mut results,
+                        &mut queue,
+                    );
+                    if aborted.is_some() {
+                        queue.clear();
+                    }
+                }
+                while in_flight < max_in_flight {
+                    let Some(job) = queue.pop_front() else { break };
+                    let step = &spec.steps[job.step_index];
+                    let source = RunSource::Workflow {
+                        workflow_id: spec.id.clone(),
+                        workflow_run_id: workflow_run_id.clone(),
+                        step_id: Some(step.id.clone()),
+                    };
+                    let tx = tx.clone();
+                    in_flight += 1;
+                    scope.spawn(move || {
+                        let run = self.run_step_target(step, &job.target, &job.intent, &source);
+                        let _ = tx.send((job.step_index, job.target_index, run));
+                    });
+                }
+                if in_flight == 0 {
+                    break;
+                }
+
+                let Ok((step_index, target_index, run)) = rx.recv() else {
+                    break;
+                };
+                in_flight -= 1;
+                if let Some(reason) = complete_target(
+                    spec,
+                    &mut step_states,
+                    &mut results,
+                    step_index,
+                    target_index,
+                    run,
+                ) {
+                    aborted = Some(reason);
+                    queue.clear();
+                }
+            }
+        });
+
+        let (status, error) = summarize(spec, &mut results, aborted);
+
+        let finished_at_ms = now_unix_ms();
+        record.status = status.to_string();
+        record.finished_at_ms = Some(finished_at_ms);
+        record.error = error;
+        self.registry.record(&record)?;
+
+        Ok(WorkflowRun {
+            workflow_run_id,
+            workflow_id: spec.id.clone(),
+            status: status.to_string(),
+            started_at_ms,
+            finished_at_ms,
+            steps: order
+                .iter()
+                .flat_map(|&i| results[i].iter().flatten().cloned())
+                .collect(),
+        })
+    }
+
+    /// The registry entry that represents the workflow run as a whole.
+    fn workflow_record(
+        &self,
+        spec: &WorkflowSpec,
+        workflow_run_id: &str,
+        started_at_ms: i64,
+    ) -> Result<RunRecord, LoglineError> {
+        let (profile, backend) = self.active_target()?;
+        Ok(RunRecord {
+            run_id: workflow_run_id.to_string(),
+            source: RunSource::Workflow {
+                workflow_id: spec.id.clone(),
+                workflow_run_id: workflow_run_id.to_string(),
+                step_id: None,
+            },
+            profile,
+            backend,
+            intent_type: format!("workflow:{}", spec.id),
+            status: "running".to_string(),
+            attempt: 1,
+            started_at_ms,
+            finished_at_ms: None,
+            error: None,
+        })
+    }
+
+    fn run_step_target(
+        &self,
+        step: &WorkflowStep,
+        target: &StepTarget,
+        intent: &Intent,
+        source: &RunSource,
+    ) -> StepRun {
+        let mut run = StepRun {
+            step_id: step.id.clone(),
+            target: target.label(),
+            status: StepStatus::Failed,
+            run_id: None,
+            attempts: 0,
+            output: BTreeMap::new(),
+            error: None,
+        };
+
+        let resolved = self.resolve_target(target);
+        let (profile, backend) = match resolved {
+            Ok(t) => t,
+            Err(e) => {
+                run.error = Some(e.to_string());
+                return run;
+            }
+        };
+
+        for attempt in 0..=step.retries {
+            if attempt > 0 {
+                thread::sleep(Duration::from_millis(250 * u64::from(attempt)));
+            }
+            run.attempts = attempt + 1;
+            match self.dispatch(
+                profile.clone(),
+                backend.clone(),
+                intent,
+                source.clone(),
+                run.attempts,
+            ) {
+                Ok(result) => {
+                    run.status = StepStatus::Succeeded;
+                    run.run_id = Some(result.run_id);
+                    run.output = result.output;
+                    run.error = None;
+                    break;
+                }
+                Err(e) => {
+                    let retryable = matches!(e, LoglineError::Connection(_))
+                        && self.is_read_only_intent(&backend, intent);
+                    run.error = Some(e.to_string());
+                    if !retryable {
+                        break;
+                    }
+                }
+            }
+        }
+        run
+    }
+
+    /// Only intents the backend plans as non-mutating are retried: a connection
+    /// error (a timeout included) may come after the backend already acted.
+    fn is_read_only_intent(&self, backend_id: &str, intent: &Intent) -> bool {
+        self.plan_with(backend_id, intent)
+            .is_ok_and(|plan| !plan.mutating)
+    }
+
+    fn resolve_target(&self, target: &StepTarget) -> Result<(ProfileId, BackendId), LoglineError> {
+        match target {
+            StepTarget::Active => self.active_target(),
+            StepTarget::Profile(id) => {
+                let profile = self
+                    .catalog
+                    .profiles
+                    .get(id)
+                    .ok_or_else(|| LoglineError::NotFound(format!("profile {id} not found")))?;
+                Ok((id.clone(), profile.backend_id.clone()))
+            }
+            // Runs under a profile that owns the backend, so its readonly flag applies;
+            // when several do, a readonly one wins.
+            StepTarget::Backend(id) => self
+                .catalog
+                .profiles
+                .iter()
+                .filter(|(_, p)| &p.backend_id == id)
+                .max_by_key(|(_, p)| p.readonly)
+                .map(|(profile, _)| (profile.clone(), id.clone()))
+                .ok_or_else(|| {
+                    LoglineError::NotFound(format!(
+                        "no profile uses backend {id}; target a profile instead"
+                    ))
+                }),
+        }
+    }
+}
+
+/// Record one finished target; returns an abort reason when its step fails under `abort`.
+fn complete_target(
+    spec: &WorkflowSpec,
+    step_states: &mut [StepState],
+    results: &mut [Vec<Option<StepRun>>],
+    step_index: usize,
+    target_index: usize,
+    run: StepRun,
+) -> Option<String> {
+    results[step_index][target_index] = Some(run);
+    if let StepState::Running { remaining } = &mut step_states[step_index] {
+        *remaining -= 1;
+        if *remaining > 0 {
+            return None;
+        }
+    }
+
+    let step = &spec.steps[step_index];
+    let any_failed = results[step_index]
+        .iter()
+        .flatten()
+        .any(|r| r.status != StepStatus::Succeeded);
+    step_states[step_index] = StepState::Done {
+        blocks_dependents: any_failed && step.on_failure != StepFailurePolicy::Continue,
+    };
+    (any_failed && step.on_failure == StepFailurePolicy::Abort)
+        .then(|| format!("step {} failed", step.id))
+}
+
+/// Fill in never-started targets as skipped and derive the workflow status.
+fn summarize(
+    spec: &WorkflowSpec,
+    results: &mut [Vec<Option<StepRun>>],
+    aborted: Option<String>,
+) -> (&'static str, Option<String>) {
+    for (i, step) in spec.steps.iter().enumerate() {
+        for (t, target) in step.targets().iter().enumerate() {
+            if results[i][t].is_none() {
+                let reason = aborted
+                    .clone()
+                    .map_or_else(|| "not started".to_string(), |r| format!("aborted: {r}"));
+                results[i][t] = Some(skipped_run(step, target, reason));
+            }
+        }
+    }
+
+    let failed_step = spec.steps.iter().zip(results.iter()).find(|(step, runs)| {
+        step.on_failure != StepFailurePolicy::Continue
+            && runs
+                .iter()
+                .flatten()
+                .any(|r| r.status == StepStatus::Failed)
+    });
+    let status = if aborted.is_some() || failed_step.is_some() {
+        "failed"
+    } else {
+        "succeeded"
+    };
+    let error = aborted.or_else(|| failed_step.map(|(s, _)| format!("step {} failed", s.id)));
+    (status, error)
+}
+
+/// Queue every pending step whose dependencies are done; skip those behind a failure.
+fn release_ready_steps(
+    spec: &WorkflowSpec,
+    order: &[usize],
+    index: &BTreeMap<&str, usize>,
+    step_states: &mut [StepState],
+    results: &mut [Vec<Option<StepRun>>],
+    queue: &mut VecDeque<Job>,
+) -> Option<String> {
+    // Skipping a step can unblock (or skip) its dependents, so repeat until stable.
+    let mut progressed = true;
+    while progressed {
+        progressed = false;
+        for &i in order {
+            if !matches!(step_states[i], StepState::Pending) {
+                continue;
+            }
+            let step = &spec.steps[i];
+            let mut blocked_by = None;
+            let mut ready = true;
+            for dep in &step.depends_on {
+                match step_states[index[dep.as_str()]] {
+                    StepState::Done { blocks_dependents } => {
+                        if blocks_dependents {
+                            blocked_by = Some(dep);
+                        }
+                    }
+                    _ => ready = false,
+                }
+            }
+            if !ready {
+                continue;
+            }
+            progressed = true;
+            let targets = step.targets();
+
+            if let Some(dep) = blocked_by {
+                for (t, target) in targets.iter().enumerate() {
+                    results[i][t] = Some(skipped_run(
+                        step,
+                        target,
+                        format!("dependency {dep} did not succeed"),
+                    ));
+                }
+                step_states[i] = StepState::Done {
+                    blocks_dependents: true,
+                };
+                continue;
+            }
+
+            step_states[i] = StepState::Running {
+                remaining: targets.len(),
+            };
+            for (t, target) in targets.into_iter().enumerate() {
+                let payload = render_step_payload(step, |r| resolve_ref(spec, index, results, r));
+                match payload {
+                    Ok(payload) => queue.push_back(Job {
+                        step_index: i,
+                        target_index: t,
+                        target,
+                        intent: Intent {
+                            intent_type: step.intent.clone(),
+                            payload,
+                        },
+                    }),
+                    Err(e) => {
+                        // Nothing to run; count the target as finished and failed.
+                        let mut run = skipped_run(step, &target, e.to_string());
+                        run.status = StepStatus::Failed;
+                        results[i][t] = Some(run);
+                        if let StepState::Running { remaining } = &mut step_states[i] {
+                            *remaining -= 1;
+                        }
+                    }
+                }
+            }
+            if matches!(step_states[i], StepState::Running { remaining: 0 }) {
+                step_states[i] = StepState::Done {
+                    blocks_dependents: step.on_failure != StepFailurePolicy::Continue,
+                };
+                if step.on_failure == StepFailurePolicy::Abort {
+                    return Some(format!("step {} failed", step.id));
+                }
+            }
+        }
+    }
+    None
+}
+
+fn resolve_ref(
+    spec: &WorkflowSpec,
+    index: &BTreeMap<&str, usize>,
+    results: &[Vec<Option<StepRun>>],
+    r: &StepRef,
+) -> Option<String> {
+    let i = *index.get(r.step.as_str())?;
+    let runs = &results[i];
+    let run = match &r.target {
+        None => runs.first()?.as_ref()?,
+        Some(label) => {
+            let t = spec.steps[i]
+                .targets()
+                .iter()
+                .position(|t| &t.label() == label)?;
+            runs.get(t)?.as_ref()?
+        }
+    };
+    match &r.field {
+        StepRefField::Output(key) => run.output.get(key).cloned(),
+        StepRefField::RunId => run.run_id.clone(),
+        StepRefField::Status => serde_json::to_value(run.status)
+            .ok()
+            .and_then(|v| v.as_str().map(str::to_string)),
+    }
+}
+
+fn skipped_run(step: &WorkflowStep, target: &StepTarget, reason: String) -> StepRun {
+    StepRun {
+        step_id: step.id.clone(),
+        target: target.label(),
+        status: StepStatus::Skipped,
+        run_id: None,
+        attempts: 0,
+        output: BTreeMap::new(),
+        error: Some(reason),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use logline_core::demo_catalog;
+    use logline_testkit::{Fault, MockConnectorFactory, MockSecretStore, Step};
+
+    use super::*;
+
+    #[test]
+    fn outputs_feed_dependents_and_abort_skips_the_rest() {
+        let runtime = LoglineRuntime::from_catalog(demo_catalog()).unwrap();
+        let spec: WorkflowSpec = toml::from_str(
+            r#"
+            id = "release"
+            [[step]]
+            id = "build"
+            intent = "build"
+            [[step]]
+            id = "deploy"
+            intent = "deploy"
+            depends_on = ["build"]
+            payload = { from = "${steps.build.output.backend}" }
+            [[step]]
+            id = "verify"
+            intent = "verify"
+            depends_on = ["build"]
+            payload = { missing = "${steps.build.output.nope}" }
+            [[step]]
+            id = "announce"
+            intent = "announce"
+            depends_on = ["deploy", "verify"]
+            "#,
+        )
+        .unwrap();
+
+        let run = runtime.run_workflow(&spec).unwrap();
+        let status = |id: &str| run.steps.iter().find(|s| s.step_id == id).unwrap().status;
+        assert_eq!(run.status, "failed");
+        assert_eq!(status("build"), StepStatus::Succeeded);
+        assert_eq!(status("verify"), StepStatus::Failed);
+        assert_eq!(status("announce"), StepStatus::Skipped);
+
+        let runs = runtime.registry().list().unwrap();
+        assert!(
+            runs.iter()
+                .any(|r| r.run_id == run.workflow_run_id && r.status == "failed")
+        );
+    }
+
+    #[test]
+    fn step_retries_recover_from_connection_faults() {
+        let factory = MockConnectorFactory::new();
+        let runtime = LoglineRuntime::from_catalog_with_factory(
+            demo_catalog(),
+            &factory,
+            &MockSecretStore::new(),
+        )
+        .unwrap();
+        let backend = factory.backend("local-main").unwrap();
+        backend
+            .strict()
+            .read_only_intents(&["build"])
+            .push(Step::intent("build").fail(Fault::Connection("reset".to_string())))
+            .push(Step::intent("build").output("artifact", "a.tgz"))
+            .push(Step::intent("deploy"));
+        let spec: WorkflowSpec = toml::from_str(
+            r#"
+            id = "release"
+            [[step]]
+            id = "build"
+            intent = "build"
+            retries = 1
+            [[step]]
+            id = "deploy"
+            intent = "deploy"
+            depends_on = ["build"]
+            payload = { artifact = "${steps.build.output.artifact}" }
+            "#,
+        )
+        .unwrap();
+
+        let run = runtime.run_workflow(&spec).unwrap();
+        assert_eq!(run.status, "succeeded");
+        assert_eq!(run.steps[0].attempts, 2);
+        backend.assert_script_done();
+        assert!(backend.calls().contains(&logline_testkit::Call::Execute {
+            intent_type: "deploy".to_string(),
+            payload: BTreeMap::from([("artifact".to_string(), "a.tgz".to_string())]),
+        }));
+
+        let metrics = runtime.metrics().unwrap();
+        let build = metrics
+            .series
+            .iter()
+            .find(|s| s.intent_type == "build")
+            .unwrap();
+        assert_eq!((build.started, build.failed, build.retried), (2, 1, 1));
+    }
+
+    #[test]
+    fn mutating_intents_and_other_errors_are_not_retried() {
+        let factory = MockConnectorFactory::new();
+        let runtime = LoglineRuntime::from_catalog_with_factory(
+            demo_catalog(),
+            &factory,
+            &MockSecretStore::new(),
+        )
+        .unwrap();
+        let backend = factory.backend("local-main").unwrap();
+        backend
+            .read_only_intents(&["status"])
+            .push(Step::intent("deploy").fail(Fault::Connection("timed out".to_string())))
+            .push(Step::intent("status").fail(Fault::Conflict("locked".to_string())));
+        let spec: WorkflowSpec = toml::from_str(
+            r#"
+            id = "release"
+            [[step]]
+            id = "deploy"
+            intent = "deploy"
+            retries = 2
+            on_failure = "continue"
+            [[step]]
+            id = "status"
+            intent = "status"
+            retries = 2
+            on_failure = "continue"
+            "#,
+        )
+        .unwrap();
+
+        let run = runtime.run_workflow(&spec).unwrap();
+        assert!(run.steps.iter().all(|s| s.attempts == 1), "{:?}", run.steps);
+        backend.assert_executed(&["deploy", "status"]);
+    }
+
+    #[test]
+    fn backend_targets_run_under_the_profile_that_owns_them() {
+        let mut catalog = demo_catalog();
+        let mut ledger = catalog.backends["local-main"].clone();
+        ledger.backend_id = "ledger".to_string();
+        catalog.backends.insert("ledger".to_string(), ledger);
+        catalog.profiles.insert(
+            "reports".to_string(),
+            logline_core::Profile {
+                id: "reports".to_string(),
+                backend_id: "ledger".to_string(),
+                readonly: true,
+            },
+        );
+        let factory = MockConnectorFactory::new();
+        let runtime =
+            LoglineRuntime::from_catalog_with_factory(catalog, &factory, &MockSecretStore::new())
+                .unwrap();
+        let spec: WorkflowSpec = toml::from_str(
+            r#"
+            id = "settle"
+            [[step]]
+            id = "settle"
+            intent = "settle"
+            backends = ["ledger"]
+            "#,
+        )
+        .unwrap();
+
+        let run = runtime.run_workflow(&spec).unwrap();
+        assert_eq!(run.status, "failed");
+        let error = run.steps[0].error.as_deref().unwrap_or_default();
+        assert!(error.contains("profile reports is readonly"), "{error}");
+        factory.backend("ledger").unwrap().assert_executed(&[]);
+    }
+}
@@ -0,0 +1,98 @@
+//! Just enough DER to read a certificate's expiry, and the calendar math it needs.
+
+/// `notAfter` of a DER-encoded X.509 certificate, in unix seconds.
+pub(crate) fn x509_not_after(der: &[u8]) -> Option<i64> {
+    let (_, cert) = read_tlv(der, 0x30)?;
+    let (_, mut tbs) = read_tlv(cert, 0x30)?;
+    if tbs.first() == Some(&0xA0) {
+        tbs = read_tlv(tbs, 0xA0)?.0;
+    }
+    let (tbs, _serial) = read_tlv(tbs, 0x02)?;
+    let (tbs, _signature) = read_tlv(tbs, 0x30)?;
+    let (tbs, _issuer) = read_tlv(tbs, 0x30)?;
+    let (_, validity) = read_tlv(tbs, 0x30)?;
+
+    let not_before_tag = *validity.first()?;
+    let (validity, _) = read_tlv(validity, not_before_tag)?;
+    let tag = *validity.first()?;
+    let (_, not_after) = read_tlv(validity, tag)?;
+    let text = std::str::from_utf8(not_after).ok()?.strip_suffix('Z')?;
+    let (year, rest) = match tag {
+        // UTCTime: two-digit years 50..99 are 19xx.
+        0x17 => {
+            let yy: i64 = text.get(..2)?.parse().ok()?;
+            (if yy >= 50 { 1900 + yy } else { 2000 + yy }, text.get(2..)?)
+        }
+        0x18 => (text.get(..4)?.parse().ok()?, text.get(4..)?),
+        _ => return None,
+    };
+    let field = |i: usize| rest.get(i..i + 2)?.parse::<i64>().ok();
+    let days = days_from_civil(year, field(0)?, field(2)?);
+    Some(days * 86_400 + field(4)? * 3_600 + field(6)? * 60 + field(8)?)
+}
+
+/// Split one TLV with the expected `tag` off `input`: (rest, value).
+fn read_tlv(input: &[u8], tag: u8) -> Option<(&[u8], &[u8])> {
+    if *input.first()? != tag {
+        return None;
+    }
+    let first = usize::from(*input.get(1)?);
+    let (len, header) = if first < 0x80 {
+        (first, 2)
+    } else {
+        let count = first & 0x7F;
+        if count == 0 || count > 4 {
+            return None;
+        }
+        let len = input
+            .get(2..2 + count)?
+            .iter()
+            .fold(0usize, |len, b| (len << 8) | usize::from(*b));
+        (len, 2 + count)
+    };
+    let value = input.get(header..header + len)?;
+    Some((&input[header + len..], value))
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date (Howard Hinnant's algorithm).
+pub(crate) fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tlv(tag: u8, value: &[u8]) -> Vec<u8> {
+        let mut out = vec![tag, u8::try_from(value.len()).unwrap()];
+        out.extend_from_slice(value);
+        out
+    }
+
+    #[test]
+    fn reads_not_after_from_a_minimal_certificate() {
+        let validity = [
+            tlv(0x17, b"250101000000Z"),
+            tlv(0x18, b"20300102030405Z"),
+        ]
+        .concat();
+        let tbs = [
+            tlv(0xA0, &tlv(0x02, &[2])),
+            tlv(0x02, &[1]),
+            tlv(0x30, &[]),
+            tlv(0x30, &[]),
+            tlv(0x30, &validity),
+        ]
+        .concat();
+        let cert = tlv(0x30, &tlv(0x30, &tbs));
+
+        assert_eq!(x509_not_after(&cert), Some(1_893_553_445));
+        assert_eq!(x509_not_after(&cert[..cert.len() - 3]), None);
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+    }
+}
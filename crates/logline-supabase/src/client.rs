@@ -0,0 +1,426 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use logline_api::{HealthReport, LoglineError};
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::cert::x509_not_after;
+
+// ─── Config ─────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SupabaseConfig {
+    pub url: String,
+    pub anon_key: String,
+}
+
+impl SupabaseConfig {
+    /// Read the project URL and anon key from `NEXT_PUBLIC_SUPABASE_*` env vars,
+    /// then `config_dir/config.json`, then `.env.local` / `.env` in the current directory.
+    pub fn from_env_or_file(config_dir: &Path) -> Result<Self, LoglineError> {
+        if let (Ok(url), Ok(key)) = (
+            std::env::var("NEXT_PUBLIC_SUPABASE_URL"),
+            std::env::var("NEXT_PUBLIC_SUPABASE_ANON_KEY"),
+        ) {
+            if !url.is_empty() && !key.is_empty() {
+                return Ok(Self { url, anon_key: key });
+            }
+        }
+
+        let config_path = config_dir.join("config.json");
+        if config_path.exists() {
+            let content = fs::read_to_string(&config_path)
+                .map_err(|e| LoglineError::NotFound(format!("Failed to read config.json: {e}")))?;
+            return serde_json::from_str(&content)
+                .map_err(|e| LoglineError::Validation(format!("Invalid config.json format: {e}")));
+        }
+
+        for filename in [".env.local", ".env"] {
+            let path = std::env::current_dir()
+                .unwrap_or_else(|_| PathBuf::from("."))
+                .join(filename);
+            if let Ok(content) = fs::read_to_string(&path) {
+                let url = parse_env_value(&content, "NEXT_PUBLIC_SUPABASE_URL");
+                let key = parse_env_value(&content, "NEXT_PUBLIC_SUPABASE_ANON_KEY");
+                if let (Some(url), Some(key)) = (url, key) {
+                    return Ok(Self { url, anon_key: key });
+                }
+            }
+        }
+
+        Err(LoglineError::NotFound(format!(
+            "Supabase config not found.\n\
+             Set NEXT_PUBLIC_SUPABASE_URL and NEXT_PUBLIC_SUPABASE_ANON_KEY env vars,\n\
+             or create {} with {{\"url\": \"...\", \"anon_key\": \"...\"}}",
+            config_path.display()
+        )))
+    }
+}
+
+// ─── Supabase HTTP client ───────────────────────────────────────────────────
+
+const RETURN_REPRESENTATION: &str = "return=representation";
+const RETURN_MINIMAL: &str = "return=minimal";
+const MERGE_DUPLICATES: &str = "resolution=merge-duplicates,return=representation";
+
+pub struct SupabaseClient {
+    pub config: SupabaseConfig,
+    http: Client,
+}
+
+impl SupabaseClient {
+    pub fn new(config: SupabaseConfig) -> Result<Self, LoglineError> {
+        Self::with_timeouts(config, Duration::from_secs(10), Duration::from_secs(15))
+    }
+
+    pub fn with_timeouts(
+        config: SupabaseConfig,
+        connect: Duration,
+        request: Duration,
+    ) -> Result<Self, LoglineError> {
+        let http = Client::builder()
+            .connect_timeout(connect)
+            .timeout(request)
+            .tls_info(true)
+            .build()
+            .map_err(|e| LoglineError::Internal(format!("failed to build HTTP client: {e}")))?;
+        Ok(Self { config, http })
+    }
+
+    /// `GET /rest/v1/`, which answers once the project and the key are usable.
+    /// The version comes from the `PostgREST` `OpenAPI` document or the `server`
+    /// header, the certificate expiry from the TLS session.
+    pub fn health(&self) -> Result<HealthReport, LoglineError> {
+        let url = format!("{}/rest/v1/", self.config.url);
+        let req = self.http.get(&url).header("apikey", &self.config.anon_key);
+        let started = Instant::now();
+        let resp = send(req, "Health check")?;
+        let latency_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+
+        let tls_expires_unix = resp
+            .extensions()
+            .get::<reqwest::tls::TlsInfo>()
+            .and_then(reqwest::tls::TlsInfo::peer_certificate)
+            .and_then(x509_not_after);
+        let server = resp
+            .headers()
+            .get(reqwest::header::SERVER)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let openapi: Option<Value> = resp.json().ok();
+        let version = openapi
+            .as_ref()
+            .and_then(|doc| doc.pointer("/info/version"))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .or(server);
+
+        Ok(HealthReport {
+            latency_ms,
+            version,
+            capabilities: None,
+            tls_expires_unix,
+        })
+    }
+
+    // ── Auth endpoints ──────────────────────────────────────────────────
+
+    pub fn login_email(
+        &self,
+        email: &str,
+        password: &str,
+    ) -> Result<AuthTokenResponse, LoglineError> {
+        let url = format!("{}/auth/v1/token?grant_type=password", self.config.url);
+        let req = self
+            .http
+            .post(&url)
+            .header("apikey", &self.config.anon_key)
+            .json(&json!({ "email": email, "password": password }));
+        decode(send(req, "Login")?)
+    }
+
+    pub fn refresh_token(&self, refresh_token: &str) -> Result<AuthTokenResponse, LoglineError> {
+        let url = format!("{}/auth/v1/token?grant_type=refresh_token", self.config.url);
+        let req = self
+            .http
+            .post(&url)
+            .header("apikey", &self.config.anon_key)
+            .json(&json!({ "refresh_token": refresh_token }));
+        decode(send(req, "Token refresh")?)
+    }
+
+    pub fn get_user(&self, access_token: &str) -> Result<Value, LoglineError> {
+        let url = format!("{}/auth/v1/user", self.config.url);
+        let req = self
+            .http
+            .get(&url)
+            .header("apikey", &self.config.anon_key)
+            .bearer_auth(access_token);
+        decode(send(req, "Get user")?)
+    }
+
+    // ── PostgREST endpoints ─────────────────────────────────────────────
+
+    pub fn postgrest_get(
+        &self,
+        table: &str,
+        query: &str,
+        access_token: &str,
+    ) -> Result<Value, LoglineError> {
+        let url = format!("{}/rest/v1/{table}?{query}", self.config.url);
+        decode(send(
+            self.postgrest(
+                self.http.get(&url),
+                access_token,
+                None,
+                RETURN_REPRESENTATION,
+            ),
+            &format!("PostgREST select {table}"),
+        )?)
+    }
+
+    pub fn postgrest_insert(
+        &self,
+        table: &str,
+        body: &Value,
+        access_token: &str,
+    ) -> Result<Value, LoglineError> {
+        let url = format!("{}/rest/v1/{table}", self.config.url);
+        let resp = send(
+            self.postgrest(
+                self.http.post(&url),
+                access_token,
+                Some(body),
+                RETURN_REPRESENTATION,
+            ),
+            &format!("PostgREST insert {table}"),
+        )?;
+        Ok(resp.json().unwrap_or(json!({"ok": true})))
+    }
+
+    /// Insert without reading the row back, for tables the caller may write
+    /// but not select (e.g. insert-only RLS policies).
+    pub fn postgrest_insert_minimal(
+        &self,
+        table: &str,
+        body: &Value,
+        access_token: &str,
+    ) -> Result<(), LoglineError> {
+        let url = format!("{}/rest/v1/{table}", self.config.url);
+        send(
+            self.postgrest(
+                self.http.post(&url),
+                access_token,
+                Some(body),
+                RETURN_MINIMAL,
+            ),
+            &format!("PostgREST insert {table}"),
+        )?;
+        Ok(())
+    }
+
+    pub fn postgrest_upsert(
+        &self,
+        table: &str,
+        body: &Value,
+        on_conflict: &str,
+        access_token: &str,
+    ) -> Result<Value, LoglineError> {
+        let url = format!(
+            "{}/rest/v1/{table}?on_conflict={on_conflict}",
+            self.config.url
+        );
+        let req = self.postgrest(
+            self.http.post(&url),
+            access_token,
+            Some(body),
+            MERGE_DUPLICATES,
+        );
+        let resp = send(req, &format!("PostgREST upsert {table}"))?;
+        Ok(resp.json().unwrap_or(json!({"ok": true})))
+    }
+
+    /// `PATCH` the rows matching `filter`, e.g. `id=eq.7`.
+    pub fn postgrest_update(
+        &self,
+        table: &str,
+        filter: &str,
+        body: &Value,
+        access_token: &str,
+    ) -> Result<Value, LoglineError> {
+        let url = format!("{}/rest/v1/{table}?{filter}", self.config.url);
+        let resp = send(
+            self.postgrest(
+                self.http.patch(&url),
+                access_token,
+                Some(body),
+                RETURN_REPRESENTATION,
+            ),
+            &format!("PostgREST update {table}"),
+        )?;
+        Ok(resp.json().unwrap_or(json!([])))
+    }
+
+    pub fn postgrest_delete(
+        &self,
+        table: &str,
+        filter: &str,
+        access_token: &str,
+    ) -> Result<Value, LoglineError> {
+        let url = format!("{}/rest/v1/{table}?{filter}", self.config.url);
+        let resp = send(
+            self.postgrest(
+                self.http.delete(&url),
+                access_token,
+                None,
+                RETURN_REPRESENTATION,
+            ),
+            &format!("PostgREST delete {table}"),
+        )?;
+        Ok(resp.json().unwrap_or(json!([])))
+    }
+
+    /// Call a Postgres function through `POST /rest/v1/rpc/<function>`.
+    pub fn rpc(
+        &self,
+        function: &str,
+        args: &Value,
+        access_token: &str,
+    ) -> Result<Value, LoglineError> {
+        let url = format!("{}/rest/v1/rpc/{function}", self.config.url);
+        let resp = send(
+            self.postgrest(
+                self.http.post(&url),
+                access_token,
+                Some(args),
+                RETURN_REPRESENTATION,
+            ),
+            &format!("RPC {function}"),
+        )?;
+        Ok(resp.json().unwrap_or(Value::Null))
+    }
+
+    fn postgrest(
+        &self,
+        req: RequestBuilder,
+        access_token: &str,
+        body: Option<&Value>,
+        prefer: &str,
+    ) -> RequestBuilder {
+        let req = req
+            .header("apikey", &self.config.anon_key)
+            .bearer_auth(access_token)
+            .header("Prefer", prefer);
+        match body {
+            Some(b) => req.json(b),
+            None => req,
+        }
+    }
+
+    // ── Edge functions ──────────────────────────────────────────────────
+
+    /// `POST /functions/v1/<name>` with the anon key. A function that is not
+    /// deployed comes back as `NotFound`.
+    pub fn invoke_function(&self, name: &str, body: &Value) -> Result<Value, LoglineError> {
+        let url = format!("{}/functions/v1/{name}", self.config.url);
+        let req = self
+            .http
+            .post(&url)
+            .header("apikey", &self.config.anon_key)
+            .bearer_auth(&self.config.anon_key)
+            .json(body);
+        decode(send(req, &format!("Function {name}"))?)
+    }
+
+    // ── Service-role operations (bootstrap only) ────────────────────────
+
+    pub fn service_role_insert(
+        &self,
+        table: &str,
+        body: &Value,
+        service_role_key: &str,
+    ) -> Result<Value, LoglineError> {
+        let url = format!("{}/rest/v1/{table}", self.config.url);
+        let req = self.postgrest(
+            self.http.post(&url),
+            service_role_key,
+            Some(body),
+            MERGE_DUPLICATES,
+        );
+        let resp = send(req, &format!("Service-role insert into {table}"))?;
+        Ok(resp.json().unwrap_or(json!({"ok": true})))
+    }
+}
+
+/// Send `req`, turning transport failures and error statuses into `LoglineError`.
+fn send(req: RequestBuilder, what: &str) -> Result<Response, LoglineError> {
+    let resp = req
+        .send()
+        .map_err(|e| LoglineError::Connection(format!("{what} failed: {e}")))?;
+    let status = resp.status();
+    if status.is_success() {
+        return Ok(resp);
+    }
+    let body = resp.text().unwrap_or_default();
+    let message = format!("{what} failed ({status}): {body}");
+    Err(match status.as_u16() {
+        401 | 403 => LoglineError::Auth(message),
+        404 => LoglineError::NotFound(message),
+        409 => LoglineError::Conflict(message),
+        400..=499 => LoglineError::Validation(message),
+        _ => LoglineError::Connection(message),
+    })
+}
+
+fn decode<T: for<'de> Deserialize<'de>>(resp: Response) -> Result<T, LoglineError> {
+    resp.json()
+        .map_err(|e| LoglineError::Internal(format!("unexpected Supabase response: {e}")))
+}
+
+// ─── Auth token response ────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct AuthTokenResponse {
+    pub access_token: String,
+    pub refresh_token: String,
+    pub expires_in: u64,
+    pub token_type: String,
+    pub user: AuthUser,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuthUser {
+    pub id: String,
+    pub email: Option<String>,
+}
+
+// ─── Helpers ────────────────────────────────────────────────────────────────
+
+fn parse_env_value(content: &str, key: &str) -> Option<String> {
+    for raw_line in content.lines() {
+        let line = raw_line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut parts = line.splitn(2, '=');
+        let k = parts.next()?.trim();
+        let v = parts.next()?.trim();
+        if k != key {
+            continue;
+        }
+        let unquoted = if (v.starts_with('"') && v.ends_with('"'))
+            || (v.starts_with('\'') && v.ends_with('\''))
+        {
+            v[1..v.len().saturating_sub(1)].trim()
+        } else {
+            v
+        };
+        if !unquoted.is_empty() {
+            return Some(unquoted.to_string());
+        }
+    }
+    None
+}
@@ -0,0 +1,391 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use logline_api::{
+    AuthMode, BackendCapabilities, BackendConfig, BackendConnector, DomainEvent, EventCursor,
+    ExecutionPlan, ExecutionResult, HealthReport, Intent, LoglineError, PlannedEffect, RunId, SecretStore,
+    SupabaseOp, SupabaseOperation, SupabaseSpec,
+};
+use serde_json::Value;
+
+use crate::cert::days_from_civil;
+use crate::client::{SupabaseClient, SupabaseConfig};
+
+/// Rows fetched per `events_since` call.
+const EVENTS_PAGE: usize = 100;
+
+/// Drives a Supabase project through `PostgREST`, one table operation or `rpc/`
+/// function per intent type.
+///
+/// The key behind `auth.secret_ref` (anon or service-role) is sent both as
+/// `apikey` and as the bearer token. Payload values become the JSON body of
+/// writes and RPC calls, and fill `{{key}}` placeholders in filters.
+pub struct SupabaseConnector {
+    id: String,
+    base_url: String,
+    spec: SupabaseSpec,
+    client: Result<SupabaseClient, String>,
+}
+
+impl SupabaseConnector {
+    /// A missing secret only fails this backend once it is used, not the whole runtime.
+    pub fn new(cfg: &BackendConfig, secrets: &dyn SecretStore) -> Self {
+        let key = match cfg.auth.mode {
+            AuthMode::None => Err(format!(
+                "supabase backend {} needs an auth_mode and secret_ref",
+                cfg.backend_id
+            )),
+            _ => secrets.get(&cfg.auth.secret_ref).map_err(|e| e.to_string()),
+        };
+        let client = key.and_then(|anon_key| {
+            SupabaseClient::with_timeouts(
+                SupabaseConfig {
+                    url: cfg.base_url.trim_end_matches('/').to_string(),
+                    anon_key,
+                },
+                Duration::from_millis(cfg.connect_timeout_ms),
+                Duration::from_millis(cfg.request_timeout_ms),
+            )
+            .map_err(|e| e.to_string())
+        });
+        Self {
+            id: cfg.backend_id.clone(),
+            base_url: cfg.base_url.clone(),
+            spec: cfg.supabase.clone().unwrap_or_default(),
+            client,
+        }
+    }
+
+    fn client(&self) -> Result<&SupabaseClient, LoglineError> {
+        self.client
+            .as_ref()
+            .map_err(|e| LoglineError::Auth(e.clone()))
+    }
+
+    fn operation(&self, intent_type: &str) -> Result<&SupabaseOperation, LoglineError> {
+        self.spec.intents.get(intent_type).ok_or_else(|| {
+            LoglineError::Validation(format!(
+                "backend {} has no mapping for intent {intent_type}",
+                self.id
+            ))
+        })
+    }
+}
+
+impl BackendConnector for SupabaseConnector {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities {
+            supports_streaming: self.spec.events_table.is_some(),
+            supports_write: true,
+            supports_history: self.spec.events_table.is_some(),
+            supports_plan: true,
+        }
+    }
+
+    fn health(&self) -> Result<HealthReport, LoglineError> {
+        self.client()?.health()
+    }
+
+    fn execute(&self, intent: &Intent) -> Result<ExecutionResult, LoglineError> {
+        let op = self.operation(&intent.intent_type)?;
+        let client = self.client()?;
+        let key = &client.config.anon_key;
+        let body = Value::Object(
+            intent
+                .payload
+                .iter()
+                .map(|(k, v)| (k.clone(), Value::String(v.clone())))
+                .collect(),
+        );
+        let filter = op
+            .filter
+            .as_deref()
+            .map(|f| render_filter(f, &intent.payload))
+            .transpose()?;
+
+        let response = match op.op {
+            SupabaseOp::Select => {
+                let query =
+                    filter.map_or_else(|| "select=*".to_string(), |f| format!("select=*&{f}"));
+                client.postgrest_get(&op.target, &query, key)?
+            }
+            SupabaseOp::Insert => client.postgrest_insert(&op.target, &body, key)?,
+            SupabaseOp::Upsert => client.postgrest_upsert(
+                &op.target,
+                &body,
+                op.on_conflict.as_deref().unwrap_or_default(),
+                key,
+            )?,
+            SupabaseOp::Update => {
+                client.postgrest_update(&op.target, &require_filter(filter, op)?, &body, key)?
+            }
+            SupabaseOp::Delete => {
+                client.postgrest_delete(&op.target, &require_filter(filter, op)?, key)?
+            }
+            SupabaseOp::Rpc => client.rpc(&op.target, &body, key)?,
+        };
+
+        Ok(ExecutionResult {
+            run_id: format!("sb-{}", next_run_seq()),
+            status: "succeeded".to_string(),
+            output: flatten_output(&response),
+            recording_error: None,
+        })
+    }
+
+    fn plan(&self, intent: &Intent) -> Result<ExecutionPlan, LoglineError> {
+        let op = self.operation(&intent.intent_type)?;
+        let mut warnings = Vec::new();
+        let filter = op.filter.as_deref().map(|f| {
+            render_filter(f, &intent.payload).unwrap_or_else(|e| {
+                warnings.push(e.to_string());
+                f.to_string()
+            })
+        });
+        if let Err(e) = &self.client {
+            warnings.push(e.clone());
+        }
+        let (action, mutating) = match op.op {
+            SupabaseOp::Select => ("select", false),
+            SupabaseOp::Insert => ("insert", true),
+            SupabaseOp::Upsert => ("upsert", true),
+            SupabaseOp::Update => ("update", true),
+            SupabaseOp::Delete => ("delete", true),
+            SupabaseOp::Rpc => ("rpc", !op.read_only),
+        };
+        let resource = match op.op {
+            SupabaseOp::Rpc => format!("{}/rest/v1/rpc/{}", self.base_url, op.target),
+            _ => format!("{}/rest/v1/{}", self.base_url, op.target),
+        };
+        let keys: Vec<&str> = intent.payload.keys().map(String::as_str).collect();
+        Ok(ExecutionPlan {
+            backend_id: self.id.clone(),
+            intent_type: intent.intent_type.clone(),
+            mutating,
+            effects: vec![PlannedEffect {
+                action: action.to_string(),
+                resource,
+                description: match filter {
+                    Some(f) => format!("{action} {} where {f}", op.target),
+                    None if keys.is_empty() => format!("{action} {}", op.target),
+                    None => format!("{action} {} with {}", op.target, keys.join(", ")),
+                },
+            }],
+            warnings,
+        })
+    }
+
+    /// `PostgREST` calls finish before `execute` returns, so there is nothing to stop.
+    fn stop(&self, _run_id: &RunId) -> Result<(), LoglineError> {
+        Ok(())
+    }
+
+    fn events_since(&self, cursor: Option<&EventCursor>) -> Result<Vec<DomainEvent>, LoglineError> {
+        let Some(table) = &self.spec.events_table else {
+            return Ok(Vec::new());
+        };
+        let client = self.client()?;
+        let column = self.spec.events_cursor.as_deref().unwrap_or("id");
+        let mut query = format!("select=*&order={column}.asc&limit={EVENTS_PAGE}");
+        if let Some(cursor) = cursor {
+            let _ = write!(query, "&{column}=gt.{}", encode_value(cursor));
+        }
+        let rows = client.postgrest_get(table, &query, &client.config.anon_key)?;
+
+        Ok(rows
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_object)
+            .map(|row| {
+                let field = |name: &str| row.get(name).map(value_text);
+                DomainEvent {
+                    cursor: field(column).unwrap_or_default(),
+                    ts_unix_ms: self
+                        .spec
+                        .events_time
+                        .as_deref()
+                        .and_then(field)
+                        .and_then(|t| parse_timestamp_ms(&t))
+                        .unwrap_or(0),
+                    kind: match self.spec.events_kind.as_deref().and_then(field) {
+                        Some(kind) => format!("{table}.{kind}"),
+                        None => format!("{table}.row"),
+                    },
+                    run_id: None,
+                    attributes: row
+                        .iter()
+                        .map(|(k, v)| (k.clone(), value_text(v)))
+                        .collect(),
+                }
+            })
+            .collect())
+    }
+}
+
+fn require_filter(filter: Option<String>, op: &SupabaseOperation) -> Result<String, LoglineError> {
+    filter.ok_or_else(|| {
+        LoglineError::Validation(format!(
+            "{} on {} needs a filter",
+            op_name(op.op),
+            op.target
+        ))
+    })
+}
+
+fn op_name(op: SupabaseOp) -> &'static str {
+    match op {
+        SupabaseOp::Select => "select",
+        SupabaseOp::Insert => "insert",
+        SupabaseOp::Upsert => "upsert",
+        SupabaseOp::Update => "update",
+        SupabaseOp::Delete => "delete",
+        SupabaseOp::Rpc => "rpc",
+    }
+}
+
+/// Fill `{{key}}` placeholders with URL-encoded payload values.
+fn render_filter(filter: &str, payload: &BTreeMap<String, String>) -> Result<String, LoglineError> {
+    let mut rendered = String::new();
+    let mut rest = filter;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let key = rest[start + 2..start + 2 + len].trim();
+        let value = payload.get(key).ok_or_else(|| {
+            LoglineError::Validation(format!("payload is missing {key} for filter {filter}"))
+        })?;
+        rendered.push_str(&rest[..start]);
+        rendered.push_str(&encode_value(value));
+        rest = &rest[start + 4 + len..];
+    }
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+/// Percent-encode everything outside the unreserved URL characters.
+fn encode_value(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                char::from(b).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+/// Top-level fields of a single returned row or object, plus `rows` for arrays
+/// and `result` for scalar RPC results.
+fn flatten_output(value: &Value) -> BTreeMap<String, String> {
+    let mut output = BTreeMap::new();
+    let object = match value {
+        Value::Array(rows) => {
+            output.insert("rows".to_string(), rows.len().to_string());
+            match rows.as_slice() {
+                [Value::Object(row)] => Some(row),
+                _ => None,
+            }
+        }
+        Value::Object(object) => Some(object),
+        Value::Null => None,
+        scalar => {
+            output.insert("result".to_string(), value_text(scalar));
+            None
+        }
+    };
+    for (k, v) in object.into_iter().flatten() {
+        output.insert(k.clone(), value_text(v));
+    }
+    output
+}
+
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+/// Parse Postgres `timestamptz` JSON such as `2026-02-27T10:00:00.123+00:00`.
+pub fn parse_timestamp_ms(text: &str) -> Option<i64> {
+    let (date, time) = text.split_once(['T', ' '])?;
+    let mut date = date.splitn(3, '-').map(str::parse::<i64>);
+    let (year, month, day) = (date.next()?.ok()?, date.next()?.ok()?, date.next()?.ok()?);
+
+    let offset_at = time.rfind(['+', '-', 'Z']).unwrap_or(time.len());
+    let (clock, zone) = time.split_at(offset_at);
+    let (hms, fraction) = clock.split_once('.').unwrap_or((clock, ""));
+    let mut hms = hms.splitn(3, ':').map(str::parse::<i64>);
+    let (hour, minute, second) = (hms.next()?.ok()?, hms.next()?.ok()?, hms.next()?.ok()?);
+    if !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let millis = format!("{fraction:0<3}")[..3].parse::<i64>().ok()?;
+    let offset_minutes = match zone {
+        "" | "Z" => 0,
+        _ => {
+            let sign = if zone.starts_with('-') { -1 } else { 1 };
+            let (h, m) = zone[1..].split_once(':').unwrap_or((&zone[1..], "0"));
+            sign * (h.parse::<i64>().ok()? * 60 + m.parse::<i64>().ok()?)
+        }
+    };
+
+    let days = days_from_civil(year, month, day);
+    let seconds = days * 86_400 + hour * 3_600 + minute * 60 + second - offset_minutes * 60;
+    Some(seconds * 1_000 + millis)
+}
+
+/// Millisecond timestamp, bumped when needed so parallel runs get distinct ids.
+fn next_run_seq() -> u64 {
+    static LAST: AtomicU64 = AtomicU64::new(0);
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| u64::try_from(d.as_millis()).unwrap_or(u64::MAX));
+    let prev = LAST
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |last| {
+            Some(now.max(last + 1))
+        })
+        .unwrap_or(now);
+    now.max(prev + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn filters_outputs_and_timestamps() {
+        let payload = BTreeMap::from([("app_id".to_string(), "my app&x".to_string())]);
+        assert_eq!(
+            render_filter("app_id=eq.{{app_id}}&status=eq.up", &payload).unwrap(),
+            "app_id=eq.my%20app%26x&status=eq.up"
+        );
+        assert!(render_filter("id=eq.{{id}}", &payload).is_err());
+
+        let output = flatten_output(&json!([{"id": 7, "name": "web", "meta": null}]));
+        assert_eq!(output["rows"], "1");
+        assert_eq!(output["id"], "7");
+        assert_eq!(output["name"], "web");
+        assert_eq!(flatten_output(&json!(42))["result"], "42");
+        assert_eq!(flatten_output(&json!([{"id": 1}, {"id": 2}])).len(), 1);
+
+        assert_eq!(parse_timestamp_ms("1970-01-01T00:00:01Z"), Some(1_000));
+        assert_eq!(
+            parse_timestamp_ms("2026-02-27T10:00:00.250+01:00"),
+            Some(1_772_182_800_250)
+        );
+        assert_eq!(parse_timestamp_ms("2026-02-27T10:00:00.2€5Z"), None);
+    }
+}
@@ -0,0 +1,9 @@
+//! Supabase access shared by the CLI and the runtime: a blocking `PostgREST` and
+//! auth client, and `SupabaseConnector`, which maps intents onto it.
+
+mod cert;
+mod client;
+mod connector;
+
+pub use client::{AuthTokenResponse, AuthUser, SupabaseClient, SupabaseConfig};
+pub use connector::{SupabaseConnector, parse_timestamp_ms};
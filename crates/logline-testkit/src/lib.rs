@@ -0,0 +1,569 @@
+//! Scripted `BackendConnector` and `ConnectorFactory` for deterministic tests.
+//!
+//! A `MockConnector` is a cheap handle: clone it before handing it to a
+//! runtime and keep the clone to script replies and assert on calls.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::Duration;
+
+use logline_api::{
+    BackendCapabilities, BackendConfig, BackendConnector, BackendId, ConnectorFactory, DomainEvent,
+    EventCursor, ExecutionPlan, ExecutionResult, HealthReport, Intent, LoglineError, PlannedEffect,
+    RunId, SecretStore,
+};
+
+/// An error to inject instead of a normal reply.
+#[derive(Debug, Clone)]
+pub enum Fault {
+    Connection(String),
+    /// Block for the duration, then fail like a request timeout.
+    Timeout(Duration),
+    Auth(String),
+    Conflict(String),
+    Internal(String),
+}
+
+impl Fault {
+    fn raise(&self) -> LoglineError {
+        match self {
+            Self::Connection(msg) => LoglineError::Connection(msg.clone()),
+            Self::Timeout(after) => {
+                thread::sleep(*after);
+                LoglineError::Connection(format!("timed out after {}ms", after.as_millis()))
+            }
+            Self::Auth(msg) => LoglineError::Auth(msg.clone()),
+            Self::Conflict(msg) => LoglineError::Conflict(msg.clone()),
+            Self::Internal(msg) => LoglineError::Internal(msg.clone()),
+        }
+    }
+}
+
+/// One expected `execute` call and the reply to give it.
+#[derive(Debug, Clone)]
+pub struct Step {
+    intent_type: Option<String>,
+    delay: Duration,
+    run_id: Option<RunId>,
+    status: String,
+    output: BTreeMap<String, String>,
+    fault: Option<Fault>,
+}
+
+impl Step {
+    /// Expect `intent_type` next; replies `accepted` unless told otherwise.
+    pub fn intent(intent_type: impl Into<String>) -> Self {
+        Self {
+            intent_type: Some(intent_type.into()),
+            ..Self::any()
+        }
+    }
+
+    /// Match whatever intent comes next.
+    pub fn any() -> Self {
+        Self {
+            intent_type: None,
+            delay: Duration::ZERO,
+            run_id: None,
+            status: "accepted".to_string(),
+            output: BTreeMap::new(),
+            fault: None,
+        }
+    }
+
+    #[must_use]
+    pub fn output(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.output.insert(key.into(), value.into());
+        self
+    }
+
+    #[must_use]
+    pub fn status(mut self, status: impl Into<String>) -> Self {
+        self.status = status.into();
+        self
+    }
+
+    #[must_use]
+    pub fn run_id(mut self, run_id: impl Into<RunId>) -> Self {
+        self.run_id = Some(run_id.into());
+        self
+    }
+
+    #[must_use]
+    pub fn fail(mut self, fault: Fault) -> Self {
+        self.fault = Some(fault);
+        self
+    }
+
+    /// Hold the call this long before replying.
+    #[must_use]
+    pub fn after(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+}
+
+/// A call the connector received, in arrival order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Call {
+    Health,
+    Execute {
+        intent_type: String,
+        payload: BTreeMap<String, String>,
+    },
+    Plan {
+        intent_type: String,
+    },
+    Stop(RunId),
+    EventsSince(Option<EventCursor>),
+}
+
+struct State {
+    script: VecDeque<Step>,
+    strict: bool,
+    calls: Vec<Call>,
+    unexpected: Vec<String>,
+    runs: u64,
+    capabilities: BackendCapabilities,
+    read_intents: Vec<String>,
+    health_fault: Option<Fault>,
+    stop_delay: Duration,
+    stop_fault: Option<Fault>,
+    events: Vec<DomainEvent>,
+    truncate_events: Option<(usize, Fault)>,
+    events_fault: Option<Fault>,
+}
+
+#[derive(Clone)]
+pub struct MockConnector {
+    id: String,
+    state: Arc<Mutex<State>>,
+}
+
+impl MockConnector {
+    /// A lenient mock: unscripted intents are accepted with a generated run id.
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            state: Arc::new(Mutex::new(State {
+                script: VecDeque::new(),
+                strict: false,
+                calls: Vec::new(),
+                unexpected: Vec::new(),
+                runs: 0,
+                capabilities: BackendCapabilities {
+                    supports_streaming: true,
+                    supports_write: true,
+                    supports_history: true,
+                    supports_plan: true,
+                },
+                read_intents: Vec::new(),
+                health_fault: None,
+                stop_delay: Duration::ZERO,
+                stop_fault: None,
+                events: Vec::new(),
+                truncate_events: None,
+                events_fault: None,
+            })),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Fail intents that the script does not cover.
+    pub fn strict(&self) -> &Self {
+        self.state().strict = true;
+        self
+    }
+
+    /// Queue the reply for the next `execute`.
+    pub fn push(&self, step: Step) -> &Self {
+        self.state().script.push_back(step);
+        self
+    }
+
+    /// Append an event; cursors are assigned 1, 2, 3, ... in push order.
+    pub fn push_event(&self, kind: &str, run_id: Option<&str>) -> &Self {
+        let mut state = self.state();
+        let cursor = (state.events.len() + 1).to_string();
+        state.events.push(DomainEvent {
+            cursor,
+            ts_unix_ms: 0,
+            kind: kind.to_string(),
+            run_id: run_id.map(str::to_string),
+            attributes: BTreeMap::from([("backend".to_string(), self.id.clone())]),
+        });
+        self
+    }
+
+    /// Cut the next event read short after `deliver` events, and fail the read after it.
+    pub fn truncate_events(&self, deliver: usize, fault: Fault) -> &Self {
+        self.state().truncate_events = Some((deliver, fault));
+        self
+    }
+
+    pub fn fail_health(&self, fault: Fault) -> &Self {
+        self.state().health_fault = Some(fault);
+        self
+    }
+
+    /// Undo `fail_health`.
+    pub fn restore_health(&self) -> &Self {
+        self.state().health_fault = None;
+        self
+    }
+
+    /// Make `stop` take `delay` before it returns.
+    pub fn slow_stop(&self, delay: Duration) -> &Self {
+        self.state().stop_delay = delay;
+        self
+    }
+
+    pub fn fail_stop(&self, fault: Fault) -> &Self {
+        self.state().stop_fault = Some(fault);
+        self
+    }
+
+    pub fn set_capabilities(&self, capabilities: BackendCapabilities) -> &Self {
+        self.state().capabilities = capabilities;
+        self
+    }
+
+    /// Intent types `plan` reports as non-mutating.
+    pub fn read_only_intents(&self, intent_types: &[&str]) -> &Self {
+        self.state().read_intents = intent_types.iter().map(|s| (*s).to_string()).collect();
+        self
+    }
+
+    pub fn calls(&self) -> Vec<Call> {
+        self.state().calls.clone()
+    }
+
+    /// Intent types passed to `execute`, in order.
+    pub fn executed(&self) -> Vec<String> {
+        self.state()
+            .calls
+            .iter()
+            .filter_map(|c| match c {
+                Call::Execute { intent_type, .. } => Some(intent_type.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn stopped(&self) -> Vec<RunId> {
+        self.state()
+            .calls
+            .iter()
+            .filter_map(|c| match c {
+                Call::Stop(run_id) => Some(run_id.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The intents passed to `execute` were exactly `expected`, in order.
+    ///
+    /// # Panics
+    ///
+    /// If they were not.
+    #[track_caller]
+    pub fn assert_executed(&self, expected: &[&str]) {
+        assert_eq!(
+            self.executed(),
+            expected,
+            "backend {} executed unexpected intents",
+            self.id
+        );
+    }
+
+    /// `stop` was called for `run_id`.
+    ///
+    /// # Panics
+    ///
+    /// If it never was.
+    #[track_caller]
+    pub fn assert_stopped(&self, run_id: &str) {
+        let stopped = self.stopped();
+        assert!(
+            stopped.iter().any(|r| r == run_id),
+            "backend {} never stopped {run_id}; stopped: {stopped:?}",
+            self.id
+        );
+    }
+
+    /// Every scripted step was consumed and nothing unexpected arrived.
+    ///
+    /// # Panics
+    ///
+    /// If a call arrived that the script did not cover, or a step was never used.
+    #[track_caller]
+    pub fn assert_script_done(&self) {
+        let state = self.state();
+        assert!(
+            state.unexpected.is_empty(),
+            "backend {} received unexpected calls: {:?}",
+            self.id,
+            state.unexpected
+        );
+        assert!(
+            state.script.is_empty(),
+            "backend {} has {} unused scripted step(s)",
+            self.id,
+            state.script.len()
+        );
+    }
+}
+
+impl BackendConnector for MockConnector {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn capabilities(&self) -> BackendCapabilities {
+        self.state().capabilities.clone()
+    }
+
+    fn health(&self) -> Result<HealthReport, LoglineError> {
+        let mut state = self.state();
+        state.calls.push(Call::Health);
+        let fault = state.health_fault.clone();
+        drop(state);
+        fault.map_or_else(|| Ok(HealthReport::default()), |f| Err(f.raise()))
+    }
+
+    fn execute(&self, intent: &Intent) -> Result<ExecutionResult, LoglineError> {
+        let mut state = self.state();
+        state.calls.push(Call::Execute {
+            intent_type: intent.intent_type.clone(),
+            payload: intent.payload.clone(),
+        });
+        let step = match state.script.front() {
+            Some(step)
+                if step
+                    .intent_type
+                    .as_ref()
+                    .is_none_or(|t| *t == intent.intent_type) =>
+            {
+                state.script.pop_front()
+            }
+            Some(step) => {
+                let msg = format!(
+                    "got intent {} while expecting {}",
+                    intent.intent_type,
+                    step.intent_type.as_deref().unwrap_or("any")
+                );
+                state.unexpected.push(msg.clone());
+                return Err(LoglineError::Validation(msg));
+            }
+            None if state.strict => {
+                let msg = format!("unscripted intent {}", intent.intent_type);
+                state.unexpected.push(msg.clone());
+                return Err(LoglineError::Validation(msg));
+            }
+            None => None,
+        };
+        state.runs += 1;
+        let run_number = state.runs;
+        drop(state);
+
+        let step = step.unwrap_or_else(|| {
+            Step::any()
+                .output("backend", self.id.clone())
+                .output("intent_type", intent.intent_type.clone())
+        });
+        if !step.delay.is_zero() {
+            thread::sleep(step.delay);
+        }
+        if let Some(fault) = &step.fault {
+            return Err(fault.raise());
+        }
+        Ok(ExecutionResult {
+            run_id: step
+                .run_id
+                .unwrap_or_else(|| format!("mock-{}-{run_number}", self.id)),
+            status: step.status,
+            output: step.output,
+            recording_error: None,
+        })
+    }
+
+    fn plan(&self, intent: &Intent) -> Result<ExecutionPlan, LoglineError> {
+        let mut state = self.state();
+        state.calls.push(Call::Plan {
+            intent_type: intent.intent_type.clone(),
+        });
+        if !state.capabilities.supports_plan {
+            return Err(LoglineError::Validation(format!(
+                "backend {} cannot plan intent {}",
+                self.id, intent.intent_type
+            )));
+        }
+        let mutating = !state.read_intents.contains(&intent.intent_type);
+        Ok(ExecutionPlan {
+            backend_id: self.id.clone(),
+            intent_type: intent.intent_type.clone(),
+            mutating,
+            effects: vec![PlannedEffect {
+                action: if mutating { "submit" } else { "read" }.to_string(),
+                resource: format!("mock://{}/{}", self.id, intent.intent_type),
+                description: "scripted mock".to_string(),
+            }],
+            warnings: Vec::new(),
+        })
+    }
+
+    fn stop(&self, run_id: &RunId) -> Result<(), LoglineError> {
+        let mut state = self.state();
+        state.calls.push(Call::Stop(run_id.clone()));
+        let (delay, fault) = (state.stop_delay, state.stop_fault.clone());
+        drop(state);
+        if !delay.is_zero() {
+            thread::sleep(delay);
+        }
+        fault.map_or(Ok(()), |f| Err(f.raise()))
+    }
+
+    fn events_since(&self, cursor: Option<&EventCursor>) -> Result<Vec<DomainEvent>, LoglineError> {
+        let mut state = self.state();
+        state.calls.push(Call::EventsSince(cursor.cloned()));
+        if let Some(fault) = state.events_fault.take() {
+            drop(state);
+            return Err(fault.raise());
+        }
+        let skip = match cursor {
+            Some(c) => c
+                .parse::<usize>()
+                .map_err(|_| LoglineError::Validation(format!("invalid mock cursor '{c}'")))?,
+            None => 0,
+        };
+        let mut events: Vec<DomainEvent> = state.events.iter().skip(skip).cloned().collect();
+        if let Some((deliver, fault)) = state.truncate_events.take() {
+            events.truncate(deliver);
+            state.events_fault = Some(fault);
+        }
+        Ok(events)
+    }
+}
+
+/// Builds `MockConnector`s; backends without a registered mock get a lenient one.
+#[derive(Default)]
+pub struct MockConnectorFactory {
+    backends: Mutex<BTreeMap<BackendId, MockConnector>>,
+}
+
+impl MockConnectorFactory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serve `connector` for the backend with the same id.
+    #[must_use]
+    pub fn with(self, connector: MockConnector) -> Self {
+        self.backends
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(connector.id.clone(), connector);
+        self
+    }
+
+    /// The mock serving `backend_id`, once it was registered or built.
+    pub fn backend(&self, backend_id: &str) -> Option<MockConnector> {
+        self.backends
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(backend_id)
+            .cloned()
+    }
+}
+
+impl ConnectorFactory for MockConnectorFactory {
+    fn build(
+        &self,
+        cfg: &BackendConfig,
+        _secrets: &dyn SecretStore,
+    ) -> Result<Box<dyn BackendConnector>, LoglineError> {
+        let connector = self
+            .backends
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(cfg.backend_id.clone())
+            .or_insert_with(|| MockConnector::new(cfg.backend_id.clone()))
+            .clone();
+        Ok(Box::new(connector))
+    }
+}
+
+/// In-memory `SecretStore`.
+#[derive(Debug, Clone, Default)]
+pub struct MockSecretStore {
+    secrets: BTreeMap<String, String>,
+}
+
+impl MockSecretStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn with(mut self, secret_ref: impl Into<String>, value: impl Into<String>) -> Self {
+        self.secrets.insert(secret_ref.into(), value.into());
+        self
+    }
+}
+
+impl SecretStore for MockSecretStore {
+    fn get(&self, secret_ref: &str) -> Result<String, LoglineError> {
+        self.secrets
+            .get(secret_ref)
+            .cloned()
+            .ok_or_else(|| LoglineError::NotFound(format!("missing secret {secret_ref}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn intent(intent_type: &str) -> Intent {
+        Intent {
+            intent_type: intent_type.to_string(),
+            payload: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn replays_script_and_injects_faults() {
+        let mock = MockConnector::new("api");
+        mock.strict()
+            .push(Step::intent("build").output("artifact", "a.tgz"))
+            .push(Step::intent("deploy").fail(Fault::Connection("reset".to_string())));
+
+        let built = mock.execute(&intent("build")).unwrap();
+        assert_eq!(built.output["artifact"], "a.tgz");
+        assert!(matches!(
+            mock.execute(&intent("deploy")),
+            Err(LoglineError::Connection(_))
+        ));
+        assert!(mock.execute(&intent("verify")).is_err());
+        mock.assert_executed(&["build", "deploy", "verify"]);
+    }
+
+    #[test]
+    fn truncated_event_stream_fails_the_following_read() {
+        let mock = MockConnector::new("api");
+        mock.push_event("run.started", Some("r1"))
+            .push_event("run.progress", Some("r1"))
+            .push_event("run.finished", Some("r1"))
+            .truncate_events(1, Fault::Connection("stream closed".to_string()));
+
+        assert_eq!(mock.events_since(None).unwrap().len(), 1);
+        assert!(mock.events_since(Some(&"1".to_string())).is_err());
+        let rest = mock.events_since(Some(&"1".to_string())).unwrap();
+        assert_eq!(rest.len(), 2);
+        assert_eq!(rest[1].kind, "run.finished");
+    }
+}
@@ -13,6 +13,7 @@ pub enum AuthMode {
     ApiKey,
     Bearer,
     Mtls,
+    None,
 }
 
 #[derive(Debug, Clone)]
@@ -21,14 +22,71 @@ pub struct BackendAuth {
     pub secret_ref: String,
 }
 
+#[derive(Debug, Clone)]
+pub enum BackendKind {
+    Http,
+    Process,
+    Plugin,
+    Supabase,
+}
+
+#[derive(Debug, Clone)]
+pub enum SupabaseOp {
+    Select,
+    Insert,
+    Upsert,
+    Update,
+    Delete,
+    Rpc,
+}
+
+#[derive(Debug, Clone)]
+pub struct SupabaseOperation {
+    pub op: SupabaseOp,
+    pub target: String,
+    pub filter: Option<String>,
+    pub on_conflict: Option<String>,
+    pub read_only: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct SupabaseSpec {
+    pub intents: BTreeMap<String, SupabaseOperation>,
+    pub events_table: Option<String>,
+    pub events_cursor: Option<String>,
+    pub events_kind: Option<String>,
+    pub events_time: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PluginSpec {
+    pub executable: String,
+    pub args: Vec<String>,
+    pub settings: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProcessCommand {
+    pub program: String,
+    pub args: Vec<String>,
+    pub env: BTreeMap<String, String>,
+    pub working_dir: Option<String>,
+    pub timeout: Option<Duration>,
+    pub read_only: bool,
+}
+
 #[derive(Debug, Clone)]
 pub struct BackendConfig {
     pub backend_id: BackendId,
+    pub kind: BackendKind,
     pub base_url: String,
     pub auth: BackendAuth,
     pub connect_timeout: Duration,
     pub request_timeout: Duration,
     pub extra_headers: BTreeMap<String, String>,
+    pub commands: BTreeMap<String, ProcessCommand>,
+    pub plugin: Option<PluginSpec>,
+    pub supabase: Option<SupabaseSpec>,
 }
 
 #[derive(Debug, Clone)]
@@ -36,6 +94,7 @@ pub struct BackendCapabilities {
     pub supports_streaming: bool,
     pub supports_write: bool,
     pub supports_history: bool,
+    pub supports_plan: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -51,6 +110,30 @@ pub struct ExecutionResult {
     pub output: BTreeMap<String, String>,
 }
 
+#[derive(Debug, Clone)]
+pub struct PlannedEffect {
+    pub action: String,
+    pub resource: String,
+    pub description: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct HealthReport {
+    pub latency: Duration,
+    pub version: Option<String>,
+    pub capabilities: Option<BackendCapabilities>,
+    pub tls_expires_unix: Option<i64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExecutionPlan {
+    pub backend_id: BackendId,
+    pub intent_type: String,
+    pub mutating: bool,
+    pub effects: Vec<PlannedEffect>,
+    pub warnings: Vec<String>,
+}
+
 #[derive(Debug, Clone)]
 pub struct RuntimeStatus {
     pub active_profile: ProfileId,
@@ -85,8 +168,9 @@ pub trait SecretStore: Send + Sync {
 pub trait BackendConnector: Send + Sync {
     fn id(&self) -> &str;
     fn capabilities(&self) -> BackendCapabilities;
-    fn health(&self) -> Result<(), LoglineError>;
+    fn health(&self) -> Result<HealthReport, LoglineError>;
     fn execute(&self, intent: &Intent) -> Result<ExecutionResult, LoglineError>;
+    fn plan(&self, intent: &Intent) -> Result<ExecutionPlan, LoglineError>;
     fn stop(&self, run_id: &RunId) -> Result<(), LoglineError>;
     fn events_since(&self, cursor: Option<&EventCursor>) -> Result<Vec<DomainEvent>, LoglineError>;
 }
@@ -102,8 +186,9 @@ pub trait ConnectorFactory: Send + Sync {
 pub trait RuntimeEngine: Send + Sync {
     fn status(&self) -> Result<RuntimeStatus, LoglineError>;
     fn run_intent(&self, intent: Intent) -> Result<ExecutionResult, LoglineError>;
+    fn plan_intent(&self, intent: Intent) -> Result<ExecutionPlan, LoglineError>;
     fn stop_run(&self, run_id: RunId) -> Result<(), LoglineError>;
     fn events_since(&self, cursor: Option<EventCursor>) -> Result<Vec<DomainEvent>, LoglineError>;
-    fn test_backend(&self, backend_id: BackendId) -> Result<(), LoglineError>;
+    fn test_backend(&self, backend_id: BackendId) -> Result<HealthReport, LoglineError>;
     fn select_profile(&self, profile_id: ProfileId) -> Result<(), LoglineError>;
 }
@@ -0,0 +1,122 @@
+// cli-passkey-verify: trade a signed passkey nonce for a session.
+//
+// POST { "nonce": "<hex>", "signature": "<hex>" }, the signature being Ed25519 over
+// "logline-cli-passkey-login/v2\n<nonce>" (logline_auth::PasskeyChallenge::signing_message)
+// made with the key registered in cli_passkey_credentials. The nonce is redeemed
+// before the signature is checked, so each one gets exactly one attempt.
+//
+// 200 { session_token, refresh_token, expires_in, credential_id, device_name }
+// 400 malformed body · 401 bad signature · 410 unknown, expired or used nonce
+
+import { createClient } from "jsr:@supabase/supabase-js@2";
+
+const SIGNING_CONTEXT = "logline-cli-passkey-login/v2";
+const HEX = /^[0-9a-f]+$/;
+
+interface Redeemed {
+  credential_id: string;
+  user_id: string;
+  device_name: string;
+  public_key: string;
+  algorithm: string;
+}
+
+function json(status: number, body: unknown): Response {
+  return new Response(JSON.stringify(body), {
+    status,
+    headers: { "content-type": "application/json" },
+  });
+}
+
+function hexField(value: unknown, length: number): string | null {
+  if (typeof value !== "string") return null;
+  const hex = value.trim().toLowerCase();
+  return hex.length === length && HEX.test(hex) ? hex : null;
+}
+
+function hexBytes(hex: string): Uint8Array {
+  const bytes = new Uint8Array(hex.length / 2);
+  for (let i = 0; i < bytes.length; i++) {
+    bytes[i] = parseInt(hex.slice(2 * i, 2 * i + 2), 16);
+  }
+  return bytes;
+}
+
+async function signatureMatches(publicKey: string, nonce: string, signature: string) {
+  try {
+    const key = await crypto.subtle.importKey(
+      "raw",
+      hexBytes(publicKey),
+      { name: "Ed25519" },
+      false,
+      ["verify"],
+    );
+    const message = new TextEncoder().encode(`${SIGNING_CONTEXT}\n${nonce}`);
+    return await crypto.subtle.verify({ name: "Ed25519" }, key, hexBytes(signature), message);
+  } catch {
+    return false;
+  }
+}
+
+Deno.serve(async (req) => {
+  if (req.method !== "POST") {
+    return json(405, { error: "use POST" });
+  }
+  const body = await req.json().catch(() => null);
+  const nonce = hexField(body?.nonce, 64);
+  const signature = hexField(body?.signature, 128);
+  if (!nonce || !signature) {
+    return json(400, { error: "nonce (64 hex) and signature (128 hex) are required" });
+  }
+
+  const url = Deno.env.get("SUPABASE_URL")!;
+  const options = { auth: { persistSession: false, autoRefreshToken: false } };
+  const admin = createClient(url, Deno.env.get("SUPABASE_SERVICE_ROLE_KEY")!, options);
+
+  const { data, error } = await admin.rpc("redeem_cli_passkey_challenge", { p_nonce: nonce });
+  if (error) {
+    return json(500, { error: "could not redeem the challenge" });
+  }
+  const redeemed = data as Redeemed | null;
+  if (!redeemed) {
+    return json(410, { error: "challenge unknown, expired or already used" });
+  }
+  if (
+    redeemed.algorithm !== "ed25519" ||
+    !(await signatureMatches(redeemed.public_key, nonce, signature))
+  ) {
+    return json(401, { error: "signature does not match the registered key" });
+  }
+
+  // A magic link generated server-side and verified on the spot: a normal GoTrue
+  // session (refreshable, access-token hook applied) without any email being sent.
+  const { data: owner } = await admin.auth.admin.getUserById(redeemed.user_id);
+  const email = owner?.user?.email;
+  if (!email) {
+    return json(500, { error: "the passkey owner has no email to sign in with" });
+  }
+  const { data: link, error: linkError } = await admin.auth.admin.generateLink({
+    type: "magiclink",
+    email,
+  });
+  if (linkError || !link?.properties?.hashed_token) {
+    return json(500, { error: "could not issue a session" });
+  }
+  const anon = createClient(url, Deno.env.get("SUPABASE_ANON_KEY")!, options);
+  const { data: verified, error: otpError } = await anon.auth.verifyOtp({
+    type: "magiclink",
+    token_hash: link.properties.hashed_token,
+  });
+  const session = verified?.session;
+  if (otpError || !session) {
+    return json(500, { error: "could not issue a session" });
+  }
+
+  return json(200, {
+    session_token: session.access_token,
+    refresh_token: session.refresh_token,
+    expires_in: session.expires_in,
+    credential_id: redeemed.credential_id,
+    device_name: redeemed.device_name,
+  });
+});
@@ -0,0 +1,12 @@
+-- Migration 006: let CLI runtimes mirror their local audit chain
+-- Depends on: 001_base_tables, 002_rbac_rls
+
+begin;
+
+-- Operators may append audit rows attributed to themselves; the existing
+-- trigger keeps rows immutable and select stays founder-only.
+drop policy if exists audit_insert_self on protected_action_audit;
+create policy audit_insert_self on protected_action_audit
+  for insert with check (actor_user_id = (select app.current_user_id()));
+
+commit;
@@ -0,0 +1,105 @@
+-- Migration 007: single-use nonces for CLI passkey login
+-- Depends on: 005_cli_passkey_credentials
+
+begin;
+
+-- One row per login attempt. Clients never read this table directly: the
+-- nonce is handed out by cli_passkey_challenge() and redeemed exactly once
+-- by app.consume_cli_passkey_challenge() on the verifying server.
+create table if not exists cli_passkey_challenges (
+  challenge_id   text primary key default gen_random_uuid()::text,
+  credential_id  text not null references cli_passkey_credentials(credential_id) on delete cascade,
+  nonce          text not null unique
+                 default replace(gen_random_uuid()::text || gen_random_uuid()::text, '-', ''),
+  expires_at     timestamptz not null default (now() + interval '2 minutes'),
+  consumed_at    timestamptz,
+  created_at     timestamptz not null default now()
+);
+
+create index if not exists idx_passkey_challenges_credential
+  on cli_passkey_challenges (credential_id, expires_at);
+create index if not exists idx_passkey_creds_public_key
+  on cli_passkey_credentials (public_key) where status = 'active';
+
+alter table cli_passkey_challenges enable row level security;
+-- No policies: only the security-definer functions below touch the rows.
+
+-- ─── Issue a challenge ────────────────────────────────────────────────────────
+-- Callable with the anon key: the CLI may have no valid session yet, and a
+-- nonce is worthless without the private key for the named public key.
+
+create or replace function public.cli_passkey_challenge(p_public_key text)
+returns jsonb
+language plpgsql
+security definer set search_path = public
+as $$
+declare
+  v_credential cli_passkey_credentials%rowtype;
+  v_challenge  cli_passkey_challenges%rowtype;
+begin
+  select * into v_credential
+  from cli_passkey_credentials
+  where public_key = lower(p_public_key)
+    and status = 'active'
+  limit 1;
+
+  if not found then
+    raise exception 'no active passkey for this public key'
+      using errcode = 'P0002';
+  end if;
+
+  insert into cli_passkey_challenges (credential_id)
+  values (v_credential.credential_id)
+  returning * into v_challenge;
+
+  return jsonb_build_object(
+    'challenge_id',  v_challenge.challenge_id,
+    'credential_id', v_credential.credential_id,
+    'user_id',       v_credential.user_id,
+    'device_name',   v_credential.device_name,
+    'nonce',         v_challenge.nonce,
+    'expires_at',    v_challenge.expires_at
+  );
+end;
+$$;
+
+revoke all on function public.cli_passkey_challenge(text) from public;
+grant execute on function public.cli_passkey_challenge(text) to anon, authenticated;
+
+-- ─── Redeem a challenge ───────────────────────────────────────────────────────
+-- Marks the challenge consumed and returns everything the verifier needs
+-- (registered public key included). Returns null when the challenge is
+-- unknown, expired, already used, or its credential was revoked, so a
+-- signature can never be checked twice against the same nonce.
+
+create or replace function app.consume_cli_passkey_challenge(p_challenge_id text)
+returns jsonb
+language plpgsql
+security definer set search_path = public
+as $$
+declare
+  v_result jsonb;
+begin
+  with consumed as (
+    update cli_passkey_challenges ch
+    set consumed_at = now()
+    from cli_passkey_credentials cr
+    where ch.challenge_id = p_challenge_id
+      and ch.consumed_at is null
+      and ch.expires_at > now()
+      and cr.credential_id = ch.credential_id
+      and cr.status = 'active'
+    returning ch.challenge_id, ch.credential_id, ch.nonce, ch.expires_at,
+              cr.user_id, cr.device_name, cr.public_key, cr.algorithm
+  )
+  select to_jsonb(consumed) into v_result from consumed;
+
+  return v_result;
+end;
+$$;
+
+revoke all on function app.consume_cli_passkey_challenge(text) from public;
+grant usage on schema app to service_role;
+grant execute on function app.consume_cli_passkey_challenge(text) to service_role;
+
+commit;
@@ -0,0 +1,135 @@
+-- Migration 008: passkey rotation, revocation and rename
+-- Depends on: 005_cli_passkey_credentials, 007_cli_passkey_challenges
+
+begin;
+
+-- Revoked credentials stay as history, so only active ones need a unique
+-- device name per user.
+alter table cli_passkey_credentials
+  drop constraint if exists cli_passkey_credentials_user_id_device_name_key;
+
+create unique index if not exists uq_passkey_creds_active_device
+  on cli_passkey_credentials (user_id, device_name)
+  where status = 'active';
+
+-- ─── Update guard ─────────────────────────────────────────────────────────────
+-- Keys never change in place (rotation registers a new row), revocation is
+-- final, and revoked_at is stamped by the database rather than the client.
+
+create or replace function app.guard_cli_passkey_update()
+returns trigger
+language plpgsql
+as $$
+begin
+  if new.public_key <> old.public_key or new.user_id <> old.user_id then
+    raise exception 'passkey public_key and user_id are immutable; rotate instead'
+      using errcode = 'P0001';
+  end if;
+
+  if old.status = 'revoked' and new.status <> 'revoked' then
+    raise exception 'revoked passkeys cannot be reactivated'
+      using errcode = 'P0001';
+  end if;
+
+  if new.status = 'revoked' and old.status <> 'revoked' then
+    new.revoked_at := now();
+  end if;
+
+  return new;
+end;
+$$;
+
+drop trigger if exists trg_cli_passkey_guard on cli_passkey_credentials;
+create trigger trg_cli_passkey_guard
+  before update on cli_passkey_credentials
+  for each row execute function app.guard_cli_passkey_update();
+
+-- ─── Register / rotate ────────────────────────────────────────────────────────
+-- Revokes the caller's active credential for the device (if any) and inserts
+-- the new key in one transaction, so a rotation never leaves two active keys
+-- or none. Runs as the caller: RLS still applies.
+
+create or replace function public.register_cli_passkey(
+  p_device_name text,
+  p_public_key  text
+)
+returns jsonb
+language plpgsql
+security invoker
+set search_path = public
+as $$
+declare
+  v_user_id  text := auth.uid()::text;
+  v_replaced text;
+  v_new      cli_passkey_credentials%rowtype;
+begin
+  if v_user_id is null then
+    raise exception 'not authenticated' using errcode = '42501';
+  end if;
+
+  update cli_passkey_credentials
+  set status = 'revoked'
+  where user_id = v_user_id
+    and device_name = p_device_name
+    and status = 'active'
+  returning credential_id into v_replaced;
+
+  insert into cli_passkey_credentials (user_id, device_name, public_key, algorithm, status)
+  values (v_user_id, p_device_name, lower(p_public_key), 'ed25519', 'active')
+  returning * into v_new;
+
+  return jsonb_build_object(
+    'credential_id', v_new.credential_id,
+    'device_name',   v_new.device_name,
+    'public_key',    v_new.public_key,
+    'replaced',      v_replaced
+  );
+end;
+$$;
+
+revoke all on function public.register_cli_passkey(text, text) from public;
+grant execute on function public.register_cli_passkey(text, text) to authenticated;
+
+-- ─── Login challenges refuse inactive credentials explicitly ──────────────────
+
+create or replace function public.cli_passkey_challenge(p_public_key text)
+returns jsonb
+language plpgsql
+security definer set search_path = public
+as $$
+declare
+  v_credential cli_passkey_credentials%rowtype;
+  v_challenge  cli_passkey_challenges%rowtype;
+begin
+  select * into v_credential
+  from cli_passkey_credentials
+  where public_key = lower(p_public_key)
+  order by (status = 'active') desc, created_at desc
+  limit 1;
+
+  if not found then
+    raise exception 'no passkey registered for this public key'
+      using errcode = 'P0002';
+  end if;
+
+  if v_credential.status <> 'active' then
+    raise exception 'passkey for device % is %', v_credential.device_name, v_credential.status
+      using errcode = 'P0001';
+  end if;
+
+  insert into cli_passkey_challenges (credential_id)
+  values (v_credential.credential_id)
+  returning * into v_challenge;
+
+  return jsonb_build_object(
+    'challenge_id',  v_challenge.challenge_id,
+    'credential_id', v_credential.credential_id,
+    'user_id',       v_credential.user_id,
+    'device_name',   v_credential.device_name,
+    'nonce',         v_challenge.nonce,
+    'expires_at',    v_challenge.expires_at
+  );
+end;
+$$;
+
+commit;
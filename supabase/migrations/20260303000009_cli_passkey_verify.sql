@@ -0,0 +1,85 @@
+-- Migration 009: anonymous passkey challenges reveal nothing but the nonce
+-- Depends on: 007_cli_passkey_challenges, 008_cli_passkey_rotation
+--
+-- cli_passkey_challenge is callable with the anon key, so it no longer returns
+-- the credential, its owner or its device name: anyone holding a public key
+-- could read them. The nonce is now the handle the verifier redeems.
+
+begin;
+
+-- ─── Issue a challenge ────────────────────────────────────────────────────────
+-- Unknown, revoked and rotated keys get the same error, so a caller cannot
+-- probe which state a key is in either.
+
+create or replace function public.cli_passkey_challenge(p_public_key text)
+returns jsonb
+language plpgsql
+security definer set search_path = public
+as $$
+declare
+  v_credential_id text;
+  v_challenge     cli_passkey_challenges%rowtype;
+begin
+  select credential_id into v_credential_id
+  from cli_passkey_credentials
+  where public_key = lower(p_public_key)
+    and status = 'active'
+  limit 1;
+
+  if not found then
+    raise exception 'no active passkey for this public key'
+      using errcode = 'P0002';
+  end if;
+
+  insert into cli_passkey_challenges (credential_id)
+  values (v_credential_id)
+  returning * into v_challenge;
+
+  return jsonb_build_object(
+    'nonce',      v_challenge.nonce,
+    'expires_at', v_challenge.expires_at
+  );
+end;
+$$;
+
+revoke all on function public.cli_passkey_challenge(text) from public;
+grant execute on function public.cli_passkey_challenge(text) to anon, authenticated;
+
+-- ─── Redeem a challenge ───────────────────────────────────────────────────────
+-- Replaces app.consume_cli_passkey_challenge: keyed by nonce, and in public so
+-- the cli-passkey-verify edge function can call it through PostgREST. Only the
+-- service role may execute it. Returns null when the nonce is unknown, expired,
+-- already used, or its credential was revoked.
+
+drop function if exists app.consume_cli_passkey_challenge(text);
+
+create or replace function public.redeem_cli_passkey_challenge(p_nonce text)
+returns jsonb
+language plpgsql
+security definer set search_path = public
+as $$
+declare
+  v_result jsonb;
+begin
+  with consumed as (
+    update cli_passkey_challenges ch
+    set consumed_at = now()
+    from cli_passkey_credentials cr
+    where ch.nonce = p_nonce
+      and ch.consumed_at is null
+      and ch.expires_at > now()
+      and cr.credential_id = ch.credential_id
+      and cr.status = 'active'
+    returning ch.credential_id, ch.nonce, ch.expires_at,
+              cr.user_id, cr.device_name, cr.public_key, cr.algorithm
+  )
+  select to_jsonb(consumed) into v_result from consumed;
+
+  return v_result;
+end;
+$$;
+
+revoke all on function public.redeem_cli_passkey_challenge(text) from public, anon, authenticated;
+grant execute on function public.redeem_cli_passkey_challenge(text) to service_role;
+
+commit;